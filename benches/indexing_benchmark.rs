@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use futures::StreamExt;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tokio::runtime::Runtime;
+use tokio::sync::oneshot;
+use vista_core::models::{AccountInfo, BlockMetaInfo, IdlVersionInfo, SlotInfo, SlotRepairInfo, SlotStatus, TransactionInfo};
+use vista_core::traits::{RpcProvider, StoragePlugin};
+use vista_core::{Indexer, IndexerError, RpcProviderRegistry, UpdateEvent};
+use vista_ingestion::providers::replay::{RecordReader, RecordWriter};
+use vista_ingestion::providers::{ReplayProvider, ReplaySpeed};
+
+// Replays captured traffic through the indexer at max speed. Point
+// VISTA_BENCH_RECORDING at a file written through a provider's `record_path`;
+// without it a synthetic recording of account and slot updates is used.
+
+struct NullStorage;
+
+#[async_trait]
+impl StoragePlugin for NullStorage {
+    fn name(&self) -> &str {
+        "null"
+    }
+
+    async fn init(&self, _config: &Value) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn store_account(&self, _account: AccountInfo) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn store_transaction(&self, _transaction: TransactionInfo) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn get_account(&self, _pubkey: &Pubkey) -> Result<Option<AccountInfo>, IndexerError> {
+        Ok(None)
+    }
+
+    async fn get_transaction(&self, _signature: &Signature) -> Result<Option<TransactionInfo>, IndexerError> {
+        Ok(None)
+    }
+
+    async fn get_failed_transactions(&self, _program_id: &Pubkey, _error_name: Option<&str>) -> Result<Vec<TransactionInfo>, IndexerError> {
+        Ok(Vec::new())
+    }
+
+    async fn store_parsed_account(&self, _pubkey: &Pubkey, _program_id: &str, _account_type: &str, _slot: u64, _data: &Value) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn store_block_meta(&self, _block_meta: BlockMetaInfo) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn store_idl_version(&self, _version: IdlVersionInfo) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn get_idl_versions(&self) -> Result<Vec<IdlVersionInfo>, IndexerError> {
+        Ok(Vec::new())
+    }
+
+    async fn store_slot_repair(&self, _repair: SlotRepairInfo) -> Result<(), IndexerError> {
+        Ok(())
+    }
+}
+
+fn synthetic_recording() -> PathBuf {
+    let path = std::env::temp_dir().join("vista-bench-synthetic.rec");
+    let _ = std::fs::remove_file(&path);
+    let mut writer = RecordWriter::open(&path).unwrap();
+    let owner = Pubkey::new_unique();
+    for slot in 0..10_000u64 {
+        writer.write(slot * 400_000, &UpdateEvent::AccountUpdate(AccountInfo {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1_000_000,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+            data: vec![0; 165],
+            slot,
+        })).unwrap();
+        writer.write(slot * 400_000 + 1, &UpdateEvent::SlotUpdate(SlotInfo {
+            slot,
+            parent: slot.checked_sub(1),
+            status: SlotStatus::Confirmed,
+        })).unwrap();
+    }
+    writer.flush().unwrap();
+    path
+}
+
+fn replay_benchmark(c: &mut Criterion) {
+    let path = std::env::var("VISTA_BENCH_RECORDING").map(PathBuf::from).unwrap_or_else(|_| synthetic_recording());
+    let records = RecordReader::open(&path).unwrap().count() as u64;
+
+    let runtime = Runtime::new().unwrap();
+    let indexer = runtime.block_on(async {
+        let indexer = Arc::new(Indexer::new(Arc::new(NullStorage), Arc::new(RpcProviderRegistry::new())));
+        // Without the processing task nothing drains the update channel
+        indexer.start().await.unwrap();
+        indexer
+    });
+    let update_channel = indexer.get_update_channel();
+
+    let mut group = c.benchmark_group("replay");
+    group.throughput(Throughput::Elements(records));
+    group.sample_size(10);
+    group.bench_function("recorded_traffic", |b| {
+        b.iter(|| runtime.block_on(async {
+            let provider = ReplayProvider::new(&path, ReplaySpeed::Max);
+            let mut updates = provider.start().await.unwrap().map(|update| update.event);
+            while let Some(event) = updates.next().await {
+                update_channel.send(event).await.unwrap();
+            }
+            // Measure until the indexer has processed the last update
+            let (done, processed) = oneshot::channel();
+            update_channel.send(UpdateEvent::Checkpoint(done)).await.unwrap();
+            processed.await.unwrap();
+        }))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, replay_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,341 @@
+use anchor_syn::idl::{EnumFields, IdlField, IdlType, IdlTypeDefinitionTy};
+use serde_json::Value;
+
+use crate::idl::{decode_hex, DiscriminatorCheck, DiscriminatorSpec, ProgramIdl};
+use crate::AnchorError;
+
+// Inverse of `AnchorParser::parse_idl_type`: accepts exactly the JSON shapes the
+// decoder produces, so decoded values re-encode to the original bytes. Only
+// non-finite floats don't, as they decode to null and are rejected here.
+pub(crate) fn encode_idl_type(program: &ProgramIdl, ty: &IdlType, value: &Value, out: &mut Vec<u8>) -> Result<(), AnchorError> {
+    match ty {
+        IdlType::Bool => out.push(as_bool(value)? as u8),
+        IdlType::U8 => out.push(as_unsigned(value, u8::MAX as u128)? as u8),
+        IdlType::I8 => out.extend((as_signed(value, i8::MIN as i128, i8::MAX as i128)? as i8).to_le_bytes()),
+        IdlType::U16 => out.extend((as_unsigned(value, u16::MAX as u128)? as u16).to_le_bytes()),
+        IdlType::I16 => out.extend((as_signed(value, i16::MIN as i128, i16::MAX as i128)? as i16).to_le_bytes()),
+        IdlType::U32 => out.extend((as_unsigned(value, u32::MAX as u128)? as u32).to_le_bytes()),
+        IdlType::I32 => out.extend((as_signed(value, i32::MIN as i128, i32::MAX as i128)? as i32).to_le_bytes()),
+        IdlType::U64 => out.extend((as_unsigned(value, u64::MAX as u128)? as u64).to_le_bytes()),
+        IdlType::I64 => out.extend((as_signed(value, i64::MIN as i128, i64::MAX as i128)? as i64).to_le_bytes()),
+        IdlType::U128 => out.extend(as_unsigned(value, u128::MAX)?.to_le_bytes()),
+        IdlType::I128 => out.extend(as_signed(value, i128::MIN, i128::MAX)?.to_le_bytes()),
+        IdlType::U256 | IdlType::I256 => {
+            let hex = value.as_str().and_then(|s| s.strip_prefix("0x"))
+                .ok_or_else(|| mismatch("0x-prefixed hex string", value))?;
+            let mut bytes = decode_hex(hex).filter(|b| b.len() == 32)
+                .ok_or_else(|| mismatch("32-byte hex string", value))?;
+            bytes.reverse();
+            out.extend(bytes);
+        }
+        IdlType::F32 => out.extend((as_float(value)? as f32).to_le_bytes()),
+        IdlType::F64 => out.extend(as_float(value)?.to_le_bytes()),
+        IdlType::PublicKey => {
+            let key = value.as_str().ok_or_else(|| mismatch("base58 public key", value))?;
+            let bytes = bs58::decode(key).into_vec()
+                .map_err(|e| AnchorError::EncodeError(e.to_string()))?;
+            if bytes.len() != 32 {
+                return Err(mismatch("32-byte public key", value));
+            }
+            out.extend(bytes);
+        }
+        IdlType::String => {
+            let s = value.as_str().ok_or_else(|| mismatch("string", value))?;
+            out.extend((s.len() as u32).to_le_bytes());
+            out.extend(s.as_bytes());
+        }
+        IdlType::Bytes => {
+            let s = value.as_str().ok_or_else(|| mismatch("base64 string", value))?;
+            let bytes = base64::decode(s).map_err(|e| AnchorError::EncodeError(e.to_string()))?;
+            out.extend((bytes.len() as u32).to_le_bytes());
+            out.extend(bytes);
+        }
+        IdlType::Option(inner) => {
+            if value.is_null() {
+                out.push(0);
+            } else {
+                out.push(1);
+                encode_idl_type(program, inner, value, out)?;
+            }
+        }
+        IdlType::Vec(inner) => {
+            let items = value.as_array().ok_or_else(|| mismatch("array", value))?;
+            out.extend((items.len() as u32).to_le_bytes());
+            for item in items {
+                encode_idl_type(program, inner, item, out)?;
+            }
+        }
+        IdlType::Array(inner, len) => {
+            let items = value.as_array().ok_or_else(|| mismatch("array", value))?;
+            if items.len() != *len {
+                return Err(AnchorError::EncodeError(format!("Expected array of length {}, got {}", len, items.len())));
+            }
+            for item in items {
+                encode_idl_type(program, inner, item, out)?;
+            }
+        }
+        IdlType::Defined(name) => {
+            let idl = &program.idl;
+            let def = idl.types.iter().chain(idl.accounts.iter())
+                .find(|t| &t.name == name)
+                .ok_or_else(|| AnchorError::IdlParseError(format!("Type {} not found in IDL", name)))?;
+            match &def.ty {
+                IdlTypeDefinitionTy::Struct { fields } => encode_fields(program, fields, value, out)?,
+                IdlTypeDefinitionTy::Enum { variants } => {
+                    // Unit variants are plain strings, others a single-key object
+                    let (variant_name, inner) = match value {
+                        Value::String(s) => (s.as_str(), &Value::Null),
+                        Value::Object(map) if map.len() == 1 => {
+                            let (k, v) = map.iter().next().unwrap();
+                            (k.as_str(), v)
+                        }
+                        _ => return Err(mismatch("enum variant", value)),
+                    };
+                    let tag = variants.iter().position(|v| v.name == variant_name)
+                        .ok_or_else(|| AnchorError::EncodeError(format!("Unknown variant {} for enum {}", variant_name, name)))?;
+                    out.extend(&(tag as u64).to_le_bytes()[..program.enum_tag_len(name)]);
+                    match &variants[tag].fields {
+                        None => {}
+                        Some(EnumFields::Named(fields)) => encode_fields(program, fields, inner, out)?,
+                        Some(EnumFields::Tuple(types)) => {
+                            let items = inner.as_array().filter(|items| items.len() == types.len())
+                                .ok_or_else(|| mismatch(&format!("array of {} values", types.len()), inner))?;
+                            for (ty, item) in types.iter().zip(items) {
+                                encode_idl_type(program, ty, item, out)?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn encode_fields(program: &ProgramIdl, fields: &[IdlField], value: &Value, out: &mut Vec<u8>) -> Result<(), AnchorError> {
+    let map = value.as_object().ok_or_else(|| mismatch("object", value))?;
+    for field in fields {
+        let field_value = map.get(&field.name)
+            .ok_or_else(|| AnchorError::EncodeError(format!("Missing field {}", field.name)))?;
+        encode_idl_type(program, &field.ty, field_value, out)
+            .map_err(|e| AnchorError::EncodeError(format!("{}: {}", field.name, e)))?;
+    }
+    Ok(())
+}
+
+// Bytes that precede the declared fields: the discriminator itself for Anchor
+// and Shank instructions, nothing for layouts whose discriminator is a field.
+pub(crate) fn discriminator_prefix(spec: &DiscriminatorSpec) -> Vec<u8> {
+    let mut prefix = vec![0u8; spec.data_offset];
+    for check in &spec.checks {
+        if let DiscriminatorCheck::Bytes { offset, bytes } = check {
+            if offset + bytes.len() <= spec.data_offset {
+                prefix[*offset..offset + bytes.len()].copy_from_slice(bytes);
+            }
+        }
+    }
+    prefix
+}
+
+fn mismatch(expected: &str, value: &Value) -> AnchorError {
+    AnchorError::EncodeError(format!("Expected {}, got {}", expected, value))
+}
+
+fn as_bool(value: &Value) -> Result<bool, AnchorError> {
+    value.as_bool().ok_or_else(|| mismatch("bool", value))
+}
+
+// 128-bit values are decoded as strings, smaller ones as numbers; accept both
+fn as_unsigned(value: &Value, max: u128) -> Result<u128, AnchorError> {
+    let n = match value {
+        Value::Number(n) => n.as_u64().map(|n| n as u128),
+        Value::String(s) => s.parse::<u128>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| mismatch("unsigned integer", value))?;
+    if n > max {
+        return Err(AnchorError::EncodeError(format!("{} is out of range (max {})", n, max)));
+    }
+    Ok(n)
+}
+
+fn as_signed(value: &Value, min: i128, max: i128) -> Result<i128, AnchorError> {
+    let n = match value {
+        Value::Number(n) => n.as_i64().map(|n| n as i128).or_else(|| n.as_u64().map(|n| n as i128)),
+        Value::String(s) => s.parse::<i128>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| mismatch("integer", value))?;
+    if n < min || n > max {
+        return Err(AnchorError::EncodeError(format!("{} is out of range ({}..={})", n, min, max)));
+    }
+    Ok(n)
+}
+
+fn as_float(value: &Value) -> Result<f64, AnchorError> {
+    value.as_f64().ok_or_else(|| mismatch("float", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::AnchorParser;
+
+    const IDL: &str = r#"{
+        "version": "0.1.0",
+        "name": "types",
+        "instructions": [],
+        "types": [
+            {
+                "name": "Scalars",
+                "type": {
+                    "kind": "struct",
+                    "fields": [
+                        { "name": "flag", "type": "bool" },
+                        { "name": "u8", "type": "u8" },
+                        { "name": "i8", "type": "i8" },
+                        { "name": "u16", "type": "u16" },
+                        { "name": "i16", "type": "i16" },
+                        { "name": "u32", "type": "u32" },
+                        { "name": "i32", "type": "i32" },
+                        { "name": "f32", "type": "f32" },
+                        { "name": "u64", "type": "u64" },
+                        { "name": "i64", "type": "i64" },
+                        { "name": "f64", "type": "f64" },
+                        { "name": "u128", "type": "u128" },
+                        { "name": "i128", "type": "i128" },
+                        { "name": "u256", "type": "u256" },
+                        { "name": "i256", "type": "i256" },
+                        { "name": "bytes", "type": "bytes" },
+                        { "name": "text", "type": "string" },
+                        { "name": "key", "type": "publicKey" }
+                    ]
+                }
+            },
+            {
+                "name": "Containers",
+                "type": {
+                    "kind": "struct",
+                    "fields": [
+                        { "name": "some", "type": { "option": "u16" } },
+                        { "name": "none", "type": { "option": "string" } },
+                        { "name": "list", "type": { "vec": { "defined": "Point" } } },
+                        { "name": "grid", "type": { "array": [{ "array": ["i8", 2] }, 2] } },
+                        { "name": "shapes", "type": { "vec": { "defined": "Shape" } } }
+                    ]
+                }
+            },
+            {
+                "name": "Point",
+                "type": { "kind": "struct", "fields": [{ "name": "x", "type": "i32" }, { "name": "y", "type": "i32" }] }
+            },
+            {
+                "name": "Shape",
+                "type": {
+                    "kind": "enum",
+                    "variants": [
+                        { "name": "Empty" },
+                        { "name": "Circle", "fields": [{ "name": "center", "type": { "defined": "Point" } }, { "name": "radius", "type": "u32" }] },
+                        { "name": "Segment", "fields": [{ "defined": "Point" }, { "defined": "Point" }] }
+                    ]
+                }
+            }
+        ]
+    }"#;
+
+    fn parser() -> AnchorParser {
+        let mut parser = AnchorParser::new();
+        parser.add_idl("program", IDL).unwrap();
+        parser
+    }
+
+    // Encodes the value, checks it decodes back unchanged from exactly the
+    // encoded bytes and returns them
+    fn round_trip(parser: &AnchorParser, type_name: &str, value: serde_json::Value) -> Vec<u8> {
+        let encoded = parser.encode_type("program", type_name, &value).unwrap();
+        let program = parser.program_idl("program", None).unwrap();
+        assert_eq!(parser.decode_struct_exact(program, type_name, &encoded).unwrap(), value);
+        encoded
+    }
+
+    #[test]
+    fn scalars_round_trip() {
+        let parser = parser();
+        let encoded = round_trip(&parser, "Scalars", json!({
+            "flag": true,
+            "u8": 255,
+            "i8": -128,
+            "u16": 65535,
+            "i16": -32768,
+            "u32": 4294967295u32,
+            "i32": -2147483648i32,
+            "f32": 1.5,
+            "u64": u64::MAX,
+            "i64": i64::MIN,
+            "f64": -2.25,
+            "u128": u128::MAX.to_string(),
+            "i128": i128::MIN.to_string(),
+            "u256": format!("0x{}{}", "ff".repeat(16), "00".repeat(16)),
+            "i256": format!("0x{}01", "00".repeat(31)),
+            "bytes": base64::encode([0, 1, 2, 254]),
+            "text": "héllo",
+            "key": bs58::encode([7u8; 32]).into_string(),
+        }));
+        // Every type is fixed size except bytes and string, which are prefixed by their length
+        let fixed = 1 + 1 + 1 + 2 + 2 + 4 + 4 + 4 + 8 + 8 + 8 + 16 + 16 + 32 + 32 + 32;
+        assert_eq!(encoded.len(), fixed + 4 + 4 + 4 + "héllo".len());
+    }
+
+    #[test]
+    fn containers_and_enums_round_trip() {
+        let parser = parser();
+        round_trip(&parser, "Containers", json!({
+            "some": 7,
+            "none": null,
+            "list": [{ "x": 1, "y": -1 }, { "x": 0, "y": 2 }],
+            "grid": [[1, -2], [-3, 4]],
+            "shapes": [
+                "Empty",
+                { "Circle": { "center": { "x": 5, "y": 6 }, "radius": 3 } },
+                { "Segment": [{ "x": 0, "y": 0 }, { "x": 9, "y": 9 }] },
+            ],
+        }));
+    }
+
+    #[test]
+    fn values_of_the_wrong_shape_are_rejected() {
+        let parser = parser();
+        let point = |x: serde_json::Value| parser.encode_type("program", "Point", &json!({ "x": x, "y": 0 }));
+        assert!(point(json!(2147483648i64)).is_err());
+        assert!(point(json!("1")).is_ok());
+        assert!(point(json!(1.5)).is_err());
+        assert!(point(json!(null)).is_err());
+
+        assert!(parser.encode_type("program", "Shape", &json!("Triangle")).is_err());
+        assert!(parser.encode_type("program", "Shape", &json!({ "Segment": [{ "x": 0, "y": 0 }] })).is_err());
+        let grid = json!({ "some": null, "none": null, "list": [], "grid": [[1, 2]], "shapes": [] });
+        assert!(parser.encode_type("program", "Containers", &grid).is_err());
+    }
+
+    #[test]
+    fn floats_must_be_numbers() {
+        let parser = parser();
+        let program = parser.program_idl("program", None).unwrap();
+        let f64_of = |value: serde_json::Value| {
+            let mut out = Vec::new();
+            super::encode_idl_type(program, &anchor_syn::idl::IdlType::F64, &value, &mut out).map(|_| out)
+        };
+        assert_eq!(f64_of(json!(3)).unwrap(), 3f64.to_le_bytes());
+        assert!(f64_of(json!(null)).is_err());
+        assert!(f64_of(json!("1.5")).is_err());
+        assert!(f64_of(json!(true)).is_err());
+
+        // Non-finite floats decode to null, which doesn't encode back
+        let mut scalars = vec![0u8; 1 + 1 + 1 + 2 + 2 + 4 + 4];
+        scalars.extend(f32::NAN.to_le_bytes());
+        scalars.extend(vec![0u8; 8 + 8 + 8 + 16 + 16 + 32 + 32 + 4 + 4 + 32]);
+        let decoded = parser.decode_struct_exact(program, "Scalars", &scalars).unwrap();
+        assert!(decoded["f32"].is_null());
+        assert!(parser.encode_type("program", "Scalars", &decoded).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::idl::{IdlFormat, ProgramIdl};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdlError {
+    pub code: u32,
+    pub name: String,
+    pub msg: Option<String>,
+}
+
+// Custom codes from 6000 up belong to the program's own `#[error_code]` enum
+pub const ANCHOR_CUSTOM_ERROR_START: u32 = 6000;
+
+// Errors raised by the Anchor framework itself, shared by every Anchor program
+const ANCHOR_FRAMEWORK_ERRORS: &[(u32, &str, &str)] = &[
+    (100, "InstructionMissing", "8 byte instruction identifier not provided"),
+    (101, "InstructionFallbackNotFound", "Fallback functions are not supported"),
+    (102, "InstructionDidNotDeserialize", "The program could not deserialize the given instruction"),
+    (103, "InstructionDidNotSerialize", "The program could not serialize the given instruction"),
+    (1000, "IdlInstructionStub", "The program was compiled without idl instructions"),
+    (1001, "IdlInstructionInvalidProgram", "Invalid program given to the IDL instruction"),
+    (1500, "EventInstructionStub", "The program was compiled without `event-cpi` feature"),
+    (2000, "ConstraintMut", "A mut constraint was violated"),
+    (2001, "ConstraintHasOne", "A has one constraint was violated"),
+    (2002, "ConstraintSigner", "A signer constraint was violated"),
+    (2003, "ConstraintRaw", "A raw constraint was violated"),
+    (2004, "ConstraintOwner", "An owner constraint was violated"),
+    (2005, "ConstraintRentExempt", "A rent exemption constraint was violated"),
+    (2006, "ConstraintSeeds", "A seeds constraint was violated"),
+    (2007, "ConstraintExecutable", "An executable constraint was violated"),
+    (2008, "ConstraintState", "Deprecated Error, feel free to replace with something else"),
+    (2009, "ConstraintAssociated", "An associated constraint was violated"),
+    (2010, "ConstraintAssociatedInit", "An associated init constraint was violated"),
+    (2011, "ConstraintClose", "A close constraint was violated"),
+    (2012, "ConstraintAddress", "An address constraint was violated"),
+    (2013, "ConstraintZero", "Expected zero account discriminant"),
+    (2014, "ConstraintTokenMint", "A token mint constraint was violated"),
+    (2015, "ConstraintTokenOwner", "A token owner constraint was violated"),
+    (2016, "ConstraintMintMintAuthority", "A mint mint authority constraint was violated"),
+    (2017, "ConstraintMintFreezeAuthority", "A mint freeze authority constraint was violated"),
+    (2018, "ConstraintMintDecimals", "A mint decimals constraint was violated"),
+    (2019, "ConstraintSpace", "A space constraint was violated"),
+    (2020, "ConstraintAccountIsNone", "A required account for the constraint is None"),
+    (2500, "RequireViolated", "A require expression was violated"),
+    (2501, "RequireEqViolated", "A require_eq expression was violated"),
+    (2502, "RequireKeysEqViolated", "A require_keys_eq expression was violated"),
+    (2503, "RequireNeqViolated", "A require_neq expression was violated"),
+    (2504, "RequireKeysNeqViolated", "A require_keys_neq expression was violated"),
+    (2505, "RequireGtViolated", "A require_gt expression was violated"),
+    (2506, "RequireGteViolated", "A require_gte expression was violated"),
+    (3000, "AccountDiscriminatorAlreadySet", "The account discriminator was already set on this account"),
+    (3001, "AccountDiscriminatorNotFound", "No 8 byte discriminator was found on the account"),
+    (3002, "AccountDiscriminatorMismatch", "8 byte discriminator did not match what was expected"),
+    (3003, "AccountDidNotDeserialize", "Failed to deserialize the account"),
+    (3004, "AccountDidNotSerialize", "Failed to serialize the account"),
+    (3005, "AccountNotEnoughKeys", "Not enough account keys given to the instruction"),
+    (3006, "AccountNotMutable", "The given account is not mutable"),
+    (3007, "AccountOwnedByWrongProgram", "The given account is owned by a different program than expected"),
+    (3008, "InvalidProgramId", "Program ID was not as expected"),
+    (3009, "InvalidProgramExecutable", "Program account is not executable"),
+    (3010, "AccountNotSigner", "The given account did not sign"),
+    (3011, "AccountNotSystemOwned", "The given account is not owned by the system program"),
+    (3012, "AccountNotInitialized", "The program expected this account to be already initialized"),
+    (3013, "AccountNotProgramData", "The given account is not a program data account"),
+    (3014, "AccountNotAssociatedTokenAccount", "The given account is not the associated token account"),
+    (3015, "AccountSysvarMismatch", "The given public key does not match the required sysvar"),
+    (3016, "AccountReallocExceedsLimit", "The account reallocation exceeds the MAX_PERMITTED_DATA_INCREASE limit"),
+    (3017, "AccountDuplicateReallocs", "The account was duplicated for more than one reallocation"),
+    (4000, "DeclaredProgramIdMismatch", "The declared program id does not match the actual program id"),
+    (4100, "TryingToInitPayerAsProgramAccount", "You cannot/should not initialize the payer account as a program account"),
+    (5000, "Deprecated", "The API being used is deprecated and should no longer be used"),
+];
+
+pub fn anchor_framework_error(code: u32) -> Option<IdlError> {
+    ANCHOR_FRAMEWORK_ERRORS.iter()
+        .find(|(c, _, _)| *c == code)
+        .map(|(code, name, msg)| IdlError { code: *code, name: name.to_string(), msg: Some(msg.to_string()) })
+}
+
+pub(crate) fn lookup_error(program: &ProgramIdl, code: u32) -> Option<IdlError> {
+    let from_idl = program.idl.errors.iter().flatten()
+        .find(|e| e.code == code)
+        .map(|e| IdlError { code: e.code, name: e.name.clone(), msg: e.msg.clone() });
+
+    // Shank and Codama programs don't share Anchor's reserved range
+    match program.format {
+        IdlFormat::Anchor if code < ANCHOR_CUSTOM_ERROR_START => from_idl.or_else(|| anchor_framework_error(code)),
+        _ => from_idl,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchor_program() -> ProgramIdl {
+        ProgramIdl::from_json(r#"{
+            "version": "0.1.0",
+            "name": "swap",
+            "instructions": [],
+            "errors": [
+                { "code": 6000, "name": "Slippage", "msg": "Slippage exceeded" },
+                { "code": 6001, "name": "Paused" },
+                { "code": 2000, "name": "LegacyLimit", "msg": "Declared below the custom range" }
+            ]
+        }"#).unwrap()
+    }
+
+    fn name(program: &ProgramIdl, code: u32) -> Option<String> {
+        lookup_error(program, code).map(|e| e.name)
+    }
+
+    #[test]
+    fn anchor_programs_resolve_idl_and_framework_errors() {
+        let program = anchor_program();
+        assert_eq!(lookup_error(&program, 6000), Some(IdlError {
+            code: 6000,
+            name: "Slippage".to_string(),
+            msg: Some("Slippage exceeded".to_string()),
+        }));
+        assert_eq!(lookup_error(&program, 6001).unwrap().msg, None);
+        assert_eq!(lookup_error(&program, 3012), Some(IdlError {
+            code: 3012,
+            name: "AccountNotInitialized".to_string(),
+            msg: Some("The program expected this account to be already initialized".to_string()),
+        }));
+        // The IDL wins over the framework table
+        assert_eq!(name(&program, 2000), Some("LegacyLimit".to_string()));
+        assert_eq!(name(&program, 6002), None);
+        assert_eq!(name(&program, 2999), None);
+    }
+
+    #[test]
+    fn other_formats_only_resolve_their_own_errors() {
+        let shank = ProgramIdl::from_json(include_str!("../tests/fixtures/shank_idl.json")).unwrap();
+        assert_eq!(name(&shank, 1), Some("Overflow".to_string()));
+        assert_eq!(name(&shank, 3012), None);
+
+        let codama = ProgramIdl::from_json(include_str!("../tests/fixtures/codama_idl.json")).unwrap();
+        let frozen = lookup_error(&codama, 6000).unwrap();
+        assert_eq!(frozen.msg, Some("Vault is frozen".to_string()));
+        assert_eq!(name(&codama, 2000), None);
+    }
+}
@@ -0,0 +1,802 @@
+use anchor_syn::idl::{Idl, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::zero_copy::{serialization_hints, Serialization};
+use crate::AnchorError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdlFormat {
+    Anchor,
+    Shank,
+    Codama,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscriminatorCheck {
+    Bytes { offset: usize, bytes: Vec<u8> },
+    Size(usize),
+}
+
+// How an account or instruction is recognised, and where its fields start.
+// Anchor and Shank instructions carry a discriminator that isn't part of the
+// declared fields, while Shank account keys and Codama discriminators are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscriminatorSpec {
+    pub checks: Vec<DiscriminatorCheck>,
+    pub data_offset: usize,
+}
+
+impl DiscriminatorSpec {
+    pub fn matches(&self, data: &[u8]) -> bool {
+        !self.checks.is_empty() && self.checks.iter().all(|check| match check {
+            DiscriminatorCheck::Bytes { offset, bytes } => {
+                data.len() >= offset + bytes.len() && &data[*offset..offset + bytes.len()] == bytes.as_slice()
+            }
+            DiscriminatorCheck::Size(size) => data.len() == *size,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProgramIdl {
+    pub idl: Idl,
+    pub format: IdlFormat,
+    pub accounts: Vec<(String, DiscriminatorSpec)>,
+    pub instructions: Vec<(String, DiscriminatorSpec)>,
+    pub serialization: Vec<(String, Serialization)>,
+    // Tag width of enums that don't use Borsh's single byte, which only Codama declares
+    pub enum_tags: Vec<(String, usize)>,
+}
+
+impl ProgramIdl {
+    pub fn from_json(idl_json: &str) -> Result<Self, AnchorError> {
+        let raw: Value = serde_json::from_str(idl_json)
+            .map_err(|e| AnchorError::IdlParseError(e.to_string()))?;
+
+        let serialization = serialization_hints(&raw);
+        let mut program = match detect_format(&raw) {
+            IdlFormat::Codama => from_codama(&raw)?,
+            IdlFormat::Shank => from_shank(raw)?,
+            IdlFormat::Anchor if raw["metadata"]["spec"].is_string() => from_anchor_spec(&raw)?,
+            IdlFormat::Anchor => {
+                let idl: Idl = serde_json::from_value(raw)
+                    .map_err(|e| AnchorError::IdlParseError(e.to_string()))?;
+                from_anchor(idl)
+            }
+        };
+        program.serialization = serialization;
+        Ok(program)
+    }
+
+    pub fn serialization(&self, name: &str) -> Serialization {
+        self.serialization.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, s)| *s)
+            .unwrap_or(Serialization::Borsh)
+    }
+
+    pub fn enum_tag_len(&self, name: &str) -> usize {
+        self.enum_tags.iter()
+            .find(|(n, _)| n == name)
+            .map_or(1, |(_, len)| *len)
+    }
+
+    pub fn account_spec(&self, name: &str) -> Option<&DiscriminatorSpec> {
+        self.accounts.iter().find(|(n, _)| n == name).map(|(_, spec)| spec)
+    }
+
+    pub fn instruction_spec(&self, name: &str) -> Option<&DiscriminatorSpec> {
+        self.instructions.iter().find(|(n, _)| n == name).map(|(_, spec)| spec)
+    }
+
+    pub fn type_definition(&self, name: &str) -> Option<&IdlTypeDefinition> {
+        self.idl.types.iter()
+            .chain(self.idl.accounts.iter())
+            .find(|t| t.name == name)
+    }
+}
+
+fn detect_format(raw: &Value) -> IdlFormat {
+    if raw["kind"] == "rootNode" || raw["standard"] == "codama" || raw["standard"] == "kinobi" {
+        IdlFormat::Codama
+    } else if raw["metadata"]["origin"] == "shank" {
+        IdlFormat::Shank
+    } else {
+        IdlFormat::Anchor
+    }
+}
+
+fn from_anchor(idl: Idl) -> ProgramIdl {
+    let accounts = idl.accounts.iter()
+        .map(|a| (a.name.clone(), DiscriminatorSpec {
+            checks: vec![DiscriminatorCheck::Bytes { offset: 0, bytes: account_discriminator(&a.name).to_vec() }],
+            data_offset: 8,
+        }))
+        .collect();
+    let instructions = idl.instructions.iter()
+        .map(|ix| (ix.name.clone(), DiscriminatorSpec {
+            checks: vec![DiscriminatorCheck::Bytes { offset: 0, bytes: instruction_discriminator(&ix.name).to_vec() }],
+            data_offset: 8,
+        }))
+        .collect();
+    ProgramIdl { idl, format: IdlFormat::Anchor, accounts, instructions, serialization: Vec::new(), enum_tags: Vec::new() }
+}
+
+// Anchor 0.30 IDLs (`metadata.spec`) carry explicit discriminators, keep
+// account layouts in `types`, and spell a few types differently, so they are
+// lowered into the legacy model like Shank and Codama.
+fn from_anchor_spec(raw: &Value) -> Result<ProgramIdl, AnchorError> {
+    let mut account_names = Vec::new();
+    let mut account_specs = Vec::new();
+    for account in raw["accounts"].as_array().into_iter().flatten() {
+        let name = node_name(account)?;
+        account_specs.push((name.clone(), explicit_discriminator(account, &name)?));
+        account_names.push(name);
+    }
+
+    let mut accounts = Vec::new();
+    let mut types = Vec::new();
+    for def in raw["types"].as_array().into_iter().flatten() {
+        let name = node_name(def)?;
+        if def["generics"].as_array().is_some_and(|g| !g.is_empty()) {
+            return Err(AnchorError::IdlParseError(format!("Generic type {} is not supported", name)));
+        }
+        let ty = anchor_spec_type_definition(&def["type"], &name)?;
+        if account_names.contains(&name) {
+            accounts.push(json!({ "name": name, "type": ty }));
+        } else {
+            types.push(json!({ "name": name, "type": ty }));
+        }
+    }
+    if let Some(missing) = account_names.iter().find(|name| !accounts.iter().any(|a| a["name"] == name.as_str())) {
+        return Err(AnchorError::IdlParseError(format!("Account {} has no type definition", missing)));
+    }
+
+    let mut instructions = Vec::new();
+    let mut instruction_specs = Vec::new();
+    for ix in raw["instructions"].as_array().into_iter().flatten() {
+        let name = node_name(ix)?;
+        let mut args = Vec::new();
+        for arg in ix["args"].as_array().into_iter().flatten() {
+            args.push(json!({ "name": node_name(arg)?, "type": anchor_spec_type(&arg["type"], &name)? }));
+        }
+        let ix_accounts: Vec<Value> = ix["accounts"].as_array().into_iter().flatten().map(anchor_spec_account).collect();
+        instruction_specs.push((name.clone(), explicit_discriminator(ix, &name)?));
+        instructions.push(json!({ "name": name, "accounts": ix_accounts, "args": args }));
+    }
+
+    let idl_value = json!({
+        "version": raw["metadata"]["version"].as_str().unwrap_or("0.0.0"),
+        "name": raw["metadata"]["name"].as_str().unwrap_or_default(),
+        "instructions": instructions,
+        "accounts": accounts,
+        "types": types,
+        "errors": raw["errors"].as_array().cloned().unwrap_or_default(),
+        "metadata": { "address": raw["address"] },
+    });
+    let idl: Idl = serde_json::from_value(idl_value)
+        .map_err(|e| AnchorError::IdlParseError(e.to_string()))?;
+
+    Ok(ProgramIdl {
+        idl,
+        format: IdlFormat::Anchor,
+        accounts: account_specs,
+        instructions: instruction_specs,
+        serialization: Vec::new(),
+        enum_tags: Vec::new(),
+    })
+}
+
+fn explicit_discriminator(item: &Value, name: &str) -> Result<DiscriminatorSpec, AnchorError> {
+    let bytes = item["discriminator"].as_array()
+        .and_then(|bytes| bytes.iter().map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok())).collect::<Option<Vec<u8>>>())
+        .ok_or_else(|| AnchorError::IdlParseError(format!("Invalid discriminator for {}", name)))?;
+    Ok(DiscriminatorSpec {
+        data_offset: bytes.len(),
+        checks: vec![DiscriminatorCheck::Bytes { offset: 0, bytes }],
+    })
+}
+
+fn anchor_spec_account(account: &Value) -> Value {
+    match account["accounts"].as_array() {
+        Some(nested) => json!({ "name": account["name"], "accounts": nested.iter().map(anchor_spec_account).collect::<Vec<_>>() }),
+        None => json!({
+            "name": account["name"],
+            "isMut": account["writable"].as_bool().unwrap_or(false),
+            "isSigner": account["signer"].as_bool().unwrap_or(false),
+            "isOptional": account["optional"].as_bool().unwrap_or(false),
+        }),
+    }
+}
+
+fn anchor_spec_type_definition(ty: &Value, owner: &str) -> Result<Value, AnchorError> {
+    let convert_fields = |fields: &Value| -> Result<Value, AnchorError> {
+        let mut out = Vec::new();
+        for field in fields.as_array().into_iter().flatten() {
+            out.push(match field.get("type") {
+                Some(inner) => json!({ "name": field["name"], "type": anchor_spec_type(inner, owner)? }),
+                None => anchor_spec_type(field, owner)?,
+            });
+        }
+        Ok(Value::Array(out))
+    };
+    match ty["kind"].as_str() {
+        Some("struct") => Ok(json!({ "kind": "struct", "fields": convert_fields(&ty["fields"])? })),
+        Some("enum") => {
+            let mut variants = Vec::new();
+            for variant in ty["variants"].as_array().into_iter().flatten() {
+                variants.push(match variant.get("fields") {
+                    Some(fields) => json!({ "name": variant["name"], "fields": convert_fields(fields)? }),
+                    None => json!({ "name": variant["name"] }),
+                });
+            }
+            Ok(json!({ "kind": "enum", "variants": variants }))
+        }
+        // Aliases become single-field structs with the same encoding, as for Codama
+        Some("type") => Ok(json!({ "kind": "struct", "fields": [{ "name": "value", "type": anchor_spec_type(&ty["alias"], owner)? }] })),
+        other => Err(AnchorError::IdlParseError(format!("Unsupported type kind {:?} in {}", other, owner))),
+    }
+}
+
+fn anchor_spec_type(ty: &Value, owner: &str) -> Result<Value, AnchorError> {
+    let unsupported = || AnchorError::IdlParseError(format!("Unsupported type {} in {}", ty, owner));
+    if let Some(name) = ty.as_str() {
+        return Ok(json!(if name == "pubkey" { "publicKey" } else { name }));
+    }
+    let (kind, inner) = ty.as_object().and_then(|obj| obj.iter().next()).ok_or_else(unsupported)?;
+    match kind.as_str() {
+        "option" | "vec" => Ok(json!({ kind.as_str(): anchor_spec_type(inner, owner)? })),
+        "array" => {
+            // Lengths may also be `{ "generic": .. }`, which has no fixed value
+            let len = inner[1].as_u64().ok_or_else(unsupported)?;
+            Ok(json!({ "array": [anchor_spec_type(&inner[0], owner)?, len] }))
+        }
+        "defined" => {
+            if inner["generics"].as_array().is_some_and(|g| !g.is_empty()) {
+                return Err(unsupported());
+            }
+            Ok(json!({ "defined": inner["name"].as_str().or(inner.as_str()).ok_or_else(unsupported)? }))
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    sighash("account", name)
+}
+
+pub fn instruction_discriminator(name: &str) -> [u8; 8] {
+    sighash("global", &to_snake_case(name))
+}
+
+fn sighash(namespace: &str, name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("{}:{}", namespace, name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// Shank IDLs follow the legacy Anchor layout but use a few extra type forms and
+// no hashed discriminators: instructions carry an explicit u8 `discriminant`,
+// accounts usually start with a `key` enum field.
+fn from_shank(mut raw: Value) -> Result<ProgramIdl, AnchorError> {
+    let mut synthetic = Vec::new();
+    for section in ["instructions", "accounts", "types"] {
+        if let Some(items) = raw[section].as_array_mut() {
+            for item in items {
+                rewrite_shank_item(item, &mut synthetic)?;
+            }
+        }
+    }
+    if let Some(types) = raw["types"].as_array_mut() {
+        types.extend(synthetic);
+    } else {
+        raw["types"] = Value::Array(synthetic);
+    }
+
+    let instruction_tags: Vec<Option<u8>> = raw["instructions"].as_array()
+        .map(|ixs| ixs.iter().map(|ix| ix["discriminant"]["value"].as_u64().map(|v| v as u8)).collect())
+        .unwrap_or_default();
+
+    let idl: Idl = serde_json::from_value(raw)
+        .map_err(|e| AnchorError::IdlParseError(e.to_string()))?;
+
+    let instructions = idl.instructions.iter().enumerate()
+        .map(|(i, ix)| {
+            // Without an explicit discriminant Shank falls back to the enum variant index
+            let tag = instruction_tags.get(i).copied().flatten().unwrap_or(i as u8);
+            (ix.name.clone(), DiscriminatorSpec {
+                checks: vec![DiscriminatorCheck::Bytes { offset: 0, bytes: vec![tag] }],
+                data_offset: 1,
+            })
+        })
+        .collect();
+
+    let accounts = idl.accounts.iter()
+        .map(|account| (account.name.clone(), shank_account_spec(&idl, account)))
+        .collect();
+
+    Ok(ProgramIdl { idl, format: IdlFormat::Shank, accounts, instructions, serialization: Vec::new(), enum_tags: Vec::new() })
+}
+
+fn rewrite_shank_item(item: &mut Value, synthetic: &mut Vec<Value>) -> Result<(), AnchorError> {
+    let name = item["name"].as_str().unwrap_or_default().to_string();
+    if let Some(args) = item["args"].as_array_mut() {
+        for arg in args {
+            rewrite_shank_field(arg, &name, synthetic)?;
+        }
+    }
+    if let Some(fields) = item["type"]["fields"].as_array_mut() {
+        for field in fields {
+            rewrite_shank_field(field, &name, synthetic)?;
+        }
+    }
+    if let Some(variants) = item["type"]["variants"].as_array_mut() {
+        for variant in variants {
+            let variant_name = format!("{}{}", name, variant["name"].as_str().unwrap_or_default());
+            if let Some(fields) = variant["fields"].as_array_mut() {
+                for field in fields {
+                    if field.get("type").is_some() {
+                        rewrite_shank_field(field, &variant_name, synthetic)?;
+                    } else {
+                        let ty = field.clone();
+                        *field = rewrite_shank_type(&ty, &variant_name, synthetic)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn rewrite_shank_field(field: &mut Value, owner: &str, synthetic: &mut Vec<Value>) -> Result<(), AnchorError> {
+    let hint = format!("{}{}", owner, upper_first(field["name"].as_str().unwrap_or_default()));
+    let ty = field["type"].clone();
+    field["type"] = rewrite_shank_type(&ty, &hint, synthetic)?;
+    Ok(())
+}
+
+// Tuples become synthetic structs with positional field names; maps and sets
+// share Borsh's u32-prefixed sequence encoding, so they become vectors.
+fn rewrite_shank_type(ty: &Value, hint: &str, synthetic: &mut Vec<Value>) -> Result<Value, AnchorError> {
+    let obj = match ty.as_object() {
+        Some(obj) => obj,
+        None => return Ok(ty.clone()),
+    };
+    let (kind, inner) = obj.iter().next()
+        .ok_or_else(|| AnchorError::IdlParseError(format!("Empty type in {}", hint)))?;
+
+    match kind.as_str() {
+        "option" | "vec" => Ok(json!({ kind.as_str(): rewrite_shank_type(inner, hint, synthetic)? })),
+        "array" => {
+            let item = rewrite_shank_type(&inner[0], hint, synthetic)?;
+            Ok(json!({ "array": [item, inner[1].clone()] }))
+        }
+        "tuple" => {
+            let items = inner.as_array()
+                .ok_or_else(|| AnchorError::IdlParseError(format!("Invalid tuple type in {}", hint)))?;
+            let mut fields = Vec::with_capacity(items.len());
+            for (i, item) in items.iter().enumerate() {
+                fields.push(json!({
+                    "name": i.to_string(),
+                    "type": rewrite_shank_type(item, &format!("{}{}", hint, i), synthetic)?,
+                }));
+            }
+            Ok(push_synthetic_struct(synthetic, hint, fields))
+        }
+        "hashMap" | "bTreeMap" => {
+            let pair = json!({ "tuple": [inner[0].clone(), inner[1].clone()] });
+            let entry = rewrite_shank_type(&pair, &format!("{}Entry", hint), synthetic)?;
+            Ok(json!({ "vec": entry }))
+        }
+        "hashSet" | "bTreeSet" => Ok(json!({ "vec": rewrite_shank_type(inner, hint, synthetic)? })),
+        "defined" => Ok(ty.clone()),
+        other => Err(AnchorError::IdlParseError(format!("Unsupported Shank type `{}` in {}", other, hint))),
+    }
+}
+
+fn push_synthetic_struct(synthetic: &mut Vec<Value>, name: &str, fields: Vec<Value>) -> Value {
+    synthetic.push(json!({
+        "name": name,
+        "type": { "kind": "struct", "fields": fields },
+    }));
+    json!({ "defined": name })
+}
+
+fn shank_account_spec(idl: &Idl, account: &IdlTypeDefinition) -> DiscriminatorSpec {
+    let fields = match &account.ty {
+        IdlTypeDefinitionTy::Struct { fields } => fields,
+        _ => return DiscriminatorSpec { checks: Vec::new(), data_offset: 0 },
+    };
+
+    // Metaplex-style `key: Key` enums name their variants after the account,
+    // optionally with a version suffix (`MetadataV1`, `MasterEditionV2`)
+    if let Some(IdlType::Defined(key_type)) = fields.first().filter(|f| f.name == "key").map(|f| &f.ty) {
+        let variants = idl.types.iter()
+            .find(|t| &t.name == key_type)
+            .and_then(|t| match &t.ty {
+                IdlTypeDefinitionTy::Enum { variants } => Some(variants),
+                _ => None,
+            });
+        if let Some(variants) = variants {
+            let tag = variants.iter().position(|v| {
+                v.name == account.name || v.name.strip_prefix(account.name.as_str())
+                    .is_some_and(|rest| rest.starts_with('V') && rest[1..].chars().all(|c| c.is_ascii_digit()))
+            });
+            if let Some(tag) = tag {
+                return DiscriminatorSpec {
+                    checks: vec![DiscriminatorCheck::Bytes { offset: 0, bytes: vec![tag as u8] }],
+                    data_offset: 0,
+                };
+            }
+        }
+    }
+
+    let checks = struct_size(idl, fields.iter().map(|f| &f.ty))
+        .map(|size| vec![DiscriminatorCheck::Size(size)])
+        .unwrap_or_default();
+    DiscriminatorSpec { checks, data_offset: 0 }
+}
+
+fn struct_size<'a>(idl: &Idl, types: impl Iterator<Item = &'a IdlType>) -> Option<usize> {
+    let mut size = 0;
+    for ty in types {
+        size += fixed_size(idl, ty)?;
+    }
+    Some(size)
+}
+
+// Borsh size of a type, or None when it depends on the data
+pub fn fixed_size(idl: &Idl, ty: &IdlType) -> Option<usize> {
+    match ty {
+        IdlType::Bool | IdlType::U8 | IdlType::I8 => Some(1),
+        IdlType::U16 | IdlType::I16 => Some(2),
+        IdlType::U32 | IdlType::I32 | IdlType::F32 => Some(4),
+        IdlType::U64 | IdlType::I64 | IdlType::F64 => Some(8),
+        IdlType::U128 | IdlType::I128 => Some(16),
+        IdlType::U256 | IdlType::I256 | IdlType::PublicKey => Some(32),
+        IdlType::Array(inner, len) => fixed_size(idl, inner).map(|s| s * len),
+        IdlType::Defined(name) => {
+            let def = idl.types.iter().chain(idl.accounts.iter()).find(|t| &t.name == name)?;
+            match &def.ty {
+                IdlTypeDefinitionTy::Struct { fields } => struct_size(idl, fields.iter().map(|f| &f.ty)),
+                IdlTypeDefinitionTy::Enum { variants } => {
+                    // Only unit-only enums have a constant size
+                    if variants.iter().all(|v| v.fields.is_none()) { Some(1) } else { None }
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+// Codama IDLs are a tree of typed nodes; we lower them into the legacy Anchor
+// model so everything downstream decodes through the same code path.
+fn from_codama(raw: &Value) -> Result<ProgramIdl, AnchorError> {
+    let program = if raw["kind"] == "rootNode" { &raw["program"] } else { raw };
+    let mut converter = CodamaConverter {
+        defined: program["definedTypes"].as_array().cloned().unwrap_or_default(),
+        synthetic: Vec::new(),
+        enum_tags: Vec::new(),
+    };
+
+    let mut types = Vec::new();
+    for def in converter.defined.clone() {
+        let name = node_name(&def)?;
+        let ty = converter.type_definition(&def["type"], &name)?;
+        types.push(json!({ "name": name, "type": ty }));
+    }
+
+    let mut accounts = Vec::new();
+    let mut account_specs = Vec::new();
+    for account in program["accounts"].as_array().cloned().unwrap_or_default() {
+        let name = node_name(&account)?;
+        let ty = converter.type_definition(&account["data"], &name)?;
+        let fields = account["data"]["fields"].as_array().cloned().unwrap_or_default();
+        account_specs.push((name.clone(), converter.discriminator_spec(&account["discriminators"], &fields)?));
+        accounts.push(json!({ "name": name, "type": ty }));
+    }
+
+    let mut instructions = Vec::new();
+    let mut instruction_specs = Vec::new();
+    for ix in program["instructions"].as_array().cloned().unwrap_or_default() {
+        let name = node_name(&ix)?;
+        let arguments = ix["arguments"].as_array().cloned().unwrap_or_default();
+        let mut args = Vec::with_capacity(arguments.len());
+        for arg in &arguments {
+            let arg_name = node_name(arg)?;
+            let hint = format!("{}{}", upper_first(&name), upper_first(&arg_name));
+            args.push(json!({ "name": arg_name, "type": converter.convert_type(&arg["type"], &hint)? }));
+        }
+        let ix_accounts: Vec<Value> = ix["accounts"].as_array().cloned().unwrap_or_default().iter()
+            .map(|a| json!({
+                "name": a["name"],
+                "isMut": a["isWritable"].as_bool().unwrap_or(false),
+                "isSigner": a["isSigner"].as_bool().unwrap_or(false),
+            }))
+            .collect();
+        instruction_specs.push((name.clone(), converter.discriminator_spec(&ix["discriminators"], &arguments)?));
+        instructions.push(json!({ "name": name, "accounts": ix_accounts, "args": args }));
+    }
+
+    let errors: Vec<Value> = program["errors"].as_array().cloned().unwrap_or_default().iter()
+        .map(|e| json!({ "code": e["code"], "name": e["name"], "msg": e["message"] }))
+        .collect();
+
+    types.extend(converter.synthetic);
+    let idl_value = json!({
+        "version": program["version"].as_str().unwrap_or("0.0.0"),
+        "name": program["name"].as_str().unwrap_or_default(),
+        "instructions": instructions,
+        "accounts": accounts,
+        "types": types,
+        "errors": errors,
+        "metadata": { "origin": "codama", "address": program["publicKey"] },
+    });
+    let idl: Idl = serde_json::from_value(idl_value)
+        .map_err(|e| AnchorError::IdlParseError(e.to_string()))?;
+
+    Ok(ProgramIdl {
+        idl,
+        format: IdlFormat::Codama,
+        accounts: account_specs,
+        instructions: instruction_specs,
+        serialization: Vec::new(),
+        enum_tags: converter.enum_tags,
+    })
+}
+
+struct CodamaConverter {
+    defined: Vec<Value>,
+    synthetic: Vec<Value>,
+    enum_tags: Vec<(String, usize)>,
+}
+
+impl CodamaConverter {
+    fn type_definition(&mut self, node: &Value, hint: &str) -> Result<Value, AnchorError> {
+        match node["kind"].as_str() {
+            Some("structTypeNode") => Ok(json!({ "kind": "struct", "fields": self.struct_fields(node, hint)? })),
+            Some("enumTypeNode") => {
+                let tag_len = match node["size"]["format"].as_str() {
+                    None | Some("u8") => 1,
+                    Some("u16") => 2,
+                    Some("u32") => 4,
+                    Some("u64") => 8,
+                    other => return Err(AnchorError::IdlParseError(format!("Unsupported Codama enum size {:?} in {}", other, hint))),
+                };
+                if tag_len != 1 {
+                    self.enum_tags.push((hint.to_string(), tag_len));
+                }
+                let mut variants = Vec::new();
+                for variant in node["variants"].as_array().cloned().unwrap_or_default() {
+                    let name = node_name(&variant)?;
+                    let variant_hint = format!("{}{}", hint, upper_first(&name));
+                    match variant["kind"].as_str() {
+                        Some("enumEmptyVariantTypeNode") => variants.push(json!({ "name": name })),
+                        Some("enumStructVariantTypeNode") => {
+                            let fields = self.struct_fields(&variant["struct"], &variant_hint)?;
+                            variants.push(json!({ "name": name, "fields": fields }));
+                        }
+                        Some("enumTupleVariantTypeNode") => {
+                            let mut items = Vec::new();
+                            for (i, item) in variant["tuple"]["items"].as_array().cloned().unwrap_or_default().iter().enumerate() {
+                                items.push(self.convert_type(item, &format!("{}{}", variant_hint, i))?);
+                            }
+                            variants.push(json!({ "name": name, "fields": items }));
+                        }
+                        other => return Err(AnchorError::IdlParseError(format!("Unsupported Codama enum variant {:?} in {}", other, hint))),
+                    }
+                }
+                Ok(json!({ "kind": "enum", "variants": variants }))
+            }
+            // Aliases (`type Foo = u64`) become single-field structs with the same encoding
+            _ => Ok(json!({ "kind": "struct", "fields": [{ "name": "value", "type": self.convert_type(node, hint)? }] })),
+        }
+    }
+
+    fn struct_fields(&mut self, node: &Value, hint: &str) -> Result<Vec<Value>, AnchorError> {
+        let mut fields = Vec::new();
+        for field in node["fields"].as_array().cloned().unwrap_or_default() {
+            let name = node_name(&field)?;
+            let ty = self.convert_type(&field["type"], &format!("{}{}", hint, upper_first(&name)))?;
+            fields.push(json!({ "name": name, "type": ty }));
+        }
+        Ok(fields)
+    }
+
+    fn convert_type(&mut self, node: &Value, hint: &str) -> Result<Value, AnchorError> {
+        let unsupported = || AnchorError::IdlParseError(format!("Unsupported Codama type {} in {}", node, hint));
+        match node["kind"].as_str().ok_or_else(unsupported)? {
+            "numberTypeNode" => {
+                let format = node["format"].as_str().ok_or_else(unsupported)?;
+                if format == "shortU16" || node["endian"] == "be" {
+                    return Err(unsupported());
+                }
+                Ok(Value::String(format.to_string()))
+            }
+            "booleanTypeNode" => match node["size"]["format"].as_str() {
+                None | Some("u8") => Ok(json!("bool")),
+                _ => Err(unsupported()),
+            },
+            "publicKeyTypeNode" => Ok(json!("publicKey")),
+            "amountTypeNode" | "solAmountTypeNode" | "dateTimeTypeNode" => self.convert_type(&node["number"], hint),
+            "sizePrefixTypeNode" => {
+                if node["prefix"]["format"] != "u32" {
+                    return Err(unsupported());
+                }
+                match node["type"]["kind"].as_str() {
+                    Some("stringTypeNode") => Ok(json!("string")),
+                    Some("bytesTypeNode") => Ok(json!("bytes")),
+                    _ => Err(unsupported()),
+                }
+            }
+            "fixedSizeTypeNode" => {
+                let size = node["size"].as_u64().ok_or_else(unsupported)?;
+                match node["type"]["kind"].as_str() {
+                    Some("bytesTypeNode") | Some("stringTypeNode") => Ok(json!({ "array": ["u8", size] })),
+                    _ => Err(unsupported()),
+                }
+            }
+            "optionTypeNode" => {
+                let prefix = node["prefix"]["format"].as_str().unwrap_or("u8");
+                if prefix != "u8" || node["fixed"].as_bool().unwrap_or(false) {
+                    return Err(unsupported());
+                }
+                Ok(json!({ "option": self.convert_type(&node["item"], hint)? }))
+            }
+            "arrayTypeNode" | "setTypeNode" => {
+                let item = self.convert_type(&node["item"], hint)?;
+                self.sized_sequence(item, &node["count"], hint)
+            }
+            "mapTypeNode" => {
+                let key = self.convert_type(&node["key"], &format!("{}Key", hint))?;
+                let value = self.convert_type(&node["value"], &format!("{}Value", hint))?;
+                let entry_name = format!("{}Entry", hint);
+                let entry = push_synthetic_struct(&mut self.synthetic, &entry_name, vec![
+                    json!({ "name": "0", "type": key }),
+                    json!({ "name": "1", "type": value }),
+                ]);
+                self.sized_sequence(entry, &node["count"], hint)
+            }
+            "tupleTypeNode" => {
+                let mut fields = Vec::new();
+                for (i, item) in node["items"].as_array().cloned().unwrap_or_default().iter().enumerate() {
+                    fields.push(json!({ "name": i.to_string(), "type": self.convert_type(item, &format!("{}{}", hint, i))? }));
+                }
+                Ok(push_synthetic_struct(&mut self.synthetic, hint, fields))
+            }
+            "structTypeNode" | "enumTypeNode" => {
+                let ty = self.type_definition(node, hint)?;
+                self.synthetic.push(json!({ "name": hint, "type": ty }));
+                Ok(json!({ "defined": hint }))
+            }
+            "definedTypeLinkNode" => Ok(json!({ "defined": node_name(node)? })),
+            _ => Err(unsupported()),
+        }
+    }
+
+    fn sized_sequence(&self, item: Value, count: &Value, hint: &str) -> Result<Value, AnchorError> {
+        match count["kind"].as_str() {
+            Some("prefixedCountNode") if count["prefix"]["format"] == "u32" => Ok(json!({ "vec": item })),
+            Some("fixedCountNode") => Ok(json!({ "array": [item, count["value"]] })),
+            _ => Err(AnchorError::IdlParseError(format!("Unsupported Codama sequence count {} in {}", count, hint))),
+        }
+    }
+
+    fn discriminator_spec(&self, discriminators: &Value, fields: &[Value]) -> Result<DiscriminatorSpec, AnchorError> {
+        let mut checks = Vec::new();
+        for node in discriminators.as_array().cloned().unwrap_or_default() {
+            let offset = node["offset"].as_u64().unwrap_or(0) as usize;
+            match node["kind"].as_str() {
+                Some("sizeDiscriminatorNode") => {
+                    let size = node["size"].as_u64()
+                        .ok_or_else(|| AnchorError::IdlParseError("Size discriminator without size".to_string()))?;
+                    checks.push(DiscriminatorCheck::Size(size as usize));
+                }
+                Some("constantDiscriminatorNode") => {
+                    let bytes = self.value_bytes(&node["constant"]["type"], &node["constant"]["value"])?;
+                    checks.push(DiscriminatorCheck::Bytes { offset, bytes });
+                }
+                Some("fieldDiscriminatorNode") => {
+                    let name = node_name(&node)?;
+                    let field = fields.iter().find(|f| f["name"] == name.as_str())
+                        .ok_or_else(|| AnchorError::IdlParseError(format!("Discriminator field {} not found", name)))?;
+                    let bytes = self.value_bytes(&field["type"], &field["defaultValue"])?;
+                    checks.push(DiscriminatorCheck::Bytes { offset, bytes });
+                }
+                other => return Err(AnchorError::IdlParseError(format!("Unsupported Codama discriminator {:?}", other))),
+            }
+        }
+        // Codama discriminators are regular fields, so decoding starts at byte 0
+        Ok(DiscriminatorSpec { checks, data_offset: 0 })
+    }
+
+    fn value_bytes(&self, ty: &Value, value: &Value) -> Result<Vec<u8>, AnchorError> {
+        let invalid = || AnchorError::IdlParseError(format!("Unsupported discriminator value {} of type {}", value, ty));
+        match value["kind"].as_str() {
+            Some("numberValueNode") => {
+                let format = ty["format"].as_str().unwrap_or("u8");
+                let number = value["number"].as_u64().ok_or_else(invalid)?;
+                number_bytes(format, number).ok_or_else(invalid)
+            }
+            Some("bytesValueNode") => {
+                let data = value["data"].as_str().ok_or_else(invalid)?;
+                match value["encoding"].as_str() {
+                    Some("base16") => decode_hex(data).ok_or_else(invalid),
+                    Some("base58") => bs58::decode(data).into_vec().map_err(|_| invalid()),
+                    Some("base64") => base64::decode(data).map_err(|_| invalid()),
+                    Some("utf8") => Ok(data.as_bytes().to_vec()),
+                    _ => Err(invalid()),
+                }
+            }
+            Some("enumValueNode") => {
+                let enum_name = value["enum"]["name"].as_str().ok_or_else(invalid)?;
+                let variant = value["variant"].as_str().ok_or_else(invalid)?;
+                let def = self.defined.iter().find(|d| d["name"] == enum_name).ok_or_else(invalid)?;
+                let index = def["type"]["variants"].as_array()
+                    .and_then(|vs| vs.iter().position(|v| v["name"] == variant))
+                    .ok_or_else(invalid)?;
+                let format = def["type"]["size"]["format"].as_str().unwrap_or("u8");
+                number_bytes(format, index as u64).ok_or_else(invalid)
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+fn number_bytes(format: &str, number: u64) -> Option<Vec<u8>> {
+    let len = match format {
+        "u8" | "i8" => 1,
+        "u16" | "i16" => 2,
+        "u32" | "i32" => 4,
+        "u64" | "i64" => 8,
+        "u128" | "i128" => 16,
+        _ => return None,
+    };
+    Some((number as u128).to_le_bytes()[..len].to_vec())
+}
+
+pub(crate) fn decode_hex(data: &str) -> Option<Vec<u8>> {
+    // Slicing by byte offset panics inside a multi-byte character
+    if !data.len().is_multiple_of(2) || !data.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..data.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&data[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn node_name(node: &Value) -> Result<String, AnchorError> {
+    node["name"].as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| AnchorError::IdlParseError(format!("Node without name: {}", node)))
+}
+
+fn upper_first(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -1,61 +1,572 @@
-use anchor_syn::idl::{Idl, IdlType};
-use serde_json::Value;
+use anchor_syn::idl::{EnumFields, IdlType, IdlTypeDefinitionTy};
+use serde_json::{json, Value};
 use thiserror::Error;
 
+mod encode;
+pub mod errors;
+pub mod idl;
+mod reader;
+pub mod registry;
+pub mod token;
+pub mod zero_copy;
+
+pub use errors::IdlError;
+pub use idl::{account_discriminator, instruction_discriminator, IdlFormat, ProgramIdl};
+pub use registry::{IdlRegistry, IdlVersion, IdlVersionRange};
+pub use token::TokenProgram;
+
+use encode::{discriminator_prefix, encode_fields, encode_idl_type};
+use idl::fixed_size;
+use reader::ByteReader;
+use zero_copy::{encode_c_type, parse_c_type, Serialization};
+
 #[derive(Error, Debug)]
 pub enum AnchorError {
     #[error("IDL parsing error: {0}")]
     IdlParseError(String),
     #[error("Account data parsing error: {0}")]
     AccountDataParseError(String),
+    #[error("Encoding error: {0}")]
+    EncodeError(String),
 }
 
 pub struct AnchorParser {
-    idls: std::collections::HashMap<String, Idl>,
+    idls: IdlRegistry,
+    builtins: std::collections::HashMap<String, TokenProgram>,
+}
+
+impl Default for AnchorParser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AnchorParser {
     pub fn new() -> Self {
         Self {
-            idls: std::collections::HashMap::new(),
+            idls: IdlRegistry::new(),
+            builtins: std::collections::HashMap::new(),
+        }
+    }
+
+    pub fn register_builtin(&mut self, program_id: &str) -> bool {
+        match TokenProgram::from_program_id(program_id) {
+            Some(program) => {
+                self.builtins.insert(program_id.to_string(), program);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn has_decoder(&self, program_id: &str) -> bool {
+        self.builtins.contains_key(program_id) || self.idls.contains(program_id)
+    }
+
+    pub fn decode_account(&self, program_id: &str, data: &[u8]) -> Result<(String, Value), AnchorError> {
+        self.decode_account_with(program_id, None, data)
+    }
+
+    pub fn decode_account_at_slot(&self, program_id: &str, slot: u64, data: &[u8]) -> Result<(String, Value), AnchorError> {
+        self.decode_account_with(program_id, Some(slot), data)
+    }
+
+    fn decode_account_with(&self, program_id: &str, slot: Option<u64>, data: &[u8]) -> Result<(String, Value), AnchorError> {
+        if let Some(program) = self.builtins.get(program_id) {
+            return program.decode_account(data);
+        }
+
+        let program = self.program_idl(program_id, slot)?;
+        if let Some((name, spec)) = program.accounts.iter().find(|(_, spec)| spec.matches(data)) {
+            let value = self.decode_struct(program, name, &data[spec.data_offset..])?;
+            return Ok((name.clone(), value));
+        }
+
+        // Some native programs give accounts no discriminator at all, so the
+        // only option left is to try each layout in turn. A layout only fits
+        // if it consumes the data exactly, and only one may fit.
+        let mut fits = program.accounts.iter()
+            .filter(|(_, spec)| spec.is_empty())
+            .filter_map(|(name, spec)| {
+                let data = data.get(spec.data_offset..)?;
+                self.decode_struct_exact(program, name, data).ok().map(|value| (name, value))
+            });
+        match (fits.next(), fits.next()) {
+            (Some((name, value)), None) => Ok((name.clone(), value)),
+            (Some((first, _)), Some((second, _))) => Err(AnchorError::AccountDataParseError(format!(
+                "Account data for program {} is ambiguous between {} and {}", program_id, first, second))),
+            _ => Err(AnchorError::AccountDataParseError(format!("No account type in IDL matches data for program {}", program_id))),
         }
     }
 
     pub fn add_idl(&mut self, program_id: &str, idl_json: &str) -> Result<(), AnchorError> {
-        let idl: Idl = serde_json::from_str(idl_json)
-            .map_err(|e| AnchorError::IdlParseError(e.to_string()))?;
-        self.idls.insert(program_id.to_string(), idl);
-        Ok(())
+        self.add_idl_version(program_id, 0, idl_json)
     }
 
-    pub fn parse_account_data(&self, program_id: &str, account_type: &str, data: &[u8]) -> Result<Value, AnchorError> {
-        let idl = self.idls.get(program_id)
-            .ok_or_else(|| AnchorError::IdlParseError(format!("IDL not found for program {}", program_id)))?;
+    pub fn add_idl_version(&mut self, program_id: &str, start_slot: u64, idl_json: &str) -> Result<(), AnchorError> {
+        self.idls.insert(program_id, start_slot, idl_json)
+    }
+
+    pub fn add_parsed_idl_version(&mut self, program_id: &str, version: IdlVersion) {
+        self.idls.insert_version(program_id, version)
+    }
 
-        let account = idl.accounts.iter()
-            .find(|a| a.name == account_type)
+    pub fn idl_versions(&self, program_id: &str) -> Vec<IdlVersionRange> {
+        self.idls.versions(program_id)
+    }
+
+    pub fn parse_account_data(&self, program_id: &str, account_type: &str, data: &[u8]) -> Result<Value, AnchorError> {
+        let program = self.program_idl(program_id, None)?;
+        let spec = program.account_spec(account_type)
             .ok_or_else(|| AnchorError::AccountDataParseError(format!("Account type {} not found in IDL", account_type)))?;
+        if data.len() < spec.data_offset {
+            return Err(AnchorError::AccountDataParseError("Account data shorter than discriminator".to_string()));
+        }
+        self.decode_struct(program, account_type, &data[spec.data_offset..])
+    }
 
-        let mut parser = borsh::BorshDeserialize::deserialize(&mut &data[8..])  // Skip the 8-byte discriminator
-            .map_err(|e| AnchorError::AccountDataParseError(e.to_string()))?;
+    pub fn parse_instruction(&self, program_id: &str, data: &[u8]) -> Result<(String, Value), AnchorError> {
+        self.parse_instruction_with(program_id, None, data)
+    }
 
+    pub fn parse_instruction_at_slot(&self, program_id: &str, slot: u64, data: &[u8]) -> Result<(String, Value), AnchorError> {
+        self.parse_instruction_with(program_id, Some(slot), data)
+    }
+
+    fn parse_instruction_with(&self, program_id: &str, slot: Option<u64>, data: &[u8]) -> Result<(String, Value), AnchorError> {
+        let program = self.program_idl(program_id, slot)?;
+        let (name, spec) = program.instructions.iter()
+            .find(|(_, spec)| spec.matches(data))
+            .ok_or_else(|| AnchorError::AccountDataParseError(format!("No instruction in IDL matches data for program {}", program_id)))?;
+        let instruction = program.idl.instructions.iter()
+            .find(|ix| &ix.name == name)
+            .ok_or_else(|| AnchorError::IdlParseError(format!("Instruction {} not found in IDL", name)))?;
+
+        let mut reader = ByteReader::new(&data[spec.data_offset..]);
         let mut result = serde_json::Map::new();
-        for field in &account.fields {
-            let value = self.parse_idl_type(&field.ty, &mut parser)?;
-            result.insert(field.name.clone(), value);
+        for arg in &instruction.args {
+            let value = self.parse_idl_type(program, &arg.ty, &mut reader)?;
+            result.insert(arg.name.clone(), value);
+        }
+        Ok((name.clone(), Value::Object(result)))
+    }
+
+    pub fn encode_account(&self, program_id: &str, account_type: &str, value: &Value) -> Result<Vec<u8>, AnchorError> {
+        let program = self.program_idl(program_id, None)?;
+        let spec = program.account_spec(account_type)
+            .ok_or_else(|| AnchorError::EncodeError(format!("Account type {} not found in IDL", account_type)))?;
+        let mut out = discriminator_prefix(spec);
+        let ty = IdlType::Defined(account_type.to_string());
+        match program.serialization(account_type) {
+            Serialization::Borsh => encode_idl_type(program, &ty, value, &mut out)?,
+            Serialization::Bytemuck { .. } => encode_c_type(program, &ty, value, &mut out)?,
+        }
+        Ok(out)
+    }
+
+    pub fn encode_instruction(&self, program_id: &str, instruction: &str, args: &Value) -> Result<Vec<u8>, AnchorError> {
+        let program = self.program_idl(program_id, None)?;
+        let spec = program.instruction_spec(instruction)
+            .ok_or_else(|| AnchorError::EncodeError(format!("Instruction {} not found in IDL", instruction)))?;
+        let ix = program.idl.instructions.iter()
+            .find(|ix| ix.name == instruction)
+            .ok_or_else(|| AnchorError::EncodeError(format!("Instruction {} not found in IDL", instruction)))?;
+        let mut out = discriminator_prefix(spec);
+        encode_fields(program, &ix.args, args, &mut out)?;
+        Ok(out)
+    }
+
+    pub fn encode_type(&self, program_id: &str, type_name: &str, value: &Value) -> Result<Vec<u8>, AnchorError> {
+        let program = self.program_idl(program_id, None)?;
+        let mut out = Vec::new();
+        encode_idl_type(program, &IdlType::Defined(type_name.to_string()), value, &mut out)?;
+        Ok(out)
+    }
+
+    // Accounts are often allocated larger than their serialized size, so the
+    // encoding only has to match up to the zero padding at the end
+    pub fn verify_account_encoding(&self, program_id: &str, account_type: &str, value: &Value, data: &[u8]) -> Result<bool, AnchorError> {
+        let encoded = self.encode_account(program_id, account_type, value)?;
+        Ok(data.len() >= encoded.len()
+            && data[..encoded.len()] == encoded[..]
+            && data[encoded.len()..].iter().all(|b| *b == 0))
+    }
+
+    pub fn decode_error(&self, program_id: &str, slot: Option<u64>, code: u32) -> Option<IdlError> {
+        let program = self.program_idl(program_id, slot).ok()?;
+        errors::lookup_error(program, code)
+    }
+
+    pub fn idl_format(&self, program_id: &str) -> Option<IdlFormat> {
+        self.idls.latest(program_id).map(|v| v.program.format)
+    }
+
+    fn program_idl(&self, program_id: &str, slot: Option<u64>) -> Result<&ProgramIdl, AnchorError> {
+        let version = match slot {
+            Some(slot) => self.idls.at_slot(program_id, slot),
+            None => self.idls.latest(program_id),
+        };
+        version
+            .map(|v| &v.program)
+            .ok_or_else(|| AnchorError::IdlParseError(format!("IDL not found for program {}{}", program_id,
+                slot.map(|s| format!(" at slot {}", s)).unwrap_or_default())))
+    }
+
+    fn decode_struct(&self, program: &ProgramIdl, type_name: &str, data: &[u8]) -> Result<Value, AnchorError> {
+        self.read_struct(program, type_name, &mut ByteReader::new(data))
+    }
+
+    fn decode_struct_exact(&self, program: &ProgramIdl, type_name: &str, data: &[u8]) -> Result<Value, AnchorError> {
+        let mut reader = ByteReader::new(data);
+        let value = self.read_struct(program, type_name, &mut reader)?;
+        if reader.remaining() != 0 {
+            return Err(AnchorError::AccountDataParseError(format!("{} leaves {} bytes unread", type_name, reader.remaining())));
         }
+        Ok(value)
+    }
 
-        Ok(Value::Object(result))
+    fn read_struct(&self, program: &ProgramIdl, type_name: &str, reader: &mut ByteReader) -> Result<Value, AnchorError> {
+        let ty = IdlType::Defined(type_name.to_string());
+        match program.serialization(type_name) {
+            Serialization::Borsh => self.parse_idl_type(program, &ty, reader),
+            Serialization::Bytemuck { .. } => parse_c_type(program, &ty, reader),
+        }
     }
 
-    fn parse_idl_type(&self, ty: &IdlType, parser: &mut borsh::maybestd::io::Cursor<&[u8]>) -> Result<Value, AnchorError> {
-        match ty {
-            IdlType::Bool => Ok(Value::Bool(borsh::BorshDeserialize::deserialize(parser)
-                .map_err(|e| AnchorError::AccountDataParseError(e.to_string()))?)),
-            IdlType::U8 => Ok(Value::Number(borsh::BorshDeserialize::deserialize(parser)
-                .map_err(|e| AnchorError::AccountDataParseError(e.to_string()))?.into())),
-            // Implement other types...
-            _ => Err(AnchorError::AccountDataParseError(format!("Unsupported IDL type: {:?}", ty))),
+    fn parse_idl_type(&self, program: &ProgramIdl, ty: &IdlType, reader: &mut ByteReader) -> Result<Value, AnchorError> {
+        let idl = &program.idl;
+        if let Some(value) = parse_scalar(ty, reader)? {
+            return Ok(value);
         }
+        let value = match ty {
+            IdlType::String => Value::String(reader.read_string()?),
+            IdlType::Bytes => {
+                let len = reader.read_u32()? as usize;
+                Value::String(base64::encode(reader.take(len)?))
+            }
+            IdlType::Option(inner) => match reader.read_u8()? {
+                0 => Value::Null,
+                1 => self.parse_idl_type(program, inner, reader)?,
+                tag => return Err(AnchorError::AccountDataParseError(format!("Invalid option tag: {}", tag))),
+            },
+            IdlType::Vec(inner) => {
+                let len = reader.read_u32()? as usize;
+                // Every element takes at least one byte, so a larger length is corrupt data
+                if len > reader.remaining() && !matches!(fixed_size(idl, inner), Some(0)) {
+                    return Err(AnchorError::AccountDataParseError(format!("Vec length {} exceeds remaining data", len)));
+                }
+                let mut items = Vec::with_capacity(len.min(reader.remaining()));
+                for _ in 0..len {
+                    items.push(self.parse_idl_type(program, inner, reader)?);
+                }
+                Value::Array(items)
+            }
+            IdlType::Array(inner, len) => {
+                let mut items = Vec::with_capacity(*len);
+                for _ in 0..*len {
+                    items.push(self.parse_idl_type(program, inner, reader)?);
+                }
+                Value::Array(items)
+            }
+            IdlType::Defined(name) => {
+                let def = idl.types.iter().chain(idl.accounts.iter())
+                    .find(|t| &t.name == name)
+                    .ok_or_else(|| AnchorError::IdlParseError(format!("Type {} not found in IDL", name)))?;
+                match &def.ty {
+                    IdlTypeDefinitionTy::Struct { fields } => {
+                        let mut result = serde_json::Map::new();
+                        for field in fields {
+                            let value = self.parse_idl_type(program, &field.ty, reader)?;
+                            result.insert(field.name.clone(), value);
+                        }
+                        Value::Object(result)
+                    }
+                    IdlTypeDefinitionTy::Enum { variants } => {
+                        let tag = match program.enum_tag_len(name) {
+                            2 => reader.read_u16()? as usize,
+                            4 => reader.read_u32()? as usize,
+                            8 => reader.read_u64()? as usize,
+                            _ => reader.read_u8()? as usize,
+                        };
+                        let variant = variants.get(tag)
+                            .ok_or_else(|| AnchorError::AccountDataParseError(format!("Invalid variant {} for enum {}", tag, name)))?;
+                        match &variant.fields {
+                            None => Value::String(variant.name.clone()),
+                            Some(EnumFields::Named(fields)) => {
+                                let mut result = serde_json::Map::new();
+                                for field in fields {
+                                    let value = self.parse_idl_type(program, &field.ty, reader)?;
+                                    result.insert(field.name.clone(), value);
+                                }
+                                json!({ variant.name.clone(): Value::Object(result) })
+                            }
+                            Some(EnumFields::Tuple(types)) => {
+                                let mut items = Vec::with_capacity(types.len());
+                                for ty in types {
+                                    items.push(self.parse_idl_type(program, ty, reader)?);
+                                }
+                                json!({ variant.name.clone(): Value::Array(items) })
+                            }
+                        }
+                    }
+                }
+            }
+            _ => return Err(AnchorError::AccountDataParseError(format!("Unsupported IDL type: {:?}", ty))),
+        };
+        Ok(value)
+    }
+}
+
+// Fixed-size scalars, which read the same way under Borsh and C layouts
+pub(crate) fn parse_scalar(ty: &IdlType, reader: &mut ByteReader) -> Result<Option<Value>, AnchorError> {
+    let value = match ty {
+        IdlType::Bool => Value::Bool(reader.read_bool()?),
+        IdlType::U8 => reader.read_u8()?.into(),
+        IdlType::I8 => reader.read_i8()?.into(),
+        IdlType::U16 => reader.read_u16()?.into(),
+        IdlType::I16 => reader.read_i16()?.into(),
+        IdlType::U32 => reader.read_u32()?.into(),
+        IdlType::I32 => reader.read_i32()?.into(),
+        IdlType::U64 => reader.read_u64()?.into(),
+        IdlType::I64 => reader.read_i64()?.into(),
+        // JSON numbers can't hold 128-bit integers without losing precision
+        IdlType::U128 => Value::String(reader.read_u128()?.to_string()),
+        IdlType::I128 => Value::String(reader.read_i128()?.to_string()),
+        IdlType::U256 | IdlType::I256 => {
+            let mut bytes = reader.take(32)?.to_vec();
+            bytes.reverse();
+            Value::String(format!("0x{}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>()))
+        }
+        IdlType::F32 => serde_json::Number::from_f64(reader.read_f32()? as f64).map_or(Value::Null, Value::Number),
+        IdlType::F64 => serde_json::Number::from_f64(reader.read_f64()?).map_or(Value::Null, Value::Number),
+        IdlType::PublicKey => Value::String(reader.read_pubkey()?),
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHANK_IDL: &str = include_str!("../tests/fixtures/shank_idl.json");
+    const CODAMA_IDL: &str = include_str!("../tests/fixtures/codama_idl.json");
+    const ANCHOR_030_IDL: &str = include_str!("../tests/fixtures/anchor_030_idl.json");
+
+    fn parser(idl_json: &str) -> AnchorParser {
+        let mut parser = AnchorParser::new();
+        parser.add_idl("program", idl_json).unwrap();
+        parser
+    }
+
+    fn borsh_string(text: &str) -> Vec<u8> {
+        let mut out = (text.len() as u32).to_le_bytes().to_vec();
+        out.extend_from_slice(text.as_bytes());
+        out
     }
-}
\ No newline at end of file
+
+    fn pubkey(byte: u8) -> ([u8; 32], String) {
+        let key = [byte; 32];
+        (key, bs58::encode(key).into_string())
+    }
+
+    #[test]
+    fn shank_accounts_are_told_apart_by_key_size_and_layout() {
+        let parser = parser(SHANK_IDL);
+        assert_eq!(parser.idl_format("program"), Some(IdlFormat::Shank));
+
+        let mut counter = vec![1];
+        counter.extend_from_slice(&42u64.to_le_bytes());
+        assert_eq!(parser.decode_account("program", &counter).unwrap(),
+            ("Counter".to_string(), json!({ "key": "CounterV1", "count": 42 })));
+
+        let (key, address) = pubkey(3);
+        let mut registry = vec![2];
+        registry.extend_from_slice(&1u32.to_le_bytes());
+        registry.extend_from_slice(&key);
+        registry.extend_from_slice(&7u32.to_le_bytes());
+        assert_eq!(parser.decode_account("program", &registry).unwrap(),
+            ("Registry".to_string(), json!({ "key": "Registry", "entries": [{ "0": address, "1": 7 }] })));
+
+        let mut config = key.to_vec();
+        config.extend_from_slice(&25u16.to_le_bytes());
+        assert_eq!(parser.decode_account("program", &config).unwrap(),
+            ("Config".to_string(), json!({ "authority": address, "fee": 25 })));
+
+        let mut instruction = vec![3];
+        instruction.extend_from_slice(&9u64.to_le_bytes());
+        assert_eq!(parser.parse_instruction("program", &instruction).unwrap(),
+            ("SetCount".to_string(), json!({ "count": 9 })));
+        assert_eq!(parser.decode_error("program", None, 1).map(|e| e.name), Some("Overflow".to_string()));
+    }
+
+    #[test]
+    fn layouts_without_discriminator_must_consume_the_data_exactly() {
+        let parser = parser(SHANK_IDL);
+        // Would also read as the start of a Pair, which then runs out of data
+        let note = borsh_string("hello");
+        assert_eq!(parser.decode_account("program", &note).unwrap(),
+            ("Note".to_string(), json!({ "text": "hello" })));
+
+        // Reads as a Note with bytes left over, which is not a match. The
+        // first length byte must not be a `Key` tag, or it reads as Counter.
+        let pair = [borsh_string("four"), borsh_string("b")].concat();
+        assert_eq!(parser.decode_account("program", &pair).unwrap(),
+            ("Pair".to_string(), json!({ "first": "four", "second": "b" })));
+
+        let mut padded = borsh_string("hello");
+        padded.push(0);
+        assert!(parser.decode_account("program", &padded).is_err());
+    }
+
+    #[test]
+    fn layouts_that_both_fit_are_ambiguous() {
+        let idl = json!({
+            "version": "0.1.0",
+            "name": "ambiguous",
+            "metadata": { "origin": "shank" },
+            "instructions": [],
+            "accounts": [
+                { "name": "Note", "type": { "kind": "struct", "fields": [{ "name": "text", "type": "string" }] } },
+                { "name": "Blob", "type": { "kind": "struct", "fields": [{ "name": "data", "type": "bytes" }] } },
+            ],
+        });
+        let parser = parser(&idl.to_string());
+        let error = parser.decode_account("program", &borsh_string("hello")).unwrap_err();
+        assert!(error.to_string().contains("ambiguous between Note and Blob"), "{}", error);
+    }
+
+    #[test]
+    fn codama_accounts_match_field_and_size_discriminators() {
+        let parser = parser(CODAMA_IDL);
+        assert_eq!(parser.idl_format("program"), Some(IdlFormat::Codama));
+
+        let (owner, address) = pubkey(9);
+        let mut vault = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        vault.extend_from_slice(&owner);
+        vault.extend_from_slice(&5u64.to_le_bytes());
+        vault.push(1);
+        vault.push(1);
+        vault.extend(borsh_string("hot"));
+        assert_eq!(parser.decode_account("program", &vault).unwrap(), ("vault".to_string(), json!({
+            "discriminator": [1, 2, 3, 4, 5, 6, 7, 8],
+            "owner": address,
+            "amount": 5,
+            "state": "frozen",
+            "label": "hot",
+        })));
+
+        let mut marker = vec![254];
+        marker.extend_from_slice(&owner);
+        assert_eq!(parser.decode_account("program", &marker).unwrap(),
+            ("marker".to_string(), json!({ "bump": 254, "authority": address })));
+        assert!(parser.decode_account("program", &marker[..32]).is_err());
+
+        let mut instruction = vec![7];
+        instruction.extend_from_slice(&100u64.to_le_bytes());
+        assert_eq!(parser.parse_instruction("program", &instruction).unwrap(),
+            ("deposit".to_string(), json!({ "discriminator": 7, "amount": 100 })));
+        assert_eq!(parser.decode_error("program", None, 6000).map(|e| e.name), Some("vaultFrozen".to_string()));
+    }
+
+    #[test]
+    fn codama_enums_use_their_declared_tag_size() {
+        let mut idl: Value = serde_json::from_str(CODAMA_IDL).unwrap();
+        let state = &mut idl["program"]["definedTypes"][0]["type"];
+        state["size"] = json!({ "kind": "numberTypeNode", "format": "u16", "endian": "le" });
+        let parser = parser(&idl.to_string());
+
+        let mut vault = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        vault.extend_from_slice(&pubkey(9).0);
+        vault.extend_from_slice(&5u64.to_le_bytes());
+        vault.extend_from_slice(&1u16.to_le_bytes());
+        vault.push(0);
+        let (name, value) = parser.decode_account("program", &vault).unwrap();
+        assert_eq!((name.as_str(), &value["state"], &value["label"]), ("vault", &json!("frozen"), &Value::Null));
+        assert_eq!(parser.encode_account("program", "vault", &value).unwrap(), vault);
+
+        idl["program"]["definedTypes"][0]["type"]["size"]["format"] = json!("i16");
+        assert!(AnchorParser::new().add_idl("program", &idl.to_string()).is_err());
+    }
+
+    #[test]
+    fn codama_hex_values_must_be_ascii() {
+        let mut idl: Value = serde_json::from_str(CODAMA_IDL).unwrap();
+        idl["program"]["accounts"][0]["data"]["fields"][0]["defaultValue"]["data"] = json!("0é0203040506070");
+        assert!(AnchorParser::new().add_idl("program", &idl.to_string()).is_err());
+    }
+
+    #[test]
+    fn anchor_030_idls_decode_with_their_explicit_discriminators() {
+        let parser = parser(ANCHOR_030_IDL);
+        assert_eq!(parser.idl_format("program"), Some(IdlFormat::Anchor));
+
+        let (owner, owner_address) = pubkey(1);
+        let (recipient, recipient_address) = pubkey(2);
+        let mut vault = account_discriminator("Vault").to_vec();
+        vault.extend_from_slice(&owner);
+        vault.extend_from_slice(&500u64.to_le_bytes());
+        vault.push(2);
+        vault.extend_from_slice(&9u64.to_le_bytes());
+        vault.extend_from_slice(&(-3i16).to_le_bytes());
+        vault.extend_from_slice(&1u32.to_le_bytes());
+        vault.extend_from_slice(&30u16.to_le_bytes());
+        vault.extend_from_slice(&recipient);
+        let expected = json!({
+            "owner": owner_address,
+            "amount": 500,
+            "state": { "Closing": [9, -3] },
+            "fees": [{ "bps": 30, "recipient": recipient_address }],
+        });
+        assert_eq!(parser.decode_account("program", &vault).unwrap(), ("Vault".to_string(), expected.clone()));
+        assert_eq!(parser.encode_account("program", "Vault", &expected).unwrap(), vault);
+
+        let mut instruction = instruction_discriminator("deposit").to_vec();
+        instruction.extend_from_slice(&75u64.to_le_bytes());
+        instruction.push(1);
+        instruction.extend(borsh_string("tip"));
+        assert_eq!(parser.parse_instruction("program", &instruction).unwrap(),
+            ("deposit".to_string(), json!({ "amount": 75, "memo": "tip" })));
+        assert_eq!(parser.decode_error("program", None, 6001).map(|e| e.name), Some("InsufficientFunds".to_string()));
+    }
+
+    #[test]
+    fn anchor_030_zero_copy_accounts_use_their_c_layout() {
+        let parser = parser(ANCHOR_030_IDL);
+
+        let (market, market_address) = pubkey(4);
+        let mut book = account_discriminator("OrderBook").to_vec();
+        book.extend_from_slice(&market);
+        book.extend_from_slice(&(u64::MAX as u128 + 1).to_le_bytes());
+        book.extend_from_slice(&3u64.to_le_bytes());
+        book.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0]);
+        for (price, size, flags) in [(10u64, 2u32, 1u8), (11, 4, 0)] {
+            book.extend_from_slice(&price.to_le_bytes());
+            book.extend_from_slice(&size.to_le_bytes());
+            book.extend_from_slice(&[flags, 0, 0, 0]);
+        }
+        assert_eq!(book.len(), 8 + 96);
+        let expected = json!({
+            "market": market_address,
+            "total": "18446744073709551616",
+            "seq": 3,
+            "side": 1,
+            "padding": [0, 0, 0, 0, 0, 0, 0],
+            "orders": [
+                { "price": 10, "size": 2, "flags": 1, "padding": [0, 0, 0] },
+                { "price": 11, "size": 4, "flags": 0, "padding": [0, 0, 0] },
+            ],
+        });
+        assert_eq!(parser.decode_account("program", &book).unwrap(), ("OrderBook".to_string(), expected.clone()));
+        assert_eq!(parser.encode_account("program", "OrderBook", &expected).unwrap(), book);
+
+        // `repr(packed)` puts the u128 right after the i32
+        let mut tick = account_discriminator("Tick").to_vec();
+        tick.extend_from_slice(&(-7i32).to_le_bytes());
+        tick.extend_from_slice(&42u128.to_le_bytes());
+        tick.push(1);
+        let expected = json!({ "index": -7, "liquidity": "42", "initialized": true });
+        assert_eq!(parser.decode_account("program", &tick).unwrap(), ("Tick".to_string(), expected.clone()));
+        assert_eq!(parser.encode_account("program", "Tick", &expected).unwrap(), tick);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::AnchorError;
+
+pub(crate) struct ByteReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.offset)
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], AnchorError> {
+        if self.remaining() < len {
+            return Err(AnchorError::AccountDataParseError(format!(
+                "Unexpected end of data: need {} bytes at offset {}, have {}",
+                len,
+                self.offset,
+                self.remaining()
+            )));
+        }
+        let bytes = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    pub fn skip(&mut self, len: usize) -> Result<(), AnchorError> {
+        self.take(len).map(|_| ())
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], AnchorError> {
+        let mut buf = [0u8; N];
+        buf.copy_from_slice(self.take(N)?);
+        Ok(buf)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, AnchorError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, AnchorError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            v => Err(AnchorError::AccountDataParseError(format!("Invalid bool value: {}", v))),
+        }
+    }
+
+    pub fn read_i8(&mut self) -> Result<i8, AnchorError> {
+        Ok(self.read_u8()? as i8)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, AnchorError> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i16(&mut self) -> Result<i16, AnchorError> {
+        Ok(i16::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, AnchorError> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, AnchorError> {
+        Ok(i32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, AnchorError> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, AnchorError> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u128(&mut self) -> Result<u128, AnchorError> {
+        Ok(u128::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i128(&mut self) -> Result<i128, AnchorError> {
+        Ok(i128::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, AnchorError> {
+        Ok(f32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_f64(&mut self) -> Result<f64, AnchorError> {
+        Ok(f64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_pubkey(&mut self) -> Result<String, AnchorError> {
+        Ok(bs58::encode(self.take(32)?).into_string())
+    }
+
+    pub fn read_string(&mut self) -> Result<String, AnchorError> {
+        let len = self.read_u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|e| AnchorError::AccountDataParseError(e.to_string()))
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use crate::idl::ProgramIdl;
+use crate::AnchorError;
+
+#[derive(Debug, Clone)]
+pub struct IdlVersion {
+    pub start_slot: u64,
+    pub idl_json: String,
+    pub program: ProgramIdl,
+}
+
+impl IdlVersion {
+    pub fn parse(start_slot: u64, idl_json: &str) -> Result<Self, AnchorError> {
+        let program = ProgramIdl::from_json(idl_json)?;
+        Ok(Self { start_slot, idl_json: idl_json.to_string(), program })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdlVersionRange {
+    pub start_slot: u64,
+    // Exclusive; `None` for the version that is currently active
+    pub end_slot: Option<u64>,
+}
+
+// Every IDL a program has had, each valid from its start slot until the next
+// version takes over. Versions are kept sorted by start slot.
+#[derive(Debug, Default)]
+pub struct IdlRegistry {
+    programs: HashMap<String, Vec<IdlVersion>>,
+}
+
+impl IdlRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, program_id: &str, start_slot: u64, idl_json: &str) -> Result<(), AnchorError> {
+        self.insert_version(program_id, IdlVersion::parse(start_slot, idl_json)?);
+        Ok(())
+    }
+
+    // Installs a version parsed beforehand, so it can be validated without
+    // holding the registry
+    pub fn insert_version(&mut self, program_id: &str, version: IdlVersion) {
+        let versions = self.programs.entry(program_id.to_string()).or_default();
+        match versions.binary_search_by_key(&version.start_slot, |v| v.start_slot) {
+            // Re-uploading for the same slot replaces that version
+            Ok(i) => versions[i] = version,
+            Err(i) => versions.insert(i, version),
+        }
+    }
+
+    pub fn contains(&self, program_id: &str) -> bool {
+        self.programs.contains_key(program_id)
+    }
+
+    pub fn latest(&self, program_id: &str) -> Option<&IdlVersion> {
+        self.programs.get(program_id).and_then(|versions| versions.last())
+    }
+
+    pub fn at_slot(&self, program_id: &str, slot: u64) -> Option<&IdlVersion> {
+        let versions = self.programs.get(program_id)?;
+        let i = versions.partition_point(|v| v.start_slot <= slot);
+        i.checked_sub(1).map(|i| &versions[i])
+    }
+
+    pub fn versions(&self, program_id: &str) -> Vec<IdlVersionRange> {
+        let versions = match self.programs.get(program_id) {
+            Some(versions) => versions,
+            None => return Vec::new(),
+        };
+        versions.iter().enumerate()
+            .map(|(i, v)| IdlVersionRange {
+                start_slot: v.start_slot,
+                end_slot: versions.get(i + 1).map(|next| next.start_slot),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn idl(name: &str) -> String {
+        json!({ "version": "0.1.0", "name": name, "instructions": [] }).to_string()
+    }
+
+    fn name_at(registry: &IdlRegistry, slot: u64) -> Option<String> {
+        registry.at_slot("program", slot).map(|v| v.program.idl.name.clone())
+    }
+
+    #[test]
+    fn versions_cover_their_slots_until_the_next_takes_over() {
+        let mut registry = IdlRegistry::new();
+        // Inserted out of order on purpose
+        registry.insert("program", 200, &idl("second")).unwrap();
+        registry.insert("program", 100, &idl("first")).unwrap();
+
+        assert_eq!(name_at(&registry, 99), None);
+        assert_eq!(name_at(&registry, 100).as_deref(), Some("first"));
+        assert_eq!(name_at(&registry, 199).as_deref(), Some("first"));
+        assert_eq!(name_at(&registry, 200).as_deref(), Some("second"));
+        assert_eq!(name_at(&registry, u64::MAX).as_deref(), Some("second"));
+        assert!(registry.at_slot("other", 200).is_none());
+
+        assert_eq!(registry.versions("program"), vec![
+            IdlVersionRange { start_slot: 100, end_slot: Some(200) },
+            IdlVersionRange { start_slot: 200, end_slot: None },
+        ]);
+    }
+
+    #[test]
+    fn reuploading_a_start_slot_replaces_that_version() {
+        let mut registry = IdlRegistry::new();
+        registry.insert("program", 0, &idl("original")).unwrap();
+        registry.insert("program", 0, &idl("fixed")).unwrap();
+
+        assert_eq!(name_at(&registry, 0).as_deref(), Some("fixed"));
+        assert_eq!(registry.versions("program").len(), 1);
+    }
+
+    #[test]
+    fn invalid_idls_leave_the_registry_untouched() {
+        let mut registry = IdlRegistry::new();
+        assert!(registry.insert("program", 0, "not json").is_err());
+        assert!(!registry.contains("program"));
+    }
+}
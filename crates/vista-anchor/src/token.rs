@@ -0,0 +1,523 @@
+use serde_json::{json, Map, Value};
+
+use crate::reader::ByteReader;
+use crate::AnchorError;
+
+pub const SPL_TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const SPL_TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PQnvGo7LPeMvDx";
+
+const MINT_LEN: usize = 82;
+const ACCOUNT_LEN: usize = 165;
+const MULTISIG_LEN: usize = 355;
+const MAX_SIGNERS: usize = 11;
+
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenProgram {
+    SplToken,
+    Token2022,
+}
+
+impl TokenProgram {
+    pub fn from_program_id(program_id: &str) -> Option<Self> {
+        match program_id {
+            SPL_TOKEN_PROGRAM_ID => Some(TokenProgram::SplToken),
+            SPL_TOKEN_2022_PROGRAM_ID => Some(TokenProgram::Token2022),
+            _ => None,
+        }
+    }
+
+    pub fn decode_account(&self, data: &[u8]) -> Result<(String, Value), AnchorError> {
+        match self {
+            TokenProgram::SplToken => decode_spl_token(data),
+            TokenProgram::Token2022 => decode_token_2022(data),
+        }
+    }
+}
+
+fn decode_spl_token(data: &[u8]) -> Result<(String, Value), AnchorError> {
+    match data.len() {
+        MINT_LEN => Ok(("mint".to_string(), parse_mint(data)?)),
+        ACCOUNT_LEN => Ok(("token_account".to_string(), parse_token_account(data)?)),
+        MULTISIG_LEN => Ok(("multisig".to_string(), parse_multisig(data)?)),
+        len => Err(AnchorError::AccountDataParseError(format!("Unrecognized SPL Token account size: {}", len))),
+    }
+}
+
+fn decode_token_2022(data: &[u8]) -> Result<(String, Value), AnchorError> {
+    // Multisig accounts are never extended, and the program pads any extended
+    // account that would otherwise be exactly as long as one
+    if data.len() == MULTISIG_LEN {
+        return Ok(("multisig".to_string(), parse_multisig(data)?));
+    }
+    if data.len() == MINT_LEN {
+        return Ok(("mint".to_string(), parse_mint(data)?));
+    }
+    if data.len() == ACCOUNT_LEN {
+        return Ok(("token_account".to_string(), parse_token_account(data)?));
+    }
+    if data.len() <= ACCOUNT_LEN {
+        return Err(AnchorError::AccountDataParseError(format!("Unrecognized Token-2022 account size: {}", data.len())));
+    }
+
+    let (account_type, mut value) = match data[ACCOUNT_LEN] {
+        ACCOUNT_TYPE_MINT => {
+            // Mints are zero-padded up to the token account length before the type byte
+            if data[MINT_LEN..ACCOUNT_LEN].iter().any(|b| *b != 0) {
+                return Err(AnchorError::AccountDataParseError("Invalid Token-2022 mint padding".to_string()));
+            }
+            ("mint", parse_mint(&data[..MINT_LEN])?)
+        }
+        ACCOUNT_TYPE_ACCOUNT => ("token_account", parse_token_account(&data[..ACCOUNT_LEN])?),
+        t => return Err(AnchorError::AccountDataParseError(format!("Unknown Token-2022 account type: {}", t))),
+    };
+
+    let extensions = parse_extensions(&data[ACCOUNT_LEN + 1..])?;
+    if let Value::Object(map) = &mut value {
+        map.insert("extensions".to_string(), Value::Array(extensions));
+    }
+    Ok((account_type.to_string(), value))
+}
+
+fn parse_mint(data: &[u8]) -> Result<Value, AnchorError> {
+    let mut reader = ByteReader::new(data);
+    Ok(json!({
+        "mint_authority": read_coption_pubkey(&mut reader)?,
+        "supply": reader.read_u64()?,
+        "decimals": reader.read_u8()?,
+        "is_initialized": reader.read_bool()?,
+        "freeze_authority": read_coption_pubkey(&mut reader)?,
+    }))
+}
+
+fn parse_token_account(data: &[u8]) -> Result<Value, AnchorError> {
+    let mut reader = ByteReader::new(data);
+    let mint = reader.read_pubkey()?;
+    let owner = reader.read_pubkey()?;
+    let amount = reader.read_u64()?;
+    let delegate = read_coption_pubkey(&mut reader)?;
+    let state = match reader.read_u8()? {
+        0 => "uninitialized",
+        1 => "initialized",
+        2 => "frozen",
+        s => return Err(AnchorError::AccountDataParseError(format!("Invalid token account state: {}", s))),
+    };
+    let is_native = match reader.read_u32()? {
+        0 => {
+            reader.skip(8)?;
+            Value::Null
+        }
+        _ => reader.read_u64()?.into(),
+    };
+    Ok(json!({
+        "mint": mint,
+        "owner": owner,
+        "amount": amount,
+        "delegate": delegate,
+        "state": state,
+        "is_native": is_native,
+        "delegated_amount": reader.read_u64()?,
+        "close_authority": read_coption_pubkey(&mut reader)?,
+    }))
+}
+
+fn parse_multisig(data: &[u8]) -> Result<Value, AnchorError> {
+    let mut reader = ByteReader::new(data);
+    let m = reader.read_u8()?;
+    let n = reader.read_u8()?;
+    let is_initialized = reader.read_bool()?;
+    let mut signers = Vec::with_capacity(n as usize);
+    for i in 0..MAX_SIGNERS {
+        let signer = reader.read_pubkey()?;
+        if i < n as usize {
+            signers.push(Value::String(signer));
+        }
+    }
+    Ok(json!({
+        "m": m,
+        "n": n,
+        "is_initialized": is_initialized,
+        "signers": signers,
+    }))
+}
+
+fn parse_extensions(data: &[u8]) -> Result<Vec<Value>, AnchorError> {
+    let mut reader = ByteReader::new(data);
+    let mut extensions = Vec::new();
+    // Each entry is a u16 extension type followed by a u16 length and the payload
+    while reader.remaining() >= 4 {
+        let extension_type = reader.read_u16()?;
+        let length = reader.read_u16()? as usize;
+        if extension_type == 0 && length == 0 {
+            // Uninitialized space at the end of the account
+            break;
+        }
+        let payload = reader.take(length)?;
+        let (name, state) = parse_extension(extension_type, payload)?;
+        let mut entry = Map::new();
+        entry.insert("extension".to_string(), Value::String(name.to_string()));
+        entry.insert("state".to_string(), state);
+        extensions.push(Value::Object(entry));
+    }
+    Ok(extensions)
+}
+
+fn parse_extension(extension_type: u16, payload: &[u8]) -> Result<(&'static str, Value), AnchorError> {
+    let mut r = ByteReader::new(payload);
+    let parsed = match extension_type {
+        1 => ("transfer_fee_config", json!({
+            "transfer_fee_config_authority": read_optional_nonzero_pubkey(&mut r)?,
+            "withdraw_withheld_authority": read_optional_nonzero_pubkey(&mut r)?,
+            "withheld_amount": r.read_u64()?,
+            "older_transfer_fee": read_transfer_fee(&mut r)?,
+            "newer_transfer_fee": read_transfer_fee(&mut r)?,
+        })),
+        2 => ("transfer_fee_amount", json!({
+            "withheld_amount": r.read_u64()?,
+        })),
+        3 => ("mint_close_authority", json!({
+            "close_authority": read_optional_nonzero_pubkey(&mut r)?,
+        })),
+        4 => ("confidential_transfer_mint", json!({
+            "authority": read_optional_nonzero_pubkey(&mut r)?,
+            "auto_approve_new_accounts": r.read_bool()?,
+            "auditor_elgamal_pubkey": read_optional_nonzero_bytes(&mut r, 32)?,
+        })),
+        5 => ("confidential_transfer_account", json!({
+            "approved": r.read_bool()?,
+            "elgamal_pubkey": read_base64(&mut r, 32)?,
+            "pending_balance_lo": read_base64(&mut r, 64)?,
+            "pending_balance_hi": read_base64(&mut r, 64)?,
+            "available_balance": read_base64(&mut r, 64)?,
+            "decryptable_available_balance": read_base64(&mut r, 36)?,
+            "allow_confidential_credits": r.read_bool()?,
+            "allow_non_confidential_credits": r.read_bool()?,
+            "pending_balance_credit_counter": r.read_u64()?,
+            "maximum_pending_balance_credit_counter": r.read_u64()?,
+            "expected_pending_balance_credit_counter": r.read_u64()?,
+            "actual_pending_balance_credit_counter": r.read_u64()?,
+        })),
+        6 => ("default_account_state", json!({
+            "state": match r.read_u8()? {
+                0 => "uninitialized",
+                1 => "initialized",
+                2 => "frozen",
+                s => return Err(AnchorError::AccountDataParseError(format!("Invalid default account state: {}", s))),
+            },
+        })),
+        7 => ("immutable_owner", json!({})),
+        8 => ("memo_transfer", json!({
+            "require_incoming_transfer_memos": r.read_bool()?,
+        })),
+        9 => ("non_transferable", json!({})),
+        10 => ("interest_bearing_config", json!({
+            "rate_authority": read_optional_nonzero_pubkey(&mut r)?,
+            "initialization_timestamp": r.read_i64()?,
+            "pre_update_average_rate": r.read_i16()?,
+            "last_update_timestamp": r.read_i64()?,
+            "current_rate": r.read_i16()?,
+        })),
+        11 => ("cpi_guard", json!({
+            "lock_cpi": r.read_bool()?,
+        })),
+        12 => ("permanent_delegate", json!({
+            "delegate": read_optional_nonzero_pubkey(&mut r)?,
+        })),
+        13 => ("non_transferable_account", json!({})),
+        14 => ("transfer_hook", json!({
+            "authority": read_optional_nonzero_pubkey(&mut r)?,
+            "program_id": read_optional_nonzero_pubkey(&mut r)?,
+        })),
+        15 => ("transfer_hook_account", json!({
+            "transferring": r.read_bool()?,
+        })),
+        16 => ("confidential_transfer_fee_config", json!({
+            "authority": read_optional_nonzero_pubkey(&mut r)?,
+            "withdraw_withheld_authority_elgamal_pubkey": read_base64(&mut r, 32)?,
+            "harvest_to_mint_enabled": r.read_bool()?,
+            "withheld_amount": read_base64(&mut r, 64)?,
+        })),
+        17 => ("confidential_transfer_fee_amount", json!({
+            "withheld_amount": read_base64(&mut r, 64)?,
+        })),
+        18 => ("metadata_pointer", json!({
+            "authority": read_optional_nonzero_pubkey(&mut r)?,
+            "metadata_address": read_optional_nonzero_pubkey(&mut r)?,
+        })),
+        19 => ("token_metadata", parse_token_metadata(&mut r)?),
+        20 => ("group_pointer", json!({
+            "authority": read_optional_nonzero_pubkey(&mut r)?,
+            "group_address": read_optional_nonzero_pubkey(&mut r)?,
+        })),
+        21 => ("token_group", json!({
+            "update_authority": read_optional_nonzero_pubkey(&mut r)?,
+            "mint": r.read_pubkey()?,
+            "size": r.read_u64()?,
+            "max_size": r.read_u64()?,
+        })),
+        22 => ("group_member_pointer", json!({
+            "authority": read_optional_nonzero_pubkey(&mut r)?,
+            "member_address": read_optional_nonzero_pubkey(&mut r)?,
+        })),
+        23 => ("token_group_member", json!({
+            "mint": r.read_pubkey()?,
+            "group": r.read_pubkey()?,
+            "member_number": r.read_u64()?,
+        })),
+        // Keep extensions we don't know about yet instead of failing the whole account
+        _ => ("unknown", json!({
+            "extension_type": extension_type,
+            "data": base64::encode(payload),
+        })),
+    };
+    Ok(parsed)
+}
+
+fn parse_token_metadata(r: &mut ByteReader) -> Result<Value, AnchorError> {
+    let update_authority = read_optional_nonzero_pubkey(r)?;
+    let mint = r.read_pubkey()?;
+    let name = r.read_string()?;
+    let symbol = r.read_string()?;
+    let uri = r.read_string()?;
+    let count = r.read_u32()?;
+    let mut additional_metadata = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let key = r.read_string()?;
+        let value = r.read_string()?;
+        additional_metadata.push(json!([key, value]));
+    }
+    Ok(json!({
+        "update_authority": update_authority,
+        "mint": mint,
+        "name": name,
+        "symbol": symbol,
+        "uri": uri,
+        "additional_metadata": additional_metadata,
+    }))
+}
+
+fn read_transfer_fee(r: &mut ByteReader) -> Result<Value, AnchorError> {
+    Ok(json!({
+        "epoch": r.read_u64()?,
+        "maximum_fee": r.read_u64()?,
+        "transfer_fee_basis_points": r.read_u16()?,
+    }))
+}
+
+fn read_coption_pubkey(r: &mut ByteReader) -> Result<Value, AnchorError> {
+    let tag = r.read_u32()?;
+    let key = r.read_pubkey()?;
+    match tag {
+        0 => Ok(Value::Null),
+        1 => Ok(Value::String(key)),
+        t => Err(AnchorError::AccountDataParseError(format!("Invalid COption tag: {}", t))),
+    }
+}
+
+fn read_optional_nonzero_pubkey(r: &mut ByteReader) -> Result<Value, AnchorError> {
+    let bytes = r.take(32)?;
+    if bytes.iter().all(|b| *b == 0) {
+        Ok(Value::Null)
+    } else {
+        Ok(Value::String(bs58::encode(bytes).into_string()))
+    }
+}
+
+fn read_optional_nonzero_bytes(r: &mut ByteReader, len: usize) -> Result<Value, AnchorError> {
+    let bytes = r.take(len)?;
+    if bytes.iter().all(|b| *b == 0) {
+        Ok(Value::Null)
+    } else {
+        Ok(Value::String(base64::encode(bytes)))
+    }
+}
+
+fn read_base64(r: &mut ByteReader, len: usize) -> Result<Value, AnchorError> {
+    Ok(Value::String(base64::encode(r.take(len)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> ([u8; 32], String) {
+        let key = [byte; 32];
+        (key, bs58::encode(key).into_string())
+    }
+
+    fn coption(key: Option<[u8; 32]>) -> Vec<u8> {
+        let mut out = (key.is_some() as u32).to_le_bytes().to_vec();
+        out.extend(key.unwrap_or_default());
+        out
+    }
+
+    fn mint() -> Vec<u8> {
+        let mut data = coption(Some(key(1).0));
+        data.extend(1_000u64.to_le_bytes());
+        data.extend([6, 1]);
+        data.extend(coption(None));
+        assert_eq!(data.len(), MINT_LEN);
+        data
+    }
+
+    fn token_account() -> Vec<u8> {
+        let mut data = [key(2).0, key(3).0].concat();
+        data.extend(500u64.to_le_bytes());
+        data.extend(coption(None));
+        data.push(2);
+        data.extend(0u32.to_le_bytes());
+        data.extend(0u64.to_le_bytes());
+        data.extend(0u64.to_le_bytes());
+        data.extend(coption(Some(key(4).0)));
+        assert_eq!(data.len(), ACCOUNT_LEN);
+        data
+    }
+
+    fn tlv(extension_type: u16, payload: &[u8]) -> Vec<u8> {
+        let mut out = extension_type.to_le_bytes().to_vec();
+        out.extend((payload.len() as u16).to_le_bytes());
+        out.extend(payload);
+        out
+    }
+
+    fn borsh_string(text: &str) -> Vec<u8> {
+        let mut out = (text.len() as u32).to_le_bytes().to_vec();
+        out.extend(text.as_bytes());
+        out
+    }
+
+    fn extensions(value: &Value) -> Vec<&str> {
+        value["extensions"].as_array().unwrap().iter().map(|e| e["extension"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn spl_token_accounts_are_told_apart_by_size() {
+        let (name, mint) = decode_spl_token(&mint()).unwrap();
+        assert_eq!(name, "mint");
+        assert_eq!(mint, json!({
+            "mint_authority": key(1).1,
+            "supply": 1000,
+            "decimals": 6,
+            "is_initialized": true,
+            "freeze_authority": null,
+        }));
+
+        let (name, account) = decode_spl_token(&token_account()).unwrap();
+        assert_eq!(name, "token_account");
+        assert_eq!(account, json!({
+            "mint": key(2).1,
+            "owner": key(3).1,
+            "amount": 500,
+            "delegate": null,
+            "state": "frozen",
+            "is_native": null,
+            "delegated_amount": 0,
+            "close_authority": key(4).1,
+        }));
+
+        let mut multisig = vec![2, 3, 1];
+        for byte in 1..=MAX_SIGNERS as u8 {
+            multisig.extend(key(byte).0);
+        }
+        let (name, multisig) = decode_spl_token(&multisig).unwrap();
+        assert_eq!(name, "multisig");
+        assert_eq!(multisig["signers"], json!([key(1).1, key(2).1, key(3).1]));
+
+        assert!(decode_spl_token(&[0; 100]).is_err());
+        // SPL Token accounts never carry extensions
+        assert!(decode_spl_token(&[token_account(), vec![ACCOUNT_TYPE_ACCOUNT]].concat()).is_err());
+    }
+
+    #[test]
+    fn token_2022_base_accounts_decode_like_spl_token() {
+        assert_eq!(decode_token_2022(&mint()).unwrap(), decode_spl_token(&mint()).unwrap());
+        assert_eq!(decode_token_2022(&token_account()).unwrap(), decode_spl_token(&token_account()).unwrap());
+    }
+
+    #[test]
+    fn token_2022_account_type_follows_the_token_account_length() {
+        let mut extended_mint = mint();
+        extended_mint.resize(ACCOUNT_LEN, 0);
+        extended_mint.push(ACCOUNT_TYPE_MINT);
+        let (name, value) = decode_token_2022(&extended_mint).unwrap();
+        assert_eq!(name, "mint");
+        assert_eq!(value["supply"], 1000);
+        assert_eq!(value["extensions"], json!([]));
+
+        let mut bad_padding = extended_mint.clone();
+        bad_padding[MINT_LEN] = 1;
+        assert!(decode_token_2022(&bad_padding).is_err());
+
+        let mut unknown_type = extended_mint;
+        unknown_type[ACCOUNT_LEN] = 3;
+        assert!(decode_token_2022(&unknown_type).is_err());
+    }
+
+    #[test]
+    fn token_2022_extensions_are_decoded_in_order() {
+        let mut fee = [key(5).0, [0; 32]].concat();
+        fee.extend(7u64.to_le_bytes());
+        for (epoch, basis_points) in [(1u64, 10u16), (2, 25)] {
+            fee.extend(epoch.to_le_bytes());
+            fee.extend(1_000u64.to_le_bytes());
+            fee.extend(basis_points.to_le_bytes());
+        }
+        let mut metadata = [key(6).0, key(7).0].concat();
+        for text in ["Token", "TKN", "https://example.com"] {
+            metadata.extend(borsh_string(text));
+        }
+        metadata.extend(1u32.to_le_bytes());
+        metadata.extend([borsh_string("color"), borsh_string("blue")].concat());
+
+        let mut data = mint();
+        data.resize(ACCOUNT_LEN, 0);
+        data.push(ACCOUNT_TYPE_MINT);
+        data.extend(tlv(1, &fee));
+        data.extend(tlv(18, &[key(6).0, key(8).0].concat()));
+        data.extend(tlv(19, &metadata));
+        let (_, value) = decode_token_2022(&data).unwrap();
+        assert_eq!(extensions(&value), vec!["transfer_fee_config", "metadata_pointer", "token_metadata"]);
+
+        let extensions = &value["extensions"];
+        assert_eq!(extensions[0]["state"]["transfer_fee_config_authority"], key(5).1);
+        assert_eq!(extensions[0]["state"]["withdraw_withheld_authority"], Value::Null);
+        assert_eq!(extensions[0]["state"]["newer_transfer_fee"], json!({
+            "epoch": 2,
+            "maximum_fee": 1000,
+            "transfer_fee_basis_points": 25,
+        }));
+        assert_eq!(extensions[1]["state"]["metadata_address"], key(8).1);
+        assert_eq!(extensions[2]["state"], json!({
+            "update_authority": key(6).1,
+            "mint": key(7).1,
+            "name": "Token",
+            "symbol": "TKN",
+            "uri": "https://example.com",
+            "additional_metadata": [["color", "blue"]],
+        }));
+    }
+
+    #[test]
+    fn token_2022_unknown_extensions_are_kept_raw() {
+        let mut data = token_account();
+        data.push(ACCOUNT_TYPE_ACCOUNT);
+        data.extend(tlv(7, &[]));
+        data.extend(tlv(999, &[1, 2, 3]));
+        data.extend(tlv(2, &42u64.to_le_bytes()));
+        // Uninitialized space reserved for later extensions
+        data.extend([0; 8]);
+        let (name, value) = decode_token_2022(&data).unwrap();
+        assert_eq!(name, "token_account");
+        assert_eq!(extensions(&value), vec!["immutable_owner", "unknown", "transfer_fee_amount"]);
+        assert_eq!(value["extensions"][1]["state"], json!({ "extension_type": 999, "data": base64::encode([1, 2, 3]) }));
+        assert_eq!(value["extensions"][2]["state"], json!({ "withheld_amount": 42 }));
+
+        // A length running past the end of the account is corrupt
+        let mut truncated = token_account();
+        truncated.push(ACCOUNT_TYPE_ACCOUNT);
+        truncated.extend(tlv(999, &[1, 2, 3])[..6].to_vec());
+        assert!(decode_token_2022(&truncated).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use anchor_syn::idl::{Idl, IdlField, IdlType, IdlTypeDefinitionTy};
+use serde_json::{Map, Value};
+
+use crate::encode::encode_idl_type;
+use crate::idl::ProgramIdl;
+use crate::reader::ByteReader;
+use crate::{parse_scalar, AnchorError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Serialization {
+    Borsh,
+    // `#[account(zero_copy)]` / bytemuck Pod types laid out with `repr(C)`
+    Bytemuck { packed: bool },
+}
+
+// Collects the serialization hints Anchor emits per account and type. The
+// legacy `Idl` model drops unknown keys, so they are read from the raw JSON.
+pub(crate) fn serialization_hints(raw: &Value) -> Vec<(String, Serialization)> {
+    let mut hints = Vec::new();
+    for section in ["accounts", "types"] {
+        for item in raw[section].as_array().into_iter().flatten() {
+            let name = match item["name"].as_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            let bytemuck = matches!(item["serialization"].as_str(), Some("bytemuck") | Some("bytemuckunsafe"))
+                || item["zeroCopy"].as_bool().unwrap_or(false);
+            if bytemuck {
+                let packed = item["repr"]["packed"].as_bool().unwrap_or(false);
+                hints.push((name.to_string(), Serialization::Bytemuck { packed }));
+            }
+        }
+    }
+    hints
+}
+
+// Size and alignment of a type under `repr(C)` as compiled for SBF, where
+// 128-bit integers are 8-byte aligned. Each defined type is laid out by its
+// own `repr`, so a packed struct nested in an aligned one (or the reverse)
+// keeps its own padding.
+pub(crate) fn c_layout(program: &ProgramIdl, ty: &IdlType) -> Result<(usize, usize), AnchorError> {
+    Ok(match ty {
+        IdlType::Bool | IdlType::U8 | IdlType::I8 => (1, 1),
+        IdlType::U16 | IdlType::I16 => (2, 2),
+        IdlType::U32 | IdlType::I32 | IdlType::F32 => (4, 4),
+        IdlType::U64 | IdlType::I64 | IdlType::F64 => (8, 8),
+        IdlType::U128 | IdlType::I128 => (16, 8),
+        IdlType::U256 | IdlType::I256 => (32, 8),
+        IdlType::PublicKey => (32, 1),
+        IdlType::Array(inner, len) => {
+            let (size, align) = c_layout(program, inner)?;
+            (size * len, align)
+        }
+        IdlType::Defined(name) => {
+            let packed = is_packed(program, name);
+            let mut offset = 0;
+            let mut align = 1;
+            for field in struct_fields(&program.idl, name)? {
+                let (field_size, field_align) = c_layout(program, &field.ty)?;
+                let field_align = if packed { 1 } else { field_align };
+                offset = align_up(offset, field_align) + field_size;
+                align = align.max(field_align);
+            }
+            (align_up(offset, align), align)
+        }
+        _ => return Err(AnchorError::AccountDataParseError(format!("Type {:?} is not valid in a zero-copy layout", ty))),
+    })
+}
+
+pub(crate) fn parse_c_type(program: &ProgramIdl, ty: &IdlType, reader: &mut ByteReader) -> Result<Value, AnchorError> {
+    match ty {
+        IdlType::Array(inner, len) => {
+            let mut items = Vec::with_capacity(*len);
+            for _ in 0..*len {
+                items.push(parse_c_type(program, inner, reader)?);
+            }
+            Ok(Value::Array(items))
+        }
+        IdlType::Defined(name) => {
+            let start = reader.offset();
+            let mut result = Map::new();
+            for field in struct_fields(&program.idl, name)? {
+                let relative = reader.offset() - start;
+                reader.skip(align_up(relative, field_align(program, name, &field.ty)?) - relative)?;
+                result.insert(field.name.clone(), parse_c_type(program, &field.ty, reader)?);
+            }
+            // Trailing padding up to the struct's alignment
+            let (size, _) = c_layout(program, ty)?;
+            reader.skip(size - (reader.offset() - start))?;
+            Ok(Value::Object(result))
+        }
+        // Scalars have no internal padding, so they read exactly like Borsh
+        _ => parse_scalar(ty, reader)?
+            .ok_or_else(|| AnchorError::AccountDataParseError(format!("Type {:?} is not valid in a zero-copy layout", ty))),
+    }
+}
+
+pub(crate) fn encode_c_type(program: &ProgramIdl, ty: &IdlType, value: &Value, out: &mut Vec<u8>) -> Result<(), AnchorError> {
+    match ty {
+        IdlType::Array(inner, len) => {
+            let items = value.as_array().filter(|items| items.len() == *len)
+                .ok_or_else(|| AnchorError::EncodeError(format!("Expected array of length {}, got {}", len, value)))?;
+            for item in items {
+                encode_c_type(program, inner, item, out)?;
+            }
+            Ok(())
+        }
+        IdlType::Defined(name) => {
+            let start = out.len();
+            let map = value.as_object()
+                .ok_or_else(|| AnchorError::EncodeError(format!("Expected object, got {}", value)))?;
+            for field in struct_fields(&program.idl, name)? {
+                let relative = out.len() - start;
+                out.resize(start + align_up(relative, field_align(program, name, &field.ty)?), 0);
+                let field_value = map.get(&field.name)
+                    .ok_or_else(|| AnchorError::EncodeError(format!("Missing field {}", field.name)))?;
+                encode_c_type(program, &field.ty, field_value, out)?;
+            }
+            let (size, _) = c_layout(program, ty)?;
+            out.resize(start + size, 0);
+            Ok(())
+        }
+        _ => {
+            c_layout(program, ty)?;
+            encode_idl_type(program, ty, value, out)
+        }
+    }
+}
+
+// Where a field of `parent` starts: packed structs place fields back to back
+fn field_align(program: &ProgramIdl, parent: &str, ty: &IdlType) -> Result<usize, AnchorError> {
+    if is_packed(program, parent) {
+        return Ok(1);
+    }
+    Ok(c_layout(program, ty)?.1)
+}
+
+fn is_packed(program: &ProgramIdl, name: &str) -> bool {
+    program.serialization(name) == Serialization::Bytemuck { packed: true }
+}
+
+fn struct_fields<'a>(idl: &'a Idl, name: &str) -> Result<&'a [IdlField], AnchorError> {
+    let def = idl.types.iter().chain(idl.accounts.iter())
+        .find(|t| t.name == name)
+        .ok_or_else(|| AnchorError::IdlParseError(format!("Type {} not found in IDL", name)))?;
+    match &def.ty {
+        IdlTypeDefinitionTy::Struct { fields } => Ok(fields),
+        IdlTypeDefinitionTy::Enum { .. } => Err(AnchorError::AccountDataParseError(format!("Enum {} is not valid in a zero-copy layout", name))),
+    }
+}
+
+fn align_up(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn program() -> ProgramIdl {
+        let pair = |name: &str, hint: Value| {
+            let mut ty = json!({
+                "name": name,
+                "type": { "kind": "struct", "fields": [{ "name": "a", "type": "u8" }, { "name": "b", "type": "u64" }] },
+            });
+            ty.as_object_mut().unwrap().extend(hint.as_object().unwrap().clone());
+            ty
+        };
+        let idl = json!({
+            "version": "0.1.0",
+            "name": "layouts",
+            "instructions": [],
+            "accounts": [
+                {
+                    "name": "Outer",
+                    "serialization": "bytemuck",
+                    "type": { "kind": "struct", "fields": [
+                        { "name": "tag", "type": "u8" },
+                        { "name": "packed", "type": { "defined": "Packed" } },
+                        { "name": "aligned", "type": { "defined": "Aligned" } },
+                    ] },
+                },
+                {
+                    "name": "PackedOuter",
+                    "serialization": "bytemuckunsafe",
+                    "repr": { "kind": "c", "packed": true },
+                    "type": { "kind": "struct", "fields": [
+                        { "name": "tag", "type": "u8" },
+                        { "name": "aligned", "type": { "defined": "Aligned" } },
+                    ] },
+                },
+            ],
+            "types": [
+                pair("Aligned", json!({ "serialization": "bytemuck", "repr": { "kind": "c" } })),
+                pair("Packed", json!({ "serialization": "bytemuckunsafe", "repr": { "kind": "c", "packed": true } })),
+            ],
+        });
+        ProgramIdl::from_json(&idl.to_string()).unwrap()
+    }
+
+    fn defined(name: &str) -> IdlType {
+        IdlType::Defined(name.to_string())
+    }
+
+    #[test]
+    fn nested_types_keep_their_own_repr() {
+        let program = program();
+        assert_eq!(c_layout(&program, &defined("Aligned")).unwrap(), (16, 8));
+        assert_eq!(c_layout(&program, &defined("Packed")).unwrap(), (9, 1));
+        // tag, then Packed at 1 without padding, then Aligned at 16
+        assert_eq!(c_layout(&program, &defined("Outer")).unwrap(), (32, 8));
+        // Aligned keeps its internal padding when placed unaligned at 1
+        assert_eq!(c_layout(&program, &defined("PackedOuter")).unwrap(), (17, 1));
+    }
+
+    #[test]
+    fn aligned_struct_round_trips_with_packed_child() {
+        let program = program();
+        let value = json!({ "tag": 1, "packed": { "a": 2, "b": 3 }, "aligned": { "a": 4, "b": 5 } });
+        let mut out = Vec::new();
+        encode_c_type(&program, &defined("Outer"), &value, &mut out).unwrap();
+
+        let mut expected = vec![0u8; 32];
+        expected[0] = 1;
+        expected[1] = 2;
+        expected[2..10].copy_from_slice(&3u64.to_le_bytes());
+        expected[16] = 4;
+        expected[24..32].copy_from_slice(&5u64.to_le_bytes());
+        assert_eq!(out, expected);
+
+        let mut reader = ByteReader::new(&out);
+        assert_eq!(parse_c_type(&program, &defined("Outer"), &mut reader).unwrap(), value);
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[test]
+    fn packed_struct_round_trips_with_aligned_child() {
+        let program = program();
+        let value = json!({ "tag": 1, "aligned": { "a": 4, "b": 5 } });
+        let mut out = Vec::new();
+        encode_c_type(&program, &defined("PackedOuter"), &value, &mut out).unwrap();
+
+        let mut expected = vec![0u8; 17];
+        expected[0] = 1;
+        expected[1] = 4;
+        expected[9..17].copy_from_slice(&5u64.to_le_bytes());
+        assert_eq!(out, expected);
+
+        let mut reader = ByteReader::new(&out);
+        assert_eq!(parse_c_type(&program, &defined("PackedOuter"), &mut reader).unwrap(), value);
+        assert_eq!(reader.remaining(), 0);
+    }
+}
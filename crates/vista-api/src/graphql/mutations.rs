@@ -1,5 +1,7 @@
 use async_graphql::{Context, Object};
-use vista_core::{Indexer, IndexerStorage, Pubkey};
+use solana_sdk::pubkey::Pubkey;
+use vista_core::{ConfigReloader, Indexer};
+use std::str::FromStr;
 use std::sync::Arc;
 
 pub struct MutationRoot;
@@ -7,16 +9,29 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     async fn track_account(&self, ctx: &Context<'_>, pubkey: String) -> async_graphql::Result<bool> {
-        let indexer = ctx.data::<Arc<Indexer<dyn IndexerStorage>>>()?;
+        let indexer = ctx.data::<Arc<Indexer>>()?;
         let pubkey = Pubkey::from_str(&pubkey)?;
-        indexer.track_account(pubkey);
+        indexer.track_account(pubkey).await?;
         Ok(true)
     }
 
     async fn track_program(&self, ctx: &Context<'_>, pubkey: String) -> async_graphql::Result<bool> {
-        let indexer = ctx.data::<Arc<Indexer<dyn IndexerStorage>>>()?;
+        let indexer = ctx.data::<Arc<Indexer>>()?;
         let pubkey = Pubkey::from_str(&pubkey)?;
-        indexer.track_program(pubkey);
+        indexer.track_program(pubkey).await?;
+        Ok(true)
+    }
+
+    async fn upload_idl(&self, ctx: &Context<'_>, program_id: String, idl: String, effective_slot: u64) -> async_graphql::Result<bool> {
+        let indexer = ctx.data::<Arc<Indexer>>()?;
+        indexer.add_program_idl_version(&program_id, effective_slot, &idl).await?;
+        Ok(true)
+    }
+
+    // Applies the current config file the same way a SIGHUP does
+    async fn reload_config(&self, ctx: &Context<'_>) -> async_graphql::Result<bool> {
+        let reloader = ctx.data::<Arc<ConfigReloader>>()?;
+        reloader.reload().await?;
         Ok(true)
     }
 }
\ No newline at end of file
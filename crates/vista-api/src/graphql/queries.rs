@@ -1,23 +1,65 @@
 use async_graphql::{Context, Object};
-use vista_core::{IndexerStorage, Pubkey, Signature};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use vista_core::Indexer;
+use std::str::FromStr;
 use std::sync::Arc;
-use super::schema::{Account, Transaction};
+use super::schema::{Account, IdlVersion, ProviderDivergence, ProviderLatency, Transaction};
 
 pub struct QueryRoot;
 
 #[Object]
 impl QueryRoot {
     async fn account(&self, ctx: &Context<'_>, pubkey: String) -> async_graphql::Result<Option<Account>> {
-        let storage = ctx.data::<Arc<dyn IndexerStorage>>()?;
+        let storage = ctx.data::<Arc<Indexer>>()?.storage();
         let pubkey = Pubkey::from_str(&pubkey)?;
         let account_info = storage.get_account(&pubkey).await?;
         Ok(account_info.map(Account::from))
     }
 
     async fn transaction(&self, ctx: &Context<'_>, signature: String) -> async_graphql::Result<Option<Transaction>> {
-        let storage = ctx.data::<Arc<dyn IndexerStorage>>()?;
+        let storage = ctx.data::<Arc<Indexer>>()?.storage();
         let signature = Signature::from_str(&signature)?;
         let transaction_info = storage.get_transaction(&signature).await?;
         Ok(transaction_info.map(Transaction::from))
     }
+
+    async fn failed_transactions(&self, ctx: &Context<'_>, program_id: String, error_name: Option<String>) -> async_graphql::Result<Vec<Transaction>> {
+        let storage = ctx.data::<Arc<Indexer>>()?.storage();
+        let program_id = Pubkey::from_str(&program_id)?;
+        let transactions = storage.get_failed_transactions(&program_id, error_name.as_deref()).await?;
+        Ok(transactions.into_iter().map(Transaction::from).collect())
+    }
+
+    async fn idl_versions(&self, ctx: &Context<'_>, program_id: String) -> async_graphql::Result<Vec<IdlVersion>> {
+        let indexer = ctx.data::<Arc<Indexer>>()?;
+        let versions = indexer.get_idl_versions(&program_id).await;
+        Ok(versions.into_iter().map(|v| IdlVersion { start_slot: v.start_slot, end_slot: v.end_slot }).collect())
+    }
+
+    // How each provider's reports compared with the quorum; empty unless quorum mode is on
+    async fn provider_divergence(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<ProviderDivergence>> {
+        let indexer = ctx.data::<Arc<Indexer>>()?;
+        Ok(indexer.divergence_report().into_iter().map(|d| ProviderDivergence {
+            provider: d.provider,
+            reported: d.reported,
+            agreed: d.agreed,
+            diverged: d.diverged,
+            unconfirmed: d.unconfirmed,
+        }).collect())
+    }
+
+    // Delays behind the fastest provider; empty unless latency tracking is on
+    async fn provider_latency(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<ProviderLatency>> {
+        let indexer = ctx.data::<Arc<Indexer>>()?;
+        Ok(indexer.latency_report().into_iter().map(|l| ProviderLatency {
+            provider: l.provider,
+            updates: l.updates,
+            first_seen: l.first_seen,
+            missed: l.missed,
+            missing_rate: l.missing_rate,
+            p50_delay_ms: l.p50_delay.as_secs_f64() * 1000.0,
+            p99_delay_ms: l.p99_delay.as_secs_f64() * 1000.0,
+        }).collect())
+    }
 }
\ No newline at end of file
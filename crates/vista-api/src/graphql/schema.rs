@@ -1,20 +1,110 @@
-use async_graphql::{Schema, EmptyMutation, EmptySubscription};
+use async_graphql::{Schema, SimpleObject};
 use std::sync::Arc;
 use tokio::sync::broadcast;
-use vista_core::{Indexer, IndexerStorage, AccountInfo, TransactionInfo};
+use vista_core::models::{AccountInfo, TransactionInfo};
+use vista_core::{ConfigReloader, Indexer};
 
 use super::{queries::QueryRoot, mutations::MutationRoot, subscriptions::SubscriptionRoot};
 
 pub type SolanaVistaSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
 pub fn create_schema(
-    indexer: Arc<Indexer<dyn IndexerStorage>>,
+    indexer: Arc<Indexer>,
+    reloader: Arc<ConfigReloader>,
     account_sender: broadcast::Sender<AccountInfo>,
     transaction_sender: broadcast::Sender<TransactionInfo>,
 ) -> SolanaVistaSchema {
     Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
         .data(indexer)
+        .data(reloader)
         .data(account_sender)
         .data(transaction_sender)
         .finish()
-}
\ No newline at end of file
+}
+
+#[derive(SimpleObject)]
+pub struct Account {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    // Base64, as returned by `getAccountInfo`
+    pub data: String,
+    pub slot: u64,
+}
+
+impl From<AccountInfo> for Account {
+    fn from(account: AccountInfo) -> Self {
+        Self {
+            pubkey: account.pubkey.to_string(),
+            lamports: account.lamports,
+            owner: account.owner.to_string(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: base64::encode(&account.data),
+            slot: account.slot,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Transaction {
+    pub signature: String,
+    pub slot: u64,
+    pub program_ids: Vec<String>,
+    pub invoked_program_ids: Vec<String>,
+    pub success: bool,
+    pub error_program_id: Option<String>,
+    pub error_instruction_index: Option<u8>,
+    pub error_code: Option<u32>,
+    pub error_name: Option<String>,
+    pub error_message: Option<String>,
+    // Seen before execution, with no outcome yet
+    pub pending: bool,
+}
+
+impl From<TransactionInfo> for Transaction {
+    fn from(transaction: TransactionInfo) -> Self {
+        let error = transaction.error.as_ref();
+        Self {
+            signature: transaction.signature.to_string(),
+            slot: transaction.status.slot,
+            program_ids: transaction.program_ids.iter().map(|p| p.to_string()).collect(),
+            invoked_program_ids: transaction.invoked_program_ids.iter().map(|p| p.to_string()).collect(),
+            success: transaction.status.err.is_none(),
+            error_program_id: error.map(|e| e.program_id.to_string()),
+            error_instruction_index: error.map(|e| e.instruction_index),
+            error_code: error.map(|e| e.code),
+            error_name: error.and_then(|e| e.name.clone()),
+            error_message: error.and_then(|e| e.message.clone()),
+            pending: transaction.pending,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct IdlVersion {
+    pub start_slot: u64,
+    pub end_slot: Option<u64>,
+}
+
+#[derive(SimpleObject)]
+pub struct ProviderDivergence {
+    pub provider: String,
+    pub reported: u64,
+    pub agreed: u64,
+    pub diverged: u64,
+    pub unconfirmed: u64,
+}
+
+#[derive(SimpleObject)]
+pub struct ProviderLatency {
+    pub provider: String,
+    pub updates: u64,
+    pub first_seen: u64,
+    pub missed: u64,
+    pub missing_rate: f64,
+    pub p50_delay_ms: f64,
+    pub p99_delay_ms: f64,
+}
@@ -1,7 +1,12 @@
 use async_graphql::{Context, Subscription};
 use futures::Stream;
-use vista_core::{AccountInfo, Pubkey, TransactionInfo};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use vista_core::models::{AccountInfo, TransactionInfo};
+use vista_core::Indexer;
 use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
 use super::schema::{Account, Transaction};
 
 pub struct SubscriptionRoot;
@@ -30,4 +35,26 @@ impl SubscriptionRoot {
             }
         }
     }
+
+    // Transactions seen before they execute, which are never stored; `success`
+    // means nothing for them yet
+    async fn pending_transactions(&self, ctx: &Context<'_>, program_id: Option<String>) -> impl Stream<Item = Transaction> {
+        let program_id = program_id.map(|program_id| Pubkey::from_str(&program_id).expect("Invalid program id"));
+        let mut receiver = ctx.data::<Arc<Indexer>>().unwrap().subscribe_pending_transactions();
+
+        async_stream::stream! {
+            loop {
+                match receiver.recv().await {
+                    Ok(transaction_info) => {
+                        if program_id.is_none_or(|program_id| transaction_info.invokes(&program_id)) {
+                            yield Transaction::from(transaction_info);
+                        }
+                    }
+                    // A subscriber that fell behind skips to the newest ones
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 pub mod graphql;
 pub mod rest;
 
-use actix_web::{web, App, HttpServer, HttpResponse};
+use actix_web::{web, App, HttpRequest, HttpServer, HttpResponse};
 use async_graphql::http::{GraphQLPlaygroundConfig, playground_source};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
 
@@ -15,7 +15,7 @@ pub async fn run_server(schema: SolanaVistaSchema) -> std::io::Result<()> {
             .app_data(web::Data::new(schema.clone()))
             .service(web::resource("/").to(graphql_playground))
             .service(web::resource("/graphql").to(graphql_handler))
-            .service(web::resource("/graphql_ws").to(GraphQLSubscription::new(schema.clone())))
+            .service(web::resource("/graphql_ws").route(web::get().to(graphql_subscription)))
     })
     .bind("127.0.0.1:8000")?
     .run()
@@ -30,4 +30,8 @@ async fn graphql_playground() -> HttpResponse {
 
 async fn graphql_handler(schema: web::Data<SolanaVistaSchema>, req: GraphQLRequest) -> GraphQLResponse {
     schema.execute(req.into_inner()).await.into()
-}
\ No newline at end of file
+}
+
+async fn graphql_subscription(schema: web::Data<SolanaVistaSchema>, req: HttpRequest, payload: web::Payload) -> actix_web::Result<HttpResponse> {
+    GraphQLSubscription::new(SolanaVistaSchema::clone(&schema)).start(&req, payload)
+}
@@ -0,0 +1,62 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Records the compiler and build for the plugin ABI handshake, so plugins
+// built by a different rustc, dependency graph or profile are rejected
+// instead of misread
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=VISTA_RUSTC_VERSION={}", version.trim());
+    println!("cargo:rerun-if-env-changed=RUSTC");
+
+    println!("cargo:rustc-env=VISTA_BUILD_HASH={:016x}", build_hash());
+}
+
+// Trait object layouts depend on every crate the traits reach (tokio, solana,
+// serde, ...) and on how they were compiled, so the hash covers the resolved
+// lockfile, the profile settings and vista-core's own features
+fn build_hash() -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    match find_lockfile() {
+        Some(path) => {
+            println!("cargo:rerun-if-changed={}", path.display());
+            std::fs::read(&path).unwrap_or_default().hash(&mut hasher);
+        }
+        None => println!("cargo:warning=Cargo.lock not found, plugin handshake won't cover dependency versions"),
+    }
+
+    for var in ["TARGET", "PROFILE", "OPT_LEVEL", "DEBUG", "CARGO_CFG_PANIC", "CARGO_ENCODED_RUSTFLAGS"] {
+        println!("cargo:rerun-if-env-changed={}", var);
+        var.hash(&mut hasher);
+        std::env::var(var).unwrap_or_default().hash(&mut hasher);
+    }
+
+    let mut features: Vec<String> = std::env::vars()
+        .map(|(key, _)| key)
+        .filter(|key| key.starts_with("CARGO_FEATURE_"))
+        .collect();
+    features.sort();
+    features.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+// The lockfile belongs to the workspace being built, which is above this
+// crate when it's a path dependency and above the target dir otherwise
+fn find_lockfile() -> Option<PathBuf> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").ok().map(PathBuf::from);
+    let out_dir = std::env::var("OUT_DIR").ok().map(PathBuf::from);
+    [manifest_dir, out_dir]
+        .into_iter()
+        .flatten()
+        .find_map(|start| start.ancestors().map(|dir| dir.join("Cargo.lock")).find(|path| Path::is_file(path)))
+}
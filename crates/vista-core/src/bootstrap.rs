@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use serde_json::json;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::{OptionalContext, RpcKeyedAccount};
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::models::AccountInfo;
+use crate::IndexerError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum BootstrapTarget {
+    Account(Pubkey),
+    Program(Pubkey),
+}
+
+impl BootstrapTarget {
+    fn covers(&self, account: &AccountInfo) -> bool {
+        match self {
+            BootstrapTarget::Account(pubkey) => account.pubkey == *pubkey,
+            BootstrapTarget::Program(program_id) => account.owner == *program_id,
+        }
+    }
+}
+
+// Live account updates held back while their target's snapshot loads
+#[derive(Default)]
+pub(crate) struct BootstrapBuffer {
+    pending: HashMap<BootstrapTarget, Vec<AccountInfo>>,
+}
+
+impl BootstrapBuffer {
+    pub(crate) fn start(&mut self, target: BootstrapTarget) {
+        self.pending.entry(target).or_default();
+    }
+
+    // Keeps the update if it belongs to a target that is still bootstrapping
+    pub(crate) fn buffer(&mut self, account: &AccountInfo) -> bool {
+        match self.pending.iter_mut().find(|(target, _)| target.covers(account)) {
+            Some((_, buffered)) => {
+                buffered.push(account.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Hands over what has been buffered so far; the target keeps buffering
+    pub(crate) fn take(&mut self, target: BootstrapTarget) -> Vec<AccountInfo> {
+        self.pending.get_mut(&target).map(std::mem::take).unwrap_or_default()
+    }
+
+    pub(crate) fn finish(&mut self, target: BootstrapTarget) -> Vec<AccountInfo> {
+        self.pending.remove(&target).unwrap_or_default()
+    }
+}
+
+// Orders a snapshot taken at `snapshot_slot` and the updates buffered while it
+// loaded. The snapshot already reflects everything up to its slot, so only
+// later updates are kept, in slot order and arrival order within a slot.
+pub(crate) fn merge(snapshot_slot: u64, snapshot: Vec<AccountInfo>, mut buffered: Vec<AccountInfo>) -> Vec<AccountInfo> {
+    buffered.retain(|account| account.slot > snapshot_slot);
+    buffered.sort_by_key(|account| account.slot);
+    let mut merged = snapshot;
+    merged.extend(buffered);
+    merged
+}
+
+pub(crate) async fn load_snapshot(client: &RpcClient, target: BootstrapTarget) -> Result<(u64, Vec<AccountInfo>), IndexerError> {
+    let account_config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        ..Default::default()
+    };
+    match target {
+        BootstrapTarget::Account(pubkey) => {
+            let response = client.get_multiple_accounts_with_config(&[pubkey], account_config).await
+                .map_err(|e| IndexerError::RpcError(e.to_string()))?;
+            let slot = response.context.slot;
+            let accounts = response.value.into_iter()
+                .flatten()
+                .map(|account| account_info(pubkey, account, slot))
+                .collect();
+            Ok((slot, accounts))
+        }
+        BootstrapTarget::Program(program_id) => {
+            // The typed client drops the context, and with it the slot to merge at
+            let config = RpcProgramAccountsConfig {
+                filters: None,
+                account_config,
+                with_context: Some(true),
+            };
+            let response: OptionalContext<Vec<RpcKeyedAccount>> = client
+                .send(RpcRequest::GetProgramAccounts, json!([program_id.to_string(), config]))
+                .await
+                .map_err(|e| IndexerError::RpcError(e.to_string()))?;
+            let (slot, keyed) = match response {
+                OptionalContext::Context(response) => (response.context.slot, response.value),
+                OptionalContext::NoContext(_) => {
+                    return Err(IndexerError::RpcError("getProgramAccounts returned no context slot".to_string()));
+                }
+            };
+            let mut accounts = Vec::with_capacity(keyed.len());
+            for keyed in keyed {
+                let pubkey = Pubkey::from_str(&keyed.pubkey).map_err(|e| IndexerError::RpcError(e.to_string()))?;
+                let account: Account = keyed.account.decode()
+                    .ok_or_else(|| IndexerError::RpcError(format!("Undecodable account {}", pubkey)))?;
+                accounts.push(account_info(pubkey, account, slot));
+            }
+            Ok((slot, accounts))
+        }
+    }
+}
+
+fn account_info(pubkey: Pubkey, account: Account, slot: u64) -> AccountInfo {
+    AccountInfo {
+        pubkey,
+        lamports: account.lamports,
+        owner: account.owner,
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        data: account.data,
+        slot,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(pubkey: Pubkey, owner: Pubkey, slot: u64, lamports: u64) -> AccountInfo {
+        AccountInfo { pubkey, lamports, owner, executable: false, rent_epoch: 0, data: Vec::new(), slot }
+    }
+
+    #[test]
+    fn merge_drops_updates_the_snapshot_already_covers() {
+        let (a, b, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let snapshot = vec![account(a, owner, 100, 1), account(b, owner, 100, 1)];
+        let buffered = vec![
+            account(a, owner, 102, 3),
+            account(b, owner, 99, 0),
+            account(a, owner, 100, 0),
+            account(b, owner, 101, 2),
+            account(a, owner, 101, 2),
+        ];
+
+        let merged: Vec<(Pubkey, u64, u64)> = merge(100, snapshot, buffered).iter()
+            .map(|account| (account.pubkey, account.slot, account.lamports))
+            .collect();
+        assert_eq!(merged, vec![(a, 100, 1), (b, 100, 1), (b, 101, 2), (a, 101, 2), (a, 102, 3)]);
+    }
+
+    #[test]
+    fn merge_without_buffered_updates_is_the_snapshot() {
+        let snapshot = vec![account(Pubkey::new_unique(), Pubkey::new_unique(), 7, 1)];
+        assert_eq!(merge(7, snapshot.clone(), Vec::new()).len(), 1);
+        assert!(merge(7, Vec::new(), Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn buffer_holds_only_updates_for_pending_targets() {
+        let (tracked, program, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut buffer = BootstrapBuffer::default();
+        assert!(!buffer.buffer(&account(tracked, other, 1, 1)));
+
+        buffer.start(BootstrapTarget::Account(tracked));
+        buffer.start(BootstrapTarget::Program(program));
+        assert!(buffer.buffer(&account(tracked, other, 1, 1)));
+        assert!(buffer.buffer(&account(Pubkey::new_unique(), program, 2, 1)));
+        assert!(buffer.buffer(&account(Pubkey::new_unique(), program, 3, 1)));
+        assert!(!buffer.buffer(&account(Pubkey::new_unique(), other, 4, 1)));
+
+        assert_eq!(buffer.finish(BootstrapTarget::Account(tracked)).len(), 1);
+        assert!(!buffer.buffer(&account(tracked, other, 5, 1)));
+        let programs: Vec<u64> = buffer.finish(BootstrapTarget::Program(program)).iter().map(|a| a.slot).collect();
+        assert_eq!(programs, vec![2, 3]);
+        assert!(buffer.finish(BootstrapTarget::Program(program)).is_empty());
+    }
+}
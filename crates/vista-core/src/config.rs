@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
+use crate::IndexerError;
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Config {
@@ -10,23 +15,61 @@ pub struct Config {
     pub tracked_accounts: Vec<String>,
     pub tracked_programs: Vec<ProgramConfig>,
     pub storage: StorageConfig,
+    // RPC endpoint used to load the current state of tracked accounts and programs
+    #[serde(default)]
+    pub bootstrap_rpc: Option<String>,
+    // Providers that must report identical data before it is indexed
+    #[serde(default)]
+    pub quorum: Option<usize>,
+    // Compare when each provider delivers the same updates
+    #[serde(default)]
+    pub latency_report: bool,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct ProgramConfig {
     pub address: String,
     pub idl_path: String,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct ProviderConfig {
     pub url: String,
     pub provider_type: String,
     pub priority: u8,
     // Add any other provider-specific configurations here
+    #[serde(default)]
+    pub x_token: Option<String>,
+    #[serde(default)]
+    pub commitment: Option<String>,
+    #[serde(default)]
+    pub from_slot: Option<u64>,
+    #[serde(default)]
+    pub helius_auth_header: Option<String>,
+    #[serde(default)]
+    pub quicknode_secret: Option<String>,
+    // Appends everything this provider emits to a recording at this path
+    #[serde(default)]
+    pub record_path: Option<String>,
+    // blockSubscribe endpoint of the block provider, which polls getBlock without one
+    #[serde(default)]
+    pub ws_url: Option<String>,
+    // Kafka and NATS: "yellowstone" or "accountsdb", the plugin the messages come from
+    #[serde(default)]
+    pub message_format: Option<String>,
+    // Kafka topics or NATS subjects, each with the kind of message it carries
+    // ("accounts", "transactions" or "slots")
+    #[serde(default)]
+    pub topics: HashMap<String, String>,
+    // Kafka consumer group, or the prefix of the NATS durable consumers
+    #[serde(default)]
+    pub consumer_group: Option<String>,
+    // JetStream stream holding the NATS subjects
+    #[serde(default)]
+    pub stream: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct StorageConfig {
     pub plugin: String,
     pub config: Value,
@@ -38,4 +81,104 @@ impl Config {
         let config: Config = serde_json::from_str(&contents)?;
         Ok(config)
     }
-}
\ No newline at end of file
+
+    // Catches what can be caught without connecting anywhere, so a bad edit
+    // is rejected before a reload touches the running indexer
+    pub fn validate(&self) -> Result<(), IndexerError> {
+        for account in &self.tracked_accounts {
+            Pubkey::from_str(account)
+                .map_err(|e| IndexerError::ConfigError(format!("Tracked account {}: {}", account, e)))?;
+        }
+        for program in &self.tracked_programs {
+            Pubkey::from_str(&program.address)
+                .map_err(|e| IndexerError::ConfigError(format!("Tracked program {}: {}", program.address, e)))?;
+            fs::metadata(&program.idl_path)
+                .map_err(|e| IndexerError::ConfigError(format!("IDL {}: {}", program.idl_path, e)))?;
+        }
+        for (name, provider) in &self.providers {
+            if provider.url.is_empty() {
+                return Err(IndexerError::ConfigError(format!("Provider {} has no url", name)));
+            }
+            if let Some(commitment) = &provider.commitment {
+                CommitmentLevel::from_str(commitment)
+                    .map_err(|e| IndexerError::ConfigError(format!("Provider {} commitment: {}", name, e)))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn diff(&self, new: &Config) -> ConfigDiff {
+        let is_tracked = |config: &Config, address: &str| config.tracked_programs.iter().any(|p| p.address == address);
+        let (kept_programs, added_programs) = new.tracked_programs.iter()
+            .cloned()
+            .partition(|program| is_tracked(self, &program.address));
+
+        let mut added_providers = Vec::new();
+        let mut changed_providers = Vec::new();
+        for (name, provider) in &new.providers {
+            match self.providers.get(name) {
+                None => added_providers.push(name.clone()),
+                Some(current) if current != provider => changed_providers.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+
+        let mut requires_restart = Vec::new();
+        if self.storage != new.storage {
+            requires_restart.push("storage");
+        }
+        if self.plugin_dir != new.plugin_dir {
+            requires_restart.push("plugin_dir");
+        }
+        if self.bootstrap_rpc != new.bootstrap_rpc {
+            requires_restart.push("bootstrap_rpc");
+        }
+        if self.quorum != new.quorum {
+            requires_restart.push("quorum");
+        }
+        if self.latency_report != new.latency_report {
+            requires_restart.push("latency_report");
+        }
+
+        ConfigDiff {
+            added_accounts: new.tracked_accounts.iter()
+                .filter(|account| !self.tracked_accounts.contains(account))
+                .cloned()
+                .collect(),
+            removed_accounts: self.tracked_accounts.iter()
+                .filter(|account| !new.tracked_accounts.contains(account))
+                .cloned()
+                .collect(),
+            added_programs,
+            removed_programs: self.tracked_programs.iter()
+                .filter(|program| !is_tracked(new, &program.address))
+                .map(|program| program.address.clone())
+                .collect(),
+            kept_programs,
+            added_providers,
+            removed_providers: self.providers.keys()
+                .filter(|name| !new.providers.contains_key(*name))
+                .cloned()
+                .collect(),
+            changed_providers,
+            requires_restart,
+        }
+    }
+}
+
+// Changes between two configs, grouped the way a running indexer applies them
+#[derive(Debug, Default, Clone)]
+pub struct ConfigDiff {
+    pub added_accounts: Vec<String>,
+    pub removed_accounts: Vec<String>,
+    pub added_programs: Vec<ProgramConfig>,
+    pub removed_programs: Vec<String>,
+    // Tracked before and after; their IDL files are reread in case they changed
+    pub kept_programs: Vec<ProgramConfig>,
+    pub added_providers: Vec<String>,
+    pub removed_providers: Vec<String>,
+    // Same name with different settings, reconnected with the new ones
+    pub changed_providers: Vec<String>,
+    // Settings only read at startup
+    pub requires_restart: Vec<&'static str>,
+}
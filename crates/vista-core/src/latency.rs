@@ -0,0 +1,317 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::TransactionConfirmationStatus;
+
+use crate::models::SlotStatus;
+use crate::UpdateEvent;
+
+// How long after its first arrival an update still counts for late providers
+const SETTLE_TIME: Duration = Duration::from_secs(30);
+// Delays kept per provider for the percentiles
+const DELAY_SAMPLES: usize = 10_000;
+
+// Arrival statistics of one provider against the fastest provider per update
+#[derive(Debug, Clone)]
+pub struct ProviderLatency {
+    pub provider: String,
+    pub updates: u64,
+    // Updates this provider delivered before every other provider
+    pub first_seen: u64,
+    // Updates of a kind it delivers that only other providers delivered
+    pub missed: u64,
+    pub missing_rate: f64,
+    // Delay behind the fastest provider over recent updates, zero when first
+    pub p50_delay: Duration,
+    pub p99_delay: Duration,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum UpdateKind {
+    Account,
+    Transaction,
+    Slot,
+    Block,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum UpdateKey {
+    Account(Pubkey, u64, u64),
+    // The same transaction is delivered once per commitment level
+    Transaction(Signature, Option<SlotStatus>),
+    Slot(u64, SlotStatus),
+    Block(u64),
+}
+
+impl UpdateKey {
+    fn kind(&self) -> UpdateKind {
+        match self {
+            UpdateKey::Account(..) => UpdateKind::Account,
+            UpdateKey::Transaction(..) => UpdateKind::Transaction,
+            UpdateKey::Slot(..) => UpdateKind::Slot,
+            UpdateKey::Block(_) => UpdateKind::Block,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Sighting {
+    // When each provider received it; forwarders run independently, so the
+    // earliest of these needn't be the one observed first
+    received: HashMap<String, Instant>,
+}
+
+impl Sighting {
+    fn tally(&self, providers: &mut HashMap<String, ProviderStats>) {
+        let first = match self.received.values().min() {
+            Some(first) => *first,
+            None => return,
+        };
+        let mut first_seen = false;
+        for (provider, received_at) in &self.received {
+            let stats = providers.entry(provider.clone()).or_default();
+            stats.updates += 1;
+            // Ties go to one provider only
+            if *received_at == first && !first_seen {
+                first_seen = true;
+                stats.first_seen += 1;
+            }
+            stats.record_delay(*received_at - first);
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct ProviderStats {
+    updates: u64,
+    first_seen: u64,
+    missed: u64,
+    kinds: HashSet<UpdateKind>,
+    delays: VecDeque<Duration>,
+}
+
+// Records when each provider delivered each update, identified by content
+// rather than by provider so the same update from two vendors lines up
+#[derive(Default)]
+pub(crate) struct LatencyTracker {
+    sightings: HashMap<UpdateKey, Sighting>,
+    // Sightings by first arrival, for settling them in order
+    arrivals: VecDeque<(Instant, UpdateKey)>,
+    providers: HashMap<String, ProviderStats>,
+}
+
+impl LatencyTracker {
+    // `received_at` is when the provider received the update, before it
+    // waited in any channel
+    pub(crate) fn observe(&mut self, provider: &str, event: &UpdateEvent, received_at: Instant) {
+        let key = match event {
+            UpdateEvent::AccountUpdate(account) => {
+                let mut hasher = DefaultHasher::new();
+                (account.lamports, account.owner, account.executable, account.rent_epoch, &account.data).hash(&mut hasher);
+                UpdateKey::Account(account.pubkey, account.slot, hasher.finish())
+            }
+            UpdateEvent::TransactionUpdate(transaction) => {
+                let commitment = transaction.status.confirmation_status.as_ref().map(|status| match status {
+                    TransactionConfirmationStatus::Processed => SlotStatus::Processed,
+                    TransactionConfirmationStatus::Confirmed => SlotStatus::Confirmed,
+                    TransactionConfirmationStatus::Finalized => SlotStatus::Finalized,
+                });
+                UpdateKey::Transaction(transaction.signature, commitment)
+            }
+            UpdateEvent::SlotUpdate(slot) => UpdateKey::Slot(slot.slot, slot.status),
+            UpdateEvent::BlockMetaUpdate(block_meta) => UpdateKey::Block(block_meta.slot),
+            UpdateEvent::Checkpoint(_) => return,
+        };
+        let now = Instant::now();
+        self.settle(now);
+
+        self.providers.entry(provider.to_string()).or_default().kinds.insert(key.kind());
+        let sighting = self.sightings.entry(key).or_default();
+        if sighting.received.is_empty() {
+            self.arrivals.push_back((now, key));
+        }
+        // A provider repeating itself keeps its first arrival
+        sighting.received.entry(provider.to_string()).or_insert(received_at);
+    }
+
+    // A stopped provider keeps its statistics but no longer misses updates;
+    // a replacement registered under the same name picks its kinds up again
+    pub(crate) fn retire(&mut self, provider: &str) {
+        if let Some(stats) = self.providers.get_mut(provider) {
+            stats.kinds.clear();
+        }
+    }
+
+    // Settled updates plus the ones still waiting on late providers, which
+    // can't be counted as missed yet
+    pub(crate) fn report(&self) -> Vec<ProviderLatency> {
+        let mut providers = self.providers.clone();
+        for sighting in self.sightings.values() {
+            sighting.tally(&mut providers);
+        }
+        let mut report: Vec<ProviderLatency> = providers.iter()
+            .map(|(provider, stats)| {
+                let mut delays: Vec<Duration> = stats.delays.iter().copied().collect();
+                delays.sort();
+                let percentile = |p: f64| delays.get(((delays.len() as f64 - 1.0) * p).round() as usize).copied().unwrap_or_default();
+                let expected = stats.updates + stats.missed;
+                ProviderLatency {
+                    provider: provider.clone(),
+                    updates: stats.updates,
+                    first_seen: stats.first_seen,
+                    missed: stats.missed,
+                    missing_rate: if expected == 0 { 0.0 } else { stats.missed as f64 / expected as f64 },
+                    p50_delay: percentile(0.5),
+                    p99_delay: percentile(0.99),
+                }
+            })
+            .collect();
+        report.sort_by(|a, b| a.provider.cmp(&b.provider));
+        report
+    }
+
+    // Counts settled updates as missed by every provider that delivers their
+    // kind but never delivered them
+    fn settle(&mut self, now: Instant) {
+        while let Some((observed_at, key)) = self.arrivals.front().copied() {
+            if now.duration_since(observed_at) < SETTLE_TIME {
+                break;
+            }
+            self.arrivals.pop_front();
+            let sighting = match self.sightings.remove(&key) {
+                Some(sighting) => sighting,
+                None => continue,
+            };
+            sighting.tally(&mut self.providers);
+            for (provider, stats) in self.providers.iter_mut() {
+                if stats.kinds.contains(&key.kind()) && !sighting.received.contains_key(provider) {
+                    stats.missed += 1;
+                }
+            }
+        }
+    }
+}
+
+impl ProviderStats {
+    fn record_delay(&mut self, delay: Duration) {
+        if self.delays.len() == DELAY_SAMPLES {
+            self.delays.pop_front();
+        }
+        self.delays.push_back(delay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_transaction_status::TransactionStatus;
+
+    use super::*;
+    use crate::models::{SlotInfo, TransactionInfo};
+
+    fn slot(slot: u64) -> UpdateEvent {
+        UpdateEvent::SlotUpdate(SlotInfo { slot, parent: None, status: SlotStatus::Confirmed })
+    }
+
+    fn transaction(signature: Signature, commitment: TransactionConfirmationStatus) -> UpdateEvent {
+        UpdateEvent::TransactionUpdate(TransactionInfo {
+            signature,
+            status: TransactionStatus {
+                slot: 1,
+                confirmations: None,
+                status: Ok(()),
+                err: None,
+                confirmation_status: Some(commitment),
+            },
+            program_ids: Vec::new(),
+            invoked_program_ids: Vec::new(),
+            error: None,
+            pending: false,
+        })
+    }
+
+    fn entry<'a>(report: &'a [ProviderLatency], provider: &str) -> &'a ProviderLatency {
+        report.iter().find(|entry| entry.provider == provider).unwrap()
+    }
+
+    #[test]
+    fn delays_come_from_receive_times_not_observation_order() {
+        let mut tracker = LatencyTracker::default();
+        let base = Instant::now();
+        // The slower forwarder hands over first, but "fast" received it earlier
+        tracker.observe("slow", &slot(1), base + Duration::from_millis(40));
+        tracker.observe("fast", &slot(1), base);
+        tracker.observe("fast", &slot(2), base + Duration::from_millis(100));
+        tracker.observe("slow", &slot(2), base + Duration::from_millis(110));
+        tracker.observe("fast", &slot(3), base + Duration::from_millis(200));
+        tracker.observe("slow", &slot(3), base + Duration::from_millis(210));
+
+        let report = tracker.report();
+        let (fast, slow) = (entry(&report, "fast"), entry(&report, "slow"));
+        assert_eq!((fast.updates, fast.first_seen), (3, 3));
+        assert_eq!((slow.updates, slow.first_seen), (3, 0));
+        assert_eq!(fast.p99_delay, Duration::ZERO);
+        assert_eq!(slow.p50_delay, Duration::from_millis(10));
+        assert_eq!(slow.p99_delay, Duration::from_millis(40));
+    }
+
+    #[test]
+    fn repeats_keep_the_first_arrival() {
+        let mut tracker = LatencyTracker::default();
+        let base = Instant::now();
+        tracker.observe("a", &slot(1), base);
+        tracker.observe("b", &slot(1), base + Duration::from_millis(5));
+        tracker.observe("b", &slot(1), base + Duration::from_millis(50));
+
+        let report = tracker.report();
+        assert_eq!(entry(&report, "b").updates, 1);
+        assert_eq!(entry(&report, "b").p99_delay, Duration::from_millis(5));
+    }
+
+    #[test]
+    fn each_commitment_of_a_transaction_is_its_own_update() {
+        let mut tracker = LatencyTracker::default();
+        let base = Instant::now();
+        let signature = Signature::new_unique();
+        tracker.observe("a", &transaction(signature, TransactionConfirmationStatus::Processed), base);
+        tracker.observe("b", &transaction(signature, TransactionConfirmationStatus::Confirmed), base + Duration::from_millis(400));
+        tracker.observe("a", &transaction(signature, TransactionConfirmationStatus::Confirmed), base + Duration::from_millis(450));
+
+        let report = tracker.report();
+        assert_eq!((entry(&report, "a").updates, entry(&report, "a").first_seen), (2, 1));
+        assert_eq!((entry(&report, "b").updates, entry(&report, "b").first_seen), (1, 1));
+        assert_eq!(entry(&report, "a").p99_delay, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn settled_updates_count_as_missed_by_providers_of_their_kind() {
+        let mut tracker = LatencyTracker::default();
+        let base = Instant::now();
+        tracker.observe("a", &slot(1), base);
+        tracker.observe("b", &slot(1), base);
+        tracker.observe("a", &slot(2), base);
+        tracker.settle(Instant::now() + SETTLE_TIME);
+
+        let report = tracker.report();
+        assert_eq!((entry(&report, "a").missed, entry(&report, "b").missed), (0, 1));
+        assert_eq!(entry(&report, "b").missing_rate, 0.5);
+        assert!(tracker.sightings.is_empty());
+    }
+
+    #[test]
+    fn retired_providers_stop_missing_updates() {
+        let mut tracker = LatencyTracker::default();
+        let base = Instant::now();
+        tracker.observe("a", &slot(1), base);
+        tracker.observe("b", &slot(1), base);
+        tracker.retire("b");
+        tracker.observe("a", &slot(2), base);
+        tracker.settle(Instant::now() + SETTLE_TIME);
+
+        let report = tracker.report();
+        assert_eq!((entry(&report, "b").updates, entry(&report, "b").missed), (1, 0));
+    }
+}
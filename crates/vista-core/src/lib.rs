@@ -1,20 +1,42 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::{RwLock, mpsc};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock, broadcast, mpsc, oneshot};
+use tokio::task::JoinHandle;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::Signature;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
 use thiserror::Error;
+use futures::StreamExt;
 
 pub mod traits;
 pub mod models;
 pub mod plugin_registry;
+pub mod plugin_abi;
 pub mod config;
+pub mod reload;
+mod bootstrap;
+mod latency;
+mod quorum;
+mod slot_gaps;
+mod subscriptions;
 
 pub use plugin_registry::RpcProviderRegistry;
 pub use config::Config;
+pub use reload::ConfigReloader;
+pub use latency::ProviderLatency;
+pub use quorum::ProviderDivergence;
 
-use traits::{Storage, RpcProvider};
-use models::{AccountInfo, TransactionInfo};
-use vista_anchor::AnchorParser;
+use traits::{RpcProvider, StoragePlugin, UpdateStream};
+use models::{AccountInfo, BlockMetaInfo, IdlVersionInfo, ProgramErrorInfo, SlotInfo, TransactionInfo};
+use vista_anchor::{AnchorParser, IdlVersion, IdlVersionRange};
+use bootstrap::{BootstrapBuffer, BootstrapTarget};
+use latency::LatencyTracker;
+use quorum::QuorumGate;
+use slot_gaps::{SlotGap, SlotGapDetector};
+use subscriptions::SubscriptionLedger;
 
 #[derive(Error, Debug)]
 pub enum IndexerError {
@@ -28,6 +50,14 @@ pub enum IndexerError {
     ConfigError(String),
 }
 
+// Pending transactions a slow subscriber can fall behind by before it skips ahead
+const PENDING_TRANSACTION_BUFFER: usize = 1024;
+// Waits between attempts to bootstrap a target whose snapshot failed to load
+const BOOTSTRAP_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const BOOTSTRAP_MAX_BACKOFF: Duration = Duration::from_secs(60);
+// Buffered updates drained per pass before the final one under the lock
+const BOOTSTRAP_CHUNK: usize = 1000;
+
 pub struct Indexer {
     storage: Arc<dyn StoragePlugin>,
     provider_registry: Arc<RpcProviderRegistry>,
@@ -35,27 +65,102 @@ pub struct Indexer {
     tracked_programs: Arc<RwLock<Vec<Pubkey>>>,
     update_channel: mpsc::Sender<UpdateEvent>,
     anchor_parser: Arc<RwLock<AnchorParser>>,
+    latest_slot: Arc<AtomicU64>,
+    // Loads the current state of newly tracked accounts and programs
+    bootstrap_rpc: Option<Arc<RpcClient>>,
+    bootstrap: Arc<Mutex<BootstrapBuffer>>,
+    // Targets tracked before `start`, bootstrapped once providers are streaming
+    // so the updates racing the snapshot are buffered; `None` once started
+    deferred_bootstraps: std::sync::Mutex<Option<Vec<BootstrapTarget>>>,
+    // Targets untracked while running, which providers that can't unsubscribe still deliver
+    released: Arc<RwLock<HashSet<Pubkey>>>,
+    // Which provider streams each target, so planning changes only the
+    // subscriptions that differ and never repeats one
+    subscriptions: Mutex<SubscriptionLedger>,
+    // Task moving each started provider's updates into `update_channel`
+    forwarders: std::sync::Mutex<HashMap<String, JoinHandle<()>>>,
+    gaps: mpsc::UnboundedSender<SlotGap>,
+    // Drained by `start`, which knows whether repairs need a quorum
+    gap_repairs: std::sync::Mutex<Option<mpsc::UnboundedReceiver<SlotGap>>>,
+    quorum: Option<Arc<std::sync::Mutex<QuorumGate>>>,
+    latency: Option<Arc<std::sync::Mutex<LatencyTracker>>>,
+    // Transactions seen before execution, which are never stored
+    pending_transactions: broadcast::Sender<TransactionInfo>,
+    // Drained by `start`; updates queue here until then
+    updates: std::sync::Mutex<Option<mpsc::Receiver<UpdateEvent>>>,
 }
 
 pub enum UpdateEvent {
     AccountUpdate(AccountInfo),
     TransactionUpdate(TransactionInfo),
+    SlotUpdate(SlotInfo),
+    BlockMetaUpdate(BlockMetaInfo),
+    // Answered once every event sent before it has been processed, so
+    // providers can acknowledge their source only for work that is done
+    Checkpoint(oneshot::Sender<()>),
+}
+
+// An update as a provider hands it over, stamped where the provider received
+// it so time spent queued behind other updates isn't blamed on the provider
+pub struct ReceivedUpdate {
+    pub received_at: Instant,
+    pub event: UpdateEvent,
+}
+
+impl ReceivedUpdate {
+    pub fn now(event: UpdateEvent) -> Self {
+        Self { received_at: Instant::now(), event }
+    }
 }
 
 impl Indexer {
     pub fn new(storage: Arc<dyn StoragePlugin>, provider_registry: Arc<RpcProviderRegistry>) -> Self {
         let (tx, rx) = mpsc::channel(1000);
-        let indexer = Self {
+        // Slots dropped by a provider's stream are refetched through a provider
+        // that can repair them and recorded with `store_slot_repair`
+        let (gaps, gap_rx) = mpsc::unbounded_channel();
+        let (pending_transactions, _) = broadcast::channel(PENDING_TRANSACTION_BUFFER);
+
+        Self {
             storage,
             provider_registry,
             tracked_accounts: Arc::new(RwLock::new(Vec::new())),
             tracked_programs: Arc::new(RwLock::new(Vec::new())),
             update_channel: tx,
             anchor_parser: Arc::new(RwLock::new(AnchorParser::new())),
-        };
-        
-        tokio::spawn(indexer.process_updates(rx));
-        indexer
+            latest_slot: Arc::new(AtomicU64::new(0)),
+            bootstrap_rpc: None,
+            bootstrap: Arc::new(Mutex::new(BootstrapBuffer::default())),
+            deferred_bootstraps: std::sync::Mutex::new(Some(Vec::new())),
+            released: Arc::new(RwLock::new(HashSet::new())),
+            subscriptions: Mutex::new(HashMap::new()),
+            forwarders: std::sync::Mutex::new(HashMap::new()),
+            gaps,
+            gap_repairs: std::sync::Mutex::new(Some(gap_rx)),
+            quorum: None,
+            latency: None,
+            pending_transactions,
+            updates: std::sync::Mutex::new(Some(rx)),
+        }
+    }
+
+    // Without this only changes made after an account or program is tracked get indexed
+    pub fn with_bootstrap_rpc(mut self, rpc_url: &str) -> Self {
+        self.bootstrap_rpc = Some(Arc::new(RpcClient::new(rpc_url.to_string())));
+        self
+    }
+
+    // Commits an account state or transaction only once `required` providers
+    // reported the same data for the same slot
+    pub fn with_quorum(mut self, required: usize) -> Self {
+        self.quorum = Some(Arc::new(std::sync::Mutex::new(QuorumGate::new(required))));
+        self
+    }
+
+    // Records when each provider delivers each update, for `latency_report`
+    pub fn with_latency_tracking(mut self) -> Self {
+        self.latency = Some(Arc::new(std::sync::Mutex::new(LatencyTracker::default())));
+        self
     }
 
     async fn process_updates(self: Arc<Self>, mut rx: mpsc::Receiver<UpdateEvent>) {
@@ -66,25 +171,61 @@ impl Indexer {
                         eprintln!("Failed to process account update: {}", e);
                     }
                 },
-                UpdateEvent::TransactionUpdate(transaction_info) => {
+                // Its outcome is stored once a provider reports the execution;
+                // until then it only goes to live subscribers
+                UpdateEvent::TransactionUpdate(transaction_info) if transaction_info.pending => {
+                    let _ = self.pending_transactions.send(transaction_info);
+                },
+                UpdateEvent::TransactionUpdate(mut transaction_info) => {
+                    self.decode_transaction_error(&mut transaction_info).await;
                     if let Err(e) = self.storage.store_transaction(transaction_info).await {
                         eprintln!("Failed to store transaction update: {}", e);
                     }
                 },
+                UpdateEvent::SlotUpdate(slot_info) => {
+                    self.latest_slot.fetch_max(slot_info.slot, Ordering::Relaxed);
+                },
+                UpdateEvent::BlockMetaUpdate(block_meta) => {
+                    if let Err(e) = self.storage.store_block_meta(block_meta).await {
+                        eprintln!("Failed to store block metadata: {}", e);
+                    }
+                },
+                UpdateEvent::Checkpoint(done) => {
+                    let _ = done.send(());
+                },
             }
         }
     }
 
     async fn process_account_update(&self, account_info: &AccountInfo) -> Result<(), IndexerError> {
+        let mut bootstrap = self.bootstrap.lock().await;
+        if bootstrap.buffer(account_info) {
+            return Ok(());
+        }
+        drop(bootstrap);
+        self.apply_account_update(account_info).await
+    }
+
+    async fn apply_account_update(&self, account_info: &AccountInfo) -> Result<(), IndexerError> {
         let programs = self.tracked_programs.read().await;
+        if self.is_released(account_info, &programs).await {
+            return Ok(());
+        }
         if programs.contains(&account_info.owner) {
             let parser = self.anchor_parser.read().await;
-            if let Ok(parsed_data) = parser.parse_account_data(
+            // Decode with the IDL that was active when this state was written
+            if let Ok((account_type, parsed_data)) = parser.decode_account_at_slot(
                 &account_info.owner.to_string(),
-                "account", // You might need to determine the actual account type
+                account_info.slot,
                 &account_info.data
             ) {
-                self.storage.store_parsed_account(&account_info.owner.to_string(), "account", &parsed_data).await?;
+                self.storage.store_parsed_account(
+                    &account_info.pubkey,
+                    &account_info.owner.to_string(),
+                    &account_type,
+                    account_info.slot,
+                    &parsed_data
+                ).await?;
             } else {
                 // Fall back to storing raw account data if parsing fails
                 self.storage.store_account(account_info.clone()).await?;
@@ -95,39 +236,444 @@ impl Indexer {
         Ok(())
     }
 
+    async fn is_released(&self, account_info: &AccountInfo, programs: &[Pubkey]) -> bool {
+        let released = self.released.read().await;
+        if !released.contains(&account_info.pubkey) && !released.contains(&account_info.owner) {
+            return false;
+        }
+        !programs.contains(&account_info.owner) && !self.tracked_accounts.read().await.contains(&account_info.pubkey)
+    }
+
+    async fn decode_transaction_error(&self, transaction_info: &mut TransactionInfo) {
+        let (instruction_index, code) = match &transaction_info.status.err {
+            Some(TransactionError::InstructionError(index, InstructionError::Custom(code))) => (*index, *code),
+            _ => return,
+        };
+
+        let parser = self.anchor_parser.read().await;
+        let slot = transaction_info.status.slot;
+        let lookup = |program_id: &Pubkey| parser.decode_error(&program_id.to_string(), Some(slot), code);
+        // The error is reported against the top-level instruction even when a
+        // CPI target returned it. Providers that read the logs attribute it
+        // already; otherwise the first CPI target that knows the code is taken
+        // when the top-level program doesn't.
+        let (program_id, decoded) = match transaction_info.error.as_ref().map(|error| error.program_id) {
+            Some(program_id) => (program_id, lookup(&program_id)),
+            None => {
+                let top_level = match transaction_info.program_ids.get(instruction_index as usize) {
+                    Some(program_id) => *program_id,
+                    None => return,
+                };
+                match lookup(&top_level) {
+                    Some(decoded) => (top_level, Some(decoded)),
+                    None => transaction_info.invoked_program_ids.iter()
+                        .find_map(|program_id| lookup(program_id).map(|decoded| (*program_id, Some(decoded))))
+                        .unwrap_or((top_level, None)),
+                }
+            }
+        };
+        transaction_info.error = Some(ProgramErrorInfo {
+            instruction_index,
+            program_id,
+            code,
+            name: decoded.as_ref().map(|e| e.name.clone()),
+            message: decoded.and_then(|e| e.msg),
+        });
+    }
+
     pub async fn track_account(&self, pubkey: Pubkey) -> Result<(), IndexerError> {
-        self.tracked_accounts.write().await.push(pubkey);
-        for provider in self.provider_registry.get_providers() {
-            provider.subscribe_account_updates(&pubkey).await
-                .map_err(|e| IndexerError::RpcError(e.to_string()))?;
+        {
+            let mut accounts = self.tracked_accounts.write().await;
+            if accounts.contains(&pubkey) {
+                return Ok(());
+            }
+            accounts.push(pubkey);
         }
-        Ok(())
+        self.released.write().await.remove(&pubkey);
+        // Buffer live updates from the moment of subscribing until the snapshot is merged
+        if self.bootstrap_rpc.is_some() {
+            self.bootstrap.lock().await.start(BootstrapTarget::Account(pubkey));
+        }
+        match self.reconcile(BootstrapTarget::Account(pubkey)).await {
+            Ok(true) => {}
+            Ok(false) => eprintln!("No provider streams account state; only transactions touching {} will be indexed", pubkey),
+            Err(e) => {
+                // No snapshot follows, so stop holding back its updates
+                self.bootstrap.lock().await.finish(BootstrapTarget::Account(pubkey));
+                return Err(e);
+            }
+        }
+        self.bootstrap(BootstrapTarget::Account(pubkey)).await
     }
 
     pub async fn track_program(&self, pubkey: Pubkey) -> Result<(), IndexerError> {
-        self.tracked_programs.write().await.push(pubkey);
+        {
+            let mut programs = self.tracked_programs.write().await;
+            if programs.contains(&pubkey) {
+                return Ok(());
+            }
+            programs.push(pubkey);
+        }
+        self.released.write().await.remove(&pubkey);
+        // SPL Token and Token-2022 are decoded natively, no IDL required
+        self.anchor_parser.write().await.register_builtin(&pubkey.to_string());
+        if self.bootstrap_rpc.is_some() {
+            self.bootstrap.lock().await.start(BootstrapTarget::Program(pubkey));
+        }
+        match self.reconcile(BootstrapTarget::Program(pubkey)).await {
+            Ok(true) => {}
+            Ok(false) => eprintln!("No provider streams program accounts; only transactions invoking {} will be indexed", pubkey),
+            Err(e) => {
+                // No snapshot follows, so stop holding back its updates
+                self.bootstrap.lock().await.finish(BootstrapTarget::Program(pubkey));
+                return Err(e);
+            }
+        }
+        self.bootstrap(BootstrapTarget::Program(pubkey)).await
+    }
+
+    // Already indexed state is kept; only new updates stop being indexed
+    pub async fn untrack_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.tracked_accounts.write().await.retain(|account| account != pubkey);
+        self.released.write().await.insert(*pubkey);
+        self.release(BootstrapTarget::Account(*pubkey)).await
+    }
+
+    pub async fn untrack_program(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.tracked_programs.write().await.retain(|program| program != pubkey);
+        self.released.write().await.insert(*pubkey);
+        self.release(BootstrapTarget::Program(*pubkey)).await
+    }
+
+    pub async fn tracked_accounts(&self) -> Vec<Pubkey> {
+        self.tracked_accounts.read().await.clone()
+    }
+
+    pub async fn tracked_programs(&self) -> Vec<Pubkey> {
+        self.tracked_programs.read().await.clone()
+    }
+
+    async fn tracked_targets(&self) -> Vec<BootstrapTarget> {
+        let accounts = self.tracked_accounts().await.into_iter().map(BootstrapTarget::Account);
+        let programs = self.tracked_programs().await.into_iter().map(BootstrapTarget::Program);
+        accounts.chain(programs).collect()
+    }
+
+    // Every capable provider is needed when their updates are compared
+    fn redundant(&self) -> bool {
+        self.quorum.is_some() || self.latency.is_some()
+    }
+
+    // Subscribes a target where the plan puts it and unsubscribes it elsewhere.
+    // Returns whether one of the planned providers streams the target's state.
+    async fn reconcile(&self, target: BootstrapTarget) -> Result<bool, IndexerError> {
+        let providers = self.provider_registry.get_providers();
+        let mut subscriptions = self.subscriptions.lock().await;
+        self.reconcile_with(target, &providers, &mut subscriptions).await
+    }
+
+    async fn reconcile_with(
+        &self,
+        target: BootstrapTarget,
+        providers: &[Arc<dyn RpcProvider>],
+        subscriptions: &mut SubscriptionLedger,
+    ) -> Result<bool, IndexerError> {
+        let planned = subscriptions::plan(&target, providers, self.redundant());
+        // New subscriptions go first so the target keeps streaming while it moves
+        for provider in &planned {
+            let targets = subscriptions.entry(provider.name().to_string()).or_default();
+            if !targets.contains(&target) {
+                subscriptions::subscribe(provider.as_ref(), &target).await?;
+                targets.insert(target);
+            }
+        }
+        for provider in providers {
+            if planned.iter().any(|planned| planned.name() == provider.name()) {
+                continue;
+            }
+            if subscriptions.get_mut(provider.name()).is_some_and(|targets| targets.remove(&target)) {
+                subscriptions::unsubscribe(provider.as_ref(), &target).await?;
+            }
+        }
+        Ok(planned.iter().any(|provider| subscriptions::coverage(&provider.capabilities(), &target).0))
+    }
+
+    // Replans every tracked target after the providers changed
+    async fn reconcile_all(&self, subscriptions: &mut SubscriptionLedger) -> Result<(), IndexerError> {
+        let providers = self.provider_registry.get_providers();
+        subscriptions.retain(|name, _| providers.iter().any(|provider| provider.name() == name));
+        for target in self.tracked_targets().await {
+            self.reconcile_with(target, &providers, subscriptions).await?;
+        }
+        Ok(())
+    }
+
+    async fn release(&self, target: BootstrapTarget) -> Result<(), IndexerError> {
+        let mut subscriptions = self.subscriptions.lock().await;
         for provider in self.provider_registry.get_providers() {
-            provider.subscribe_program_updates(&pubkey).await
-                .map_err(|e| IndexerError::RpcError(e.to_string()))?;
+            if subscriptions.get_mut(provider.name()).is_some_and(|targets| targets.remove(&target)) {
+                subscriptions::unsubscribe(provider.as_ref(), &target).await?;
+            }
         }
         Ok(())
     }
 
+    // Applies the target's current state followed by the live updates buffered
+    // while it loaded, so nothing is lost or applied out of order. Only the
+    // final handover holds the buffer lock, so other live updates keep flowing.
+    async fn bootstrap(&self, target: BootstrapTarget) -> Result<(), IndexerError> {
+        let client = match &self.bootstrap_rpc {
+            Some(client) => client,
+            None => return Ok(()),
+        };
+        if let Some(deferred) = self.deferred_bootstraps.lock().unwrap().as_mut() {
+            deferred.push(target);
+            return Ok(());
+        }
+        let (slot, snapshot) = match bootstrap::load_snapshot(client, target).await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                let mut buffer = self.bootstrap.lock().await;
+                for account in &buffer.finish(target) {
+                    if let Err(e) = self.apply_account_update(account).await {
+                        eprintln!("Failed to process account update: {}", e);
+                    }
+                }
+                return Err(e);
+            }
+        };
+        // The target's live updates keep buffering behind the snapshot
+        self.apply_bootstrapped(&snapshot).await;
+        loop {
+            let mut buffer = self.bootstrap.lock().await;
+            let buffered = buffer.take(target);
+            if buffered.len() > BOOTSTRAP_CHUNK {
+                drop(buffer);
+                self.apply_bootstrapped(&bootstrap::merge(slot, Vec::new(), buffered)).await;
+                continue;
+            }
+            // Live updates wait on this lock, so none can land between the last merged ones
+            buffer.finish(target);
+            self.apply_bootstrapped(&bootstrap::merge(slot, Vec::new(), buffered)).await;
+            return Ok(());
+        }
+    }
+
+    async fn apply_bootstrapped(&self, accounts: &[AccountInfo]) {
+        for account in accounts {
+            if let Err(e) = self.apply_account_update(account).await {
+                eprintln!("Failed to process bootstrapped account {}: {}", account.pubkey, e);
+            }
+        }
+    }
+
+    // Keeps retrying a failed bootstrap until it loads or the target is untracked
+    async fn retry_bootstrap(self: Arc<Self>, target: BootstrapTarget) {
+        let mut backoff = BOOTSTRAP_INITIAL_BACKOFF;
+        loop {
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(BOOTSTRAP_MAX_BACKOFF);
+            if !self.is_tracked(&target).await {
+                return;
+            }
+            self.bootstrap.lock().await.start(target);
+            match self.bootstrap(target).await {
+                Ok(()) => return,
+                Err(e) => eprintln!("Bootstrap of {:?} failed again: {}", target, e),
+            }
+        }
+    }
+
+    async fn is_tracked(&self, target: &BootstrapTarget) -> bool {
+        match target {
+            BootstrapTarget::Account(pubkey) => self.tracked_accounts.read().await.contains(pubkey),
+            BootstrapTarget::Program(program_id) => self.tracked_programs.read().await.contains(program_id),
+        }
+    }
+
     pub async fn add_program_idl(&self, program_id: &str, idl_json: &str) -> Result<(), IndexerError> {
         let mut parser = self.anchor_parser.write().await;
         parser.add_idl(program_id, idl_json)
             .map_err(|e| IndexerError::AnchorError(e.to_string()))
     }
 
+    pub async fn add_program_idl_version(&self, program_id: &str, start_slot: u64, idl_json: &str) -> Result<(), IndexerError> {
+        // Validate before persisting so a bad upload never reaches storage,
+        // and install only once stored. Decoding isn't blocked on storage.
+        let version = IdlVersion::parse(start_slot, idl_json)
+            .map_err(|e| IndexerError::AnchorError(e.to_string()))?;
+
+        self.storage.store_idl_version(IdlVersionInfo {
+            program_id: program_id.to_string(),
+            start_slot,
+            idl_json: idl_json.to_string(),
+        }).await?;
+
+        self.anchor_parser.write().await.add_parsed_idl_version(program_id, version);
+        Ok(())
+    }
+
+    pub async fn load_idl_versions(&self) -> Result<(), IndexerError> {
+        let versions = self.storage.get_idl_versions().await?;
+        let mut parser = self.anchor_parser.write().await;
+        for version in versions {
+            parser.add_idl_version(&version.program_id, version.start_slot, &version.idl_json)
+                .map_err(|e| IndexerError::AnchorError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_idl_versions(&self, program_id: &str) -> Vec<IdlVersionRange> {
+        self.anchor_parser.read().await.idl_versions(program_id)
+    }
+
+    pub fn latest_slot(&self) -> u64 {
+        self.latest_slot.load(Ordering::Relaxed)
+    }
+
+    // Empty unless quorum mode is on
+    pub fn divergence_report(&self) -> Vec<ProviderDivergence> {
+        self.quorum.as_ref().map_or_else(Vec::new, |quorum| quorum.lock().unwrap().divergence_report())
+    }
+
+    // Empty unless latency tracking is on
+    pub fn latency_report(&self) -> Vec<ProviderLatency> {
+        self.latency.as_ref().map_or_else(Vec::new, |latency| latency.lock().unwrap().report())
+    }
+
+    pub fn storage(&self) -> &Arc<dyn StoragePlugin> {
+        &self.storage
+    }
+
+    pub fn provider_registry(&self) -> &Arc<RpcProviderRegistry> {
+        &self.provider_registry
+    }
+
     pub fn get_update_channel(&self) -> mpsc::Sender<UpdateEvent> {
         self.update_channel.clone()
     }
 
-    pub async fn start(&self) -> Result<(), IndexerError> {
+    // Tracked transactions as soon as a provider sees them, before they execute
+    pub fn subscribe_pending_transactions(&self) -> broadcast::Receiver<TransactionInfo> {
+        self.pending_transactions.subscribe()
+    }
+
+    pub async fn start(self: &Arc<Self>) -> Result<(), IndexerError> {
+        if let Some(rx) = self.updates.lock().unwrap().take() {
+            tokio::spawn(self.clone().process_updates(rx));
+        }
+        if let Some(gap_rx) = self.gap_repairs.lock().unwrap().take() {
+            tokio::spawn(slot_gaps::repair_gaps(
+                gap_rx,
+                self.provider_registry.clone(),
+                self.storage.clone(),
+                self.update_channel.clone(),
+                self.quorum.clone(),
+            ));
+        }
         for provider in self.provider_registry.get_providers() {
-            provider.start().await
-                .map_err(|e| IndexerError::RpcError(e.to_string()))?;
+            self.forward(provider).await?;
+        }
+        let deferred = self.deferred_bootstraps.lock().unwrap().take().unwrap_or_default();
+        for target in deferred {
+            // Live ingestion carries on; the snapshot is applied once it loads
+            if let Err(e) = self.bootstrap(target).await {
+                eprintln!("Bootstrap of {:?} failed, retrying in the background: {}", target, e);
+                tokio::spawn(self.clone().retry_bootstrap(target));
+            }
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    // Registers and starts a provider on a running indexer, subscribed to the
+    // tracked targets the plan gives it. A provider of the same name is
+    // replaced once the new one has started, and keeps running if it fails to.
+    pub async fn add_provider(&self, provider: Box<dyn RpcProvider>) -> Result<(), IndexerError> {
+        let provider: Arc<dyn RpcProvider> = Arc::from(provider);
+        let name = provider.name().to_string();
+        let mut subscriptions = self.subscriptions.lock().await;
+        // Planned as if it had already replaced the provider of the same name
+        let mut providers: Vec<_> = self.provider_registry.get_providers()
+            .into_iter()
+            .filter(|registered| registered.name() != name)
+            .collect();
+        providers.push(provider.clone());
+        let mut targets = HashSet::new();
+        for target in self.tracked_targets().await {
+            if subscriptions::plan(&target, &providers, self.redundant()).iter().any(|planned| planned.name() == name) {
+                subscriptions::subscribe(provider.as_ref(), &target).await?;
+                targets.insert(target);
+            }
+        }
+        let updates = provider.start().await?;
+        self.stop_provider(&name);
+        self.provider_registry.register_provider(provider.clone());
+        subscriptions.insert(name, targets);
+        self.spawn_forwarder(&provider, updates);
+        // Takes over what the replaced provider streamed and drops what the new one now covers
+        self.reconcile_all(&mut subscriptions).await
+    }
+
+    // Stops a provider and moves what it streamed to the remaining ones
+    pub async fn remove_provider(&self, name: &str) -> Result<bool, IndexerError> {
+        let mut subscriptions = self.subscriptions.lock().await;
+        if !self.stop_provider(name) {
+            return Ok(false);
+        }
+        self.reconcile_all(&mut subscriptions).await?;
+        Ok(true)
+    }
+
+    // Dropping its stream ends the provider's connection tasks
+    fn stop_provider(&self, name: &str) -> bool {
+        if let Some(forwarder) = self.forwarders.lock().unwrap().remove(name) {
+            forwarder.abort();
+        }
+        if let Some(latency) = &self.latency {
+            latency.lock().unwrap().retire(name);
+        }
+        self.provider_registry.remove_provider(name).is_some()
+    }
+
+    async fn forward(&self, provider: Arc<dyn RpcProvider>) -> Result<(), IndexerError> {
+        let updates = provider.start().await?;
+        self.spawn_forwarder(&provider, updates);
+        Ok(())
+    }
+
+    fn spawn_forwarder(&self, provider: &Arc<dyn RpcProvider>, mut updates: UpdateStream) {
+        let update_channel = self.update_channel.clone();
+        let name = provider.name().to_string();
+        // Gaps can only be seen in streams that announce every slot
+        let mut detector = provider.capabilities().slots.then(SlotGapDetector::default);
+        let gaps = self.gaps.clone();
+        let quorum = self.quorum.clone();
+        let latency = self.latency.clone();
+        let provider_name = name.clone();
+        let forwarder = tokio::spawn(async move {
+            while let Some(ReceivedUpdate { received_at, event }) = updates.next().await {
+                if let Some(detector) = &mut detector {
+                    for (slot, kind) in detector.observe(&event) {
+                        let _ = gaps.send(SlotGap { slot, kind, detected_by: provider_name.clone() });
+                    }
+                }
+                if let Some(latency) = &latency {
+                    latency.lock().unwrap().observe(&provider_name, &event, received_at);
+                }
+                let event = match &quorum {
+                    Some(quorum) => match quorum.lock().unwrap().report(&provider_name, event) {
+                        Some(event) => event,
+                        None => continue,
+                    },
+                    None => event,
+                };
+                if update_channel.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+        if let Some(previous) = self.forwarders.lock().unwrap().insert(name, forwarder) {
+            previous.abort();
+        }
+    }
+}
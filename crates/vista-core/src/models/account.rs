@@ -1,6 +1,7 @@
+use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountInfo {
     pub pubkey: Pubkey,
     pub lamports: u64,
@@ -8,4 +9,5 @@ pub struct AccountInfo {
     pub executable: bool,
     pub rent_epoch: u64,
     pub data: Vec<u8>,
+    pub slot: u64,
 }
\ No newline at end of file
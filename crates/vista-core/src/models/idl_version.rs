@@ -0,0 +1,6 @@
+#[derive(Debug, Clone)]
+pub struct IdlVersionInfo {
+    pub program_id: String,
+    pub start_slot: u64,
+    pub idl_json: String,
+}
@@ -1,5 +1,9 @@
-mod accounts;
+mod account;
+mod idl_version;
+mod slot;
 mod transaction;
 
-pub use accounts::AccountInfo;
-pub use transaction::TransactionInfo;
\ No newline at end of file
+pub use account::AccountInfo;
+pub use idl_version::IdlVersionInfo;
+pub use slot::{BlockMetaInfo, SlotGapKind, SlotInfo, SlotRepairInfo, SlotStatus};
+pub use transaction::{ProgramErrorInfo, TransactionInfo};
\ No newline at end of file
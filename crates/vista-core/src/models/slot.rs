@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use solana_transaction_status::Reward;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SlotStatus {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotInfo {
+    pub slot: u64,
+    pub parent: Option<u64>,
+    pub status: SlotStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockMetaInfo {
+    pub slot: u64,
+    pub blockhash: String,
+    pub parent_slot: u64,
+    pub parent_blockhash: String,
+    pub block_time: Option<i64>,
+    pub block_height: Option<u64>,
+    pub executed_transaction_count: u64,
+    pub rewards: Vec<Reward>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SlotGapKind {
+    // Nothing from the slot arrived
+    Missing,
+    // Transactions or accounts of the slot arrived, but not the slot itself
+    Partial,
+}
+
+// Audit record of one attempt to repair a slot a provider's stream dropped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotRepairInfo {
+    pub slot: u64,
+    pub kind: SlotGapKind,
+    pub detected_by: String,
+    pub repaired_by: String,
+    pub transactions: u64,
+    // Account states as of the repaired slot; zero for repairers that can
+    // only fetch current state, which leave accounts to their next update
+    pub accounts: u64,
+    pub error: Option<String>,
+    // Unix timestamp in seconds
+    pub repaired_at: i64,
+}
@@ -1,8 +1,38 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
 use solana_transaction_status::TransactionStatus;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionInfo {
     pub signature: Signature,
     pub status: TransactionStatus,
-}
\ No newline at end of file
+    // Program invoked by each top-level instruction, in instruction order
+    pub program_ids: Vec<Pubkey>,
+    // Programs reached through CPI, once each, where the source reports them
+    #[serde(default)]
+    pub invoked_program_ids: Vec<Pubkey>,
+    // Providers that read the logs fill in the failing program; the indexer
+    // names the error and attributes it where they couldn't
+    pub error: Option<ProgramErrorInfo>,
+    // Seen before execution, e.g. in leader shreds: `status` holds no outcome
+    // yet and only the execution reported by another provider is kept
+    #[serde(default)]
+    pub pending: bool,
+}
+
+impl TransactionInfo {
+    // Whether `program_id` ran in this transaction, directly or through CPI
+    pub fn invokes(&self, program_id: &Pubkey) -> bool {
+        self.program_ids.contains(program_id) || self.invoked_program_ids.contains(program_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramErrorInfo {
+    pub instruction_index: u8,
+    pub program_id: Pubkey,
+    pub code: u32,
+    pub name: Option<String>,
+    pub message: Option<String>,
+}
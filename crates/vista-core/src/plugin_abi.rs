@@ -0,0 +1,179 @@
+use std::ffi::{c_char, c_void, CStr};
+
+use libloading::Library;
+use thiserror::Error;
+
+use crate::traits::{RpcProvider, StoragePlugin};
+
+// Plugins hand `Box<dyn Trait>` objects to the host. Rust has no stable ABI,
+// so their layout (and that of every type the traits mention) is only the
+// same when host and plugin were built by the same compiler, from the same
+// lockfile, with the same profile. Every plugin library therefore exports a
+// `PluginDeclaration` that is checked before anything else is touched.
+//
+// Even a matching build does not share state with the host: each cdylib
+// statically links its own copy of tokio and its other dependencies. A plugin
+// can't see the host's runtime through `Handle::current()`, so anything that
+// needs a reactor (tokio::net, timers, tokio::spawn) fails with "there is no
+// reactor running" unless the plugin builds and drives a runtime of its own.
+// Futures the host awaits may only use runtime-independent primitives.
+
+// Bumped whenever `PluginDeclaration` changes shape
+pub const PLUGIN_ABI_VERSION: u32 = 2;
+pub const CORE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+pub const RUSTC_VERSION: &str = concat!(env!("VISTA_RUSTC_VERSION"), "\0");
+// Hash of the resolved dependency graph and build profile, see build.rs
+pub const BUILD_HASH: &str = concat!(env!("VISTA_BUILD_HASH"), "\0");
+
+const DECLARATION_SYMBOL: &[u8] = b"VISTA_PLUGIN_DECLARATION\0";
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginKind {
+    Provider = 1,
+    Storage = 2,
+}
+
+impl PluginKind {
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            1 => Some(PluginKind::Provider),
+            2 => Some(PluginKind::Storage),
+            _ => None,
+        }
+    }
+}
+
+// Exported by every plugin through `declare_provider_plugin!` or
+// `declare_storage_plugin!`. `abi_version` stays the first field in every
+// version so an incompatible declaration is recognized before it is read.
+#[repr(C)]
+pub struct PluginDeclaration {
+    pub abi_version: u32,
+    pub kind: u32,
+    pub core_version: *const c_char,
+    pub rustc_version: *const c_char,
+    pub build_hash: *const c_char,
+    // Returns a `Box<Box<dyn Trait>>` for the declared kind, or null if the
+    // plugin's constructor panicked
+    pub create: unsafe extern "C" fn() -> *mut c_void,
+}
+
+// Only ever points at static strings
+unsafe impl Sync for PluginDeclaration {}
+
+#[derive(Error, Debug)]
+pub enum PluginAbiError {
+    #[error("Not a vista plugin: {0}")]
+    MissingDeclaration(String),
+    #[error("Plugin ABI version {found} is not supported, expected {expected}")]
+    AbiVersion { found: u32, expected: u32 },
+    #[error("Plugin is a {found} plugin, expected a {expected:?} plugin")]
+    WrongKind { found: String, expected: PluginKind },
+    #[error("Plugin was built against vista-core {found}, expected {expected}")]
+    CoreVersion { found: String, expected: String },
+    #[error("Plugin was built with {found}, expected {expected}")]
+    RustcVersion { found: String, expected: String },
+    #[error("Plugin was built from a different dependency graph or profile (build {found}, expected {expected})")]
+    BuildHash { found: String, expected: String },
+    #[error("Plugin constructor panicked")]
+    ConstructorFailed,
+}
+
+/// Builds the provider a plugin library declares.
+///
+/// # Safety
+/// The library must stay loaded for as long as the provider lives.
+pub unsafe fn load_provider_plugin(library: &Library) -> Result<Box<dyn RpcProvider>, PluginAbiError> {
+    let create = handshake(library, PluginKind::Provider)?;
+    let plugin = create() as *mut Box<dyn RpcProvider>;
+    if plugin.is_null() {
+        return Err(PluginAbiError::ConstructorFailed);
+    }
+    Ok(*Box::from_raw(plugin))
+}
+
+/// Builds the storage plugin a plugin library declares.
+///
+/// # Safety
+/// The library must stay loaded for as long as the plugin lives.
+pub unsafe fn load_storage_plugin(library: &Library) -> Result<Box<dyn StoragePlugin>, PluginAbiError> {
+    let create = handshake(library, PluginKind::Storage)?;
+    let plugin = create() as *mut Box<dyn StoragePlugin>;
+    if plugin.is_null() {
+        return Err(PluginAbiError::ConstructorFailed);
+    }
+    Ok(*Box::from_raw(plugin))
+}
+
+unsafe fn handshake(library: &Library, expected: PluginKind) -> Result<unsafe extern "C" fn() -> *mut c_void, PluginAbiError> {
+    let declaration = library.get::<*const PluginDeclaration>(DECLARATION_SYMBOL)
+        .map_err(|e| PluginAbiError::MissingDeclaration(e.to_string()))?;
+    let declaration = &**declaration;
+
+    if declaration.abi_version != PLUGIN_ABI_VERSION {
+        return Err(PluginAbiError::AbiVersion { found: declaration.abi_version, expected: PLUGIN_ABI_VERSION });
+    }
+    match PluginKind::from_raw(declaration.kind) {
+        Some(kind) if kind == expected => {}
+        Some(kind) => return Err(PluginAbiError::WrongKind { found: format!("{:?}", kind), expected }),
+        None => return Err(PluginAbiError::WrongKind { found: format!("unknown ({})", declaration.kind), expected }),
+    }
+    let check = |found: *const c_char, expected: &str| {
+        let found = CStr::from_ptr(found).to_string_lossy().into_owned();
+        let expected = expected.trim_end_matches('\0').to_string();
+        if found == expected { Ok(()) } else { Err((found, expected)) }
+    };
+    check(declaration.core_version, CORE_VERSION)
+        .map_err(|(found, expected)| PluginAbiError::CoreVersion { found, expected })?;
+    check(declaration.rustc_version, RUSTC_VERSION)
+        .map_err(|(found, expected)| PluginAbiError::RustcVersion { found, expected })?;
+    check(declaration.build_hash, BUILD_HASH)
+        .map_err(|(found, expected)| PluginAbiError::BuildHash { found, expected })?;
+    Ok(declaration.create)
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __declare_plugin {
+    ($kind:ident, $trait:path, $constructor:path) => {
+        #[no_mangle]
+        pub static VISTA_PLUGIN_DECLARATION: $crate::plugin_abi::PluginDeclaration = $crate::plugin_abi::PluginDeclaration {
+            abi_version: $crate::plugin_abi::PLUGIN_ABI_VERSION,
+            kind: $crate::plugin_abi::PluginKind::$kind as u32,
+            core_version: $crate::plugin_abi::CORE_VERSION.as_ptr() as *const ::std::ffi::c_char,
+            rustc_version: $crate::plugin_abi::RUSTC_VERSION.as_ptr() as *const ::std::ffi::c_char,
+            build_hash: $crate::plugin_abi::BUILD_HASH.as_ptr() as *const ::std::ffi::c_char,
+            create: {
+                unsafe extern "C" fn create() -> *mut ::std::ffi::c_void {
+                    // Unwinding out of an extern "C" fn aborts the host
+                    match ::std::panic::catch_unwind(|| -> Box<dyn $trait> { $constructor() }) {
+                        Ok(plugin) => Box::into_raw(Box::new(plugin)) as *mut ::std::ffi::c_void,
+                        Err(_) => ::std::ptr::null_mut(),
+                    }
+                }
+                create
+            },
+        };
+    };
+}
+
+// Exports a provider plugin from a cdylib. `$constructor` is a
+// `fn() -> Box<dyn RpcProvider>`. The plugin must be built in the host's
+// workspace and profile, and must bring its own runtime for any I/O.
+#[macro_export]
+macro_rules! declare_provider_plugin {
+    ($constructor:path) => {
+        $crate::__declare_plugin!(Provider, $crate::traits::RpcProvider, $constructor);
+    };
+}
+
+// Exports a storage plugin from a cdylib. `$constructor` is a
+// `fn() -> Box<dyn StoragePlugin>`. The plugin must be built in the host's
+// workspace and profile, and must bring its own runtime for any I/O.
+#[macro_export]
+macro_rules! declare_storage_plugin {
+    ($constructor:path) => {
+        $crate::__declare_plugin!(Storage, $crate::traits::StoragePlugin, $constructor);
+    };
+}
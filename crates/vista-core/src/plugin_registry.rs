@@ -1,46 +1,61 @@
 use std::collections::HashMap;
-use libloading::{Library, Symbol};
+use std::sync::{Arc, Mutex, RwLock};
+use libloading::Library;
+use crate::plugin_abi;
 use crate::traits::RpcProvider;
 use crate::config::Config;
 use crate::traits::StoragePlugin;
 
+// Providers can be added and removed while the indexer runs, e.g. on a config reload
 pub struct RpcProviderRegistry {
-    providers: HashMap<String, Box<dyn RpcProvider>>,
-    libraries: Vec<Library>,
+    providers: RwLock<HashMap<String, Arc<dyn RpcProvider>>>,
+    libraries: Mutex<Vec<Library>>,
+}
+
+impl Default for RpcProviderRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RpcProviderRegistry {
     pub fn new() -> Self {
         Self {
-            providers: HashMap::new(),
-            libraries: Vec::new(),
+            providers: RwLock::new(HashMap::new()),
+            libraries: Mutex::new(Vec::new()),
         }
     }
 
-    pub fn register_provider(&mut self, provider: Box<dyn RpcProvider>) {
-        self.providers.insert(provider.name().to_string(), provider);
+    pub fn register_provider(&self, provider: impl Into<Arc<dyn RpcProvider>>) -> Arc<dyn RpcProvider> {
+        let provider: Arc<dyn RpcProvider> = provider.into();
+        self.providers.write().unwrap().insert(provider.name().to_string(), provider.clone());
+        provider
+    }
+
+    pub fn remove_provider(&self, name: &str) -> Option<Arc<dyn RpcProvider>> {
+        self.providers.write().unwrap().remove(name)
     }
 
-    pub fn get_provider(&self, name: &str) -> Option<&Box<dyn RpcProvider>> {
-        self.providers.get(name)
+    pub fn get_provider(&self, name: &str) -> Option<Arc<dyn RpcProvider>> {
+        self.providers.read().unwrap().get(name).cloned()
     }
 
-    pub fn get_providers(&self) -> Vec<&Box<dyn RpcProvider>> {
-        self.providers.values().collect()
+    pub fn get_providers(&self) -> Vec<Arc<dyn RpcProvider>> {
+        self.providers.read().unwrap().values().cloned().collect()
     }
 
-    pub fn load_plugins(&mut self, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn load_plugins(&self, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
         if let Some(plugin_dir) = &config.plugin_dir {
             for entry in std::fs::read_dir(plugin_dir)? {
                 let entry = entry?;
                 let path = entry.path();
-                if path.is_file() && path.extension().map_or(false, |ext| ext == "so" || ext == "dll") {
+                if path.is_file() && path.extension().is_some_and(|ext| ext == "so" || ext == "dll") {
                     unsafe {
-                        let lib = Library::new(path)?;
-                        self.libraries.push(lib);
-                        let lib = self.libraries.last().unwrap();
-                        let constructor: Symbol<fn() -> Box<dyn RpcProvider>> = lib.get(b"_plugin_create")?;
-                        let provider = constructor();
+                        let lib = Library::new(&path)?;
+                        let provider = plugin_abi::load_provider_plugin(&lib)
+                            .map_err(|e| format!("{}: {}", path.display(), e))?;
+                        // Unloaded only when the registry is dropped
+                        self.libraries.lock().unwrap().push(lib);
                         self.register_provider(provider);
                     }
                 }
@@ -54,6 +69,12 @@ pub struct StoragePluginRegistry {
     plugins: HashMap<String, Box<dyn StoragePlugin>>,
 }
 
+impl Default for StoragePluginRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl StoragePluginRegistry {
     pub fn new() -> Self {
         Self {
@@ -65,7 +86,7 @@ impl StoragePluginRegistry {
         self.plugins.insert(plugin.name().to_string(), plugin);
     }
 
-    pub fn get_plugin(&self, name: &str) -> Option<&Box<dyn StoragePlugin>> {
-        self.plugins.get(name)
+    pub fn get_plugin(&self, name: &str) -> Option<&dyn StoragePlugin> {
+        self.plugins.get(name).map(|plugin| plugin.as_ref())
     }
 }
\ No newline at end of file
@@ -0,0 +1,352 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+use crate::UpdateEvent;
+
+// Slots a report waits for the other providers before it is given up on
+const WINDOW_SLOTS: u64 = 150;
+
+// How one provider's reports compared with what the quorum committed
+#[derive(Debug, Clone, Default)]
+pub struct ProviderDivergence {
+    pub provider: String,
+    pub reported: u64,
+    // Part of a quorum
+    pub agreed: u64,
+    // Differed from the version the quorum committed
+    pub diverged: u64,
+    // Not confirmed by enough providers, with no version committed either
+    pub unconfirmed: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum QuorumKey {
+    Account(Pubkey, u64),
+    Transaction(Signature, u64),
+}
+
+struct Vote {
+    slot: u64,
+    // Each reporter's arrival number, which orders the versions it reported
+    reporters: HashMap<String, u64>,
+    // The first reporter's update, handed out on commit
+    event: Option<UpdateEvent>,
+    committed: bool,
+}
+
+// Holds account and transaction updates back until `required` providers
+// reported the same data for the same slot. An account can change several
+// times within a slot, so every distinct version is voted on separately and
+// disagreement only shows once a slot settles: versions nobody else
+// confirmed, next to one that was committed. Only a version newer than the
+// one already committed for its key is passed on, so a version that reaches
+// quorum late can't roll the account back. Everything else passes through.
+pub(crate) struct QuorumGate {
+    required: usize,
+    votes: HashMap<(QuorumKey, u64), Vote>,
+    // Hash of the newest version committed for each key
+    newest: HashMap<QuorumKey, u64>,
+    arrivals: u64,
+    highest_slot: u64,
+    report: HashMap<String, ProviderDivergence>,
+}
+
+impl QuorumGate {
+    pub(crate) fn new(required: usize) -> Self {
+        Self {
+            required: required.max(1),
+            votes: HashMap::new(),
+            newest: HashMap::new(),
+            arrivals: 0,
+            highest_slot: 0,
+            report: HashMap::new(),
+        }
+    }
+
+    // Returns the update to commit, if this report completed a quorum
+    pub(crate) fn report(&mut self, provider: &str, event: UpdateEvent) -> Option<UpdateEvent> {
+        let (key, slot, hash) = match &event {
+            UpdateEvent::AccountUpdate(account) => {
+                let mut hasher = DefaultHasher::new();
+                (account.lamports, account.owner, account.executable, account.rent_epoch, &account.data).hash(&mut hasher);
+                (QuorumKey::Account(account.pubkey, account.slot), account.slot, hasher.finish())
+            }
+            // Nothing to vote on before execution; passed on as it arrives
+            UpdateEvent::TransactionUpdate(transaction) if transaction.pending => return Some(event),
+            UpdateEvent::TransactionUpdate(transaction) => {
+                // The signature and slot are the key; the outcome is all that
+                // can differ, and sources disagree on how they list programs
+                let mut hasher = DefaultHasher::new();
+                // TransactionError has no Hash impl; its message identifies it
+                transaction.status.err.as_ref().map(|err| err.to_string()).hash(&mut hasher);
+                let slot = transaction.status.slot;
+                (QuorumKey::Transaction(transaction.signature, slot), slot, hasher.finish())
+            }
+            _ => return Some(event),
+        };
+        if slot + WINDOW_SLOTS < self.highest_slot {
+            // Too late to count; the slot has already settled
+            return None;
+        }
+        if slot > self.highest_slot {
+            self.highest_slot = slot;
+            self.expire();
+        }
+
+        let vote = self.votes.entry((key, hash)).or_insert_with(|| Vote {
+            slot,
+            reporters: HashMap::new(),
+            event: None,
+            committed: false,
+        });
+        if vote.reporters.contains_key(provider) {
+            // A provider repeating itself doesn't count twice
+            return None;
+        }
+        vote.reporters.insert(provider.to_string(), self.arrivals);
+        self.arrivals += 1;
+        let entry = self.report.entry(provider.to_string())
+            .or_insert_with(|| ProviderDivergence { provider: provider.to_string(), ..Default::default() });
+        entry.reported += 1;
+
+        if vote.committed {
+            entry.agreed += 1;
+            return None;
+        }
+        vote.event.get_or_insert(event);
+        if vote.reporters.len() < self.required {
+            return None;
+        }
+        vote.committed = true;
+        for reporter in vote.reporters.keys() {
+            if let Some(entry) = self.report.get_mut(reporter) {
+                entry.agreed += 1;
+            }
+        }
+        let event = vote.event.take();
+
+        let version = &self.votes[&(key, hash)];
+        if let Some(committed) = self.newest.get(&key).and_then(|committed| self.votes.get(&(key, *committed))) {
+            if !is_newer(version, committed) {
+                return None;
+            }
+        }
+        self.newest.insert(key, hash);
+        event
+    }
+
+    pub(crate) fn divergence_report(&self) -> Vec<ProviderDivergence> {
+        let mut report: Vec<ProviderDivergence> = self.report.values().cloned().collect();
+        report.sort_by(|a, b| a.provider.cmp(&b.provider));
+        report
+    }
+
+    fn expire(&mut self) {
+        let cutoff = self.highest_slot.saturating_sub(WINDOW_SLOTS);
+        let (expired, kept): (HashMap<_, _>, HashMap<_, _>) = self.votes.drain()
+            .partition(|(_, vote)| vote.slot < cutoff);
+        self.votes = kept;
+        self.newest.retain(|key, _| key.slot() >= cutoff);
+
+        let settled: HashSet<QuorumKey> = expired.iter()
+            .filter(|(_, vote)| vote.committed)
+            .map(|((key, _), _)| *key)
+            .collect();
+        for ((key, _), vote) in expired.iter().filter(|(_, vote)| !vote.committed) {
+            let diverged = settled.contains(key);
+            if diverged {
+                eprintln!("{} at slot {} diverged from the quorum: {:?}", describe(key), vote.slot, vote.reporters.keys());
+            } else {
+                eprintln!("{} at slot {} never reached quorum: {:?}", describe(key), vote.slot, vote.reporters.keys());
+            }
+            for reporter in vote.reporters.keys() {
+                if let Some(entry) = self.report.get_mut(reporter) {
+                    if diverged {
+                        entry.diverged += 1;
+                    } else {
+                        entry.unconfirmed += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl QuorumKey {
+    fn slot(&self) -> u64 {
+        match self {
+            QuorumKey::Account(_, slot) | QuorumKey::Transaction(_, slot) => *slot,
+        }
+    }
+}
+
+// Providers deliver the versions of an account within a slot in write order,
+// so those that reported both versions decide which is newer. With no
+// reporter in common, the version that arrived last is taken as the newest.
+fn is_newer(version: &Vote, committed: &Vote) -> bool {
+    let (mut later, mut earlier) = (0, 0);
+    for (reporter, arrival) in &version.reporters {
+        match committed.reporters.get(reporter) {
+            Some(other) if arrival > other => later += 1,
+            Some(_) => earlier += 1,
+            None => {}
+        }
+    }
+    if later + earlier == 0 {
+        return version.reporters.values().max() > committed.reporters.values().max();
+    }
+    later > earlier
+}
+
+fn describe(key: &QuorumKey) -> String {
+    match key {
+        QuorumKey::Account(pubkey, _) => format!("account {}", pubkey),
+        QuorumKey::Transaction(signature, _) => format!("transaction {}", signature),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::transaction::TransactionError;
+    use solana_transaction_status::TransactionStatus;
+
+    use super::*;
+    use crate::models::{AccountInfo, SlotInfo, SlotStatus, TransactionInfo};
+
+    fn account(pubkey: Pubkey, slot: u64, lamports: u64) -> UpdateEvent {
+        UpdateEvent::AccountUpdate(AccountInfo {
+            pubkey,
+            lamports,
+            owner: Pubkey::default(),
+            executable: false,
+            rent_epoch: 0,
+            data: vec![1, 2, 3],
+            slot,
+        })
+    }
+
+    fn transaction(signature: Signature, slot: u64, program_ids: Vec<Pubkey>, err: Option<TransactionError>) -> UpdateEvent {
+        UpdateEvent::TransactionUpdate(TransactionInfo {
+            signature,
+            status: TransactionStatus {
+                slot,
+                confirmations: None,
+                status: err.clone().map_or(Ok(()), Err),
+                err,
+                confirmation_status: None,
+            },
+            program_ids,
+            invoked_program_ids: Vec::new(),
+            error: None,
+            pending: false,
+        })
+    }
+
+    fn entry<'a>(report: &'a [ProviderDivergence], provider: &str) -> &'a ProviderDivergence {
+        report.iter().find(|entry| entry.provider == provider).unwrap()
+    }
+
+    #[test]
+    fn commits_once_enough_providers_agree() {
+        let mut gate = QuorumGate::new(2);
+        let pubkey = Pubkey::new_unique();
+        assert!(gate.report("a", account(pubkey, 10, 5)).is_none());
+        // Repeating a report doesn't make a quorum
+        assert!(gate.report("a", account(pubkey, 10, 5)).is_none());
+        assert!(matches!(gate.report("b", account(pubkey, 10, 5)), Some(UpdateEvent::AccountUpdate(a)) if a.lamports == 5));
+        // Later confirmations are counted, not committed again
+        assert!(gate.report("c", account(pubkey, 10, 5)).is_none());
+
+        let report = gate.divergence_report();
+        assert_eq!(report.iter().map(|entry| entry.provider.as_str()).collect::<Vec<_>>(), vec!["a", "b", "c"]);
+        assert!(report.iter().all(|entry| entry.reported == 1 && entry.agreed == 1));
+    }
+
+    #[test]
+    fn versions_within_a_slot_are_voted_on_separately() {
+        let mut gate = QuorumGate::new(2);
+        let pubkey = Pubkey::new_unique();
+        assert!(gate.report("a", account(pubkey, 10, 5)).is_none());
+        assert!(gate.report("b", account(pubkey, 10, 6)).is_none());
+        assert!(matches!(gate.report("a", account(pubkey, 10, 6)), Some(UpdateEvent::AccountUpdate(a)) if a.lamports == 6));
+        // a saw 5 before 6 and b the other way round, so 5 isn't known to be
+        // newer; it's counted as agreed but not committed over 6
+        assert!(gate.report("b", account(pubkey, 10, 5)).is_none());
+        assert!(gate.divergence_report().iter().all(|entry| entry.agreed == 2));
+    }
+
+    #[test]
+    fn only_newer_versions_are_committed_over_an_earlier_one() {
+        let mut gate = QuorumGate::new(2);
+        let pubkey = Pubkey::new_unique();
+        gate.report("a", account(pubkey, 10, 5));
+        assert!(gate.report("b", account(pubkey, 10, 5)).is_some());
+        gate.report("a", account(pubkey, 10, 6));
+        assert!(matches!(gate.report("b", account(pubkey, 10, 6)), Some(UpdateEvent::AccountUpdate(a)) if a.lamports == 6));
+
+        // a saw 7 before 8, so c confirming 7 late doesn't roll 8 back
+        gate.report("a", account(pubkey, 10, 7));
+        gate.report("a", account(pubkey, 10, 8));
+        assert!(gate.report("b", account(pubkey, 10, 8)).is_some());
+        assert!(gate.report("c", account(pubkey, 10, 7)).is_none());
+    }
+
+    #[test]
+    fn pending_transactions_do_not_vote() {
+        let mut gate = QuorumGate::new(2);
+        let signature = Signature::new_unique();
+        let mut pending = transaction(signature, 10, Vec::new(), None);
+        if let UpdateEvent::TransactionUpdate(info) = &mut pending {
+            info.pending = true;
+        }
+        assert!(gate.report("shreds", pending).is_some());
+        assert!(gate.report("a", transaction(signature, 10, Vec::new(), None)).is_none());
+        assert!(gate.report("b", transaction(signature, 10, Vec::new(), None)).is_some());
+        assert!(gate.divergence_report().iter().all(|entry| entry.provider != "shreds"));
+    }
+
+    #[test]
+    fn transactions_agree_on_outcome_whatever_programs_are_listed() {
+        let mut gate = QuorumGate::new(2);
+        let signature = Signature::new_unique();
+        let program = Pubkey::new_unique();
+        assert!(gate.report("a", transaction(signature, 10, vec![program, program], None)).is_none());
+        assert!(gate.report("b", transaction(signature, 10, vec![program], None)).is_some());
+
+        // A different outcome is a different version
+        let signature = Signature::new_unique();
+        assert!(gate.report("a", transaction(signature, 10, vec![program], None)).is_none());
+        let failed = Some(TransactionError::AccountInUse);
+        assert!(gate.report("b", transaction(signature, 10, vec![program], failed)).is_none());
+    }
+
+    #[test]
+    fn settled_slots_report_divergence_and_reject_late_votes() {
+        let mut gate = QuorumGate::new(2);
+        let (committed, lonely) = (Pubkey::new_unique(), Pubkey::new_unique());
+        gate.report("a", account(committed, 10, 5));
+        gate.report("b", account(committed, 10, 5));
+        gate.report("c", account(committed, 10, 9));
+        gate.report("c", account(lonely, 10, 1));
+
+        // Advancing past the window settles slot 10
+        gate.report("a", account(Pubkey::new_unique(), 10 + WINDOW_SLOTS + 1, 1));
+        let report = gate.divergence_report();
+        assert_eq!((entry(&report, "c").diverged, entry(&report, "c").unconfirmed), (1, 1));
+        assert_eq!((entry(&report, "a").diverged, entry(&report, "a").unconfirmed), (0, 0));
+
+        assert!(gate.report("b", account(lonely, 10, 1)).is_none());
+        assert_eq!(entry(&gate.divergence_report(), "b").reported, 1);
+    }
+
+    #[test]
+    fn other_updates_pass_through() {
+        let mut gate = QuorumGate::new(3);
+        let slot = UpdateEvent::SlotUpdate(SlotInfo { slot: 1, parent: None, status: SlotStatus::Processed });
+        assert!(gate.report("a", slot).is_some());
+    }
+}
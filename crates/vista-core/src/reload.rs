@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::Mutex;
+use vista_anchor::AnchorParser;
+
+use crate::config::{Config, ConfigDiff, ProviderConfig};
+use crate::traits::RpcProvider;
+use crate::{Indexer, IndexerError};
+
+// How often the config file's modification time is checked
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+// Builds the provider configured under `name`, or `None` for a provider_type it doesn't know
+pub type ProviderFactory = Box<dyn Fn(&str, &ProviderConfig) -> Result<Option<Box<dyn RpcProvider>>, IndexerError> + Send + Sync>;
+
+// Applies edits of the config file to a running indexer: tracked accounts and
+// programs, IDLs and providers. Storage and plugin settings need a restart.
+pub struct ConfigReloader {
+    path: PathBuf,
+    indexer: Arc<Indexer>,
+    provider_factory: ProviderFactory,
+    // Also serializes reloads
+    current: Mutex<Config>,
+    // Registry name of the provider built for each config entry
+    provider_names: Mutex<HashMap<String, String>>,
+}
+
+impl ConfigReloader {
+    pub fn new(path: impl Into<PathBuf>, config: Config, indexer: Arc<Indexer>, provider_factory: ProviderFactory) -> Self {
+        Self {
+            path: path.into(),
+            indexer,
+            provider_factory,
+            current: Mutex::new(config),
+            provider_names: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Registers the providers of the initial config, before the indexer starts
+    pub async fn register_providers(&self) -> Result<(), IndexerError> {
+        let current = self.current.lock().await;
+        let mut names = self.provider_names.lock().await;
+        for (name, config) in &current.providers {
+            match (self.provider_factory)(name, config)? {
+                Some(provider) => {
+                    names.insert(name.clone(), provider.name().to_string());
+                    self.indexer.provider_registry().register_provider(provider);
+                }
+                None => eprintln!("Provider {} has unknown type {}; ignored", name, config.provider_type),
+            }
+        }
+        Ok(())
+    }
+
+    // Rereads the config file and applies what changed. Everything that can
+    // fail without side effects (parsing, validation, IDLs, building
+    // providers) happens first, so a bad config leaves the pipeline as it was.
+    // The returned diff holds the changes that took effect.
+    pub async fn reload(&self) -> Result<ConfigDiff, IndexerError> {
+        let mut current = self.current.lock().await;
+        let config = Config::from_file(&self.path)
+            .map_err(|e| IndexerError::ConfigError(e.to_string()))?;
+        config.validate()?;
+        let mut diff = current.diff(&config);
+
+        let mut idls = Vec::new();
+        let mut parser = AnchorParser::new();
+        for program in diff.added_programs.iter().chain(&diff.kept_programs) {
+            let idl_json = std::fs::read_to_string(&program.idl_path)
+                .map_err(|e| IndexerError::ConfigError(format!("IDL {}: {}", program.idl_path, e)))?;
+            parser.add_idl(&program.address, &idl_json)
+                .map_err(|e| IndexerError::AnchorError(format!("{}: {}", program.idl_path, e)))?;
+            idls.push((program.address.clone(), idl_json));
+        }
+
+        let mut providers = Vec::new();
+        for name in diff.added_providers.iter().chain(&diff.changed_providers) {
+            match (self.provider_factory)(name, &config.providers[name])? {
+                Some(provider) => providers.push((name.clone(), provider)),
+                None => eprintln!("Provider {} has unknown type {}; ignored", name, config.providers[name].provider_type),
+            }
+        }
+
+        // `current` records the provider entries actually in effect, so the
+        // next reload retries any that failed to start
+        let mut names = self.provider_names.lock().await;
+        for name in &diff.removed_providers {
+            if let Some(registered) = names.remove(name) {
+                self.remove_provider(&registered).await;
+            }
+            current.providers.remove(name);
+        }
+        for (name, provider) in providers {
+            match self.apply_provider(&name, provider, &config.providers[&name], current.providers.get(&name), &mut names).await {
+                Some(applied) => current.providers.insert(name, applied),
+                None => current.providers.remove(&name),
+            };
+        }
+        drop(names);
+
+        // Failures from here on are logged and the rest still applied; `current`
+        // leaves out what couldn't be tracked, so the next reload retries it.
+        // IDLs go first so accounts of newly tracked programs are decoded
+        for (address, idl_json) in &idls {
+            if let Err(e) = self.indexer.add_program_idl(address, idl_json).await {
+                eprintln!("Failed to load the IDL of {}: {}", address, e);
+            }
+        }
+        // Untracking always takes effect; a failure only leaves a provider streaming it
+        for account in &diff.removed_accounts {
+            if let Err(e) = self.indexer.untrack_account(&parse_pubkey(account)?).await {
+                eprintln!("Untracked account {} but couldn't unsubscribe it: {}", account, e);
+            }
+        }
+        for address in &diff.removed_programs {
+            if let Err(e) = self.indexer.untrack_program(&parse_pubkey(address)?).await {
+                eprintln!("Untracked program {} but couldn't unsubscribe it: {}", address, e);
+            }
+        }
+        let mut untracked = Vec::new();
+        for account in &diff.added_accounts {
+            let pubkey = parse_pubkey(account)?;
+            if let Err(e) = self.indexer.track_account(pubkey).await {
+                eprintln!("Failed to track account {}: {}", account, e);
+                if let Err(e) = self.indexer.untrack_account(&pubkey).await {
+                    eprintln!("Couldn't unsubscribe {} after failing to track it: {}", account, e);
+                }
+                untracked.push(account.clone());
+            }
+        }
+        for program in &diff.added_programs {
+            let pubkey = parse_pubkey(&program.address)?;
+            if let Err(e) = self.indexer.track_program(pubkey).await {
+                eprintln!("Failed to track program {}: {}", program.address, e);
+                if let Err(e) = self.indexer.untrack_program(&pubkey).await {
+                    eprintln!("Couldn't unsubscribe {} after failing to track it: {}", program.address, e);
+                }
+                untracked.push(program.address.clone());
+            }
+        }
+        for setting in &diff.requires_restart {
+            eprintln!("{} changed in {}; restart to apply it", setting, self.path.display());
+        }
+
+        let providers = std::mem::take(&mut current.providers);
+        *current = Config { providers, ..config };
+        current.tracked_accounts.retain(|account| !untracked.contains(account));
+        current.tracked_programs.retain(|program| !untracked.contains(&program.address));
+        diff.added_accounts.retain(|account| !untracked.contains(account));
+        diff.added_programs.retain(|program| !untracked.contains(&program.address));
+        Ok(diff)
+    }
+
+    // Starts the provider built for `name`, replacing the running one only
+    // once it has started. Some can't run next to the one they replace (a
+    // webhook's bind address), so the old one is then stopped first and
+    // rebuilt if the new one still fails. Returns the entry now in effect.
+    async fn apply_provider(
+        &self,
+        name: &str,
+        provider: Box<dyn RpcProvider>,
+        config: &ProviderConfig,
+        previous: Option<&ProviderConfig>,
+        names: &mut HashMap<String, String>,
+    ) -> Option<ProviderConfig> {
+        let registered = provider.name().to_string();
+        let error = match self.indexer.add_provider(provider).await {
+            Ok(()) => {
+                // A changed entry can build a provider registered under another name
+                if let Some(replaced) = names.insert(name.to_string(), registered.clone()) {
+                    if replaced != registered {
+                        self.remove_provider(&replaced).await;
+                    }
+                }
+                return Some(config.clone());
+            }
+            Err(e) => e,
+        };
+        let previous = match previous {
+            Some(previous) => previous,
+            None => {
+                eprintln!("Failed to start provider {}: {}", name, error);
+                return None;
+            }
+        };
+
+        eprintln!("Failed to start provider {} next to the running one: {}", name, error);
+        if let Some(replaced) = names.remove(name) {
+            self.remove_provider(&replaced).await;
+        }
+        match self.start_provider(name, config, names).await {
+            Ok(()) => return Some(config.clone()),
+            Err(e) => eprintln!("Failed to start provider {}: {}", name, e),
+        }
+        match self.start_provider(name, previous, names).await {
+            Ok(()) => {
+                eprintln!("Restored the previous {} provider", name);
+                Some(previous.clone())
+            }
+            Err(e) => {
+                eprintln!("Failed to restore provider {}, it is stopped: {}", name, e);
+                None
+            }
+        }
+    }
+
+    // The provider is stopped even when its targets can't all be moved to
+    // another one; the next provider change replans them
+    async fn remove_provider(&self, name: &str) {
+        if let Err(e) = self.indexer.remove_provider(name).await {
+            eprintln!("Stopped provider {} but couldn't move its subscriptions: {}", name, e);
+        }
+    }
+
+    async fn start_provider(&self, name: &str, config: &ProviderConfig, names: &mut HashMap<String, String>) -> Result<(), IndexerError> {
+        let provider = (self.provider_factory)(name, config)?
+            .ok_or_else(|| IndexerError::ConfigError(format!("Provider {} has unknown type {}", name, config.provider_type)))?;
+        let registered = provider.name().to_string();
+        self.indexer.add_provider(provider).await?;
+        names.insert(name.to_string(), registered);
+        Ok(())
+    }
+
+    // Reloads whenever the config file is modified or the process gets SIGHUP
+    pub fn watch(self: Arc<Self>) {
+        let reloader = self.clone();
+        tokio::spawn(async move {
+            let mut last_modified = reloader.modified();
+            let mut interval = tokio::time::interval(WATCH_INTERVAL);
+            loop {
+                interval.tick().await;
+                let modified = reloader.modified();
+                if modified != last_modified {
+                    last_modified = modified;
+                    reloader.reload_logged().await;
+                }
+            }
+        });
+
+        #[cfg(unix)]
+        tokio::spawn(async move {
+            use tokio::signal::unix::{signal, SignalKind};
+            let mut hangups = match signal(SignalKind::hangup()) {
+                Ok(hangups) => hangups,
+                Err(e) => {
+                    eprintln!("Failed to listen for SIGHUP: {}", e);
+                    return;
+                }
+            };
+            while hangups.recv().await.is_some() {
+                self.reload_logged().await;
+            }
+        });
+    }
+
+    async fn reload_logged(&self) {
+        match self.reload().await {
+            Ok(_) => println!("Reloaded {}", self.path.display()),
+            Err(e) => eprintln!("Rejected {}, keeping the running config: {}", self.path.display(), e),
+        }
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok()
+    }
+}
+
+fn parse_pubkey(address: &str) -> Result<Pubkey, IndexerError> {
+    Pubkey::from_str(address).map_err(|e| IndexerError::ConfigError(format!("{}: {}", address, e)))
+}
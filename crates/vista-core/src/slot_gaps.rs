@@ -0,0 +1,368 @@
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::sync::mpsc;
+
+use crate::models::{SlotGapKind, SlotRepairInfo};
+use crate::quorum::QuorumGate;
+use crate::traits::StoragePlugin;
+use crate::{RpcProviderRegistry, UpdateEvent};
+
+// Slots a gap stays open for a late arrival before it is reported
+const GRACE_SLOTS: u64 = 32;
+// Slots remembered for classifying gaps and deduplicating repairs
+const WINDOW_SLOTS: u64 = 4096;
+// Larger jumps are an outage, not a gap, and are left to the bootstrap
+const MAX_GAP_SLOTS: u64 = 1000;
+
+pub(crate) struct SlotGap {
+    pub(crate) slot: u64,
+    pub(crate) kind: SlotGapKind,
+    pub(crate) detected_by: String,
+}
+
+// Follows one provider's stream and reports the slots it skipped over
+#[derive(Default)]
+pub(crate) struct SlotGapDetector {
+    highest: Option<u64>,
+    // Slots below `highest` the provider hasn't announced yet
+    open: BTreeSet<u64>,
+    // Slots transactions or accounts arrived for
+    with_updates: BTreeSet<u64>,
+}
+
+impl SlotGapDetector {
+    // Returns the gaps whose grace period ended with this event
+    pub(crate) fn observe(&mut self, event: &UpdateEvent) -> Vec<(u64, SlotGapKind)> {
+        match event {
+            UpdateEvent::SlotUpdate(slot_info) => self.announce(slot_info.slot, slot_info.parent),
+            UpdateEvent::BlockMetaUpdate(block_meta) => self.announce(block_meta.slot, Some(block_meta.parent_slot)),
+            UpdateEvent::TransactionUpdate(transaction) => {
+                self.with_updates.insert(transaction.status.slot);
+                Vec::new()
+            }
+            UpdateEvent::AccountUpdate(account) => {
+                self.with_updates.insert(account.slot);
+                Vec::new()
+            }
+            UpdateEvent::Checkpoint(_) => Vec::new(),
+        }
+    }
+
+    fn announce(&mut self, slot: u64, parent: Option<u64>) -> Vec<(u64, SlotGapKind)> {
+        self.open.remove(&slot);
+        // Slots between a block and its parent were skipped by their leaders
+        if let Some(parent) = parent {
+            self.close(parent + 1..slot);
+        }
+        let highest = match self.highest {
+            Some(highest) if slot > highest => highest,
+            Some(_) => return Vec::new(),
+            None => {
+                self.highest = Some(slot);
+                return Vec::new();
+            }
+        };
+        self.highest = Some(slot);
+
+        if slot - highest > MAX_GAP_SLOTS {
+            eprintln!("Slot stream jumped from {} to {}; not repairing the skipped range", highest, slot);
+        } else {
+            let skipped = parent.map_or(slot, |parent| parent.min(slot) + 1);
+            self.open.extend((highest + 1..slot).filter(|s| *s < skipped));
+        }
+
+        let remaining = self.open.split_off(&slot.saturating_sub(GRACE_SLOTS));
+        let due = std::mem::replace(&mut self.open, remaining);
+        self.with_updates = self.with_updates.split_off(&slot.saturating_sub(WINDOW_SLOTS));
+        due.into_iter()
+            .map(|slot| {
+                let kind = if self.with_updates.contains(&slot) { SlotGapKind::Partial } else { SlotGapKind::Missing };
+                (slot, kind)
+            })
+            .collect()
+    }
+
+    fn close(&mut self, slots: Range<u64>) {
+        let closed: Vec<u64> = self.open.range(slots).copied().collect();
+        for slot in closed {
+            self.open.remove(&slot);
+        }
+    }
+}
+
+// Refetches every reported gap through a provider that can repair slots and
+// records each attempt. Repaired updates go through the indexer's update
+// channel so they are processed like live ones, and in quorum mode count as
+// the repairer's votes like its streamed updates would.
+pub(crate) async fn repair_gaps(
+    mut gaps: mpsc::UnboundedReceiver<SlotGap>,
+    provider_registry: Arc<RpcProviderRegistry>,
+    storage: Arc<dyn StoragePlugin>,
+    update_channel: mpsc::Sender<UpdateEvent>,
+    quorum: Option<Arc<std::sync::Mutex<QuorumGate>>>,
+) {
+    // Slots several providers dropped are only repaired once; a failed
+    // attempt leaves the slot open for the next report of the same gap
+    let mut repaired = BTreeSet::new();
+    while let Some(gap) = gaps.recv().await {
+        if repaired.contains(&gap.slot) {
+            continue;
+        }
+
+        let repairer = provider_registry.get_providers().into_iter()
+            .find(|provider| provider.capabilities().repair && provider.name() != gap.detected_by);
+        let repairer = match repairer {
+            Some(repairer) => repairer,
+            None => {
+                eprintln!("{} dropped slot {} and no provider can repair it", gap.detected_by, gap.slot);
+                continue;
+            }
+        };
+
+        let mut repair = SlotRepairInfo {
+            slot: gap.slot,
+            kind: gap.kind,
+            detected_by: gap.detected_by,
+            repaired_by: repairer.name().to_string(),
+            transactions: 0,
+            accounts: 0,
+            error: None,
+            repaired_at: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64),
+        };
+        match repairer.repair_slot(gap.slot).await {
+            // Skipped by its leader, nothing was dropped
+            Ok(None) => {
+                mark_repaired(&mut repaired, gap.slot);
+                continue;
+            }
+            Ok(Some(events)) => {
+                mark_repaired(&mut repaired, gap.slot);
+                for event in events {
+                    match &event {
+                        UpdateEvent::TransactionUpdate(_) => repair.transactions += 1,
+                        UpdateEvent::AccountUpdate(_) => repair.accounts += 1,
+                        _ => {}
+                    }
+                    let event = match &quorum {
+                        Some(quorum) => match quorum.lock().unwrap().report(&repair.repaired_by, event) {
+                            Some(event) => event,
+                            None => continue,
+                        },
+                        None => event,
+                    };
+                    if update_channel.send(event).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to repair slot {}: {}", repair.slot, e);
+                repair.error = Some(e.to_string());
+            }
+        }
+        if let Err(e) = storage.store_slot_repair(repair).await {
+            eprintln!("Failed to store slot repair: {}", e);
+        }
+    }
+}
+
+fn mark_repaired(repaired: &mut BTreeSet<u64>, slot: u64) {
+    repaired.insert(slot);
+    *repaired = repaired.split_off(&slot.saturating_sub(WINDOW_SLOTS));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use serde_json::Value;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+
+    use super::*;
+    use crate::models::{AccountInfo, BlockMetaInfo, IdlVersionInfo, SlotInfo, SlotStatus, TransactionInfo};
+    use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+    use crate::IndexerError;
+
+    fn slot(slot: u64, parent: Option<u64>) -> UpdateEvent {
+        UpdateEvent::SlotUpdate(SlotInfo { slot, parent, status: SlotStatus::Confirmed })
+    }
+
+    fn account(slot: u64) -> UpdateEvent {
+        UpdateEvent::AccountUpdate(AccountInfo {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1,
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+            data: Vec::new(),
+            slot,
+        })
+    }
+
+    // Announces each slot as the child of the previous one and returns the
+    // gaps reported along the way, keyed by the slot that reported them
+    fn advance(detector: &mut SlotGapDetector, slots: Range<u64>) -> Vec<(u64, (u64, SlotGapKind))> {
+        slots
+            .flat_map(|s| detector.observe(&slot(s, Some(s - 1))).into_iter().map(move |gap| (s, gap)))
+            .collect()
+    }
+
+    #[test]
+    fn reports_gaps_once_their_grace_period_ends() {
+        let mut detector = SlotGapDetector::default();
+        detector.observe(&slot(100, Some(99)));
+        detector.observe(&slot(104, Some(103)));
+        // A late arrival within the grace period closes its gap
+        detector.observe(&slot(103, Some(102)));
+        detector.observe(&account(102));
+
+        assert_eq!(advance(&mut detector, 105..134), Vec::new());
+        assert_eq!(advance(&mut detector, 134..140), vec![
+            (134, (101, SlotGapKind::Missing)),
+            (135, (102, SlotGapKind::Partial)),
+        ]);
+    }
+
+    #[test]
+    fn slots_skipped_by_their_leader_are_not_gaps() {
+        let mut detector = SlotGapDetector::default();
+        detector.observe(&slot(100, Some(99)));
+        // 102..105 were skipped, but the parent 101 itself still has to arrive
+        detector.observe(&slot(105, Some(101)));
+        detector.observe(&slot(101, Some(100)));
+        // A later block can also reveal skipped slots after the fact
+        detector.observe(&slot(110, None));
+        detector.observe(&slot(109, Some(105)));
+
+        assert_eq!(advance(&mut detector, 111..200), Vec::new());
+    }
+
+    #[test]
+    fn older_slots_do_not_move_the_tip() {
+        let mut detector = SlotGapDetector::default();
+        detector.observe(&slot(100, Some(99)));
+        detector.observe(&slot(90, Some(89)));
+
+        assert_eq!(advance(&mut detector, 101..200), Vec::new());
+    }
+
+    #[test]
+    fn outages_are_left_to_the_bootstrap() {
+        let mut detector = SlotGapDetector::default();
+        detector.observe(&slot(100, Some(99)));
+        let resumed = 101 + MAX_GAP_SLOTS;
+        detector.observe(&slot(resumed, Some(resumed - 1)));
+
+        assert_eq!(advance(&mut detector, resumed + 1..resumed + 100), Vec::new());
+    }
+
+    // Fails its first repair, then succeeds
+    struct FlakyRepairer {
+        attempts: Mutex<u32>,
+    }
+
+    #[async_trait]
+    impl RpcProvider for FlakyRepairer {
+        fn name(&self) -> &str {
+            "repairer"
+        }
+        fn provider_type(&self) -> RpcProviderType {
+            RpcProviderType::Http
+        }
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities { repair: true, ..Default::default() }
+        }
+        async fn subscribe_account(&self, _pubkey: &Pubkey) -> Result<(), IndexerError> {
+            Ok(())
+        }
+        async fn subscribe_program(&self, _program_id: &Pubkey) -> Result<(), IndexerError> {
+            Ok(())
+        }
+        async fn start(&self) -> Result<UpdateStream, IndexerError> {
+            Ok(Box::pin(futures::stream::empty()))
+        }
+        async fn repair_slot(&self, gap: u64) -> Result<Option<Vec<UpdateEvent>>, IndexerError> {
+            let mut attempts = self.attempts.lock().unwrap();
+            *attempts += 1;
+            match *attempts {
+                1 => Err(IndexerError::RpcError("unavailable".to_string())),
+                _ => Ok(Some(vec![slot(gap, None)])),
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct RepairLog {
+        repairs: Mutex<Vec<SlotRepairInfo>>,
+    }
+
+    #[async_trait]
+    impl StoragePlugin for RepairLog {
+        fn name(&self) -> &str {
+            "repair-log"
+        }
+        async fn init(&self, _config: &Value) -> Result<(), IndexerError> {
+            Ok(())
+        }
+        async fn store_account(&self, _account: AccountInfo) -> Result<(), IndexerError> {
+            Ok(())
+        }
+        async fn store_transaction(&self, _transaction: TransactionInfo) -> Result<(), IndexerError> {
+            Ok(())
+        }
+        async fn get_account(&self, _pubkey: &Pubkey) -> Result<Option<AccountInfo>, IndexerError> {
+            Ok(None)
+        }
+        async fn get_transaction(&self, _signature: &Signature) -> Result<Option<TransactionInfo>, IndexerError> {
+            Ok(None)
+        }
+        async fn get_failed_transactions(&self, _program_id: &Pubkey, _error_name: Option<&str>) -> Result<Vec<TransactionInfo>, IndexerError> {
+            Ok(Vec::new())
+        }
+        async fn store_parsed_account(&self, _pubkey: &Pubkey, _program_id: &str, _account_type: &str, _slot: u64, _data: &Value) -> Result<(), IndexerError> {
+            Ok(())
+        }
+        async fn store_block_meta(&self, _block_meta: BlockMetaInfo) -> Result<(), IndexerError> {
+            Ok(())
+        }
+        async fn store_idl_version(&self, _version: IdlVersionInfo) -> Result<(), IndexerError> {
+            Ok(())
+        }
+        async fn get_idl_versions(&self) -> Result<Vec<IdlVersionInfo>, IndexerError> {
+            Ok(Vec::new())
+        }
+        async fn store_slot_repair(&self, repair: SlotRepairInfo) -> Result<(), IndexerError> {
+            self.repairs.lock().unwrap().push(repair);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_repairs_are_retried_on_the_next_report() {
+        let registry = Arc::new(RpcProviderRegistry::new());
+        let repairer: Arc<dyn RpcProvider> = Arc::new(FlakyRepairer { attempts: Mutex::new(0) });
+        registry.register_provider(repairer);
+        let storage = Arc::new(RepairLog::default());
+        let (gap_sender, gaps) = mpsc::unbounded_channel();
+        let (update_channel, mut updates) = mpsc::channel(16);
+
+        for detected_by in ["a", "b", "c"] {
+            let gap = SlotGap { slot: 7, kind: SlotGapKind::Missing, detected_by: detected_by.to_string() };
+            gap_sender.send(gap).unwrap();
+        }
+        drop(gap_sender);
+        repair_gaps(gaps, registry, storage.clone(), update_channel, None).await;
+
+        // "a"'s report failed, "b"'s repaired the slot and "c"'s was a duplicate
+        let repairs = storage.repairs.lock().unwrap();
+        let attempts: Vec<_> = repairs.iter().map(|r| (r.detected_by.as_str(), r.error.is_some())).collect();
+        assert_eq!(attempts, vec![("a", true), ("b", false)]);
+        assert!(matches!(updates.try_recv(), Ok(UpdateEvent::SlotUpdate(info)) if info.slot == 7));
+        assert!(updates.try_recv().is_err());
+    }
+}
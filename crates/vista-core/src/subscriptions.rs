@@ -0,0 +1,175 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::IndexerError;
+use crate::bootstrap::BootstrapTarget;
+use crate::traits::{ProviderCapabilities, RpcProvider};
+
+// Targets each registered provider is subscribed to, by provider name
+pub(crate) type SubscriptionLedger = HashMap<String, HashSet<BootstrapTarget>>;
+
+// What a provider delivers for a target: its state, and the transactions touching it
+pub(crate) fn coverage(capabilities: &ProviderCapabilities, target: &BootstrapTarget) -> (bool, bool) {
+    let state = match target {
+        BootstrapTarget::Account(_) => capabilities.accounts,
+        BootstrapTarget::Program(_) => capabilities.programs,
+    };
+    (state, capabilities.transactions)
+}
+
+// Picks the providers a target is subscribed on. Its state and its
+// transactions are each taken from one provider, preferring providers that
+// deliver both, so the same updates don't stream in from every provider.
+// `redundant` keeps every capable provider, as quorum votes and latency
+// comparisons need them all. Providers that repair slots only refetch their
+// own subscriptions, so they are kept too once a stream can report gaps, and
+// no other provider stands in for one that sees transactions before execution.
+pub(crate) fn plan(target: &BootstrapTarget, providers: &[Arc<dyn RpcProvider>], redundant: bool) -> Vec<Arc<dyn RpcProvider>> {
+    let gaps_detected = providers.iter().any(|provider| provider.capabilities().slots);
+    let mut candidates: Vec<_> = providers
+        .iter()
+        .filter_map(|provider| {
+            let capabilities = provider.capabilities();
+            let (state, transactions) = coverage(&capabilities, target);
+            let pending = capabilities.pending_transactions;
+            let kept = (capabilities.repair && gaps_detected) || pending;
+            (state || transactions || pending).then_some((state, transactions, kept, provider))
+        })
+        .collect();
+    // Sorted by name so a replan keeps the providers it picked before
+    candidates.sort_by(|a, b| (!(a.0 && a.1), a.3.name()).cmp(&(!(b.0 && b.1), b.3.name())));
+
+    let (mut has_state, mut has_transactions) = (false, false);
+    candidates
+        .into_iter()
+        .filter(|(state, transactions, kept, _)| {
+            let adds = (*state && !has_state) || (*transactions && !has_transactions);
+            has_state |= state;
+            has_transactions |= transactions;
+            redundant || *kept || adds
+        })
+        .map(|(_, _, _, provider)| provider.clone())
+        .collect()
+}
+
+pub(crate) async fn subscribe(provider: &dyn RpcProvider, target: &BootstrapTarget) -> Result<(), IndexerError> {
+    match target {
+        BootstrapTarget::Account(pubkey) => provider.subscribe_account(pubkey).await,
+        BootstrapTarget::Program(program_id) => provider.subscribe_program(program_id).await,
+    }
+}
+
+pub(crate) async fn unsubscribe(provider: &dyn RpcProvider, target: &BootstrapTarget) -> Result<(), IndexerError> {
+    match target {
+        BootstrapTarget::Account(pubkey) => provider.unsubscribe_account(pubkey).await,
+        BootstrapTarget::Program(program_id) => provider.unsubscribe_program(program_id).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use solana_sdk::pubkey::Pubkey;
+    use crate::traits::{RpcProviderType, UpdateStream};
+
+    struct Capable {
+        name: &'static str,
+        capabilities: ProviderCapabilities,
+    }
+
+    #[async_trait]
+    impl RpcProvider for Capable {
+        fn name(&self) -> &str {
+            self.name
+        }
+        fn provider_type(&self) -> RpcProviderType {
+            RpcProviderType::WebSocket
+        }
+        fn capabilities(&self) -> ProviderCapabilities {
+            self.capabilities.clone()
+        }
+        async fn subscribe_account(&self, _pubkey: &Pubkey) -> Result<(), IndexerError> {
+            Ok(())
+        }
+        async fn subscribe_program(&self, _program_id: &Pubkey) -> Result<(), IndexerError> {
+            Ok(())
+        }
+        async fn start(&self) -> Result<UpdateStream, IndexerError> {
+            Ok(Box::pin(futures::stream::empty()))
+        }
+    }
+
+    fn provider(name: &'static str, capabilities: ProviderCapabilities) -> Arc<dyn RpcProvider> {
+        Arc::new(Capable { name, capabilities })
+    }
+
+    fn names(planned: Vec<Arc<dyn RpcProvider>>) -> Vec<String> {
+        planned.iter().map(|provider| provider.name().to_string()).collect()
+    }
+
+    fn streams_everything() -> ProviderCapabilities {
+        ProviderCapabilities { accounts: true, programs: true, transactions: true, ..Default::default() }
+    }
+
+    #[test]
+    fn one_provider_serves_a_target_that_several_could() {
+        let providers = vec![
+            provider("websocket-b", streams_everything()),
+            provider("websocket-a", streams_everything()),
+            provider("transactions", ProviderCapabilities { transactions: true, ..Default::default() }),
+        ];
+        let target = BootstrapTarget::Account(Pubkey::new_unique());
+
+        assert_eq!(names(plan(&target, &providers, false)), vec!["websocket-a"]);
+        let mut redundant = names(plan(&target, &providers, true));
+        redundant.sort();
+        assert_eq!(redundant, vec!["transactions", "websocket-a", "websocket-b"]);
+    }
+
+    #[test]
+    fn state_and_transactions_come_from_separate_providers_when_needed() {
+        let providers = vec![
+            provider("accounts", ProviderCapabilities { accounts: true, ..Default::default() }),
+            provider("blocks", ProviderCapabilities { transactions: true, ..Default::default() }),
+            provider("more-blocks", ProviderCapabilities { transactions: true, ..Default::default() }),
+        ];
+
+        let account = BootstrapTarget::Account(Pubkey::new_unique());
+        assert_eq!(names(plan(&account, &providers, false)), vec!["accounts", "blocks"]);
+        // Only accounts subscribed one by one are streamed by `accounts`
+        let program = BootstrapTarget::Program(Pubkey::new_unique());
+        assert_eq!(names(plan(&program, &providers, false)), vec!["blocks"]);
+    }
+
+    #[test]
+    fn providers_of_pending_transactions_are_always_kept() {
+        let providers = vec![
+            provider("geyser", streams_everything()),
+            provider("shreds", ProviderCapabilities { pending_transactions: true, ..Default::default() }),
+        ];
+        let target = BootstrapTarget::Program(Pubkey::new_unique());
+        assert_eq!(names(plan(&target, &providers, false)), vec!["geyser", "shreds"]);
+    }
+
+    #[test]
+    fn providers_that_serve_nothing_for_the_target_are_skipped() {
+        let providers = vec![provider("slots", ProviderCapabilities { slots: true, ..Default::default() })];
+        assert!(plan(&BootstrapTarget::Program(Pubkey::new_unique()), &providers, true).is_empty());
+    }
+
+    #[test]
+    fn repairing_providers_stay_subscribed_while_gaps_can_be_detected() {
+        let http = ProviderCapabilities { repair: true, ..streams_everything() };
+        let target = BootstrapTarget::Account(Pubkey::new_unique());
+
+        let providers = vec![provider("geyser", streams_everything()), provider("http", http.clone())];
+        assert_eq!(names(plan(&target, &providers, false)), vec!["geyser"]);
+
+        let providers = vec![
+            provider("geyser", ProviderCapabilities { slots: true, ..streams_everything() }),
+            provider("http", http),
+        ];
+        assert_eq!(names(plan(&target, &providers, false)), vec!["geyser", "http"]);
+    }
+}
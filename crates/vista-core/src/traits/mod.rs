@@ -0,0 +1,5 @@
+pub mod rpc_provider;
+pub mod storage;
+
+pub use rpc_provider::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+pub use storage::StoragePlugin;
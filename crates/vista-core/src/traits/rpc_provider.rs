@@ -1,21 +1,75 @@
+use std::pin::Pin;
+
 use async_trait::async_trait;
+use futures::Stream;
+use solana_sdk::commitment_config::CommitmentLevel;
 use solana_sdk::pubkey::Pubkey;
-use crate::models::{AccountInfo, TransactionInfo};
-use crate::IndexerError;
+use crate::{IndexerError, ReceivedUpdate, UpdateEvent};
+
+// Updates produced by a started provider, in the order it observed them
+pub type UpdateStream = Pin<Box<dyn Stream<Item = ReceivedUpdate> + Send>>;
 
 #[async_trait]
 pub trait RpcProvider: Send + Sync {
     fn name(&self) -> &str;
     fn provider_type(&self) -> RpcProviderType;
-    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<AccountInfo>, IndexerError>;
-    async fn get_transaction(&self, signature: &Signature) -> Result<Option<TransactionInfo>, IndexerError>;
-    async fn subscribe_account_updates(&self, pubkey: &Pubkey) -> Result<(), IndexerError>;
-    async fn subscribe_program_updates(&self, program_id: &Pubkey) -> Result<(), IndexerError>;
-    async fn process_updates(&self) -> Result<(), IndexerError>;
+    fn capabilities(&self) -> ProviderCapabilities;
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError>;
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError>;
+    // Providers that can't narrow a running subscription keep delivering the
+    // target; the indexer drops updates for untracked targets
+    async fn unsubscribe_account(&self, _pubkey: &Pubkey) -> Result<(), IndexerError> {
+        Ok(())
+    }
+    async fn unsubscribe_program(&self, _program_id: &Pubkey) -> Result<(), IndexerError> {
+        Ok(())
+    }
+    // Can only be called once; subscriptions made afterwards apply to the same stream
+    async fn start(&self) -> Result<UpdateStream, IndexerError>;
+    // Refetches what a slot held for the current subscriptions, for slots
+    // another provider's stream dropped. `None` means no block was produced.
+    // Account updates may only be returned with their state at that slot.
+    async fn repair_slot(&self, _slot: u64) -> Result<Option<Vec<UpdateEvent>>, IndexerError> {
+        Err(IndexerError::RpcError(format!("{} cannot repair slots", self.name())))
+    }
 }
 
 pub enum RpcProviderType {
     Geyser,
     WebSocket,
+    Grpc,
     Http,
-}
\ No newline at end of file
+    Webhook,
+}
+
+// What a provider can deliver, so subscriptions are only sent where they can be served
+#[derive(Debug, Clone, Default)]
+pub struct ProviderCapabilities {
+    // State updates of individually subscribed accounts
+    pub accounts: bool,
+    // State updates of every account owned by a subscribed program
+    pub programs: bool,
+    // Transactions touching subscribed accounts or programs
+    pub transactions: bool,
+    // The same, seen before they execute and without an outcome (`TransactionInfo::pending`)
+    pub pending_transactions: bool,
+    pub slots: bool,
+    pub blocks: bool,
+    // memcmp/dataSize filters on program subscriptions
+    pub program_filters: bool,
+    // Can resume from a past slot or offset instead of only the live tip
+    pub replay: bool,
+    // Implements `repair_slot`
+    pub repair: bool,
+    pub commitments: Vec<CommitmentLevel>,
+}
+
+impl ProviderCapabilities {
+    pub fn serves_accounts(&self) -> bool {
+        self.accounts || self.transactions
+    }
+
+    pub fn serves_programs(&self) -> bool {
+        self.programs || self.transactions
+    }
+}
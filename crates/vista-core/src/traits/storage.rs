@@ -1,5 +1,7 @@
 use async_trait::async_trait;
-use crate::models::{AccountInfo, TransactionInfo};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use crate::models::{AccountInfo, BlockMetaInfo, IdlVersionInfo, SlotRepairInfo, TransactionInfo};
 use crate::IndexerError;
 use serde_json::Value;
 
@@ -11,5 +13,10 @@ pub trait StoragePlugin: Send + Sync {
     async fn store_transaction(&self, transaction: TransactionInfo) -> Result<(), IndexerError>;
     async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<AccountInfo>, IndexerError>;
     async fn get_transaction(&self, signature: &Signature) -> Result<Option<TransactionInfo>, IndexerError>;
-    async fn store_parsed_account(&self, program_id: &str, account_type: &str, data: &Value) -> Result<(), IndexerError>;
+    async fn get_failed_transactions(&self, program_id: &Pubkey, error_name: Option<&str>) -> Result<Vec<TransactionInfo>, IndexerError>;
+    async fn store_parsed_account(&self, pubkey: &Pubkey, program_id: &str, account_type: &str, slot: u64, data: &Value) -> Result<(), IndexerError>;
+    async fn store_block_meta(&self, block_meta: BlockMetaInfo) -> Result<(), IndexerError>;
+    async fn store_idl_version(&self, version: IdlVersionInfo) -> Result<(), IndexerError>;
+    async fn get_idl_versions(&self) -> Result<Vec<IdlVersionInfo>, IndexerError>;
+    async fn store_slot_repair(&self, repair: SlotRepairInfo) -> Result<(), IndexerError>;
 }
\ No newline at end of file
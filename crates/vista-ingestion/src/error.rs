@@ -1,4 +1,5 @@
 use thiserror::Error;
+use vista_core::IndexerError;
 
 #[derive(Error, Debug)]
 pub enum IngestionError {
@@ -8,4 +9,12 @@ pub enum IngestionError {
     SubscriptionError(String),
     #[error("Plugin load error: {0}")]
     PluginLoadError(String),
-}
\ No newline at end of file
+    #[error("Snapshot error: {0}")]
+    SnapshotError(String),
+}
+
+impl From<IngestionError> for IndexerError {
+    fn from(error: IngestionError) -> Self {
+        IndexerError::RpcError(error.to_string())
+    }
+}
@@ -2,6 +2,7 @@ pub mod traits;
 pub mod providers;
 pub mod plugin_registry;
 pub mod error;
+pub mod snapshot;
 
 pub use plugin_registry::ProviderPluginRegistry;
 pub use error::IngestionError;
\ No newline at end of file
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
-use libloading::{Library, Symbol};
+use libloading::Library;
+use vista_core::plugin_abi;
 use crate::traits::RpcProvider;
 use crate::error::IngestionError;
 
@@ -8,6 +9,12 @@ pub struct ProviderPluginRegistry {
     libraries: Vec<Library>,
 }
 
+impl Default for ProviderPluginRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ProviderPluginRegistry {
     pub fn new() -> Self {
         Self {
@@ -25,19 +32,18 @@ impl ProviderPluginRegistry {
             let lib = Library::new(path).map_err(|e| IngestionError::PluginLoadError(e.to_string()))?;
             self.libraries.push(lib);
             let lib = self.libraries.last().unwrap();
-            let constructor: Symbol<fn() -> Box<dyn RpcProvider>> = lib.get(b"_create_provider")
-                .map_err(|e| IngestionError::PluginLoadError(e.to_string()))?;
-            let provider = constructor();
+            let provider = plugin_abi::load_provider_plugin(lib)
+                .map_err(|e| IngestionError::PluginLoadError(format!("{}: {}", path, e)))?;
             self.register_provider(provider);
         }
         Ok(())
     }
 
-    pub fn get_provider(&self, name: &str) -> Option<&Box<dyn RpcProvider>> {
-        self.providers.get(name)
+    pub fn get_provider(&self, name: &str) -> Option<&dyn RpcProvider> {
+        self.providers.get(name).map(|provider| provider.as_ref())
     }
 
-    pub fn get_providers(&self) -> Vec<&Box<dyn RpcProvider>> {
-        self.providers.values().collect()
+    pub fn get_providers(&self) -> Vec<&dyn RpcProvider> {
+        self.providers.values().map(|provider| provider.as_ref()).collect()
     }
 }
\ No newline at end of file
@@ -0,0 +1,444 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcBlockConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter};
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{TransactionDetails, UiConfirmedBlock, UiTransactionEncoding};
+use tokio::sync::{Mutex, RwLock};
+use vista_core::{IndexerError, UpdateEvent};
+
+use crate::error::IngestionError;
+use crate::providers::{block_events, update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// getBlocks rejects ranges wider than this
+const MAX_BLOCKS_RANGE: u64 = 500_000;
+
+#[derive(Debug, Clone)]
+pub enum BlockSource {
+    // `blockSubscribe` on this WebSocket endpoint, with gaps filled through getBlock
+    Subscribe(String),
+    // Sequential getBlock calls
+    Poll,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockProviderConfig {
+    pub source: BlockSource,
+    // Blocks are only served at confirmed or finalized; processed is raised to confirmed
+    pub commitment: CommitmentLevel,
+    // First slot to index; defaults to the tip at startup
+    pub start_slot: Option<u64>,
+    pub poll_interval: Duration,
+}
+
+impl BlockProviderConfig {
+    pub fn new(source: BlockSource) -> Self {
+        Self {
+            source,
+            commitment: CommitmentLevel::Confirmed,
+            start_slot: None,
+            poll_interval: Duration::from_millis(400),
+        }
+    }
+}
+
+// Indexes whole blocks. Every produced slot after the start slot is processed
+// exactly once and in order: slots are enumerated with getBlocks, so a slot is
+// only passed over when the cluster reports it skipped.
+pub struct BlockProvider {
+    name: String,
+    inner: Arc<Inner>,
+    updates: UpdateReceiver,
+}
+
+struct Inner {
+    client: RpcClient,
+    config: BlockProviderConfig,
+    update_channel: UpdateSender,
+    tracked: RwLock<HashSet<Pubkey>>,
+    // Next slot to process; `None` until the start slot is resolved
+    next_slot: Mutex<Option<u64>>,
+}
+
+impl BlockProvider {
+    pub fn new(rpc_url: &str, mut config: BlockProviderConfig) -> Self {
+        if config.commitment == CommitmentLevel::Processed {
+            config.commitment = CommitmentLevel::Confirmed;
+        }
+        let commitment = CommitmentConfig { commitment: config.commitment };
+        let (update_channel, updates) = update_channel();
+        Self {
+            name: "block".to_string(),
+            inner: Arc::new(Inner {
+                client: RpcClient::new_with_commitment(rpc_url.to_string(), commitment),
+                next_slot: Mutex::new(config.start_slot),
+                config,
+                update_channel,
+                tracked: RwLock::new(HashSet::new()),
+            }),
+            updates,
+        }
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl RpcProvider for BlockProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        match self.inner.config.source {
+            BlockSource::Subscribe(_) => RpcProviderType::WebSocket,
+            BlockSource::Poll => RpcProviderType::Http,
+        }
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            transactions: true,
+            slots: true,
+            blocks: true,
+            replay: true,
+            commitments: vec![CommitmentLevel::Confirmed, CommitmentLevel::Finalized],
+            ..Default::default()
+        }
+    }
+
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.tracked.write().await.insert(*pubkey);
+        Ok(())
+    }
+
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.tracked.write().await.insert(*program_id);
+        Ok(())
+    }
+
+    async fn unsubscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.tracked.write().await.remove(pubkey);
+        Ok(())
+    }
+
+    async fn unsubscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.tracked.write().await.remove(program_id);
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        let updates = self.updates.take()?;
+        let inner = self.inner.clone();
+        tokio::spawn(async move { inner.run().await });
+        Ok(updates)
+    }
+}
+
+impl Inner {
+    fn commitment(&self) -> CommitmentConfig {
+        CommitmentConfig { commitment: self.config.commitment }
+    }
+
+    async fn run(&self) {
+        let mut backoff = INITIAL_BACKOFF;
+        while !self.update_channel.is_closed() {
+            let result = match &self.config.source {
+                BlockSource::Subscribe(ws_url) => self.stream_blocks(ws_url, &mut backoff).await,
+                BlockSource::Poll => self.poll_blocks(&mut backoff).await,
+            };
+            if let Err(e) = result {
+                eprintln!("Block provider error: {}", e);
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    async fn poll_blocks(&self, backoff: &mut Duration) -> Result<(), IngestionError> {
+        let mut interval = tokio::time::interval(self.config.poll_interval);
+        while !self.update_channel.is_closed() {
+            interval.tick().await;
+            let tip = self.client.get_slot().await
+                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+            self.catch_up(tip).await?;
+            *backoff = INITIAL_BACKOFF;
+        }
+        Ok(())
+    }
+
+    async fn stream_blocks(&self, ws_url: &str, backoff: &mut Duration) -> Result<(), IngestionError> {
+        let pubsub = PubsubClient::new(ws_url).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let (mut blocks, unsubscribe) = pubsub.block_subscribe(RpcBlockSubscribeFilter::All, Some(RpcBlockSubscribeConfig {
+            commitment: Some(self.commitment()),
+            encoding: Some(UiTransactionEncoding::Base64),
+            transaction_details: Some(TransactionDetails::Full),
+            show_rewards: Some(true),
+            max_supported_transaction_version: Some(0),
+        })).await
+            .map_err(|e| IngestionError::SubscriptionError(e.to_string()))?;
+        *backoff = INITIAL_BACKOFF;
+
+        while let Some(update) = blocks.next().await {
+            let update = update.value;
+            if let Some(err) = update.err {
+                eprintln!("blockSubscribe error at slot {}: {:?}", update.slot, err);
+                continue;
+            }
+            let block = match update.block {
+                Some(block) => block,
+                None => continue,
+            };
+
+            // Fetch whatever was missed since the last block, e.g. across a reconnect
+            if update.slot > 0 {
+                self.catch_up(update.slot - 1).await?;
+            }
+            let mut next_slot = self.next_slot.lock().await;
+            if next_slot.is_none_or(|next| update.slot >= next) {
+                self.process_block(update.slot, block).await?;
+                *next_slot = Some(update.slot + 1);
+            }
+            if self.update_channel.is_closed() {
+                break;
+            }
+        }
+        unsubscribe().await;
+        Ok(())
+    }
+
+    // Processes every produced slot from the cursor up to and including `until`
+    async fn catch_up(&self, until: u64) -> Result<(), IngestionError> {
+        let mut next_slot = self.next_slot.lock().await;
+        let mut next = match *next_slot {
+            Some(next) => next,
+            // Nothing processed yet: start at the tip rather than replaying history
+            None => until,
+        };
+
+        while next <= until {
+            let end = until.min(next + MAX_BLOCKS_RANGE - 1);
+            let slots = self.client.get_blocks_with_commitment(next, Some(end), self.commitment()).await
+                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+            for slot in slots {
+                let block = self.client.get_block_with_config(slot, RpcBlockConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    transaction_details: Some(TransactionDetails::Full),
+                    rewards: Some(true),
+                    commitment: Some(self.commitment()),
+                    max_supported_transaction_version: Some(0),
+                }).await
+                    .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                self.process_block(slot, block).await?;
+                // Advance per block so a failure resumes right after the last processed one
+                *next_slot = Some(slot + 1);
+            }
+            next = end + 1;
+            *next_slot = Some(next);
+        }
+        Ok(())
+    }
+
+    async fn process_block(&self, slot: u64, block: UiConfirmedBlock) -> Result<(), IngestionError> {
+        let events = block_events(slot, block, &*self.tracked.read().await, self.config.commitment);
+        for event in events {
+            self.send(event).await?;
+        }
+        Ok(())
+    }
+
+    async fn send(&self, event: UpdateEvent) -> Result<(), IngestionError> {
+        self.update_channel.send(event).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+    use serde_json::{json, Value};
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::Message;
+    use solana_sdk::signature::Signature;
+    use solana_sdk::transaction::Transaction;
+    use solana_transaction_status::Encodable;
+    use vista_core::models::SlotStatus;
+
+    use super::*;
+    use crate::providers::scripted_client;
+
+    // `start` and `end` of every getBlocks request
+    type Ranges = Arc<std::sync::Mutex<Vec<(u64, u64)>>>;
+
+    fn provider(client: RpcClient, next_slot: Option<u64>) -> (Inner, UpdateStream) {
+        let (update_channel, updates) = update_channel();
+        let inner = Inner {
+            client,
+            config: BlockProviderConfig::new(BlockSource::Poll),
+            update_channel,
+            tracked: RwLock::new(HashSet::new()),
+            next_slot: Mutex::new(next_slot),
+        };
+        (inner, updates.take().unwrap())
+    }
+
+    fn drain(updates: &mut UpdateStream) -> Vec<UpdateEvent> {
+        std::iter::from_fn(|| updates.next().now_or_never().flatten()).map(|update| update.event).collect()
+    }
+
+    fn slots(events: &[UpdateEvent]) -> Vec<u64> {
+        events.iter().filter_map(|event| match event {
+            UpdateEvent::SlotUpdate(info) => Some(info.slot),
+            _ => None,
+        }).collect()
+    }
+
+    fn block_json(slot: u64, transactions: Vec<Value>) -> Value {
+        json!({
+            "previousBlockhash": Hash::new_unique().to_string(),
+            "blockhash": Hash::new_unique().to_string(),
+            "parentSlot": slot - 1,
+            "transactions": transactions,
+            "rewards": [],
+            "blockTime": null,
+            "blockHeight": null,
+        })
+    }
+
+    // Serves the `produced` slots to getBlocks and an empty block for each of
+    // them, recording the requested ranges. The first getBlock of `failing` fails.
+    fn chain(produced: Vec<u64>, failing: Option<u64>) -> (RpcClient, Ranges) {
+        let ranges = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = ranges.clone();
+        let mut failing = failing;
+        let client = scripted_client(move |request, params| match request {
+            RpcRequest::GetBlocks => {
+                let (start, end) = (params[0].as_u64()?, params[1].as_u64()?);
+                recorded.lock().unwrap().push((start, end));
+                Some(json!(produced.iter().filter(|slot| (start..=end).contains(*slot)).collect::<Vec<_>>()))
+            }
+            RpcRequest::GetBlock => {
+                let slot = params[0].as_u64()?;
+                if failing == Some(slot) {
+                    failing = None;
+                    return None;
+                }
+                Some(block_json(slot, Vec::new()))
+            }
+            other => panic!("unexpected {}", other),
+        });
+        (client, ranges)
+    }
+
+    #[tokio::test]
+    async fn only_produced_slots_are_processed_in_order() {
+        let (client, ranges) = chain(vec![10, 12, 13, 15], None);
+        let (inner, mut updates) = provider(client, Some(10));
+
+        inner.catch_up(13).await.unwrap();
+        assert_eq!(slots(&drain(&mut updates)), vec![10, 12, 13]);
+        inner.catch_up(15).await.unwrap();
+        assert_eq!(slots(&drain(&mut updates)), vec![15]);
+        // Already past the tip, so nothing is requested
+        inner.catch_up(15).await.unwrap();
+        assert!(drain(&mut updates).is_empty());
+
+        assert_eq!(*ranges.lock().unwrap(), vec![(10, 13), (14, 15)]);
+        assert_eq!(*inner.next_slot.lock().await, Some(16));
+    }
+
+    #[tokio::test]
+    async fn without_a_start_slot_indexing_begins_at_the_tip() {
+        let (client, ranges) = chain(vec![5, 20], None);
+        let (inner, mut updates) = provider(client, None);
+
+        inner.catch_up(20).await.unwrap();
+        assert_eq!(slots(&drain(&mut updates)), vec![20]);
+        assert_eq!(*ranges.lock().unwrap(), vec![(20, 20)]);
+    }
+
+    #[tokio::test]
+    async fn a_failed_block_is_retried_without_repeating_earlier_ones() {
+        let (client, _) = chain(vec![10, 11, 12], Some(11));
+        let (inner, mut updates) = provider(client, Some(10));
+
+        assert!(inner.catch_up(12).await.is_err());
+        assert_eq!(slots(&drain(&mut updates)), vec![10]);
+        assert_eq!(*inner.next_slot.lock().await, Some(11));
+
+        inner.catch_up(12).await.unwrap();
+        assert_eq!(slots(&drain(&mut updates)), vec![11, 12]);
+    }
+
+    #[tokio::test]
+    async fn wide_ranges_are_enumerated_in_chunks() {
+        let (client, ranges) = chain(Vec::new(), None);
+        let (inner, _updates) = provider(client, Some(0));
+
+        inner.catch_up(MAX_BLOCKS_RANGE + 10).await.unwrap();
+        assert_eq!(*ranges.lock().unwrap(), vec![(0, MAX_BLOCKS_RANGE - 1), (MAX_BLOCKS_RANGE, MAX_BLOCKS_RANGE + 10)]);
+        assert_eq!(*inner.next_slot.lock().await, Some(MAX_BLOCKS_RANGE + 11));
+    }
+
+    #[tokio::test]
+    async fn blocks_emit_tracked_transactions_then_their_slot_and_meta() {
+        let (client, _) = chain(Vec::new(), None);
+        let (inner, mut updates) = provider(client, None);
+        let (tracked, program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        inner.tracked.write().await.insert(tracked);
+
+        // One transaction writing the tracked account and one that doesn't touch it
+        let transaction = |account: Pubkey| {
+            let instruction = Instruction::new_with_bytes(program, &[], vec![AccountMeta::new(account, false)]);
+            let mut transaction = Transaction::new_unsigned(Message::new(&[instruction], Some(&Pubkey::new_unique())));
+            transaction.signatures = vec![Signature::new_unique()];
+            let json = json!({
+                "transaction": transaction.encode(UiTransactionEncoding::Base64),
+                "meta": { "err": null, "status": { "Ok": null }, "fee": 5000, "preBalances": [], "postBalances": [] },
+            });
+            (transaction.signatures[0], json)
+        };
+        let (touching, touching_json) = transaction(tracked);
+        let (_, other_json) = transaction(Pubkey::new_unique());
+        let block_json = block_json(30, vec![touching_json, other_json]);
+        let blockhash = block_json["blockhash"].as_str().unwrap().to_string();
+        let block: UiConfirmedBlock = serde_json::from_value(block_json).unwrap();
+
+        inner.process_block(30, block).await.unwrap();
+        let events = drain(&mut updates);
+        assert_eq!(events.len(), 3);
+        match &events[0] {
+            UpdateEvent::TransactionUpdate(info) => {
+                assert_eq!((info.signature, info.status.slot), (touching, 30));
+                assert_eq!(info.program_ids, vec![program]);
+            }
+            _ => panic!("expected a transaction"),
+        }
+        match &events[1] {
+            UpdateEvent::SlotUpdate(info) => assert_eq!((info.slot, info.parent, info.status), (30, Some(29), SlotStatus::Confirmed)),
+            _ => panic!("expected a slot update"),
+        }
+        match &events[2] {
+            UpdateEvent::BlockMetaUpdate(meta) => {
+                assert_eq!((meta.slot, meta.parent_slot, meta.blockhash.as_str()), (30, 29, blockhash.as_str()));
+                assert_eq!(meta.executed_transaction_count, 2);
+            }
+            _ => panic!("expected block meta"),
+        }
+    }
+}
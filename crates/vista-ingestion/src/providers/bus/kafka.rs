@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::{Message, Offset, TopicPartitionList};
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::RwLock;
+use vista_core::IndexerError;
+
+use super::{checkpoint, decode_message, BusFilter, MessageFormat, TopicKind};
+use crate::error::IngestionError;
+use crate::providers::{update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+// Backoff between consumers recreated after an error
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct KafkaConfig {
+    pub brokers: String,
+    pub group_id: String,
+    pub format: MessageFormat,
+    pub topics: Vec<(String, TopicKind)>,
+    // Commitment the publishing plugin emits at
+    pub commitment: CommitmentLevel,
+    // Offsets are committed after at most this many messages or this much time,
+    // once the indexer has processed everything consumed before
+    pub commit_every: usize,
+    pub commit_interval: Duration,
+    // Extra librdkafka settings (security, fetch sizes, ...)
+    pub options: HashMap<String, String>,
+}
+
+impl KafkaConfig {
+    pub fn new(brokers: &str, group_id: &str, format: MessageFormat) -> Self {
+        Self {
+            brokers: brokers.to_string(),
+            group_id: group_id.to_string(),
+            format,
+            topics: Vec::new(),
+            commitment: CommitmentLevel::Confirmed,
+            commit_every: 10_000,
+            commit_interval: Duration::from_secs(5),
+            options: HashMap::new(),
+        }
+    }
+
+    pub fn topic(mut self, topic: &str, kind: TopicKind) -> Self {
+        self.topics.push((topic.to_string(), kind));
+        self
+    }
+}
+
+pub struct KafkaProvider {
+    name: String,
+    inner: Arc<Inner>,
+    updates: UpdateReceiver,
+}
+
+struct Inner {
+    config: KafkaConfig,
+    update_channel: UpdateSender,
+    filter: RwLock<BusFilter>,
+}
+
+impl KafkaProvider {
+    pub fn new(config: KafkaConfig) -> Self {
+        let (update_channel, updates) = update_channel();
+        Self {
+            name: "kafka".to_string(),
+            inner: Arc::new(Inner {
+                config,
+                update_channel,
+                filter: RwLock::new(BusFilter::default()),
+            }),
+            updates,
+        }
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl RpcProvider for KafkaProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        RpcProviderType::Geyser
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            accounts: true,
+            programs: true,
+            transactions: true,
+            slots: true,
+            // Committed offsets let a consumer group resume where it stopped
+            replay: true,
+            commitments: vec![self.inner.config.commitment],
+            ..Default::default()
+        }
+    }
+
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.filter.write().await.accounts.insert(*pubkey);
+        Ok(())
+    }
+
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.filter.write().await.programs.insert(*program_id);
+        Ok(())
+    }
+
+    async fn unsubscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.filter.write().await.accounts.remove(pubkey);
+        Ok(())
+    }
+
+    async fn unsubscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.filter.write().await.programs.remove(program_id);
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        let config = &self.inner.config;
+        // librdkafka treats `^...` as a pattern, whose topics have no kind to decode them as
+        if let Some((topic, _)) = config.topics.iter().find(|(topic, _)| topic.starts_with('^')) {
+            return Err(IndexerError::ConfigError(format!("Kafka topic {} is a pattern; list each topic with its kind", topic)));
+        }
+        let updates = self.updates.take()?;
+        // Settings the consumer can't be created with fail here rather than in the background
+        let consumer = self.inner.connect()?;
+        tokio::spawn(self.inner.clone().run(consumer));
+        Ok(updates)
+    }
+}
+
+impl Inner {
+    fn connect(&self) -> Result<StreamConsumer, IngestionError> {
+        let config = &self.config;
+        let mut client_config = ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", &config.brokers)
+            .set("group.id", &config.group_id)
+            // Offsets are only committed for events the indexer has processed
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest");
+        for (key, value) in &config.options {
+            client_config.set(key, value);
+        }
+        let consumer: StreamConsumer = client_config.create()
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let topics: Vec<&str> = config.topics.iter().map(|(topic, _)| topic.as_str()).collect();
+        consumer.subscribe(&topics)
+            .map_err(|e| IngestionError::SubscriptionError(e.to_string()))?;
+        Ok(consumer)
+    }
+
+    // Recreates the consumer after errors until the indexer stops listening.
+    // A new consumer resumes from the group's committed offsets, so whatever
+    // was consumed but not yet committed is delivered again.
+    async fn run(self: Arc<Self>, consumer: StreamConsumer) {
+        let mut consumer = Some(consumer);
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            let result = match consumer.take() {
+                Some(consumer) => Ok(consumer),
+                None => self.connect(),
+            };
+            let result = match result {
+                Ok(consumer) => self.consume(consumer, &mut backoff).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                eprintln!("Kafka consumer error: {}", e);
+            }
+            if self.update_channel.is_closed() {
+                return;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    async fn consume(&self, consumer: StreamConsumer, backoff: &mut Duration) -> Result<(), IngestionError> {
+        let kinds: HashMap<&str, TopicKind> = self.config.topics.iter()
+            .map(|(topic, kind)| (topic.as_str(), *kind))
+            .collect();
+        // Next offset to commit per partition
+        let mut pending: HashMap<(String, i32), i64> = HashMap::new();
+        let mut uncommitted = 0;
+        let mut last_commit = Instant::now();
+
+        loop {
+            let received = tokio::time::timeout(self.config.commit_interval, consumer.recv()).await;
+            if let Ok(message) = received {
+                let message = message.map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                *backoff = INITIAL_BACKOFF;
+                let kind = kinds.get(message.topic()).copied();
+                if kind.is_none() {
+                    eprintln!("Skipping message from unconfigured topic {}", message.topic());
+                }
+                if let (Some(kind), Some(payload)) = (kind, message.payload()) {
+                    match decode_message(self.config.format, kind, payload, self.config.commitment) {
+                        Ok(Some((event, account_keys))) if self.filter.read().await.wants(&event, &account_keys) => {
+                            self.update_channel.send(event).await
+                                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                        }
+                        Ok(_) => {}
+                        Err(e) => eprintln!("Skipping undecodable message at {}/{}@{}: {}",
+                            message.topic(), message.partition(), message.offset(), e),
+                    }
+                }
+                pending.insert((message.topic().to_string(), message.partition()), message.offset() + 1);
+                uncommitted += 1;
+            }
+
+            if uncommitted > 0 && (uncommitted >= self.config.commit_every || last_commit.elapsed() >= self.config.commit_interval) {
+                checkpoint(&self.update_channel).await?;
+                let mut offsets = TopicPartitionList::new();
+                for ((topic, partition), offset) in pending.drain() {
+                    offsets.add_partition_offset(&topic, partition, Offset::Offset(offset))
+                        .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                }
+                consumer.commit(&offsets, CommitMode::Async)
+                    .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                uncommitted = 0;
+                last_commit = Instant::now();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn start_rejects_topic_patterns() {
+        let config = KafkaConfig::new("localhost:9092", "vista", MessageFormat::Yellowstone)
+            .topic("accounts", TopicKind::Accounts)
+            .topic("^transactions-.*", TopicKind::Transactions);
+        let provider = KafkaProvider::new(config);
+        assert!(matches!(provider.start().await, Err(IndexerError::ConfigError(_))));
+    }
+
+    #[tokio::test]
+    async fn unsubscribed_addresses_leave_the_filter() {
+        let provider = KafkaProvider::new(KafkaConfig::new("localhost:9092", "vista", MessageFormat::Yellowstone));
+        let (account, program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        provider.subscribe_account(&account).await.unwrap();
+        provider.subscribe_program(&program).await.unwrap();
+        provider.unsubscribe_account(&account).await.unwrap();
+
+        let filter = provider.inner.filter.read().await;
+        assert!(filter.accounts.is_empty());
+        assert!(filter.programs.contains(&program));
+        drop(filter);
+        provider.unsubscribe_program(&program).await.unwrap();
+        assert!(provider.inner.filter.read().await.programs.is_empty());
+    }
+}
@@ -0,0 +1,290 @@
+#[cfg(feature = "kafka")]
+mod kafka;
+#[cfg(feature = "nats")]
+mod nats;
+mod proto;
+
+use std::collections::HashSet;
+
+use prost::Message as _;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use tokio::sync::oneshot;
+use vista_core::models::{AccountInfo, SlotInfo, SlotStatus};
+use vista_core::UpdateEvent;
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+use yellowstone_grpc_proto::geyser::{SubscribeUpdate, SubscribeUpdateTransaction};
+
+#[cfg(feature = "kafka")]
+pub use kafka::{KafkaConfig, KafkaProvider};
+#[cfg(feature = "nats")]
+pub use nats::{NatsConfig, NatsProvider};
+
+use crate::error::IngestionError;
+use crate::providers::{failed_program, grpc, invoked_program_ids, program_error, transaction_info, UpdateSender};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    // solana-accountsdb-plugin-kafka, one message type per topic
+    AccountsDbPlugin,
+    // Yellowstone `SubscribeUpdate`, as published by yellowstone-grpc-kafka
+    Yellowstone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopicKind {
+    Accounts,
+    Transactions,
+    Slots,
+}
+
+// Tracked accounts and programs. While both are empty everything on the bus is
+// passed through, since buses are usually filtered by the publishing plugin.
+#[derive(Default)]
+pub(crate) struct BusFilter {
+    pub(crate) accounts: HashSet<Pubkey>,
+    pub(crate) programs: HashSet<Pubkey>,
+}
+
+impl BusFilter {
+    // `account_keys` are every address a transaction loads, so transactions
+    // touching a tracked account without invoking a tracked program still match
+    pub(crate) fn wants(&self, event: &UpdateEvent, account_keys: &[Pubkey]) -> bool {
+        if self.accounts.is_empty() && self.programs.is_empty() {
+            return true;
+        }
+        match event {
+            UpdateEvent::AccountUpdate(account) => {
+                self.accounts.contains(&account.pubkey) || self.programs.contains(&account.owner)
+            }
+            UpdateEvent::TransactionUpdate(transaction) => account_keys.iter()
+                .chain(&transaction.program_ids)
+                .chain(&transaction.invoked_program_ids)
+                .any(|key| self.programs.contains(key) || self.accounts.contains(key)),
+            _ => true,
+        }
+    }
+}
+
+// Waits until the indexer has processed everything sent so far, after which
+// the consumed offsets can be committed
+pub(crate) async fn checkpoint(update_channel: &UpdateSender) -> Result<(), IngestionError> {
+    let (done, processed) = oneshot::channel();
+    update_channel.send(UpdateEvent::Checkpoint(done)).await
+        .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+    processed.await.map_err(|e| IngestionError::ProviderError(e.to_string()))
+}
+
+// Decodes one bus message into an event and, for transactions, the account
+// keys the filter matches against
+pub(crate) fn decode_message(
+    format: MessageFormat,
+    kind: TopicKind,
+    payload: &[u8],
+    commitment: CommitmentLevel,
+) -> Result<Option<(UpdateEvent, Vec<Pubkey>)>, IngestionError> {
+    let to_error = |e: prost::DecodeError| IngestionError::ProviderError(e.to_string());
+    match format {
+        MessageFormat::Yellowstone => {
+            let update = SubscribeUpdate::decode(payload).map_err(to_error)?;
+            let account_keys = match &update.update_oneof {
+                Some(UpdateOneof::Transaction(SubscribeUpdateTransaction { transaction: Some(info), .. })) => grpc::account_keys(info)?,
+                _ => Vec::new(),
+            };
+            match update.update_oneof {
+                Some(update) => Ok(grpc::update_event(update, commitment)?.map(|(_, event)| (event, account_keys))),
+                None => Ok(None),
+            }
+        }
+        MessageFormat::AccountsDbPlugin => match kind {
+            TopicKind::Accounts => {
+                let event = proto::UpdateAccountEvent::decode(payload).map_err(to_error)?;
+                Ok(Some((UpdateEvent::AccountUpdate(AccountInfo {
+                    pubkey: to_pubkey(&event.pubkey)?,
+                    lamports: event.lamports,
+                    owner: to_pubkey(&event.owner)?,
+                    executable: event.executable,
+                    rent_epoch: event.rent_epoch,
+                    data: event.data,
+                    slot: event.slot,
+                }), Vec::new())))
+            }
+            TopicKind::Slots => {
+                let event = proto::SlotStatusEvent::decode(payload).map_err(to_error)?;
+                let status = match event.status {
+                    0 => SlotStatus::Processed,
+                    1 => SlotStatus::Finalized,
+                    2 => SlotStatus::Confirmed,
+                    _ => return Ok(None),
+                };
+                Ok(Some((UpdateEvent::SlotUpdate(SlotInfo { slot: event.slot, parent: Some(event.parent), status }), Vec::new())))
+            }
+            TopicKind::Transactions => {
+                let event = proto::TransactionEvent::decode(payload).map_err(to_error)?;
+                if event.is_vote {
+                    return Ok(None);
+                }
+                accounts_db_transaction(event, commitment).map(Some)
+            }
+        },
+    }
+}
+
+fn accounts_db_transaction(event: proto::TransactionEvent, commitment: CommitmentLevel) -> Result<(UpdateEvent, Vec<Pubkey>), IngestionError> {
+    use proto::sanitized_message::MessagePayload;
+
+    let signature = Signature::try_from(event.signature.as_slice())
+        .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+    let (message, loaded) = match event.transaction.and_then(|t| t.message).and_then(|m| m.message_payload) {
+        Some(MessagePayload::Legacy(legacy)) => (legacy.message, None),
+        Some(MessagePayload::V0(v0)) => (v0.message, v0.loaded_addresses),
+        None => (None, None),
+    };
+    let message = message.unwrap_or_default();
+
+    let mut account_keys = message.account_keys.iter()
+        .map(|key| to_pubkey(key))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(loaded) = loaded {
+        for key in loaded.writable.iter().chain(&loaded.readonly) {
+            account_keys.push(to_pubkey(key)?);
+        }
+    }
+
+    let meta = event.transaction_status_meta.unwrap_or_default();
+    let inner = meta.inner_instructions.iter()
+        .flat_map(|inner| &inner.instructions)
+        .filter_map(|inner| inner.instruction.as_ref());
+    let program_id = |ix: &proto::CompiledInstruction| account_keys.get(ix.program_id_index as usize).copied();
+    let program_ids = message.instructions.iter().filter_map(program_id).collect();
+
+    let err = if meta.is_status_err { parse_error_info(&meta.error_info) } else { None };
+    let mut info = transaction_info(signature, event.slot, program_ids, err, commitment);
+    info.invoked_program_ids = invoked_program_ids(inner.filter_map(program_id));
+    info.error = program_error(info.status.err.as_ref(), failed_program(&meta.log_messages));
+    Ok((UpdateEvent::TransactionUpdate(info), account_keys))
+}
+
+// The plugin only publishes the error's display string. Instruction errors
+// ("Error processing Instruction 2: custom program error: 0x1771") are
+// recovered with their index and custom code; anything else can't be mapped
+// back and is reported as a generic failure of instruction 0.
+fn parse_error_info(error_info: &str) -> Option<TransactionError> {
+    let (index, detail) = match error_info.strip_prefix("Error processing Instruction ").and_then(|rest| rest.split_once(": ")) {
+        Some((index, detail)) => (index.parse::<u8>().unwrap_or(0), detail),
+        None => {
+            eprintln!("Unrecognized transaction error from bus: {}", error_info);
+            return Some(TransactionError::InstructionError(0, InstructionError::GenericError));
+        }
+    };
+    let error = detail.strip_prefix("custom program error: 0x")
+        .and_then(|code| u32::from_str_radix(code, 16).ok())
+        .map(InstructionError::Custom)
+        .unwrap_or(InstructionError::GenericError);
+    Some(TransactionError::InstructionError(index, error))
+}
+
+fn to_pubkey(bytes: &[u8]) -> Result<Pubkey, IngestionError> {
+    Pubkey::try_from(bytes).map_err(|_| IngestionError::ProviderError(format!("Invalid pubkey length: {}", bytes.len())))
+}
+
+#[cfg(test)]
+mod tests {
+    use yellowstone_grpc_proto::prelude::{
+        CompiledInstruction, Message, SubscribeUpdateTransactionInfo, Transaction, TransactionStatusMeta,
+    };
+
+    use super::*;
+
+    fn bytes(keys: &[Pubkey]) -> Vec<Vec<u8>> {
+        keys.iter().map(|key| key.to_bytes().to_vec()).collect()
+    }
+
+    fn filter(accounts: &[Pubkey], programs: &[Pubkey]) -> BusFilter {
+        BusFilter { accounts: accounts.iter().copied().collect(), programs: programs.iter().copied().collect() }
+    }
+
+    // Payer and program in the message, `account` loaded through a lookup table
+    fn accounts_db_payload(payer: Pubkey, program: Pubkey, account: Pubkey) -> Vec<u8> {
+        proto::TransactionEvent {
+            signature: Signature::new_unique().as_ref().to_vec(),
+            transaction: Some(proto::SanitizedTransaction {
+                message: Some(proto::SanitizedMessage {
+                    message_payload: Some(proto::sanitized_message::MessagePayload::V0(proto::LoadedMessageV0 {
+                        message: Some(proto::Message {
+                            account_keys: bytes(&[payer, program]),
+                            instructions: vec![proto::CompiledInstruction { program_id_index: 1 }],
+                        }),
+                        loaded_addresses: Some(proto::LoadedAddresses { writable: bytes(&[account]), readonly: Vec::new() }),
+                    })),
+                }),
+            }),
+            slot: 9,
+            ..Default::default()
+        }.encode_to_vec()
+    }
+
+    fn yellowstone_payload(payer: Pubkey, program: Pubkey, account: Pubkey) -> Vec<u8> {
+        let signature = Signature::new_unique();
+        SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+                slot: 9,
+                transaction: Some(SubscribeUpdateTransactionInfo {
+                    signature: signature.as_ref().to_vec(),
+                    transaction: Some(Transaction {
+                        signatures: vec![signature.as_ref().to_vec()],
+                        message: Some(Message {
+                            account_keys: bytes(&[payer, program]),
+                            instructions: vec![CompiledInstruction { program_id_index: 1, ..Default::default() }],
+                            ..Default::default()
+                        }),
+                    }),
+                    meta: Some(TransactionStatusMeta { loaded_readonly_addresses: bytes(&[account]), ..Default::default() }),
+                    ..Default::default()
+                }),
+            })),
+            ..Default::default()
+        }.encode_to_vec()
+    }
+
+    #[test]
+    fn transactions_match_every_account_they_load() {
+        let (payer, program, account) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let payloads = [
+            (MessageFormat::AccountsDbPlugin, accounts_db_payload(payer, program, account)),
+            (MessageFormat::Yellowstone, yellowstone_payload(payer, program, account)),
+        ];
+        for (format, payload) in payloads {
+            let (event, account_keys) = decode_message(format, TopicKind::Transactions, &payload, CommitmentLevel::Confirmed)
+                .unwrap()
+                .unwrap();
+            assert_eq!(account_keys, vec![payer, program, account]);
+            // Neither the payer nor the looked-up account is a program the transaction invokes
+            assert!(filter(&[payer], &[]).wants(&event, &account_keys));
+            assert!(filter(&[account], &[]).wants(&event, &account_keys));
+            assert!(filter(&[], &[program]).wants(&event, &account_keys));
+            assert!(!filter(&[Pubkey::new_unique()], &[Pubkey::new_unique()]).wants(&event, &account_keys));
+        }
+    }
+
+    #[test]
+    fn accounts_match_by_address_or_owner() {
+        let account = AccountInfo {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1,
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+            data: Vec::new(),
+            slot: 9,
+        };
+        let event = UpdateEvent::AccountUpdate(account.clone());
+        assert!(filter(&[], &[]).wants(&event, &[]));
+        assert!(filter(&[account.pubkey], &[]).wants(&event, &[]));
+        assert!(filter(&[], &[account.owner]).wants(&event, &[]));
+        assert!(!filter(&[account.owner], &[account.pubkey]).wants(&event, &[]));
+    }
+}
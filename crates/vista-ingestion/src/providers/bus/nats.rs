@@ -0,0 +1,253 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_nats::jetstream::{self, consumer::{pull, AckPolicy, PullConsumer}};
+use async_trait::async_trait;
+use futures::StreamExt;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::RwLock;
+use vista_core::IndexerError;
+
+use super::{checkpoint, decode_message, BusFilter, MessageFormat, TopicKind};
+use crate::error::IngestionError;
+use crate::providers::{update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+// Backoff between consumers recreated after an error
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct NatsConfig {
+    pub url: String,
+    // JetStream stream holding the plugin's subjects
+    pub stream: String,
+    // Durable consumer name; instances sharing it split the work and resume
+    // from the last acknowledged message
+    pub durable_name: String,
+    pub format: MessageFormat,
+    pub subjects: Vec<(String, TopicKind)>,
+    pub commitment: CommitmentLevel,
+    pub ack_every: usize,
+    pub ack_interval: Duration,
+}
+
+impl NatsConfig {
+    pub fn new(url: &str, stream: &str, durable_name: &str, format: MessageFormat) -> Self {
+        Self {
+            url: url.to_string(),
+            stream: stream.to_string(),
+            durable_name: durable_name.to_string(),
+            format,
+            subjects: Vec::new(),
+            commitment: CommitmentLevel::Confirmed,
+            ack_every: 10_000,
+            ack_interval: Duration::from_secs(5),
+        }
+    }
+
+    pub fn subject(mut self, subject: &str, kind: TopicKind) -> Self {
+        self.subjects.push((subject.to_string(), kind));
+        self
+    }
+}
+
+pub struct NatsProvider {
+    name: String,
+    inner: Arc<Inner>,
+    updates: UpdateReceiver,
+}
+
+struct Inner {
+    config: NatsConfig,
+    update_channel: UpdateSender,
+    filter: RwLock<BusFilter>,
+}
+
+impl NatsProvider {
+    pub fn new(config: NatsConfig) -> Self {
+        let (update_channel, updates) = update_channel();
+        Self {
+            name: "nats".to_string(),
+            inner: Arc::new(Inner {
+                config,
+                update_channel,
+                filter: RwLock::new(BusFilter::default()),
+            }),
+            updates,
+        }
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl RpcProvider for NatsProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        RpcProviderType::Geyser
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            accounts: true,
+            programs: true,
+            transactions: true,
+            slots: true,
+            replay: true,
+            commitments: vec![self.inner.config.commitment],
+            ..Default::default()
+        }
+    }
+
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.filter.write().await.accounts.insert(*pubkey);
+        Ok(())
+    }
+
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.filter.write().await.programs.insert(*program_id);
+        Ok(())
+    }
+
+    async fn unsubscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.filter.write().await.accounts.remove(pubkey);
+        Ok(())
+    }
+
+    async fn unsubscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.filter.write().await.programs.remove(program_id);
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        let updates = self.updates.take()?;
+        let config = &self.inner.config;
+        let client = async_nats::connect(&config.url).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let stream = jetstream::new(client).get_stream(&config.stream).await
+            .map_err(|e| IngestionError::SubscriptionError(e.to_string()))?;
+
+        // One durable consumer per subject so each keeps its own ack floor
+        for (subject, kind) in &config.subjects {
+            let consumer = self.inner.create_consumer(&stream, subject).await?;
+            tokio::spawn(self.inner.clone().run(stream.clone(), consumer, subject.clone(), *kind));
+        }
+        Ok(updates)
+    }
+}
+
+impl Inner {
+    // Creating an existing durable updates its editable settings, so
+    // consumers made before `max_ack_pending` was set pick it up
+    async fn create_consumer(&self, stream: &jetstream::stream::Stream, subject: &str) -> Result<PullConsumer, IngestionError> {
+        stream.create_consumer(pull::Config {
+            durable_name: Some(durable_name(&self.config.durable_name, subject)),
+            filter_subject: subject.to_string(),
+            // Acknowledging a message acknowledges everything before it
+            ack_policy: AckPolicy::All,
+            // Delivery stalls at this many unacknowledged messages, so it must
+            // leave room for a full batch (the server default is 1000)
+            max_ack_pending: (self.config.ack_every as i64).saturating_mul(2),
+            ..Default::default()
+        }).await.map_err(|e| IngestionError::SubscriptionError(e.to_string()))
+    }
+
+    // Resumes a subject's consumer after errors until the indexer stops
+    // listening. Delivery restarts after the last acknowledged message, so
+    // whatever was consumed but not yet acknowledged arrives again.
+    async fn run(self: Arc<Self>, stream: jetstream::stream::Stream, consumer: PullConsumer, subject: String, kind: TopicKind) {
+        let mut consumer = Some(consumer);
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            let result = match consumer.take() {
+                Some(consumer) => Ok(consumer),
+                // Also recreates a durable that was deleted on the server
+                None => self.create_consumer(&stream, &subject).await,
+            };
+            let result = match result {
+                Ok(consumer) => self.consume(consumer, kind, &mut backoff).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => eprintln!("NATS consumer for {} ended", subject),
+                Err(e) => eprintln!("NATS consumer for {} error: {}", subject, e),
+            }
+            if self.update_channel.is_closed() {
+                return;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    async fn consume(&self, consumer: PullConsumer, kind: TopicKind, backoff: &mut Duration) -> Result<(), IngestionError> {
+        let mut messages = consumer.messages().await
+            .map_err(|e| IngestionError::SubscriptionError(e.to_string()))?;
+        let mut last_unacked = None;
+        let mut unacked = 0;
+        let mut last_ack = Instant::now();
+
+        loop {
+            match tokio::time::timeout(self.config.ack_interval, messages.next()).await {
+                Ok(Some(message)) => {
+                    let message = message.map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                    *backoff = INITIAL_BACKOFF;
+                    match decode_message(self.config.format, kind, &message.payload, self.config.commitment) {
+                        Ok(Some((event, account_keys))) if self.filter.read().await.wants(&event, &account_keys) => {
+                            self.update_channel.send(event).await
+                                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                        }
+                        Ok(_) => {}
+                        Err(e) => eprintln!("Skipping undecodable message on {}: {}", message.subject, e),
+                    }
+                    last_unacked = Some(message);
+                    unacked += 1;
+                }
+                Ok(None) => return Ok(()),
+                Err(_) => {}
+            }
+
+            if unacked >= self.config.ack_every || (unacked > 0 && last_ack.elapsed() >= self.config.ack_interval) {
+                if let Some(message) = last_unacked.take() {
+                    checkpoint(&self.update_channel).await?;
+                    message.ack().await.map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                }
+                unacked = 0;
+                last_ack = Instant::now();
+            }
+        }
+    }
+}
+
+fn durable_name(prefix: &str, subject: &str) -> String {
+    format!("{}-{}", prefix, subject.replace(['.', '*', '>'], "_"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durable_names_replace_subject_wildcards() {
+        assert_eq!(durable_name("vista", "solana.accounts.>"), "vista-solana_accounts__");
+        assert_eq!(durable_name("vista", "solana.*.slots"), "vista-solana___slots");
+    }
+
+    #[tokio::test]
+    async fn start_fails_without_a_server() {
+        // Bound and dropped, so nothing is listening on the port
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let config = NatsConfig::new(&format!("nats://127.0.0.1:{}", port), "vista", "vista", MessageFormat::Yellowstone)
+            .subject("solana.slots", TopicKind::Slots);
+        let provider = NatsProvider::new(config);
+        assert!(provider.start().await.is_err());
+    }
+}
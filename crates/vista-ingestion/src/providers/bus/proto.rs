@@ -0,0 +1,132 @@
+// Messages published by solana-accountsdb-plugin-kafka (`event.proto`).
+// Only the fields Vista reads are declared; prost skips the rest.
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct UpdateAccountEvent {
+    #[prost(uint64, tag = "1")]
+    pub slot: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub pubkey: Vec<u8>,
+    #[prost(uint64, tag = "3")]
+    pub lamports: u64,
+    #[prost(bytes = "vec", tag = "4")]
+    pub owner: Vec<u8>,
+    #[prost(bool, tag = "5")]
+    pub executable: bool,
+    #[prost(uint64, tag = "6")]
+    pub rent_epoch: u64,
+    #[prost(bytes = "vec", tag = "7")]
+    pub data: Vec<u8>,
+    #[prost(uint64, tag = "8")]
+    pub write_version: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SlotStatusEvent {
+    #[prost(uint64, tag = "1")]
+    pub slot: u64,
+    #[prost(uint64, tag = "2")]
+    pub parent: u64,
+    // 0 = processed, 1 = rooted, 2 = confirmed
+    #[prost(int32, tag = "3")]
+    pub status: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TransactionEvent {
+    #[prost(bytes = "vec", tag = "1")]
+    pub signature: Vec<u8>,
+    #[prost(bool, tag = "2")]
+    pub is_vote: bool,
+    #[prost(message, optional, tag = "3")]
+    pub transaction: Option<SanitizedTransaction>,
+    #[prost(message, optional, tag = "4")]
+    pub transaction_status_meta: Option<TransactionStatusMeta>,
+    #[prost(uint64, tag = "5")]
+    pub slot: u64,
+    #[prost(uint64, tag = "6")]
+    pub index: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SanitizedTransaction {
+    #[prost(message, optional, tag = "1")]
+    pub message: Option<SanitizedMessage>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SanitizedMessage {
+    #[prost(oneof = "sanitized_message::MessagePayload", tags = "1, 2")]
+    pub message_payload: Option<sanitized_message::MessagePayload>,
+}
+
+pub mod sanitized_message {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum MessagePayload {
+        #[prost(message, tag = "1")]
+        Legacy(super::LegacyLoadedMessage),
+        #[prost(message, tag = "2")]
+        V0(super::LoadedMessageV0),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct LegacyLoadedMessage {
+    #[prost(message, optional, tag = "1")]
+    pub message: Option<Message>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct LoadedMessageV0 {
+    #[prost(message, optional, tag = "1")]
+    pub message: Option<Message>,
+    #[prost(message, optional, tag = "2")]
+    pub loaded_addresses: Option<LoadedAddresses>,
+}
+
+// Legacy and v0 messages share these field numbers
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Message {
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub account_keys: Vec<Vec<u8>>,
+    #[prost(message, repeated, tag = "4")]
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct LoadedAddresses {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub writable: Vec<Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub readonly: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CompiledInstruction {
+    #[prost(uint32, tag = "1")]
+    pub program_id_index: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TransactionStatusMeta {
+    #[prost(bool, tag = "1")]
+    pub is_status_err: bool,
+    #[prost(string, tag = "2")]
+    pub error_info: String,
+    #[prost(message, repeated, tag = "6")]
+    pub inner_instructions: Vec<InnerInstructions>,
+    #[prost(string, repeated, tag = "7")]
+    pub log_messages: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct InnerInstructions {
+    #[prost(message, repeated, tag = "2")]
+    pub instructions: Vec<InnerInstruction>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct InnerInstruction {
+    #[prost(message, optional, tag = "1")]
+    pub instruction: Option<CompiledInstruction>,
+}
@@ -0,0 +1,467 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::EncodedTransactionWithStatusMeta;
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use vista_core::{IndexerError, UpdateEvent};
+
+use crate::error::IngestionError;
+use crate::providers::{encoded_transaction_info, update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// Helius closes sockets that stay silent for a minute
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+// Filters applied on top of the tracked accounts and programs, which always
+// go into `accountInclude`
+#[derive(Debug, Clone)]
+pub struct HeliusFilterConfig {
+    pub account_exclude: Vec<Pubkey>,
+    pub account_required: Vec<Pubkey>,
+    pub include_votes: bool,
+    pub include_failed: bool,
+    pub commitment: CommitmentLevel,
+}
+
+impl Default for HeliusFilterConfig {
+    fn default() -> Self {
+        Self {
+            account_exclude: Vec::new(),
+            account_required: Vec::new(),
+            include_votes: false,
+            include_failed: true,
+            commitment: CommitmentLevel::Confirmed,
+        }
+    }
+}
+
+// Streams full transactions through Helius' enhanced WebSocket `transactionSubscribe`
+pub struct HeliusTransactionProvider {
+    name: String,
+    inner: Arc<Inner>,
+    updates: UpdateReceiver,
+}
+
+struct Inner {
+    url: String,
+    filters: HeliusFilterConfig,
+    update_channel: UpdateSender,
+    addresses: RwLock<HashSet<Pubkey>>,
+    connection: Mutex<Connection>,
+    next_id: AtomicU64,
+}
+
+#[derive(Default)]
+struct Connection {
+    writer: Option<mpsc::UnboundedSender<Message>>,
+    // Requests awaiting a response, by JSON-RPC id
+    pending: HashMap<u64, Request>,
+    // Id of the subscribe call carrying the current filters; subscriptions
+    // confirmed for older calls are released right away
+    newest: u64,
+    subscription: Option<u64>,
+}
+
+enum Request {
+    Subscribe,
+    Unsubscribe(u64),
+}
+
+impl HeliusTransactionProvider {
+    pub fn new(url: &str) -> Self {
+        Self::with_filters(url, HeliusFilterConfig::default())
+    }
+
+    pub fn with_filters(url: &str, filters: HeliusFilterConfig) -> Self {
+        let (update_channel, updates) = update_channel();
+        Self {
+            name: "helius_transactions".to_string(),
+            inner: Arc::new(Inner {
+                url: url.to_string(),
+                filters,
+                update_channel,
+                addresses: RwLock::new(HashSet::new()),
+                connection: Mutex::new(Connection::default()),
+                next_id: AtomicU64::new(1),
+            }),
+            updates,
+        }
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl RpcProvider for HeliusTransactionProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        RpcProviderType::WebSocket
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            transactions: true,
+            commitments: vec![CommitmentLevel::Processed, CommitmentLevel::Confirmed, CommitmentLevel::Finalized],
+            ..Default::default()
+        }
+    }
+
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        Ok(self.inner.track(*pubkey).await?)
+    }
+
+    // Every invoked program, CPI targets included, is among a transaction's
+    // account keys, so programs share `accountInclude` with accounts
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        Ok(self.inner.track(*program_id).await?)
+    }
+
+    async fn unsubscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        Ok(self.inner.untrack(pubkey).await?)
+    }
+
+    async fn unsubscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        Ok(self.inner.untrack(program_id).await?)
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        let updates = self.updates.take()?;
+        let inner = self.inner.clone();
+        tokio::spawn(async move { inner.run().await });
+        Ok(updates)
+    }
+}
+
+impl Inner {
+    async fn track(&self, address: Pubkey) -> Result<(), IngestionError> {
+        if !self.addresses.write().await.insert(address) {
+            return Ok(());
+        }
+        let mut connection = self.connection.lock().await;
+        self.send_subscribe(&mut connection).await
+    }
+
+    async fn untrack(&self, address: &Pubkey) -> Result<(), IngestionError> {
+        if !self.addresses.write().await.remove(address) {
+            return Ok(());
+        }
+        let mut connection = self.connection.lock().await;
+        self.send_subscribe(&mut connection).await
+    }
+
+    // transactionSubscribe filters can't be amended, so every change opens a
+    // new subscription and drops the old one once the new one is confirmed
+    async fn send_subscribe(&self, connection: &mut Connection) -> Result<(), IngestionError> {
+        if connection.writer.is_none() {
+            return Ok(());
+        }
+        let addresses = self.addresses.read().await;
+        if addresses.is_empty() {
+            // Nothing left to stream; calls still in flight are released once confirmed
+            connection.newest = 0;
+            return match connection.subscription.take() {
+                Some(subscription) => self.send_unsubscribe(connection, subscription),
+                None => Ok(()),
+            };
+        }
+        let params = json!([
+            {
+                "vote": self.filters.include_votes,
+                "failed": self.filters.include_failed,
+                "accountInclude": to_strings(addresses.iter()),
+                "accountExclude": to_strings(&self.filters.account_exclude),
+                "accountRequired": to_strings(&self.filters.account_required),
+            },
+            {
+                "commitment": self.filters.commitment,
+                "encoding": "base64",
+                "transactionDetails": "full",
+                "showRewards": false,
+                "maxSupportedTransactionVersion": 0,
+            }
+        ]);
+        connection.newest = self.send_request(connection, "transactionSubscribe", params, Request::Subscribe)?;
+        Ok(())
+    }
+
+    fn send_unsubscribe(&self, connection: &mut Connection, subscription: u64) -> Result<(), IngestionError> {
+        self.send_request(connection, "transactionUnsubscribe", json!([subscription]), Request::Unsubscribe(subscription))?;
+        Ok(())
+    }
+
+    fn send_request(&self, connection: &mut Connection, method: &str, params: Value, request: Request) -> Result<u64, IngestionError> {
+        let writer = connection.writer.as_ref()
+            .ok_or_else(|| IngestionError::SubscriptionError("Not connected".to_string()))?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        writer.send(Message::Text(message.to_string()))
+            .map_err(|e| IngestionError::SubscriptionError(e.to_string()))?;
+        connection.pending.insert(id, request);
+        Ok(id)
+    }
+
+    async fn run(self: Arc<Self>) {
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            if let Err(e) = self.stream_updates(&mut backoff).await {
+                eprintln!("Helius transaction stream error: {}", e);
+            }
+            *self.connection.lock().await = Connection::default();
+            if self.update_channel.is_closed() {
+                return;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    async fn stream_updates(&self, backoff: &mut Duration) -> Result<(), IngestionError> {
+        let (socket, _) = connect_async(self.url.as_str()).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let (mut sink, mut stream) = socket.split();
+
+        let (writer, mut outgoing) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(message) = outgoing.recv().await {
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        {
+            let mut connection = self.connection.lock().await;
+            *connection = Connection { writer: Some(writer.clone()), ..Default::default() };
+            self.send_subscribe(&mut connection).await?;
+        }
+        *backoff = INITIAL_BACKOFF;
+
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        loop {
+            tokio::select! {
+                message = stream.next() => {
+                    match message {
+                        Some(Ok(Message::Text(text))) => self.handle_message(&text).await?,
+                        Some(Ok(Message::Ping(payload))) => {
+                            let _ = writer.send(Message::Pong(payload));
+                        }
+                        Some(Ok(Message::Close(_))) | None => return Ok(()),
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Err(IngestionError::ProviderError(e.to_string())),
+                    }
+                }
+                _ = ping.tick() => {
+                    let _ = writer.send(Message::Ping(Vec::new()));
+                }
+            }
+        }
+    }
+
+    async fn handle_message(&self, text: &str) -> Result<(), IngestionError> {
+        let message: Value = serde_json::from_str(text)
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+
+        if let Some(id) = message["id"].as_u64() {
+            let mut connection = self.connection.lock().await;
+            match connection.pending.remove(&id) {
+                Some(Request::Subscribe) => {
+                    let subscription = match message["result"].as_u64() {
+                        Some(subscription) => subscription,
+                        None => {
+                            eprintln!("transactionSubscribe rejected: {}", message["error"]);
+                            return Ok(());
+                        }
+                    };
+                    // A newer call may have been answered first, in which case
+                    // this one's filters are already out of date
+                    let previous = if id == connection.newest {
+                        connection.subscription.replace(subscription)
+                    } else {
+                        Some(subscription)
+                    };
+                    if let Some(previous) = previous {
+                        self.send_unsubscribe(&mut connection, previous)?;
+                    }
+                }
+                Some(Request::Unsubscribe(subscription)) if message["result"].as_bool() != Some(true) => {
+                    eprintln!("transactionUnsubscribe of {} failed: {}", subscription, message["error"]);
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if message["method"].as_str() != Some("transactionNotification") {
+            return Ok(());
+        }
+        // Notifications for a released subscription may still arrive until
+        // its unsubscribe is processed; the indexer drops what it no longer tracks
+        let result = &message["params"]["result"];
+        let slot = result["slot"].as_u64()
+            .ok_or_else(|| IngestionError::ProviderError("Notification without a slot".to_string()))?;
+        let transaction: EncodedTransactionWithStatusMeta = serde_json::from_value(result["transaction"].clone())
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let info = encoded_transaction_info(slot, &transaction, self.filters.commitment)
+            .ok_or_else(|| IngestionError::ProviderError(format!("Undecodable transaction in slot {}", slot)))?;
+
+        self.update_channel.send(UpdateEvent::TransactionUpdate(info)).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))
+    }
+}
+
+fn to_strings<'a>(keys: impl IntoIterator<Item = &'a Pubkey>) -> Vec<String> {
+    keys.into_iter().map(|key| key.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::Message as TransactionMessage;
+    use solana_sdk::signature::Signature;
+    use solana_sdk::transaction::Transaction;
+    use solana_transaction_status::{Encodable, UiTransactionEncoding};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::{accept_async, WebSocketStream};
+
+    use super::*;
+
+    async fn connect(provider: &HeliusTransactionProvider, listener: &TcpListener) -> (UpdateStream, WebSocketStream<TcpStream>) {
+        let updates = provider.start().await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        (updates, accept_async(stream).await.unwrap())
+    }
+
+    async fn next_request(socket: &mut WebSocketStream<TcpStream>) -> Value {
+        loop {
+            if let Message::Text(text) = socket.next().await.unwrap().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    async fn reply(socket: &mut WebSocketStream<TcpStream>, request: &Value, result: Value) {
+        let reply = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+        socket.send(Message::Text(reply.to_string())).await.unwrap();
+    }
+
+    fn included(request: &Value) -> Vec<String> {
+        assert_eq!(request["method"], "transactionSubscribe");
+        let mut addresses: Vec<String> = serde_json::from_value(request["params"][0]["accountInclude"].clone()).unwrap();
+        addresses.sort();
+        addresses
+    }
+
+    fn released(request: &Value) -> Value {
+        assert_eq!(request["method"], "transactionUnsubscribe");
+        request["params"][0].clone()
+    }
+
+    #[tokio::test]
+    async fn subscriptions_follow_the_tracked_addresses() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let provider = HeliusTransactionProvider::new(&format!("ws://{}", listener.local_addr().unwrap()));
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        provider.subscribe_account(&first).await.unwrap();
+        let (_updates, mut socket) = connect(&provider, &listener).await;
+
+        let initial = next_request(&mut socket).await;
+        assert_eq!(included(&initial), vec![first.to_string()]);
+        provider.subscribe_program(&second).await.unwrap();
+        let widened = next_request(&mut socket).await;
+        let mut both = vec![first.to_string(), second.to_string()];
+        both.sort();
+        assert_eq!(included(&widened), both);
+
+        // The newer call is answered first, so the older one is released as
+        // soon as it is confirmed instead of being taken as current
+        reply(&mut socket, &widened, json!(20)).await;
+        reply(&mut socket, &initial, json!(10)).await;
+        let unsubscribe = next_request(&mut socket).await;
+        assert_eq!(released(&unsubscribe), json!(10));
+        reply(&mut socket, &unsubscribe, json!(true)).await;
+
+        provider.unsubscribe_program(&second).await.unwrap();
+        let narrowed = next_request(&mut socket).await;
+        assert_eq!(included(&narrowed), vec![first.to_string()]);
+        reply(&mut socket, &narrowed, json!(30)).await;
+        assert_eq!(released(&next_request(&mut socket).await), json!(20));
+
+        // Nothing left to track
+        provider.unsubscribe_account(&first).await.unwrap();
+        assert_eq!(released(&next_request(&mut socket).await), json!(30));
+    }
+
+    #[tokio::test]
+    async fn notifications_decode_into_transactions() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let provider = HeliusTransactionProvider::new(&format!("ws://{}", listener.local_addr().unwrap()));
+        let (payer, program, callee) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        provider.subscribe_program(&program).await.unwrap();
+        let (mut updates, mut socket) = connect(&provider, &listener).await;
+        let subscribe = next_request(&mut socket).await;
+        reply(&mut socket, &subscribe, json!(7)).await;
+
+        let instruction = Instruction::new_with_bytes(program, &[], vec![AccountMeta::new_readonly(callee, false)]);
+        let mut transaction = Transaction::new_unsigned(TransactionMessage::new(&[instruction], Some(&payer)));
+        let signature = Signature::new_unique();
+        transaction.signatures = vec![signature];
+        let callee_index = transaction.message.account_keys.iter().position(|key| *key == callee).unwrap();
+        let err = json!({ "InstructionError": [0, { "Custom": 6000 }] });
+        let notification = json!({ "jsonrpc": "2.0", "method": "transactionNotification", "params": {
+            "subscription": 7,
+            "result": {
+                "signature": signature.to_string(),
+                "slot": 55,
+                "transaction": {
+                    "transaction": transaction.encode(UiTransactionEncoding::Base64),
+                    "meta": {
+                        "err": err,
+                        "status": { "Err": err },
+                        "fee": 5000,
+                        "preBalances": [],
+                        "postBalances": [],
+                        "innerInstructions": [{
+                            "index": 0,
+                            "instructions": [{ "programIdIndex": callee_index, "accounts": [], "data": "" }],
+                        }],
+                        "logMessages": [
+                            format!("Program {} invoke [1]", program),
+                            format!("Program {} invoke [2]", callee),
+                            format!("Program {} failed: custom program error: 0x1770", callee),
+                            format!("Program {} failed: custom program error: 0x1770", program),
+                        ],
+                    },
+                },
+            },
+        } });
+        socket.send(Message::Text(notification.to_string())).await.unwrap();
+
+        match updates.next().await.unwrap().event {
+            UpdateEvent::TransactionUpdate(info) => {
+                assert_eq!((info.signature, info.status.slot), (signature, 55));
+                assert_eq!(info.program_ids, vec![program]);
+                assert_eq!(info.invoked_program_ids, vec![callee]);
+                let error = info.error.unwrap();
+                assert_eq!((error.program_id, error.code), (callee, 6000));
+            }
+            _ => panic!("expected a transaction"),
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod helius;
+mod plugin;
+
+pub use helius::{HeliusFilterConfig, HeliusTransactionProvider};
+pub use plugin::GeyserRpcProvider;
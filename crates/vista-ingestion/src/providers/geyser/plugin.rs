@@ -0,0 +1,367 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::Pubkey;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+use tokio::sync::{Mutex, RwLock};
+use vista_core::models::{AccountInfo, BlockMetaInfo, SlotInfo, SlotStatus};
+use vista_core::{IndexerError, UpdateEvent};
+use vista_geyser_plugin::wire::{self, ClientMessage, GeyserMessage};
+
+use crate::error::IngestionError;
+use crate::providers::{program_error, transaction_info, update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(200);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+// The plugin sends every slot to every client, a few times a second, so a
+// connection that stays silent this long is dead even if it is still open
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+type Reader = Box<dyn AsyncRead + Unpin + Send>;
+type Writer = Box<dyn AsyncWrite + Unpin + Send>;
+
+// Consumes the framed stream served by `vista-geyser-plugin` running inside
+// one of our own validators. The url is `tcp://host:port` or `unix:///path`.
+pub struct GeyserRpcProvider {
+    name: String,
+    inner: Arc<Inner>,
+    updates: UpdateReceiver,
+}
+
+struct Inner {
+    url: String,
+    read_timeout: Duration,
+    update_channel: UpdateSender,
+    accounts: RwLock<HashSet<Pubkey>>,
+    programs: RwLock<HashSet<Pubkey>>,
+    writer: Mutex<Option<Writer>>,
+}
+
+impl GeyserRpcProvider {
+    pub fn new(url: &str) -> Self {
+        Self::with_read_timeout(url, READ_TIMEOUT)
+    }
+
+    fn with_read_timeout(url: &str, read_timeout: Duration) -> Self {
+        let (update_channel, updates) = update_channel();
+        Self {
+            name: "geyser".to_string(),
+            inner: Arc::new(Inner {
+                url: url.to_string(),
+                read_timeout,
+                update_channel,
+                accounts: RwLock::new(HashSet::new()),
+                programs: RwLock::new(HashSet::new()),
+                writer: Mutex::new(None),
+            }),
+            updates,
+        }
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl RpcProvider for GeyserRpcProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        RpcProviderType::Geyser
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            accounts: true,
+            programs: true,
+            transactions: true,
+            slots: true,
+            blocks: true,
+            // Accounts and transactions are forwarded as the validator
+            // processes them; later slot statuses don't restate them
+            commitments: vec![CommitmentLevel::Processed],
+            ..Default::default()
+        }
+    }
+
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        if self.inner.accounts.write().await.insert(*pubkey) {
+            self.inner.send_filter().await?;
+        }
+        Ok(())
+    }
+
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        if self.inner.programs.write().await.insert(*program_id) {
+            self.inner.send_filter().await?;
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        if self.inner.accounts.write().await.remove(pubkey) {
+            self.inner.send_filter().await?;
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        if self.inner.programs.write().await.remove(program_id) {
+            self.inner.send_filter().await?;
+        }
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        let updates = self.updates.take()?;
+        let inner = self.inner.clone();
+        tokio::spawn(async move { inner.run().await });
+        Ok(updates)
+    }
+}
+
+impl Inner {
+    async fn connect(&self) -> Result<(Reader, Writer), IngestionError> {
+        let to_error = |e: std::io::Error| IngestionError::ProviderError(e.to_string());
+        if let Some(path) = self.url.strip_prefix("unix://") {
+            let (reader, writer) = tokio::io::split(UnixStream::connect(path).await.map_err(to_error)?);
+            Ok((Box::new(reader), Box::new(writer)))
+        } else {
+            let stream = TcpStream::connect(self.url.strip_prefix("tcp://").unwrap_or(&self.url)).await.map_err(to_error)?;
+            stream.set_nodelay(true).map_err(to_error)?;
+            let (reader, writer) = tokio::io::split(stream);
+            Ok((Box::new(reader), Box::new(writer)))
+        }
+    }
+
+    // The plugin only forwards what matches the client's filter, so it's
+    // resent whenever tracking changes and after every reconnect
+    async fn send_filter(&self) -> Result<(), IngestionError> {
+        let message = ClientMessage::Subscribe {
+            accounts: self.accounts.read().await.iter().copied().collect(),
+            programs: self.programs.read().await.iter().copied().collect(),
+        };
+        let frame = wire::encode_frame(&message)
+            .map_err(|e| IngestionError::SubscriptionError(e.to_string()))?;
+        if let Some(writer) = self.writer.lock().await.as_mut() {
+            writer.write_all(&frame).await
+                .map_err(|e| IngestionError::SubscriptionError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn run(self: Arc<Self>) {
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            if let Err(e) = self.stream_updates(&mut backoff).await {
+                eprintln!("Geyser plugin stream error: {}", e);
+            }
+            *self.writer.lock().await = None;
+            if self.update_channel.is_closed() {
+                return;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    async fn stream_updates(&self, backoff: &mut Duration) -> Result<(), IngestionError> {
+        let (mut reader, writer) = self.connect().await?;
+        *self.writer.lock().await = Some(writer);
+        self.send_filter().await?;
+        *backoff = INITIAL_BACKOFF;
+
+        loop {
+            let message = match tokio::time::timeout(self.read_timeout, read_message(&mut reader)).await {
+                Ok(Ok(Some(message))) => message,
+                Ok(Ok(None)) => return Ok(()),
+                Ok(Err(e)) => return Err(IngestionError::ProviderError(e.to_string())),
+                Err(_) => return Err(IngestionError::ProviderError(
+                    format!("No frame from the plugin in {:?}", self.read_timeout))),
+            };
+            if let Some(event) = update_event(message) {
+                self.update_channel.send(event).await
+                    .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+            }
+        }
+    }
+}
+
+// `None` once the plugin closed the connection between frames
+async fn read_message(reader: &mut Reader) -> std::io::Result<Option<GeyserMessage>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let mut payload = vec![0u8; wire::checked_frame_len(len)?];
+    reader.read_exact(&mut payload).await?;
+    wire::decode_payload(&payload).map(Some)
+}
+
+fn update_event(message: GeyserMessage) -> Option<UpdateEvent> {
+    Some(match message {
+        GeyserMessage::Account { pubkey, lamports, owner, executable, rent_epoch, data, slot, .. } => {
+            UpdateEvent::AccountUpdate(AccountInfo { pubkey, lamports, owner, executable, rent_epoch, data, slot })
+        }
+        GeyserMessage::Transaction { is_vote: true, .. } => return None,
+        // The validator notifies transactions as soon as they are processed
+        GeyserMessage::Transaction { signature, slot, program_ids, invoked_program_ids, err, failed_program_id, .. } => {
+            let mut info = transaction_info(signature, slot, program_ids, err, CommitmentLevel::Processed);
+            info.invoked_program_ids = invoked_program_ids;
+            info.error = program_error(info.status.err.as_ref(), failed_program_id);
+            UpdateEvent::TransactionUpdate(info)
+        }
+        GeyserMessage::Slot { slot, parent, status } => UpdateEvent::SlotUpdate(SlotInfo {
+            slot,
+            parent,
+            status: match status {
+                wire::SlotStatus::Processed => SlotStatus::Processed,
+                wire::SlotStatus::Confirmed => SlotStatus::Confirmed,
+                wire::SlotStatus::Rooted => SlotStatus::Finalized,
+            },
+        }),
+        GeyserMessage::BlockMeta {
+            slot, blockhash, parent_slot, parent_blockhash, block_time, block_height, executed_transaction_count, rewards,
+        } => UpdateEvent::BlockMetaUpdate(BlockMetaInfo {
+            slot,
+            blockhash,
+            parent_slot,
+            parent_blockhash,
+            block_time,
+            block_height,
+            executed_transaction_count,
+            rewards,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::signature::Signature;
+    use solana_sdk::transaction::TransactionError;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    async fn read_subscribe(socket: &mut TcpStream) -> ClientMessage {
+        let mut len = [0u8; 4];
+        socket.read_exact(&mut len).await.unwrap();
+        let mut payload = vec![0u8; wire::checked_frame_len(len).unwrap()];
+        socket.read_exact(&mut payload).await.unwrap();
+        wire::decode_payload(&payload).unwrap()
+    }
+
+    #[tokio::test]
+    async fn frames_become_update_events() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let provider = GeyserRpcProvider::new(&format!("tcp://{}", listener.local_addr().unwrap()));
+        let (account, program, callee) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        provider.subscribe_account(&account).await.unwrap();
+        let mut updates = provider.start().await.unwrap();
+        let (mut socket, _) = listener.accept().await.unwrap();
+        assert_eq!(read_subscribe(&mut socket).await, ClientMessage::Subscribe { accounts: vec![account], programs: vec![] });
+
+        let signature = Signature::new_unique();
+        let transaction = |signature, is_vote| GeyserMessage::Transaction {
+            signature,
+            slot: 10,
+            index: Some(0),
+            is_vote,
+            program_ids: vec![program],
+            invoked_program_ids: vec![callee],
+            err: Some(TransactionError::InstructionError(0, InstructionError::Custom(6000))),
+            failed_program_id: Some(callee),
+        };
+        let messages = [
+            GeyserMessage::Account {
+                pubkey: account, lamports: 5, owner: program, executable: false, rent_epoch: 0, data: vec![1, 2],
+                slot: 10, write_version: 1, is_startup: false,
+            },
+            transaction(Signature::new_unique(), true),
+            transaction(signature, false),
+            GeyserMessage::Slot { slot: 10, parent: Some(9), status: wire::SlotStatus::Rooted },
+        ];
+        for message in &messages {
+            socket.write_all(&wire::encode_frame(message).unwrap()).await.unwrap();
+        }
+
+        match updates.next().await.unwrap().event {
+            UpdateEvent::AccountUpdate(info) => {
+                assert_eq!((info.pubkey, info.owner, info.lamports, info.slot), (account, program, 5, 10));
+                assert_eq!(info.data, vec![1, 2]);
+            }
+            _ => panic!("expected an account"),
+        }
+        // Votes are skipped
+        match updates.next().await.unwrap().event {
+            UpdateEvent::TransactionUpdate(info) => {
+                assert_eq!((info.signature, info.status.slot), (signature, 10));
+                assert_eq!((info.program_ids, info.invoked_program_ids), (vec![program], vec![callee]));
+                let error = info.error.unwrap();
+                assert_eq!((error.program_id, error.code), (callee, 6000));
+            }
+            _ => panic!("expected a transaction"),
+        }
+        match updates.next().await.unwrap().event {
+            UpdateEvent::SlotUpdate(slot) => assert_eq!((slot.slot, slot.parent, slot.status), (10, Some(9), SlotStatus::Finalized)),
+            _ => panic!("expected a slot"),
+        }
+    }
+
+    #[tokio::test]
+    async fn tracking_changes_resend_the_filter() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let provider = GeyserRpcProvider::new(&format!("tcp://{}", listener.local_addr().unwrap()));
+        let (account, program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        provider.subscribe_account(&account).await.unwrap();
+        let _updates = provider.start().await.unwrap();
+        let (mut socket, _) = listener.accept().await.unwrap();
+        assert_eq!(read_subscribe(&mut socket).await, ClientMessage::Subscribe { accounts: vec![account], programs: vec![] });
+
+        provider.subscribe_program(&program).await.unwrap();
+        assert_eq!(read_subscribe(&mut socket).await, ClientMessage::Subscribe { accounts: vec![account], programs: vec![program] });
+        provider.unsubscribe_account(&account).await.unwrap();
+        assert_eq!(read_subscribe(&mut socket).await, ClientMessage::Subscribe { accounts: vec![], programs: vec![program] });
+        // Releasing an untracked address leaves the filter alone, so the next
+        // frame is the one for the program
+        provider.unsubscribe_account(&account).await.unwrap();
+        provider.unsubscribe_program(&program).await.unwrap();
+        assert_eq!(read_subscribe(&mut socket).await, ClientMessage::Subscribe { accounts: vec![], programs: vec![] });
+    }
+
+    #[tokio::test]
+    async fn silent_or_closed_connections_are_reopened() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let provider = GeyserRpcProvider::with_read_timeout(&format!("tcp://{}", listener.local_addr().unwrap()), Duration::from_millis(100));
+        let program = Pubkey::new_unique();
+        provider.subscribe_program(&program).await.unwrap();
+        let _updates = provider.start().await.unwrap();
+        let subscribe = ClientMessage::Subscribe { accounts: vec![], programs: vec![program] };
+        let accept = || async { tokio::time::timeout(Duration::from_secs(5), listener.accept()).await.unwrap().unwrap().0 };
+
+        // Left open but silent
+        let mut silent = accept().await;
+        assert_eq!(read_subscribe(&mut silent).await, subscribe);
+
+        // Closed by the plugin, as it does with clients that fall behind
+        let mut closed = accept().await;
+        assert_eq!(read_subscribe(&mut closed).await, subscribe);
+        drop(closed);
+
+        let mut reopened = accept().await;
+        assert_eq!(read_subscribe(&mut reopened).await, subscribe);
+    }
+}
@@ -0,0 +1,5 @@
+mod yellowstone;
+
+pub use yellowstone::{ReplaySubscribeRequest, YellowstoneGrpcConfig, YellowstoneGrpcProvider};
+#[cfg(any(feature = "kafka", feature = "nats"))]
+pub(crate) use yellowstone::{account_keys, update_event};
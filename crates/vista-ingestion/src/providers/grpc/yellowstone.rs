@@ -0,0 +1,561 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::{Reward, RewardType};
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio_stream::wrappers::ReceiverStream;
+use prost::bytes::{Buf, BufMut};
+use prost::encoding::{DecodeContext, WireType};
+use prost::Message;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::AsciiMetadataValue;
+use tonic::transport::{ClientTlsConfig, Endpoint};
+use tonic::Request;
+use vista_core::models::{AccountInfo, BlockMetaInfo, SlotInfo, SlotStatus};
+use vista_core::{IndexerError, UpdateEvent};
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+use yellowstone_grpc_proto::geyser::{
+    CommitmentLevel as GrpcCommitmentLevel, SubscribeRequest, SubscribeRequestFilterAccounts,
+    SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterSlots, SubscribeRequestFilterTransactions,
+    SubscribeRequestPing, SubscribeUpdate, SubscribeUpdateAccount, SubscribeUpdateBlockMeta, SubscribeUpdateSlot,
+    SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+};
+
+use crate::error::IngestionError;
+use crate::providers::{failed_program, invoked_program_ids, program_error, transaction_info, update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// Field number of `SubscribeRequest.from_slot` in newer Yellowstone protos
+const FROM_SLOT_TAG: u32 = 11;
+
+#[derive(Debug, Clone)]
+pub struct YellowstoneGrpcConfig {
+    pub endpoint: String,
+    pub x_token: Option<String>,
+    pub commitment: CommitmentLevel,
+    // Replay from this slot on the first connection; reconnects resume from the last slot seen
+    pub from_slot: Option<u64>,
+    pub subscribe_transactions: bool,
+    pub subscribe_slots: bool,
+    pub subscribe_blocks_meta: bool,
+    pub ping_interval: Duration,
+}
+
+impl YellowstoneGrpcConfig {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            x_token: None,
+            commitment: CommitmentLevel::Confirmed,
+            from_slot: None,
+            subscribe_transactions: true,
+            subscribe_slots: true,
+            subscribe_blocks_meta: true,
+            ping_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+pub struct YellowstoneGrpcProvider {
+    name: String,
+    inner: Arc<Inner>,
+    updates: UpdateReceiver,
+}
+
+struct Inner {
+    config: YellowstoneGrpcConfig,
+    update_channel: UpdateSender,
+    accounts: RwLock<HashSet<Pubkey>>,
+    programs: RwLock<HashSet<Pubkey>>,
+    // Sender half of the live request stream, used to push updated filters and pings
+    request_tx: Mutex<Option<mpsc::Sender<ReplaySubscribeRequest>>>,
+    last_slot: AtomicU64,
+}
+
+impl YellowstoneGrpcProvider {
+    pub fn new(config: YellowstoneGrpcConfig) -> Self {
+        let (update_channel, updates) = update_channel();
+        Self {
+            name: "yellowstone_grpc".to_string(),
+            inner: Arc::new(Inner {
+                config,
+                update_channel,
+                accounts: RwLock::new(HashSet::new()),
+                programs: RwLock::new(HashSet::new()),
+                request_tx: Mutex::new(None),
+                last_slot: AtomicU64::new(0),
+            }),
+            updates,
+        }
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl RpcProvider for YellowstoneGrpcProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        RpcProviderType::Grpc
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            accounts: true,
+            programs: true,
+            transactions: self.inner.config.subscribe_transactions,
+            pending_transactions: false,
+            slots: self.inner.config.subscribe_slots,
+            blocks: self.inner.config.subscribe_blocks_meta,
+            program_filters: false,
+            replay: true,
+            repair: false,
+            commitments: vec![CommitmentLevel::Processed, CommitmentLevel::Confirmed, CommitmentLevel::Finalized],
+        }
+    }
+
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.accounts.write().await.insert(*pubkey);
+        Ok(self.inner.refresh_subscription().await?)
+    }
+
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.programs.write().await.insert(*program_id);
+        Ok(self.inner.refresh_subscription().await?)
+    }
+
+    async fn unsubscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.accounts.write().await.remove(pubkey);
+        Ok(self.inner.refresh_subscription().await?)
+    }
+
+    async fn unsubscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.programs.write().await.remove(program_id);
+        Ok(self.inner.refresh_subscription().await?)
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        let updates = self.updates.take()?;
+        let inner = self.inner.clone();
+        tokio::spawn(async move { inner.run().await });
+        Ok(updates)
+    }
+}
+
+impl Inner {
+    async fn run(self: Arc<Self>) {
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            if let Err(e) = self.stream_updates(&mut backoff).await {
+                eprintln!("Yellowstone gRPC stream error: {}", e);
+            }
+            *self.request_tx.lock().await = None;
+            if self.update_channel.is_closed() {
+                return;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    async fn stream_updates(&self, backoff: &mut Duration) -> Result<(), IngestionError> {
+        let mut endpoint = Endpoint::from_shared(self.config.endpoint.clone())
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?
+            .http2_keep_alive_interval(self.config.ping_interval)
+            .keep_alive_while_idle(true)
+            .tcp_keepalive(Some(self.config.ping_interval));
+        if self.config.endpoint.starts_with("https") {
+            endpoint = endpoint.tls_config(ClientTlsConfig::new())
+                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        }
+        let channel = endpoint.connect().await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let mut client = tonic::client::Grpc::new(channel);
+
+        let (tx, rx) = mpsc::channel(16);
+        tx.send(self.build_request(self.replay_slot()).await).await
+            .map_err(|e| IngestionError::SubscriptionError(e.to_string()))?;
+
+        let mut request = Request::new(ReceiverStream::new(rx));
+        if let Some(token) = &self.config.x_token {
+            let value = AsciiMetadataValue::try_from(token.as_str())
+                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+            request.metadata_mut().insert("x-token", value);
+        }
+
+        // The generated client can't send `from_slot`, so the call is made by hand
+        client.ready().await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let codec = ProstCodec::<ReplaySubscribeRequest, SubscribeUpdate>::default();
+        let mut stream = client.streaming(request, PathAndQuery::from_static("/geyser.Geyser/Subscribe"), codec).await
+            .map_err(|e| IngestionError::SubscriptionError(e.to_string()))?
+            .into_inner();
+        *self.request_tx.lock().await = Some(tx.clone());
+        *backoff = INITIAL_BACKOFF;
+
+        let mut ping = tokio::time::interval(self.config.ping_interval);
+        let mut ping_id = 0;
+        loop {
+            tokio::select! {
+                message = stream.next() => {
+                    let update = match message {
+                        Some(update) => update.map_err(|e| IngestionError::ProviderError(e.to_string()))?,
+                        None => return Ok(()),
+                    };
+                    match update.update_oneof {
+                        // Load balancers drop idle streams, so answer server pings
+                        Some(UpdateOneof::Ping(_)) => {
+                            ping_id += 1;
+                            let _ = tx.send(ping_request(ping_id)).await;
+                        }
+                        Some(update) => {
+                            if let Some((slot, event)) = update_event(update, self.config.commitment)? {
+                                if at_commitment(&event, self.config.commitment) {
+                                    self.last_slot.fetch_max(slot, Ordering::Relaxed);
+                                }
+                                self.emit(event).await?;
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                _ = ping.tick() => {
+                    ping_id += 1;
+                    let _ = tx.send(ping_request(ping_id)).await;
+                }
+            }
+        }
+    }
+
+    fn replay_slot(&self) -> Option<u64> {
+        match self.last_slot.load(Ordering::Relaxed) {
+            0 => self.config.from_slot,
+            // Resume at the last slot seen; it may have been only partially delivered
+            slot => Some(slot),
+        }
+    }
+
+    async fn refresh_subscription(&self) -> Result<(), IngestionError> {
+        // Yellowstone replaces all filters with each request sent on the stream
+        let tx = self.request_tx.lock().await.clone();
+        if let Some(tx) = tx {
+            tx.send(self.build_request(None).await).await
+                .map_err(|e| IngestionError::SubscriptionError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn build_request(&self, from_slot: Option<u64>) -> ReplaySubscribeRequest {
+        let accounts: Vec<String> = self.accounts.read().await.iter().map(|p| p.to_string()).collect();
+        let programs: Vec<String> = self.programs.read().await.iter().map(|p| p.to_string()).collect();
+
+        let mut request = SubscribeRequest {
+            commitment: Some(grpc_commitment(self.config.commitment) as i32),
+            ..Default::default()
+        };
+        if !accounts.is_empty() {
+            request.accounts.insert("tracked_accounts".to_string(), SubscribeRequestFilterAccounts {
+                account: accounts.clone(),
+                ..Default::default()
+            });
+        }
+        if !programs.is_empty() {
+            request.accounts.insert("tracked_programs".to_string(), SubscribeRequestFilterAccounts {
+                owner: programs.clone(),
+                ..Default::default()
+            });
+        }
+        if self.config.subscribe_transactions && !(accounts.is_empty() && programs.is_empty()) {
+            request.transactions.insert("tracked".to_string(), SubscribeRequestFilterTransactions {
+                vote: Some(false),
+                account_include: accounts.into_iter().chain(programs).collect(),
+                ..Default::default()
+            });
+        }
+        if self.config.subscribe_slots {
+            request.slots.insert("slots".to_string(), SubscribeRequestFilterSlots::default());
+        }
+        if self.config.subscribe_blocks_meta {
+            request.blocks_meta.insert("blocks_meta".to_string(), SubscribeRequestFilterBlocksMeta::default());
+        }
+        ReplaySubscribeRequest { request, from_slot }
+    }
+
+    async fn emit(&self, event: UpdateEvent) -> Result<(), IngestionError> {
+        self.update_channel.send(event).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))
+    }
+}
+
+// Converts one Yellowstone update into the indexer's event and the slot it
+// belongs to. Shared with providers reading Yellowstone messages off a bus.
+pub(crate) fn update_event(update: UpdateOneof, commitment: CommitmentLevel) -> Result<Option<(u64, UpdateEvent)>, IngestionError> {
+    match update {
+        UpdateOneof::Account(account) => account_event(account),
+        UpdateOneof::Transaction(transaction) => transaction_event(transaction, commitment),
+        UpdateOneof::Slot(slot) => Ok(slot_event(slot)),
+        UpdateOneof::BlockMeta(block_meta) => Ok(Some(block_meta_event(block_meta))),
+        _ => Ok(None),
+    }
+}
+
+fn account_event(update: SubscribeUpdateAccount) -> Result<Option<(u64, UpdateEvent)>, IngestionError> {
+    let account = match update.account {
+        Some(account) => account,
+        None => return Ok(None),
+    };
+    Ok(Some((update.slot, UpdateEvent::AccountUpdate(AccountInfo {
+        pubkey: to_pubkey(&account.pubkey)?,
+        lamports: account.lamports,
+        owner: to_pubkey(&account.owner)?,
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        data: account.data,
+        slot: update.slot,
+    }))))
+}
+
+fn transaction_event(update: SubscribeUpdateTransaction, commitment: CommitmentLevel) -> Result<Option<(u64, UpdateEvent)>, IngestionError> {
+    let info = match update.transaction {
+        Some(info) => info,
+        None => return Ok(None),
+    };
+    if info.is_vote {
+        return Ok(None);
+    }
+
+    let signature = Signature::try_from(info.signature.as_slice())
+        .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+    let account_keys = account_keys(&info)?;
+    let message = info.transaction.and_then(|tx| tx.message).unwrap_or_default();
+    let meta = info.meta.unwrap_or_default();
+    let program_id = |index: u32| account_keys.get(index as usize).copied();
+    let program_ids = message.instructions.iter().filter_map(|ix| program_id(ix.program_id_index)).collect();
+    let inner = meta.inner_instructions.iter()
+        .flat_map(|inner| &inner.instructions)
+        .filter_map(|ix| program_id(ix.program_id_index));
+
+    let err = match meta.err {
+        Some(err) => Some(bincode::deserialize::<TransactionError>(&err.err)
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?),
+        None => None,
+    };
+
+    let mut info = transaction_info(signature, update.slot, program_ids, err, commitment);
+    info.invoked_program_ids = invoked_program_ids(inner);
+    info.error = program_error(info.status.err.as_ref(), failed_program(&meta.log_messages));
+    Ok(Some((update.slot, UpdateEvent::TransactionUpdate(info))))
+}
+
+// Every address the transaction loads. CPI targets can also be among the
+// addresses loaded through lookup tables.
+pub(crate) fn account_keys(info: &SubscribeUpdateTransactionInfo) -> Result<Vec<Pubkey>, IngestionError> {
+    let message_keys = info.transaction.as_ref()
+        .and_then(|tx| tx.message.as_ref())
+        .map(|message| message.account_keys.as_slice())
+        .unwrap_or_default();
+    let loaded = info.meta.as_ref()
+        .map(|meta| (meta.loaded_writable_addresses.as_slice(), meta.loaded_readonly_addresses.as_slice()))
+        .unwrap_or_default();
+    message_keys.iter()
+        .chain(loaded.0)
+        .chain(loaded.1)
+        .map(|key| to_pubkey(key))
+        .collect()
+}
+
+// Slot statuses arrive for every commitment, ahead of the data at the
+// subscribed one; resuming from a processed slot would skip confirmed data
+// still to come for the slots before it. Everything else is already filtered
+// to the subscribed commitment.
+fn at_commitment(event: &UpdateEvent, commitment: CommitmentLevel) -> bool {
+    match event {
+        UpdateEvent::SlotUpdate(slot) => matches!(
+            (slot.status, commitment),
+            (SlotStatus::Processed, CommitmentLevel::Processed)
+                | (SlotStatus::Confirmed, CommitmentLevel::Confirmed)
+                | (SlotStatus::Finalized, CommitmentLevel::Finalized)
+        ),
+        _ => true,
+    }
+}
+
+fn slot_event(update: SubscribeUpdateSlot) -> Option<(u64, UpdateEvent)> {
+    let status = match update.status {
+        s if s == GrpcCommitmentLevel::Processed as i32 => SlotStatus::Processed,
+        s if s == GrpcCommitmentLevel::Confirmed as i32 => SlotStatus::Confirmed,
+        s if s == GrpcCommitmentLevel::Finalized as i32 => SlotStatus::Finalized,
+        // Intermediate states (first shred, completed, dead) aren't tracked
+        _ => return None,
+    };
+    Some((update.slot, UpdateEvent::SlotUpdate(SlotInfo {
+        slot: update.slot,
+        parent: update.parent,
+        status,
+    })))
+}
+
+fn block_meta_event(update: SubscribeUpdateBlockMeta) -> (u64, UpdateEvent) {
+    let rewards = update.rewards.map(|r| r.rewards).unwrap_or_default().into_iter()
+        .map(|reward| Reward {
+            pubkey: reward.pubkey,
+            lamports: reward.lamports,
+            post_balance: reward.post_balance,
+            reward_type: match reward.reward_type {
+                1 => Some(RewardType::Fee),
+                2 => Some(RewardType::Rent),
+                3 => Some(RewardType::Staking),
+                4 => Some(RewardType::Voting),
+                _ => None,
+            },
+            commission: reward.commission.parse().ok(),
+        })
+        .collect();
+
+    (update.slot, UpdateEvent::BlockMetaUpdate(BlockMetaInfo {
+        slot: update.slot,
+        blockhash: update.blockhash,
+        parent_slot: update.parent_slot,
+        parent_blockhash: update.parent_blockhash,
+        block_time: update.block_time.map(|t| t.timestamp),
+        block_height: update.block_height.map(|h| h.block_height),
+        executed_transaction_count: update.executed_transaction_count,
+        rewards,
+    }))
+}
+
+fn ping_request(id: i32) -> ReplaySubscribeRequest {
+    let request = SubscribeRequest {
+        ping: Some(SubscribeRequestPing { id }),
+        ..Default::default()
+    };
+    ReplaySubscribeRequest { request, from_slot: None }
+}
+
+// `SubscribeRequest` with the `from_slot` field of newer Yellowstone servers,
+// whose generated types need Solana 2. Older servers skip the unknown field
+// and stream from the tip.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplaySubscribeRequest {
+    pub request: SubscribeRequest,
+    pub from_slot: Option<u64>,
+}
+
+impl Message for ReplaySubscribeRequest {
+    fn encode_raw<B: BufMut>(&self, buf: &mut B) {
+        self.request.encode_raw(buf);
+        if let Some(from_slot) = &self.from_slot {
+            prost::encoding::uint64::encode(FROM_SLOT_TAG, from_slot, buf);
+        }
+    }
+
+    fn merge_field<B: Buf>(&mut self, tag: u32, wire_type: WireType, buf: &mut B, ctx: DecodeContext) -> Result<(), prost::DecodeError> {
+        if tag == FROM_SLOT_TAG {
+            prost::encoding::uint64::merge(wire_type, self.from_slot.get_or_insert(0), buf, ctx)
+        } else {
+            self.request.merge_field(tag, wire_type, buf, ctx)
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.request.encoded_len()
+            + self.from_slot.as_ref().map_or(0, |from_slot| prost::encoding::uint64::encoded_len(FROM_SLOT_TAG, from_slot))
+    }
+
+    fn clear(&mut self) {
+        self.request.clear();
+        self.from_slot = None;
+    }
+}
+
+fn to_pubkey(bytes: &[u8]) -> Result<Pubkey, IngestionError> {
+    Pubkey::try_from(bytes).map_err(|_| IngestionError::ProviderError(format!("Invalid pubkey length: {}", bytes.len())))
+}
+
+fn grpc_commitment(commitment: CommitmentLevel) -> GrpcCommitmentLevel {
+    match commitment {
+        CommitmentLevel::Processed => GrpcCommitmentLevel::Processed,
+        CommitmentLevel::Finalized => GrpcCommitmentLevel::Finalized,
+        _ => GrpcCommitmentLevel::Confirmed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yellowstone_grpc_proto::prelude::{
+        CompiledInstruction, InnerInstruction, InnerInstructions, Message, SubscribeUpdateTransactionInfo, Transaction,
+        TransactionStatusMeta,
+    };
+
+    use super::*;
+
+    fn inner(program_id_index: u32) -> InnerInstruction {
+        InnerInstruction { program_id_index, ..Default::default() }
+    }
+
+    #[test]
+    fn cpi_targets_include_lookup_table_addresses() {
+        let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let signature = Signature::new_unique();
+        let update = SubscribeUpdateTransaction {
+            slot: 12,
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: signature.as_ref().to_vec(),
+                transaction: Some(Transaction {
+                    signatures: vec![signature.as_ref().to_vec()],
+                    message: Some(Message {
+                        account_keys: keys[..2].iter().map(|key| key.to_bytes().to_vec()).collect(),
+                        instructions: vec![CompiledInstruction { program_id_index: 1, ..Default::default() }],
+                        ..Default::default()
+                    }),
+                }),
+                meta: Some(TransactionStatusMeta {
+                    // Indexes 2 and 3 are the loaded writable and readonly addresses
+                    inner_instructions: vec![InnerInstructions { index: 0, instructions: vec![inner(3), inner(2), inner(3)] }],
+                    loaded_writable_addresses: vec![keys[2].to_bytes().to_vec()],
+                    loaded_readonly_addresses: vec![keys[3].to_bytes().to_vec()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        };
+
+        let (slot, event) = transaction_event(update, CommitmentLevel::Confirmed).unwrap().unwrap();
+        let info = match event {
+            UpdateEvent::TransactionUpdate(info) => info,
+            _ => panic!("expected a transaction"),
+        };
+        assert_eq!((slot, info.signature), (12, signature));
+        assert_eq!(info.program_ids, vec![keys[1]]);
+        assert_eq!(info.invoked_program_ids, vec![keys[3], keys[2]]);
+    }
+
+    #[test]
+    fn only_slots_at_the_subscribed_commitment_move_the_resume_point() {
+        let slot = |status| UpdateEvent::SlotUpdate(SlotInfo { slot: 5, parent: None, status });
+        assert!(!at_commitment(&slot(SlotStatus::Processed), CommitmentLevel::Confirmed));
+        assert!(at_commitment(&slot(SlotStatus::Confirmed), CommitmentLevel::Confirmed));
+        assert!(!at_commitment(&slot(SlotStatus::Confirmed), CommitmentLevel::Finalized));
+        assert!(at_commitment(&slot(SlotStatus::Processed), CommitmentLevel::Processed));
+
+        let block = block_meta_event(SubscribeUpdateBlockMeta { slot: 5, ..Default::default() }).1;
+        assert!(at_commitment(&block, CommitmentLevel::Finalized));
+    }
+}
@@ -0,0 +1,677 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::json;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcBlockConfig, RpcProgramAccountsConfig, RpcTransactionConfig};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::{OptionalContext, RpcKeyedAccount};
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{TransactionDetails, UiTransactionEncoding};
+use tokio::sync::{Mutex, RwLock};
+use vista_core::models::AccountInfo;
+use vista_core::{IndexerError, UpdateEvent};
+
+use crate::error::IngestionError;
+use crate::providers::{block_events, encoded_transaction_info, update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+// getMultipleAccounts rejects more keys than this per request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+// Signatures remembered to avoid emitting a transaction once per tracked address
+const SEEN_SIGNATURES: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct HttpPollConfig {
+    pub commitment: CommitmentConfig,
+    pub account_interval: Duration,
+    pub program_interval: Duration,
+    pub transaction_interval: Duration,
+    pub signature_limit: usize,
+}
+
+impl Default for HttpPollConfig {
+    fn default() -> Self {
+        Self {
+            commitment: CommitmentConfig::confirmed(),
+            account_interval: Duration::from_secs(2),
+            program_interval: Duration::from_secs(10),
+            transaction_interval: Duration::from_secs(2),
+            signature_limit: 100,
+        }
+    }
+}
+
+pub struct HttpRpcProvider {
+    name: String,
+    inner: Arc<Inner>,
+    updates: UpdateReceiver,
+}
+
+struct Inner {
+    client: RpcClient,
+    config: HttpPollConfig,
+    update_channel: UpdateSender,
+    accounts: RwLock<Vec<Pubkey>>,
+    programs: RwLock<HashMap<Pubkey, Vec<RpcFilterType>>>,
+    state: Mutex<PollState>,
+}
+
+#[derive(Default)]
+struct PollState {
+    // Hash of the last emitted contents of every account, so unchanged accounts are skipped
+    account_hashes: HashMap<Pubkey, u64>,
+    // Accounts last returned by getProgramAccounts, per program, to detect closed accounts
+    program_accounts: HashMap<Pubkey, HashSet<Pubkey>>,
+    // Newest signature seen per address; `None` until the first poll sets the cursor
+    signature_cursors: HashMap<Pubkey, Option<Signature>>,
+    seen_signatures: HashSet<Signature>,
+    seen_order: VecDeque<Signature>,
+}
+
+impl PollState {
+    fn account_changed(&mut self, pubkey: Pubkey, account: &Account) -> bool {
+        let mut hasher = DefaultHasher::new();
+        (account.lamports, account.owner, account.executable, account.rent_epoch, &account.data).hash(&mut hasher);
+        let hash = hasher.finish();
+        self.account_hashes.insert(pubkey, hash) != Some(hash)
+    }
+
+    fn mark_seen(&mut self, signature: Signature) {
+        if !self.seen_signatures.insert(signature) {
+            return;
+        }
+        self.seen_order.push_back(signature);
+        if self.seen_order.len() > SEEN_SIGNATURES {
+            if let Some(oldest) = self.seen_order.pop_front() {
+                self.seen_signatures.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl HttpRpcProvider {
+    pub fn new(url: &str) -> Self {
+        Self::with_config(url, HttpPollConfig::default())
+    }
+
+    pub fn with_config(url: &str, config: HttpPollConfig) -> Self {
+        let (update_channel, updates) = update_channel();
+        Self {
+            name: "http".to_string(),
+            inner: Arc::new(Inner {
+                client: RpcClient::new_with_commitment(url.to_string(), config.commitment),
+                config,
+                update_channel,
+                accounts: RwLock::new(Vec::new()),
+                programs: RwLock::new(HashMap::new()),
+                state: Mutex::new(PollState::default()),
+            }),
+            updates,
+        }
+    }
+
+    pub async fn subscribe_program_with_filters(&self, program_id: &Pubkey, filters: Vec<RpcFilterType>) -> Result<(), IngestionError> {
+        self.inner.programs.write().await.insert(*program_id, filters);
+        Ok(())
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl RpcProvider for HttpRpcProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        RpcProviderType::Http
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            accounts: true,
+            programs: true,
+            transactions: true,
+            program_filters: true,
+            repair: true,
+            // getSignaturesForAddress has no processed view
+            commitments: vec![CommitmentLevel::Confirmed, CommitmentLevel::Finalized],
+            ..Default::default()
+        }
+    }
+
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        let mut accounts = self.inner.accounts.write().await;
+        if !accounts.contains(pubkey) {
+            accounts.push(*pubkey);
+        }
+        Ok(())
+    }
+
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.programs.write().await.entry(*program_id).or_default();
+        Ok(())
+    }
+
+    async fn unsubscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.accounts.write().await.retain(|account| account != pubkey);
+        Ok(())
+    }
+
+    async fn unsubscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.programs.write().await.remove(program_id);
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        let updates = self.updates.take()?;
+        let inner = self.inner.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(inner.config.account_interval);
+            while !inner.update_channel.is_closed() {
+                interval.tick().await;
+                if let Err(e) = inner.poll_accounts().await {
+                    eprintln!("HTTP account poll failed: {}", e);
+                }
+            }
+        });
+
+        let inner = self.inner.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(inner.config.program_interval);
+            while !inner.update_channel.is_closed() {
+                interval.tick().await;
+                if let Err(e) = inner.poll_programs().await {
+                    eprintln!("HTTP program poll failed: {}", e);
+                }
+            }
+        });
+
+        let inner = self.inner.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(inner.config.transaction_interval);
+            while !inner.update_channel.is_closed() {
+                interval.tick().await;
+                if let Err(e) = inner.poll_transactions().await {
+                    eprintln!("HTTP transaction poll failed: {}", e);
+                }
+            }
+        });
+
+        Ok(updates)
+    }
+
+    async fn repair_slot(&self, slot: u64) -> Result<Option<Vec<UpdateEvent>>, IndexerError> {
+        Ok(self.inner.repair_slot(slot).await?)
+    }
+}
+
+impl Inner {
+    fn account_config(&self) -> RpcAccountInfoConfig {
+        RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(self.config.commitment),
+            ..Default::default()
+        }
+    }
+
+    async fn emit_account(&self, pubkey: Pubkey, account: Account, slot: u64) -> Result<(), IngestionError> {
+        self.update_channel.send(UpdateEvent::AccountUpdate(AccountInfo {
+            pubkey,
+            lamports: account.lamports,
+            owner: account.owner,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: account.data,
+            slot,
+        })).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))
+    }
+
+    async fn poll_accounts(&self) -> Result<(), IngestionError> {
+        let accounts = self.accounts.read().await.clone();
+        for chunk in accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let response = self.client.get_multiple_accounts_with_config(chunk, self.account_config()).await
+                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+            let slot = response.context.slot;
+            for (pubkey, account) in chunk.iter().zip(response.value) {
+                // A missing account is reported as an empty one so closures are indexed
+                let account = account.unwrap_or_default();
+                let changed = self.state.lock().await.account_changed(*pubkey, &account);
+                if changed {
+                    self.emit_account(*pubkey, account, slot).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn poll_programs(&self) -> Result<(), IngestionError> {
+        let programs = self.programs.read().await.clone();
+        for (program_id, filters) in programs {
+            let config = RpcProgramAccountsConfig {
+                filters: if filters.is_empty() { None } else { Some(filters) },
+                account_config: self.account_config(),
+                with_context: Some(true),
+            };
+            let (slot, accounts) = self.program_accounts(&program_id, config).await?;
+
+            let current: HashSet<Pubkey> = accounts.iter().map(|(pubkey, _)| *pubkey).collect();
+            let changed: Vec<(Pubkey, Account)> = {
+                let mut state = self.state.lock().await;
+                accounts.into_iter()
+                    .filter(|(pubkey, account)| state.account_changed(*pubkey, account))
+                    .collect()
+            };
+            for (pubkey, account) in changed {
+                self.emit_account(pubkey, account, slot).await?;
+            }
+
+            // Accounts that dropped out of the result set were closed or reassigned
+            let previous = self.state.lock().await.program_accounts.insert(program_id, current.clone());
+            for pubkey in previous.unwrap_or_default().difference(&current) {
+                let closed = Account::default();
+                let changed = self.state.lock().await.account_changed(*pubkey, &closed);
+                if changed {
+                    self.emit_account(*pubkey, closed, slot).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // getProgramAccounts with the slot the RPC node read the accounts at,
+    // which the client's own wrapper drops
+    async fn program_accounts(&self, program_id: &Pubkey, config: RpcProgramAccountsConfig) -> Result<(u64, Vec<(Pubkey, Account)>), IngestionError> {
+        let response = self.client.send::<OptionalContext<Vec<RpcKeyedAccount>>>(
+            RpcRequest::GetProgramAccounts,
+            json!([program_id.to_string(), config]),
+        ).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let response = match response {
+            OptionalContext::Context(response) => response,
+            OptionalContext::NoContext(_) => {
+                return Err(IngestionError::ProviderError("getProgramAccounts returned no context slot".to_string()));
+            }
+        };
+
+        let mut accounts = Vec::with_capacity(response.value.len());
+        for keyed in response.value {
+            let pubkey = Pubkey::from_str(&keyed.pubkey)
+                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+            let account = keyed.account.decode()
+                .ok_or_else(|| IngestionError::ProviderError(format!("Failed to decode account {}", pubkey)))?;
+            accounts.push((pubkey, account));
+        }
+        Ok((response.context.slot, accounts))
+    }
+
+    // Signatures newer than `until`, newest first. A busy address can have
+    // more than a page between polls, so pages are walked backwards until
+    // one comes back short.
+    async fn signatures_since(&self, address: &Pubkey, until: Option<Signature>, first_page_only: bool) -> Result<Vec<Signature>, IngestionError> {
+        let mut signatures = Vec::new();
+        let mut before = None;
+        loop {
+            let config = GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit: Some(self.config.signature_limit),
+                commitment: Some(self.config.commitment),
+            };
+            let page = self.client.get_signatures_for_address_with_config(address, config).await
+                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+            let full = page.len() >= self.config.signature_limit;
+            for status in page {
+                signatures.push(Signature::from_str(&status.signature)
+                    .map_err(|e| IngestionError::ProviderError(e.to_string()))?);
+            }
+            if first_page_only || !full {
+                return Ok(signatures);
+            }
+            before = signatures.last().copied();
+        }
+    }
+
+    async fn poll_transactions(&self) -> Result<(), IngestionError> {
+        let mut addresses = self.accounts.read().await.clone();
+        addresses.extend(self.programs.read().await.keys().copied());
+
+        for address in addresses {
+            let cursor = self.state.lock().await.signature_cursors.get(&address).copied();
+            // The first poll only positions the cursor; history is the bootstrap's job
+            let until = match cursor {
+                Some(until) => until,
+                None => {
+                    let newest = self.signatures_since(&address, None, true).await?.first().copied();
+                    self.state.lock().await.signature_cursors.insert(address, newest);
+                    continue;
+                }
+            };
+            let signatures = self.signatures_since(&address, until, false).await?;
+            let newest = signatures.first().copied();
+
+            // Signatures come newest first; emit them in the order they landed
+            for signature in signatures.into_iter().rev() {
+                if self.state.lock().await.seen_signatures.contains(&signature) {
+                    continue;
+                }
+                let config = RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(self.config.commitment),
+                    max_supported_transaction_version: Some(0),
+                };
+                let transaction = self.client.get_transaction_with_config(&signature, config).await
+                    .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                if let Some(info) = encoded_transaction_info(transaction.slot, &transaction.transaction, self.config.commitment.commitment) {
+                    self.update_channel.send(UpdateEvent::TransactionUpdate(info)).await
+                        .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                }
+                // Marked only once emitted, so a failed fetch isn't skipped on the retry
+                self.state.lock().await.mark_seen(signature);
+            }
+            // Only advanced once everything up to it was emitted, so a failed
+            // poll is retried from the same place
+            if newest.is_some() {
+                self.state.lock().await.signature_cursors.insert(address, newest);
+            }
+        }
+        Ok(())
+    }
+
+    async fn repair_slot(&self, slot: u64) -> Result<Option<Vec<UpdateEvent>>, IngestionError> {
+        let produced = self.client.get_blocks_with_commitment(slot, Some(slot), self.config.commitment).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        if produced.is_empty() {
+            return Ok(None);
+        }
+        let block = self.client.get_block_with_config(slot, RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            transaction_details: Some(TransactionDetails::Full),
+            rewards: Some(true),
+            commitment: Some(self.config.commitment),
+            max_supported_transaction_version: Some(0),
+        }).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+
+        let mut tracked: HashSet<Pubkey> = self.accounts.read().await.iter().copied().collect();
+        tracked.extend(self.programs.read().await.keys().copied());
+
+        // RPC only serves the current state of an account, not its state at
+        // `slot`, so accounts are left to the next update instead of being
+        // stored under a slot they don't belong to
+        Ok(Some(block_events(slot, block, &tracked, self.config.commitment.commitment)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{FutureExt, StreamExt};
+    use serde_json::Value;
+    use solana_client::rpc_client::Mocks;
+
+    use super::*;
+    use crate::providers::scripted_client;
+
+    fn poller(client: RpcClient, config: HttpPollConfig) -> (Inner, UpdateStream) {
+        let (update_channel, updates) = update_channel();
+        let inner = Inner {
+            client,
+            config,
+            update_channel,
+            accounts: RwLock::new(Vec::new()),
+            programs: RwLock::new(HashMap::new()),
+            state: Mutex::new(PollState::default()),
+        };
+        (inner, updates.take().unwrap())
+    }
+
+    fn drain(updates: &mut UpdateStream) -> Vec<UpdateEvent> {
+        std::iter::from_fn(|| updates.next().now_or_never().flatten()).map(|update| update.event).collect()
+    }
+
+    fn account_json(lamports: u64, owner: &Pubkey) -> Value {
+        json!({
+            "lamports": lamports,
+            "owner": owner.to_string(),
+            "data": ["", "base64"],
+            "executable": false,
+            "rentEpoch": 0,
+            "space": 0,
+        })
+    }
+
+    fn lamports(events: &[UpdateEvent]) -> Vec<(Pubkey, u64)> {
+        events.iter().map(|event| match event {
+            UpdateEvent::AccountUpdate(account) => (account.pubkey, account.lamports),
+            _ => panic!("expected an account update"),
+        }).collect()
+    }
+
+    fn signatures(events: &[UpdateEvent]) -> Vec<Signature> {
+        events.iter().map(|event| match event {
+            UpdateEvent::TransactionUpdate(info) => info.signature,
+            _ => panic!("expected a transaction update"),
+        }).collect()
+    }
+
+    fn signature_page(signatures: &[Signature]) -> Value {
+        signatures.iter().map(|signature| json!({
+            "signature": signature.to_string(),
+            "slot": 3,
+            "err": null,
+            "memo": null,
+            "blockTime": null,
+        })).collect()
+    }
+
+    fn transaction_json(signature: &str) -> Value {
+        json!({
+            "slot": 3,
+            "blockTime": null,
+            "transaction": {
+                "signatures": [signature],
+                "message": {
+                    "header": { "numRequiredSignatures": 1, "numReadonlySignedAccounts": 0, "numReadonlyUnsignedAccounts": 0 },
+                    "accountKeys": [Pubkey::new_unique().to_string()],
+                    "recentBlockhash": "11111111111111111111111111111111",
+                    "instructions": [],
+                },
+            },
+            "meta": null,
+        })
+    }
+
+    // `until` and `before` of every getSignaturesForAddress request
+    type Cursors = Arc<std::sync::Mutex<Vec<(Option<String>, Option<String>)>>>;
+
+    // Serves `pages` to getSignaturesForAddress in order, recording the
+    // `until` and `before` of each request, and fails the first fetch of
+    // `failing`
+    fn signature_client(
+        pages: Vec<Vec<Signature>>,
+        failing: Option<Signature>,
+    ) -> (RpcClient, Cursors) {
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let mut pages = pages.into_iter();
+        let mut failing = failing.map(|signature| signature.to_string());
+        let client = scripted_client(move |request, params| match request {
+            RpcRequest::GetSignaturesForAddress => {
+                let cursor = |field: &str| params[1][field].as_str().map(String::from);
+                recorded.lock().unwrap().push((cursor("until"), cursor("before")));
+                Some(signature_page(&pages.next()?))
+            }
+            RpcRequest::GetTransaction => {
+                let signature = params[0].as_str()?.to_string();
+                if failing.as_ref() == Some(&signature) {
+                    failing = None;
+                    return None;
+                }
+                Some(transaction_json(&signature))
+            }
+            other => panic!("unexpected {}", other),
+        });
+        (client, requests)
+    }
+
+    #[tokio::test]
+    async fn program_accounts_carry_the_context_slot() {
+        let (pubkey, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut mocks = Mocks::new();
+        mocks.insert(RpcRequest::GetProgramAccounts, json!({
+            "context": { "slot": 42 },
+            "value": [{
+                "pubkey": pubkey.to_string(),
+                "account": {
+                    "lamports": 5,
+                    "owner": owner.to_string(),
+                    "data": ["AQI=", "base64"],
+                    "executable": false,
+                    "rentEpoch": 0,
+                    "space": 2,
+                },
+            }],
+        }));
+        let (inner, _updates) = poller(RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks), HttpPollConfig::default());
+
+        let config = RpcProgramAccountsConfig {
+            filters: None,
+            account_config: inner.account_config(),
+            with_context: Some(true),
+        };
+        let (slot, accounts) = inner.program_accounts(&owner, config).await.unwrap();
+        assert_eq!(slot, 42);
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].0, pubkey);
+        assert_eq!((accounts[0].1.lamports, accounts[0].1.owner, accounts[0].1.data.as_slice()), (5, owner, &[1, 2][..]));
+    }
+
+    #[tokio::test]
+    async fn tracked_accounts_are_emitted_when_they_change() {
+        let (tracked, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // Balance per poll; `None` once the account is gone
+        let mut balances = vec![Some(5), Some(5), Some(6), None].into_iter();
+        let client = scripted_client(move |request, _| {
+            assert_eq!(request, RpcRequest::GetMultipleAccounts);
+            let account = balances.next()?.map(|lamports| account_json(lamports, &owner));
+            Some(json!({ "context": { "slot": 9 }, "value": [account] }))
+        });
+        let (inner, mut updates) = poller(client, HttpPollConfig::default());
+        inner.accounts.write().await.push(tracked);
+
+        let mut emitted = Vec::new();
+        for _ in 0..4 {
+            inner.poll_accounts().await.unwrap();
+            emitted.push(lamports(&drain(&mut updates)));
+        }
+        assert_eq!(emitted, vec![vec![(tracked, 5)], vec![], vec![(tracked, 6)], vec![(tracked, 0)]]);
+    }
+
+    #[tokio::test]
+    async fn program_accounts_that_drop_out_are_emitted_closed() {
+        let (program, first, second) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut polls = vec![
+            vec![(first, 1), (second, 1)],
+            vec![(first, 1), (second, 1)],
+            vec![(first, 2)],
+        ].into_iter();
+        let client = scripted_client(move |request, _| {
+            assert_eq!(request, RpcRequest::GetProgramAccounts);
+            let accounts: Vec<Value> = polls.next()?.into_iter()
+                .map(|(pubkey, lamports)| json!({ "pubkey": pubkey.to_string(), "account": account_json(lamports, &program) }))
+                .collect();
+            Some(json!({ "context": { "slot": 9 }, "value": accounts }))
+        });
+        let (inner, mut updates) = poller(client, HttpPollConfig::default());
+        inner.programs.write().await.insert(program, Vec::new());
+
+        let mut emitted = Vec::new();
+        for _ in 0..3 {
+            inner.poll_programs().await.unwrap();
+            emitted.push(lamports(&drain(&mut updates)));
+        }
+        assert_eq!(emitted, vec![
+            vec![(first, 1), (second, 1)],
+            vec![],
+            vec![(first, 2), (second, 0)],
+        ]);
+    }
+
+    #[tokio::test]
+    async fn the_signature_cursor_starts_at_the_newest_and_follows_emitted_transactions() {
+        let address = Pubkey::new_unique();
+        let s: Vec<Signature> = (0..4).map(|_| Signature::new_unique()).collect();
+        let (client, requests) = signature_client(vec![vec![s[1], s[0]], vec![s[3], s[2]], vec![]], None);
+        let (inner, mut updates) = poller(client, HttpPollConfig::default());
+        inner.accounts.write().await.push(address);
+
+        // History before the first poll is left alone
+        inner.poll_transactions().await.unwrap();
+        assert!(drain(&mut updates).is_empty());
+
+        inner.poll_transactions().await.unwrap();
+        assert_eq!(signatures(&drain(&mut updates)), vec![s[2], s[3]]);
+
+        inner.poll_transactions().await.unwrap();
+        assert!(drain(&mut updates).is_empty());
+
+        let untils: Vec<Option<String>> = requests.lock().unwrap().iter().map(|(until, _)| until.clone()).collect();
+        assert_eq!(untils, vec![None, Some(s[1].to_string()), Some(s[3].to_string())]);
+    }
+
+    #[tokio::test]
+    async fn busy_addresses_are_paged_until_a_short_page() {
+        let address = Pubkey::new_unique();
+        let s: Vec<Signature> = (0..5).map(|_| Signature::new_unique()).collect();
+        let (client, requests) = signature_client(vec![vec![s[4], s[3]], vec![s[2], s[1]], vec![]], None);
+        let config = HttpPollConfig { signature_limit: 2, ..Default::default() };
+        let (inner, mut updates) = poller(client, config);
+        inner.accounts.write().await.push(address);
+        inner.state.lock().await.signature_cursors.insert(address, Some(s[0]));
+
+        inner.poll_transactions().await.unwrap();
+        assert_eq!(signatures(&drain(&mut updates)), vec![s[1], s[2], s[3], s[4]]);
+        assert_eq!(*requests.lock().unwrap(), vec![
+            (Some(s[0].to_string()), None),
+            (Some(s[0].to_string()), Some(s[3].to_string())),
+            (Some(s[0].to_string()), Some(s[1].to_string())),
+        ]);
+        assert_eq!(inner.state.lock().await.signature_cursors[&address], Some(s[4]));
+    }
+
+    #[tokio::test]
+    async fn transactions_that_fail_to_fetch_are_retried() {
+        let address = Pubkey::new_unique();
+        let s: Vec<Signature> = (0..3).map(|_| Signature::new_unique()).collect();
+        let (client, _requests) = signature_client(vec![vec![s[2], s[1]], vec![s[2], s[1]]], Some(s[2]));
+        let (inner, mut updates) = poller(client, HttpPollConfig::default());
+        inner.accounts.write().await.push(address);
+        inner.state.lock().await.signature_cursors.insert(address, Some(s[0]));
+
+        assert!(inner.poll_transactions().await.is_err());
+        assert_eq!(signatures(&drain(&mut updates)), vec![s[1]]);
+        assert_eq!(inner.state.lock().await.signature_cursors[&address], Some(s[0]));
+
+        // The retry skips what was already emitted and picks up the failure
+        inner.poll_transactions().await.unwrap();
+        assert_eq!(signatures(&drain(&mut updates)), vec![s[2]]);
+        assert_eq!(inner.state.lock().await.signature_cursors[&address], Some(s[2]));
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use tokio::sync::{oneshot, Mutex, RwLock};
+use vista_core::models::{AccountInfo, SlotInfo, SlotStatus};
+use vista_core::{IndexerError, UpdateEvent};
+
+pub use solana_program_test::{processor, ProgramTest};
+
+use crate::error::IngestionError;
+use crate::providers::{failed_program, program_error, transaction_info, update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+// Outcome of a transaction executed by a `LocalValidator`
+#[derive(Debug, Clone)]
+pub struct SentTransaction {
+    pub signature: Signature,
+    pub slot: u64,
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+}
+
+// An in-process bank for tests, without validator or network. Programs and
+// fixture accounts are deployed at genesis through the `ProgramTest` it is
+// started from, either as `.so` files found in `SBF_OUT_DIR` or as native
+// entrypoints wrapped in `processor!`. What its transactions change is
+// streamed by the `LocalValidatorProvider` it is started with.
+pub struct LocalValidator {
+    inner: Arc<Inner>,
+}
+
+// Streams the subscribed accounts and programs written by the transactions a
+// `LocalValidator` executes, right after each one
+pub struct LocalValidatorProvider {
+    inner: Arc<Inner>,
+    updates: UpdateReceiver,
+}
+
+struct Inner {
+    // Also serializes transactions, so every update carries the slot it was written in
+    context: Mutex<ProgramTestContext>,
+    accounts: RwLock<HashSet<Pubkey>>,
+    programs: RwLock<HashSet<Pubkey>>,
+    update_channel: UpdateSender,
+}
+
+impl LocalValidator {
+    pub async fn start(program_test: ProgramTest) -> Result<(Self, LocalValidatorProvider), IndexerError> {
+        let mut context = program_test.start_with_context().await;
+        let slot = current_slot(&mut context).await?;
+        let (update_channel, updates) = update_channel();
+        let _ = update_channel.try_send(UpdateEvent::SlotUpdate(SlotInfo { slot, parent: None, status: SlotStatus::Processed }));
+
+        let inner = Arc::new(Inner {
+            context: Mutex::new(context),
+            accounts: RwLock::new(HashSet::new()),
+            programs: RwLock::new(HashSet::new()),
+            update_channel,
+        });
+        Ok((Self { inner: inner.clone() }, LocalValidatorProvider { inner, updates }))
+    }
+
+    // Pays the fees of every transaction and is funded at genesis
+    pub async fn payer(&self) -> Keypair {
+        self.inner.context.lock().await.payer.insecure_clone()
+    }
+
+    // Executes a transaction paid by `payer`. Failing instructions still
+    // land, as on a cluster, and show up in `err`; only transactions the
+    // bank rejects outright, like ones missing a signature, return an error.
+    pub async fn send_transaction(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<SentTransaction, IndexerError> {
+        let mut context = self.inner.context.lock().await;
+        let context = &mut *context;
+        let blockhash = context.banks_client.get_latest_blockhash().await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let mut keypairs = vec![&context.payer];
+        keypairs.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &keypairs, blockhash);
+
+        let signature = transaction.signatures[0];
+        let processed = context.banks_client.process_transaction_with_metadata(transaction.clone()).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let slot = current_slot(context).await?;
+        let err = processed.result.err();
+        let logs = processed.metadata.map(|metadata| metadata.log_messages).unwrap_or_default();
+
+        let message = &transaction.message;
+        let accounts = self.inner.accounts.read().await.clone();
+        let programs = self.inner.programs.read().await.clone();
+        if message.account_keys.iter().any(|key| accounts.contains(key) || programs.contains(key)) {
+            let program_ids = message.instructions.iter()
+                .map(|ix| message.account_keys[ix.program_id_index as usize])
+                .collect();
+            let mut info = transaction_info(signature, slot, program_ids, err.clone(), CommitmentLevel::Processed);
+            info.error = program_error(err.as_ref(), failed_program(&logs));
+            self.inner.emit(UpdateEvent::TransactionUpdate(info)).await;
+        }
+        for (index, pubkey) in message.account_keys.iter().enumerate() {
+            if !message.is_writable(index) {
+                continue;
+            }
+            let account = context.banks_client.get_account(*pubkey).await
+                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+            // Closed accounts are reported like a validator does, emptied and
+            // handed back to the system program
+            let account = account.unwrap_or_default();
+            if accounts.contains(pubkey) || programs.contains(&account.owner) {
+                self.inner.emit(UpdateEvent::AccountUpdate(account_info(*pubkey, account, slot))).await;
+            }
+        }
+        Ok(SentTransaction { signature, slot, err, logs })
+    }
+
+    // Sends lamports from the payer, creating `to` if it doesn't exist
+    pub async fn airdrop(&self, to: &Pubkey, lamports: u64) -> Result<SentTransaction, IndexerError> {
+        let payer = self.payer().await.pubkey();
+        self.send_transaction(&[solana_sdk::system_instruction::transfer(&payer, to, lamports)], &[]).await
+    }
+
+    // Overwrites an account outside of any transaction, for fixtures that
+    // would be tedious to build through instructions
+    pub async fn set_account(&self, pubkey: &Pubkey, account: Account) -> Result<(), IndexerError> {
+        let mut context = self.inner.context.lock().await;
+        context.set_account(pubkey, &AccountSharedData::from(account.clone()));
+        let slot = current_slot(&mut context).await?;
+        if self.inner.accounts.read().await.contains(pubkey) || self.inner.programs.read().await.contains(&account.owner) {
+            self.inner.emit(UpdateEvent::AccountUpdate(account_info(*pubkey, account, slot))).await;
+        }
+        Ok(())
+    }
+
+    pub async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>, IndexerError> {
+        let mut context = self.inner.context.lock().await;
+        let account = context.banks_client.get_account(*pubkey).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        Ok(account)
+    }
+
+    // Moves the bank forward; the slots in between are skipped
+    pub async fn warp_to_slot(&self, slot: u64) -> Result<(), IndexerError> {
+        let mut context = self.inner.context.lock().await;
+        let parent = current_slot(&mut context).await?;
+        context.warp_to_slot(slot)
+            .map_err(|e| IngestionError::ProviderError(format!("Cannot warp to slot {}: {:?}", slot, e)))?;
+        self.inner.emit(UpdateEvent::SlotUpdate(SlotInfo { slot, parent: Some(parent), status: SlotStatus::Processed })).await;
+        Ok(())
+    }
+
+    // Waits until the consumer of the provider's stream, usually an
+    // `Indexer`, processed every update sent so far
+    pub async fn settle(&self) -> Result<(), IndexerError> {
+        let (done, processed) = oneshot::channel();
+        self.inner.update_channel.send(UpdateEvent::Checkpoint(done)).await
+            .map_err(|_| IngestionError::ProviderError("Local validator stream was dropped".to_string()))?;
+        processed.await
+            .map_err(|_| IngestionError::ProviderError("Local validator stream was dropped".to_string()))?;
+        Ok(())
+    }
+}
+
+impl Inner {
+    async fn emit(&self, event: UpdateEvent) {
+        // Nothing to do once the stream is gone; the bank keeps working for the test
+        let _ = self.update_channel.send(event).await;
+    }
+}
+
+#[async_trait]
+impl RpcProvider for LocalValidatorProvider {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        RpcProviderType::Geyser
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            accounts: true,
+            programs: true,
+            transactions: true,
+            slots: true,
+            commitments: vec![CommitmentLevel::Processed],
+            ..Default::default()
+        }
+    }
+
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.accounts.write().await.insert(*pubkey);
+        Ok(())
+    }
+
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.programs.write().await.insert(*program_id);
+        Ok(())
+    }
+
+    async fn unsubscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.accounts.write().await.remove(pubkey);
+        Ok(())
+    }
+
+    async fn unsubscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.programs.write().await.remove(program_id);
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        Ok(self.updates.take()?)
+    }
+}
+
+async fn current_slot(context: &mut ProgramTestContext) -> Result<u64, IndexerError> {
+    let clock = context.banks_client.get_sysvar::<Clock>().await
+        .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+    Ok(clock.slot)
+}
+
+fn account_info(pubkey: Pubkey, account: Account, slot: u64) -> AccountInfo {
+    AccountInfo {
+        pubkey,
+        lamports: account.lamports,
+        owner: account.owner,
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        data: account.data,
+        slot,
+    }
+}
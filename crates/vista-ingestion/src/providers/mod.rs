@@ -1,4 +1,384 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::{
+    EncodedTransaction, EncodedTransactionWithStatusMeta, TransactionConfirmationStatus, TransactionStatus,
+    UiConfirmedBlock, UiInnerInstructions, UiInstruction, UiLoadedAddresses, UiMessage, UiParsedInstruction,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use vista_core::models::{BlockMetaInfo, ProgramErrorInfo, SlotInfo, SlotStatus, TransactionInfo};
+use vista_core::{ReceivedUpdate, UpdateEvent};
+
+use crate::error::IngestionError;
+use crate::traits::UpdateStream;
+
+pub mod block;
+#[cfg(any(feature = "kafka", feature = "nats"))]
+pub mod bus;
 pub mod geyser;
 pub mod websocket;
 pub mod grpc;
-pub mod http;
\ No newline at end of file
+pub mod http;
+#[cfg(feature = "local-validator")]
+pub mod local;
+pub mod replay;
+#[cfg(feature = "shredstream")]
+pub mod shredstream;
+pub mod webhook;
+
+pub use block::{BlockProvider, BlockProviderConfig, BlockSource};
+#[cfg(any(feature = "kafka", feature = "nats"))]
+pub use bus::{MessageFormat, TopicKind};
+#[cfg(feature = "kafka")]
+pub use bus::{KafkaConfig, KafkaProvider};
+#[cfg(feature = "nats")]
+pub use bus::{NatsConfig, NatsProvider};
+pub use geyser::{GeyserRpcProvider, HeliusFilterConfig, HeliusTransactionProvider};
+pub use grpc::{YellowstoneGrpcConfig, YellowstoneGrpcProvider};
+pub use http::{HttpPollConfig, HttpRpcProvider};
+#[cfg(feature = "local-validator")]
+pub use local::{LocalValidator, LocalValidatorProvider, SentTransaction};
+pub use replay::{RecordingProvider, ReplayProvider, ReplaySpeed, ReplayStepper};
+#[cfg(feature = "shredstream")]
+pub use shredstream::{ShredSource, ShredStreamProvider};
+pub use webhook::{WebhookConfig, WebhookProvider};
+pub use websocket::WebSocketRpcProvider;
+
+// Updates buffered between a provider's tasks and its consumer
+const UPDATE_BUFFER: usize = 10_000;
+
+// Sending half of a provider's update channel; stamps each update as it is
+// sent, which is where the provider received it
+#[derive(Clone)]
+pub(crate) struct UpdateSender(mpsc::Sender<ReceivedUpdate>);
+
+impl UpdateSender {
+    pub(crate) async fn send(&self, event: UpdateEvent) -> Result<(), mpsc::error::SendError<ReceivedUpdate>> {
+        self.0.send(ReceivedUpdate::now(event)).await
+    }
+
+    // Whether the update was queued
+    #[cfg(feature = "local-validator")]
+    pub(crate) fn try_send(&self, event: UpdateEvent) -> bool {
+        self.0.try_send(ReceivedUpdate::now(event)).is_ok()
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+}
+
+// Receiving half of a provider's update channel, handed out once by `start`
+pub(crate) struct UpdateReceiver(std::sync::Mutex<Option<mpsc::Receiver<ReceivedUpdate>>>);
+
+impl UpdateReceiver {
+    pub(crate) fn take(&self) -> Result<UpdateStream, IngestionError> {
+        let receiver = self.0.lock().unwrap().take()
+            .ok_or_else(|| IngestionError::ProviderError("Provider already started".to_string()))?;
+        Ok(Box::pin(ReceiverStream::new(receiver)))
+    }
+}
+
+pub(crate) fn update_channel() -> (UpdateSender, UpdateReceiver) {
+    let (sender, receiver) = mpsc::channel(UPDATE_BUFFER);
+    (UpdateSender(sender), UpdateReceiver(std::sync::Mutex::new(Some(receiver))))
+}
+
+pub(crate) fn confirmation_status(commitment: CommitmentLevel) -> TransactionConfirmationStatus {
+    match commitment {
+        CommitmentLevel::Processed => TransactionConfirmationStatus::Processed,
+        CommitmentLevel::Finalized => TransactionConfirmationStatus::Finalized,
+        _ => TransactionConfirmationStatus::Confirmed,
+    }
+}
+
+pub(crate) fn transaction_info(
+    signature: Signature,
+    slot: u64,
+    program_ids: Vec<Pubkey>,
+    err: Option<TransactionError>,
+    commitment: CommitmentLevel,
+) -> TransactionInfo {
+    TransactionInfo {
+        signature,
+        status: TransactionStatus {
+            slot,
+            confirmations: None,
+            status: err.clone().map_or(Ok(()), Err),
+            err,
+            confirmation_status: Some(confirmation_status(commitment)),
+        },
+        program_ids,
+        invoked_program_ids: Vec::new(),
+        error: None,
+        pending: false,
+    }
+}
+
+// The program that returned the transaction's error. Under CPI that's the
+// callee, not the program of the failing top-level instruction. Every caller
+// up the stack logs "Program <id> failed" again on the way out, so the first
+// such line is the one.
+pub(crate) fn failed_program(logs: &[String]) -> Option<Pubkey> {
+    logs.iter().find_map(|line| {
+        let (program_id, _) = line.strip_prefix("Program ")?.split_once(" failed: ")?;
+        Pubkey::from_str(program_id).ok()
+    })
+}
+
+// Attributes a custom error to the program that returned it, where the source
+// knows which one that is; the indexer names the error later
+pub(crate) fn program_error(err: Option<&TransactionError>, program_id: Option<Pubkey>) -> Option<ProgramErrorInfo> {
+    match err? {
+        TransactionError::InstructionError(index, InstructionError::Custom(code)) => Some(ProgramErrorInfo {
+            instruction_index: *index,
+            program_id: program_id?,
+            code: *code,
+            name: None,
+            message: None,
+        }),
+        _ => None,
+    }
+}
+
+// Programs reached through CPI, in order of first call
+pub(crate) fn invoked_program_ids(inner: impl IntoIterator<Item = Pubkey>) -> Vec<Pubkey> {
+    let mut seen = HashSet::new();
+    inner.into_iter().filter(|program_id| seen.insert(*program_id)).collect()
+}
+
+// Builds a `TransactionInfo` from a transaction fetched over JSON-RPC, in
+// either a binary or the `json`/`jsonParsed` encoding. Program ids resolve
+// address lookup tables through the loaded addresses; CPI targets come from
+// the inner instructions in the status meta.
+pub(crate) fn encoded_transaction_info(
+    slot: u64,
+    transaction: &EncodedTransactionWithStatusMeta,
+    commitment: CommitmentLevel,
+) -> Option<TransactionInfo> {
+    let meta = transaction.meta.as_ref();
+    let loaded_addresses: Vec<Pubkey> = meta
+        .and_then(|meta| Option::<&UiLoadedAddresses>::from(meta.loaded_addresses.as_ref()))
+        .map(|loaded| loaded.writable.iter().chain(&loaded.readonly)
+            .filter_map(|key| Pubkey::from_str(key).ok())
+            .collect())
+        .unwrap_or_default();
+
+    let (signature, account_keys, program_ids) = match &transaction.transaction {
+        EncodedTransaction::Json(ui) => {
+            let signature = Signature::from_str(ui.signatures.first()?).ok()?;
+            match &ui.message {
+                UiMessage::Raw(message) => {
+                    let mut account_keys: Vec<Pubkey> = message.account_keys.iter()
+                        .filter_map(|key| Pubkey::from_str(key).ok())
+                        .collect();
+                    account_keys.extend(&loaded_addresses);
+                    let program_ids = message.instructions.iter()
+                        .filter_map(|ix| account_keys.get(ix.program_id_index as usize).copied())
+                        .collect();
+                    (signature, account_keys, program_ids)
+                }
+                // Parsed messages already list the loaded addresses
+                UiMessage::Parsed(message) => {
+                    let account_keys: Vec<Pubkey> = message.account_keys.iter()
+                        .filter_map(|key| Pubkey::from_str(&key.pubkey).ok())
+                        .collect();
+                    let program_ids = message.instructions.iter()
+                        .filter_map(|ix| instruction_program_id(ix, &account_keys))
+                        .collect();
+                    (signature, account_keys, program_ids)
+                }
+            }
+        }
+        encoded => {
+            let decoded = encoded.decode()?;
+            let signature = *decoded.signatures.first()?;
+            let mut account_keys = decoded.message.static_account_keys().to_vec();
+            account_keys.extend(&loaded_addresses);
+            let program_ids = decoded.message.instructions().iter()
+                .filter_map(|ix| account_keys.get(ix.program_id_index as usize).copied())
+                .collect();
+            (signature, account_keys, program_ids)
+        }
+    };
+
+    let inner_instructions = meta
+        .and_then(|meta| Option::<&Vec<UiInnerInstructions>>::from(meta.inner_instructions.as_ref()))
+        .into_iter()
+        .flatten()
+        .flat_map(|inner| &inner.instructions);
+    let invoked = invoked_program_ids(inner_instructions.filter_map(|ix| instruction_program_id(ix, &account_keys)));
+
+    let logs = meta.and_then(|meta| Option::<&Vec<String>>::from(meta.log_messages.as_ref()));
+
+    let err = meta.and_then(|meta| meta.err.clone());
+    let mut info = transaction_info(signature, slot, program_ids, err, commitment);
+    info.invoked_program_ids = invoked;
+    info.error = program_error(info.status.err.as_ref(), logs.and_then(|logs| failed_program(logs)));
+    Some(info)
+}
+
+fn instruction_program_id(ix: &UiInstruction, account_keys: &[Pubkey]) -> Option<Pubkey> {
+    match ix {
+        UiInstruction::Compiled(ix) => account_keys.get(ix.program_id_index as usize).copied(),
+        UiInstruction::Parsed(UiParsedInstruction::Parsed(ix)) => Pubkey::from_str(&ix.program_id).ok(),
+        UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(ix)) => Pubkey::from_str(&ix.program_id).ok(),
+    }
+}
+
+// Tracked transactions of a block fetched over JSON-RPC, followed by its slot
+// and block metadata
+pub(crate) fn block_events(
+    slot: u64,
+    block: UiConfirmedBlock,
+    tracked: &HashSet<Pubkey>,
+    commitment: CommitmentLevel,
+) -> Vec<UpdateEvent> {
+    let transactions = block.transactions.unwrap_or_default();
+    let mut events: Vec<UpdateEvent> = transactions.iter()
+        .filter(|transaction| touches_tracked(transaction, tracked))
+        .filter_map(|transaction| encoded_transaction_info(slot, transaction, commitment))
+        .map(UpdateEvent::TransactionUpdate)
+        .collect();
+
+    let status = match commitment {
+        CommitmentLevel::Finalized => SlotStatus::Finalized,
+        _ => SlotStatus::Confirmed,
+    };
+    events.push(UpdateEvent::SlotUpdate(SlotInfo { slot, parent: Some(block.parent_slot), status }));
+    events.push(UpdateEvent::BlockMetaUpdate(BlockMetaInfo {
+        slot,
+        blockhash: block.blockhash,
+        parent_slot: block.parent_slot,
+        parent_blockhash: block.previous_blockhash,
+        block_time: block.block_time,
+        block_height: block.block_height,
+        executed_transaction_count: transactions.len() as u64,
+        rewards: block.rewards.unwrap_or_default(),
+    }));
+    events
+}
+
+// Static keys of a binary-encoded transaction followed by the addresses it
+// loaded through lookup tables
+pub(crate) fn transaction_keys(transaction: &EncodedTransactionWithStatusMeta) -> Vec<Pubkey> {
+    let mut keys = transaction.transaction.decode()
+        .map(|decoded| decoded.message.static_account_keys().to_vec())
+        .unwrap_or_default();
+    if let Some(loaded) = transaction.meta.as_ref()
+        .and_then(|meta| Option::<&UiLoadedAddresses>::from(meta.loaded_addresses.as_ref()))
+    {
+        keys.extend(loaded.writable.iter().chain(&loaded.readonly).filter_map(|key| Pubkey::from_str(key).ok()));
+    }
+    keys
+}
+
+// Whether the transaction references a tracked account or program, either as
+// a static key or through an address lookup table
+pub(crate) fn touches_tracked(transaction: &EncodedTransactionWithStatusMeta, tracked: &HashSet<Pubkey>) -> bool {
+    transaction_keys(transaction).iter().any(|key| tracked.contains(key))
+}
+
+// RpcClient answering every request from `handler`, for tests that need a
+// different response each time a method is called. `None` fails the request.
+#[cfg(test)]
+pub(crate) fn scripted_client(
+    handler: impl FnMut(solana_client::rpc_request::RpcRequest, serde_json::Value) -> Option<serde_json::Value> + Send + 'static,
+) -> solana_client::nonblocking::rpc_client::RpcClient {
+    use solana_client::client_error::{ClientError, ClientErrorKind};
+    use solana_client::rpc_client::RpcClientConfig;
+    use solana_client::rpc_request::{RpcError, RpcRequest};
+    use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+
+    type Handler = Box<dyn FnMut(RpcRequest, serde_json::Value) -> Option<serde_json::Value> + Send>;
+    struct ScriptedSender(std::sync::Mutex<Handler>);
+
+    #[async_trait::async_trait]
+    impl RpcSender for ScriptedSender {
+        async fn send(&self, request: RpcRequest, params: serde_json::Value) -> Result<serde_json::Value, ClientError> {
+            // Asked by the client itself before its first commitment-aware call
+            if request == RpcRequest::GetVersion {
+                return Ok(serde_json::json!({ "solana-core": "1.18.26" }));
+            }
+            (self.0.lock().unwrap())(request, params).ok_or_else(|| {
+                ClientError::from(ClientErrorKind::RpcError(RpcError::RpcRequestError(format!("{} failed", request))))
+            })
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "scripted".to_string()
+        }
+    }
+
+    solana_client::nonblocking::rpc_client::RpcClient::new_sender(
+        ScriptedSender(std::sync::Mutex::new(Box::new(handler))),
+        RpcClientConfig::default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn compiled(program_id_index: u8) -> serde_json::Value {
+        json!({ "programIdIndex": program_id_index, "accounts": [], "data": "", "stackHeight": null })
+    }
+
+    #[test]
+    fn program_ids_stay_top_level_and_cpis_are_separate() {
+        let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let signature = Signature::new_unique();
+        let transaction: EncodedTransactionWithStatusMeta = serde_json::from_value(json!({
+            "transaction": {
+                "signatures": [signature.to_string()],
+                "message": {
+                    "header": { "numRequiredSignatures": 1, "numReadonlySignedAccounts": 0, "numReadonlyUnsignedAccounts": 3 },
+                    "accountKeys": keys.iter().map(|k| k.to_string()).collect::<Vec<_>>(),
+                    "recentBlockhash": "11111111111111111111111111111111",
+                    // The same program twice, then another one
+                    "instructions": [compiled(1), compiled(1), compiled(2)],
+                },
+            },
+            "meta": {
+                "err": { "InstructionError": [2, { "Custom": 6000 }] },
+                "status": { "Err": { "InstructionError": [2, { "Custom": 6000 }] } },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [
+                    { "index": 0, "instructions": [compiled(3), compiled(2)] },
+                    { "index": 2, "instructions": [compiled(3)] },
+                ],
+                // The CPI target fails and its caller reports the same error
+                "logMessages": [
+                    format!("Program {} invoke [1]", keys[2]),
+                    format!("Program {} invoke [2]", keys[3]),
+                    format!("Program {} failed: custom program error: 0x1770", keys[3]),
+                    format!("Program {} failed: custom program error: 0x1770", keys[2]),
+                ],
+            },
+        })).unwrap();
+
+        let info = encoded_transaction_info(7, &transaction, CommitmentLevel::Confirmed).unwrap();
+        assert_eq!(info.signature, signature);
+        assert_eq!(info.status.slot, 7);
+        assert_eq!(info.program_ids, vec![keys[1], keys[1], keys[2]]);
+        assert_eq!(info.invoked_program_ids, vec![keys[3], keys[2]]);
+        assert!(info.invokes(&keys[3]));
+        assert!(!info.invokes(&keys[0]));
+        let error = info.error.unwrap();
+        assert_eq!((error.instruction_index, error.program_id, error.code), (2, keys[3], 6000));
+    }
+}
@@ -0,0 +1,268 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use vista_core::models::{AccountInfo, BlockMetaInfo, SlotInfo, TransactionInfo};
+use vista_core::UpdateEvent;
+
+use crate::error::IngestionError;
+
+// A recording is this header followed by records, each a little-endian u32
+// length and a bincode `(arrival time in unix microseconds, update)`. Files
+// are only ever appended to; a record cut short by a crash ends the recording
+// until the writer reopens it and truncates the partial record away.
+const MAGIC: &[u8; 8] = b"VISTAREC";
+// 2 split CPI targets out of `TransactionInfo::program_ids`
+const VERSION: u16 = 2;
+const HEADER_LEN: u64 = 10;
+// Guards against reading garbage as a huge length
+const MAX_RECORD_LEN: usize = 64 * 1024 * 1024;
+
+// Variant order is the on-disk tag: only ever append new variants
+#[derive(Serialize)]
+enum RecordRef<'a> {
+    Account(&'a AccountInfo),
+    Transaction(&'a TransactionInfo),
+    Slot(&'a SlotInfo),
+    BlockMeta(&'a BlockMetaInfo),
+}
+
+#[derive(Deserialize)]
+enum Record {
+    Account(AccountInfo),
+    Transaction(TransactionInfo),
+    Slot(SlotInfo),
+    BlockMeta(BlockMetaInfo),
+}
+
+pub struct RecordedEvent {
+    pub received_at_micros: u64,
+    pub event: UpdateEvent,
+}
+
+// Encodes one record, or `None` for checkpoints, which only mean something
+// to the live source that sent them
+pub(crate) fn encode_record(received_at_micros: u64, event: &UpdateEvent) -> Result<Option<Vec<u8>>, IngestionError> {
+    let record = match event {
+        UpdateEvent::AccountUpdate(account) => RecordRef::Account(account),
+        UpdateEvent::TransactionUpdate(transaction) => RecordRef::Transaction(transaction),
+        UpdateEvent::SlotUpdate(slot) => RecordRef::Slot(slot),
+        UpdateEvent::BlockMetaUpdate(block_meta) => RecordRef::BlockMeta(block_meta),
+        UpdateEvent::Checkpoint(_) => return Ok(None),
+    };
+    let payload = bincode::serialize(&(received_at_micros, record))
+        .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&payload);
+    Ok(Some(frame))
+}
+
+pub struct RecordWriter {
+    out: BufWriter<File>,
+}
+
+impl RecordWriter {
+    // Appends to `path`, writing the header if the file is new or empty.
+    // Anything after the last complete record, left by a crash mid-write, is
+    // truncated first so the records appended after it stay readable.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, IngestionError> {
+        let to_error = |e: std::io::Error| IngestionError::ProviderError(e.to_string());
+        let mut file = OpenOptions::new().create(true).truncate(false).read(true).write(true).open(path).map_err(to_error)?;
+        let len = file.metadata().map_err(to_error)?.len();
+        let end = if len < HEADER_LEN {
+            // New, or cut short while its header was written
+            let mut existing = Vec::new();
+            file.read_to_end(&mut existing).map_err(to_error)?;
+            let mut header = MAGIC.to_vec();
+            header.extend_from_slice(&VERSION.to_le_bytes());
+            if !header.starts_with(&existing) {
+                return Err(IngestionError::ProviderError("Not a Vista recording".to_string()));
+            }
+            file.seek(SeekFrom::Start(0)).map_err(to_error)?;
+            file.write_all(&header).map_err(to_error)?;
+            HEADER_LEN
+        } else {
+            let mut input = BufReader::new(&file);
+            read_header(&mut input)?;
+            complete_len(&mut input, len)?
+        };
+        file.set_len(end).map_err(to_error)?;
+        file.seek(SeekFrom::Start(end)).map_err(to_error)?;
+        Ok(Self { out: BufWriter::new(file) })
+    }
+
+    pub fn write(&mut self, received_at_micros: u64, event: &UpdateEvent) -> Result<(), IngestionError> {
+        if let Some(frame) = encode_record(received_at_micros, event)? {
+            self.write_frame(&frame)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_frame(&mut self, frame: &[u8]) -> Result<(), IngestionError> {
+        self.out.write_all(frame).map_err(|e| IngestionError::ProviderError(e.to_string()))
+    }
+
+    pub fn flush(&mut self) -> Result<(), IngestionError> {
+        self.out.flush().map_err(|e| IngestionError::ProviderError(e.to_string()))
+    }
+}
+
+pub struct RecordReader {
+    input: BufReader<File>,
+}
+
+impl RecordReader {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, IngestionError> {
+        let to_error = |e: std::io::Error| IngestionError::ProviderError(e.to_string());
+        let mut input = BufReader::new(File::open(path).map_err(to_error)?);
+        read_header(&mut input)?;
+        Ok(Self { input })
+    }
+
+    fn read_record(&mut self) -> Result<Option<RecordedEvent>, IngestionError> {
+        let mut len = [0u8; 4];
+        if let Err(e) = self.input.read_exact(&mut len) {
+            return match e.kind() {
+                ErrorKind::UnexpectedEof => Ok(None),
+                _ => Err(IngestionError::ProviderError(e.to_string())),
+            };
+        }
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_RECORD_LEN {
+            return Err(IngestionError::ProviderError(format!("Record of {} bytes", len)));
+        }
+        let mut payload = vec![0u8; len];
+        if let Err(e) = self.input.read_exact(&mut payload) {
+            return match e.kind() {
+                ErrorKind::UnexpectedEof => Ok(None),
+                _ => Err(IngestionError::ProviderError(e.to_string())),
+            };
+        }
+
+        let (received_at_micros, record): (u64, Record) = bincode::deserialize(&payload)
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let event = match record {
+            Record::Account(account) => UpdateEvent::AccountUpdate(account),
+            Record::Transaction(transaction) => UpdateEvent::TransactionUpdate(transaction),
+            Record::Slot(slot) => UpdateEvent::SlotUpdate(slot),
+            Record::BlockMeta(block_meta) => UpdateEvent::BlockMetaUpdate(block_meta),
+        };
+        Ok(Some(RecordedEvent { received_at_micros, event }))
+    }
+}
+
+fn read_header(input: &mut impl Read) -> Result<(), IngestionError> {
+    let mut header = [0u8; HEADER_LEN as usize];
+    input.read_exact(&mut header).map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+    if &header[..8] != MAGIC {
+        return Err(IngestionError::ProviderError("Not a Vista recording".to_string()));
+    }
+    let version = u16::from_le_bytes([header[8], header[9]]);
+    if version != VERSION {
+        return Err(IngestionError::ProviderError(format!("Unsupported recording version {}", version)));
+    }
+    Ok(())
+}
+
+// Length of the recording up to the end of its last complete record, reading
+// from just after the header
+fn complete_len(input: &mut BufReader<&File>, file_len: u64) -> Result<u64, IngestionError> {
+    let to_error = |e: std::io::Error| IngestionError::ProviderError(e.to_string());
+    let mut end = HEADER_LEN;
+    while end + 4 <= file_len {
+        let mut len = [0u8; 4];
+        input.read_exact(&mut len).map_err(to_error)?;
+        let len = u32::from_le_bytes(len) as u64;
+        // A torn write only shortens the tail, so a bad length is corruption
+        if len > MAX_RECORD_LEN as u64 {
+            return Err(IngestionError::ProviderError(format!("Record of {} bytes at offset {}", len, end)));
+        }
+        if end + 4 + len > file_len {
+            break;
+        }
+        input.seek_relative(len as i64).map_err(to_error)?;
+        end += 4 + len;
+    }
+    Ok(end)
+}
+
+impl Iterator for RecordReader {
+    type Item = Result<RecordedEvent, IngestionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+    use vista_core::models::SlotStatus;
+
+    use super::*;
+
+    fn recording_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("vista-{}-{}-{}.rec", name, std::process::id(), Pubkey::new_unique()))
+    }
+
+    fn slot_update(slot: u64) -> UpdateEvent {
+        UpdateEvent::SlotUpdate(SlotInfo { slot, parent: Some(slot - 1), status: SlotStatus::Confirmed })
+    }
+
+    fn recorded_slots(path: &Path) -> Vec<u64> {
+        RecordReader::open(path).unwrap().map(|record| match record.unwrap().event {
+            UpdateEvent::SlotUpdate(info) => info.slot,
+            _ => panic!("expected a slot update"),
+        }).collect()
+    }
+
+    #[test]
+    fn reopening_appends_after_the_last_complete_record() {
+        let path = recording_path("torn");
+        let mut writer = RecordWriter::open(&path).unwrap();
+        for slot in 1..=2 {
+            writer.write(slot, &slot_update(slot)).unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+        // A crash partway through the third record
+        let partial = encode_record(3, &slot_update(3)).unwrap().unwrap();
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(&partial[..partial.len() / 2]).unwrap();
+
+        let mut writer = RecordWriter::open(&path).unwrap();
+        writer.write(4, &slot_update(4)).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(recorded_slots(&path), vec![1, 2, 4]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn a_header_cut_short_is_rewritten() {
+        let path = recording_path("header");
+        std::fs::write(&path, &MAGIC[..4]).unwrap();
+        let mut writer = RecordWriter::open(&path).unwrap();
+        writer.write(1, &slot_update(1)).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(recorded_slots(&path), vec![1]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn other_files_and_versions_are_refused() {
+        let path = recording_path("foreign");
+        std::fs::write(&path, b"not a recording at all").unwrap();
+        assert!(RecordWriter::open(&path).is_err());
+        std::fs::write(&path, b"VIST!").unwrap();
+        assert!(RecordWriter::open(&path).is_err());
+
+        let mut older = MAGIC.to_vec();
+        older.extend_from_slice(&(VERSION - 1).to_le_bytes());
+        std::fs::write(&path, &older).unwrap();
+        assert!(RecordWriter::open(&path).is_err());
+        // Left as it was
+        assert_eq!(std::fs::read(&path).unwrap(), older);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
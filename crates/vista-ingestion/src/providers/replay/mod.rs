@@ -0,0 +1,168 @@
+mod format;
+mod recorder;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::time::Instant;
+use vista_core::IndexerError;
+
+pub use format::{RecordReader, RecordWriter, RecordedEvent};
+pub use recorder::RecordingProvider;
+
+use crate::error::IngestionError;
+use crate::providers::{update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+// Records read ahead of the one being paced
+const READ_AHEAD: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplaySpeed {
+    // Keeps the recorded gaps between updates
+    Original,
+    // Emits as fast as the consumer takes them
+    Max,
+    // Emits only as many updates as `ReplayStepper::step` has released
+    Stepped,
+}
+
+// Releases updates of a `ReplaySpeed::Stepped` replay
+#[derive(Clone)]
+pub struct ReplayStepper {
+    permits: Arc<Semaphore>,
+}
+
+impl ReplayStepper {
+    pub fn step(&self, updates: usize) {
+        self.permits.add_permits(updates);
+    }
+}
+
+// Plays a recording back as a provider. The stream ends after the last
+// record. Subscriptions are ignored: the recording already holds exactly
+// what the recorded provider was subscribed to.
+pub struct ReplayProvider {
+    name: String,
+    path: PathBuf,
+    speed: ReplaySpeed,
+    permits: Arc<Semaphore>,
+    update_channel: std::sync::Mutex<Option<UpdateSender>>,
+    updates: UpdateReceiver,
+}
+
+impl ReplayProvider {
+    pub fn new(path: impl Into<PathBuf>, speed: ReplaySpeed) -> Self {
+        let (update_channel, updates) = update_channel();
+        Self {
+            name: "replay".to_string(),
+            path: path.into(),
+            speed,
+            permits: Arc::new(Semaphore::new(0)),
+            update_channel: std::sync::Mutex::new(Some(update_channel)),
+            updates,
+        }
+    }
+
+    pub fn stepper(&self) -> ReplayStepper {
+        ReplayStepper { permits: self.permits.clone() }
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl RpcProvider for ReplayProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        RpcProviderType::Geyser
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            accounts: true,
+            programs: true,
+            transactions: true,
+            slots: true,
+            blocks: true,
+            replay: true,
+            commitments: vec![CommitmentLevel::Processed, CommitmentLevel::Confirmed, CommitmentLevel::Finalized],
+            ..Default::default()
+        }
+    }
+
+    async fn subscribe_account(&self, _pubkey: &Pubkey) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn subscribe_program(&self, _program_id: &Pubkey) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        let updates = self.updates.take()?;
+        let update_channel = self.update_channel.lock().unwrap().take()
+            .ok_or_else(|| IngestionError::ProviderError("Replay already started".to_string()))?;
+        let reader = RecordReader::open(&self.path)?;
+
+        let (records_tx, records_rx) = mpsc::channel(READ_AHEAD);
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || {
+            for record in reader {
+                match record {
+                    Ok(record) => {
+                        if records_tx.blocking_send(record).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Replay of {} stopped: {}", path.display(), e);
+                        return;
+                    }
+                }
+            }
+        });
+
+        let speed = self.speed;
+        let permits = self.permits.clone();
+        tokio::spawn(pace(records_rx, update_channel, speed, permits));
+        Ok(updates)
+    }
+}
+
+async fn pace(
+    mut records: mpsc::Receiver<RecordedEvent>,
+    update_channel: UpdateSender,
+    speed: ReplaySpeed,
+    permits: Arc<Semaphore>,
+) {
+    let mut origin: Option<(u64, Instant)> = None;
+    while let Some(record) = records.recv().await {
+        match speed {
+            ReplaySpeed::Original => {
+                let (first_micros, started) = *origin.get_or_insert((record.received_at_micros, Instant::now()));
+                let offset = Duration::from_micros(record.received_at_micros.saturating_sub(first_micros));
+                tokio::time::sleep_until(started + offset).await;
+            }
+            ReplaySpeed::Max => {}
+            ReplaySpeed::Stepped => match permits.acquire().await {
+                Ok(permit) => permit.forget(),
+                Err(_) => return,
+            },
+        }
+        if update_channel.send(record.event).await.is_err() {
+            return;
+        }
+    }
+}
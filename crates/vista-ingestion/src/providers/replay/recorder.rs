@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc;
+use vista_core::{IndexerError, UpdateEvent};
+
+use super::format::{encode_record, RecordWriter};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+// Passes a provider through unchanged while appending everything it emits,
+// with arrival timestamps, to a recording that `ReplayProvider` can play back
+pub struct RecordingProvider {
+    provider: Box<dyn RpcProvider>,
+    path: PathBuf,
+}
+
+impl RecordingProvider {
+    pub fn new(provider: Box<dyn RpcProvider>, path: impl Into<PathBuf>) -> Self {
+        Self { provider, path: path.into() }
+    }
+}
+
+#[async_trait]
+impl RpcProvider for RecordingProvider {
+    fn name(&self) -> &str {
+        self.provider.name()
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        self.provider.provider_type()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.provider.capabilities()
+    }
+
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.provider.subscribe_account(pubkey).await
+    }
+
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.provider.subscribe_program(program_id).await
+    }
+
+    async fn unsubscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.provider.unsubscribe_account(pubkey).await
+    }
+
+    async fn unsubscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.provider.unsubscribe_program(program_id).await
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        let mut writer = RecordWriter::open(&self.path)?;
+        let updates = self.provider.start().await?;
+
+        // Frames are written off the async runtime; encoding happens inline so
+        // the recording keeps the order the provider emitted in
+        let (frames_tx, mut frames_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut next = frames_rx.blocking_recv();
+            while let Some(frame) = next {
+                let mut result = writer.write_frame(&frame);
+                next = match frames_rx.try_recv() {
+                    Ok(frame) => Some(frame),
+                    // Flush whenever the backlog is drained so a crash loses little
+                    Err(_) => {
+                        result = result.and_then(|_| writer.flush());
+                        frames_rx.blocking_recv()
+                    }
+                };
+                if let Err(e) = result {
+                    eprintln!("Recording to {} stopped: {}", path.display(), e);
+                    return;
+                }
+            }
+            let _ = writer.flush();
+        });
+
+        let name = self.provider.name().to_string();
+        Ok(Box::pin(updates.map(move |update| {
+            // The wrapped provider's receive time, not when this stream was polled
+            let received_at = SystemTime::now() - update.received_at.elapsed();
+            let received_at_micros = received_at.duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_micros() as u64);
+            match encode_record(received_at_micros, &update.event) {
+                Ok(Some(frame)) => {
+                    let _ = frames_tx.send(frame);
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Failed to record update from {}: {}", name, e),
+            }
+            update
+        })))
+    }
+
+    // Repairs aren't part of the stream and so aren't recorded
+    async fn repair_slot(&self, slot: u64) -> Result<Option<Vec<UpdateEvent>>, IndexerError> {
+        self.provider.repair_slot(slot).await
+    }
+}
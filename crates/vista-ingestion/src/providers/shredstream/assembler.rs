@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, HashSet};
+
+use solana_entry::entry::Entry;
+use solana_ledger::shred::{Shred, Shredder};
+
+// Slots kept behind the highest one seen before their shreds are dropped
+const SLOT_WINDOW: u64 = 16;
+
+#[derive(Default)]
+struct SlotShreds {
+    data: BTreeMap<u32, Shred>,
+    // Index of the data-complete shred closing each batch already emitted
+    emitted: HashSet<u32>,
+}
+
+impl SlotShreds {
+    // A batch runs from just after the previous data-complete shred (or index
+    // 0) up to and including the next data-complete shred
+    fn batch_start(&self, end: u32) -> Option<u32> {
+        let mut index = end;
+        while index > 0 {
+            match self.data.get(&(index - 1)) {
+                Some(shred) if shred.data_complete() => return Some(index),
+                Some(_) => index -= 1,
+                None => return None,
+            }
+        }
+        Some(0)
+    }
+
+    fn batch_end(&self, from: u32) -> Option<u32> {
+        let mut index = from;
+        loop {
+            let shred = self.data.get(&index)?;
+            if shred.data_complete() {
+                return Some(index);
+            }
+            index += 1;
+        }
+    }
+
+    fn take_batch(&mut self, end: u32) -> Option<Vec<Shred>> {
+        if self.emitted.contains(&end) {
+            return None;
+        }
+        let start = self.batch_start(end)?;
+        let shreds: Vec<Shred> = self.data.range(start..=end).map(|(_, shred)| shred.clone()).collect();
+        self.emitted.insert(end);
+        Some(shreds)
+    }
+}
+
+// Reassembles entry batches from data shreds arriving in any order. Coding
+// shreds are ignored, so a batch with a lost data shred is never emitted.
+#[derive(Default)]
+pub(super) struct EntryAssembler {
+    slots: BTreeMap<u64, SlotShreds>,
+    highest_slot: u64,
+}
+
+impl EntryAssembler {
+    pub(super) fn insert(&mut self, payload: Vec<u8>) -> Vec<(u64, Vec<Entry>)> {
+        let shred = match Shred::new_from_serialized_shred(payload) {
+            Ok(shred) if shred.is_data() => shred,
+            _ => return Vec::new(),
+        };
+        let slot = shred.slot();
+        if slot + SLOT_WINDOW < self.highest_slot {
+            return Vec::new();
+        }
+        if slot > self.highest_slot {
+            self.highest_slot = slot;
+            let cutoff = slot.saturating_sub(SLOT_WINDOW);
+            self.slots = self.slots.split_off(&cutoff);
+        }
+
+        let index = shred.index();
+        let closes_batch = shred.data_complete();
+        let slot_shreds = self.slots.entry(slot).or_default();
+        if slot_shreds.data.insert(index, shred).is_some() {
+            return Vec::new();
+        }
+
+        // The new shred can complete its own batch and, when it is a batch
+        // boundary, make the following batch's start known
+        let mut ends = Vec::new();
+        if let Some(end) = slot_shreds.batch_end(index) {
+            ends.push(end);
+        }
+        if closes_batch {
+            if let Some(end) = slot_shreds.batch_end(index + 1) {
+                ends.push(end);
+            }
+        }
+
+        let mut batches = Vec::new();
+        for end in ends {
+            let shreds = match slot_shreds.take_batch(end) {
+                Some(shreds) => shreds,
+                None => continue,
+            };
+            let entries = Shredder::deshred(&shreds).ok()
+                .and_then(|payload| bincode::deserialize::<Vec<Entry>>(&payload).ok());
+            match entries {
+                Some(entries) => batches.push((slot, entries)),
+                None => eprintln!("Failed to deshred batch ending at shred {} of slot {}", end, slot),
+            }
+        }
+        batches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_ledger::shred::{ProcessShredsStats, ReedSolomonCache};
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::{Keypair, Signature};
+    use solana_sdk::system_transaction;
+
+    use super::*;
+
+    const SLOT: u64 = 100;
+
+    // One batch of entries spread over several data shreds, starting at
+    // `next_index`
+    fn batch(leader: &Keypair, next_index: u32, last_in_slot: bool) -> (Vec<Signature>, Vec<Vec<u8>>) {
+        let transactions: Vec<_> = (0..24)
+            .map(|lamports| system_transaction::transfer(leader, &solana_sdk::pubkey::Pubkey::new_unique(), lamports, Hash::default()))
+            .collect();
+        let signatures = transactions.iter().map(|transaction| transaction.signatures[0]).collect();
+        let entries = vec![Entry::new(&Hash::default(), 1, transactions)];
+        let (data, _coding) = Shredder::new(SLOT, SLOT - 1, 0, 0).unwrap().entries_to_shreds(
+            leader,
+            &entries,
+            last_in_slot,
+            None,
+            next_index,
+            next_index,
+            true,
+            &ReedSolomonCache::default(),
+            &mut ProcessShredsStats::default(),
+        );
+        assert!(data.len() > 2, "the batch should span several shreds");
+        (signatures, data.into_iter().map(Shred::into_payload).collect())
+    }
+
+    fn signatures(batches: &[(u64, Vec<Entry>)]) -> Vec<Signature> {
+        batches.iter()
+            .flat_map(|(_, entries)| entries.iter().flat_map(|entry| &entry.transactions))
+            .map(|transaction| transaction.signatures[0])
+            .collect()
+    }
+
+    #[test]
+    fn batch_is_emitted_once_its_last_shred_arrives_in_any_order() {
+        let leader = Keypair::new();
+        let (expected, mut shreds) = batch(&leader, 0, true);
+        shreds.reverse();
+        let duplicate = shreds[0].clone();
+
+        let mut assembler = EntryAssembler::default();
+        let last = shreds.pop().unwrap();
+        for shred in shreds {
+            assert!(assembler.insert(shred).is_empty());
+        }
+        let batches = assembler.insert(last);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].0, SLOT);
+        assert_eq!(signatures(&batches), expected);
+
+        assert!(assembler.insert(duplicate).is_empty());
+    }
+
+    #[test]
+    fn later_batch_waits_for_the_one_before_it() {
+        let leader = Keypair::new();
+        let (first_signatures, first) = batch(&leader, 0, false);
+        let (second_signatures, second) = batch(&leader, first.len() as u32, true);
+
+        let mut assembler = EntryAssembler::default();
+        // Complete, but where it starts is only known once the first batch closes
+        for shred in second {
+            assert!(assembler.insert(shred).is_empty());
+        }
+        let mut emitted = Vec::new();
+        for shred in first {
+            emitted.extend(assembler.insert(shred));
+        }
+        assert_eq!(signatures(&emitted), [first_signatures, second_signatures].concat());
+    }
+
+    #[test]
+    fn batch_with_a_lost_shred_is_never_emitted() {
+        let leader = Keypair::new();
+        let (_, mut shreds) = batch(&leader, 0, true);
+        shreds.remove(1);
+
+        let mut assembler = EntryAssembler::default();
+        for shred in shreds {
+            assert!(assembler.insert(shred).is_empty());
+        }
+    }
+
+    #[test]
+    fn shreds_of_slots_behind_the_window_are_dropped() {
+        let leader = Keypair::new();
+        let (_, shreds) = batch(&leader, 0, true);
+
+        let mut assembler = EntryAssembler::default();
+        assembler.highest_slot = SLOT + SLOT_WINDOW + 1;
+        for shred in shreds {
+            assert!(assembler.insert(shred).is_empty());
+        }
+        assert!(assembler.slots.is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+mod assembler;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::Pubkey;
+use tokio::io::AsyncReadExt;
+use tokio::net::UdpSocket;
+use tokio::sync::{Mutex, RwLock};
+use vista_core::models::TransactionInfo;
+use vista_core::{IndexerError, UpdateEvent};
+
+use self::assembler::EntryAssembler;
+use crate::error::IngestionError;
+use crate::providers::{transaction_info, update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+// Largest shred packet the proxy forwards
+const PACKET_SIZE: usize = 1232;
+
+#[derive(Debug, Clone)]
+pub enum ShredSource {
+    // Address the ShredStream proxy forwards shreds to (`--dest-ip-ports`)
+    Udp(String),
+    // Recorded shreds, each packet prefixed with its length as a little-endian u32
+    File(PathBuf),
+}
+
+// Emits tracked transactions straight from leader shreds, before they are
+// executed. They are marked `pending`: their status holds no outcome, which
+// only arrives later from a provider that saw the execution, so they reach
+// live subscribers of the indexer but are never stored.
+pub struct ShredStreamProvider {
+    name: String,
+    inner: Arc<Inner>,
+    updates: UpdateReceiver,
+}
+
+struct Inner {
+    source: ShredSource,
+    update_channel: UpdateSender,
+    accounts: RwLock<HashSet<Pubkey>>,
+    programs: RwLock<HashSet<Pubkey>>,
+    assembler: Mutex<EntryAssembler>,
+}
+
+impl ShredStreamProvider {
+    pub fn new(source: ShredSource) -> Self {
+        let (update_channel, updates) = update_channel();
+        Self {
+            name: "jito_shredstream".to_string(),
+            inner: Arc::new(Inner {
+                source,
+                update_channel,
+                accounts: RwLock::new(HashSet::new()),
+                programs: RwLock::new(HashSet::new()),
+                assembler: Mutex::new(EntryAssembler::default()),
+            }),
+            updates,
+        }
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl RpcProvider for ShredStreamProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        RpcProviderType::Geyser
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            pending_transactions: true,
+            commitments: vec![CommitmentLevel::Processed],
+            ..Default::default()
+        }
+    }
+
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.accounts.write().await.insert(*pubkey);
+        Ok(())
+    }
+
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.programs.write().await.insert(*program_id);
+        Ok(())
+    }
+
+    async fn unsubscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.accounts.write().await.remove(pubkey);
+        Ok(())
+    }
+
+    async fn unsubscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.programs.write().await.remove(program_id);
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        let updates = self.updates.take()?;
+        let inner = self.inner.clone();
+        match self.inner.source.clone() {
+            ShredSource::Udp(bind) => {
+                let socket = UdpSocket::bind(&bind).await
+                    .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                tokio::spawn(async move {
+                    if let Err(e) = inner.receive(socket).await {
+                        eprintln!("ShredStream receiver stopped: {}", e);
+                    }
+                });
+            }
+            ShredSource::File(path) => {
+                tokio::spawn(async move {
+                    if let Err(e) = inner.replay(path).await {
+                        eprintln!("Shred file replay failed: {}", e);
+                    }
+                });
+            }
+        }
+        Ok(updates)
+    }
+}
+
+impl Inner {
+    async fn receive(&self, socket: UdpSocket) -> Result<(), IngestionError> {
+        let mut buf = [0u8; PACKET_SIZE];
+        loop {
+            let (len, _) = socket.recv_from(&mut buf).await
+                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+            self.handle_shred(buf[..len].to_vec()).await?;
+        }
+    }
+
+    async fn replay(&self, path: PathBuf) -> Result<(), IngestionError> {
+        let to_error = |e: std::io::Error| IngestionError::ProviderError(e.to_string());
+        let mut file = tokio::io::BufReader::new(tokio::fs::File::open(&path).await.map_err(to_error)?);
+        loop {
+            let len = match file.read_u32_le().await {
+                Ok(len) => len as usize,
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(to_error(e)),
+            };
+            if len > PACKET_SIZE {
+                return Err(IngestionError::ProviderError(format!("Shred of {} bytes in {}", len, path.display())));
+            }
+            let mut payload = vec![0u8; len];
+            file.read_exact(&mut payload).await.map_err(to_error)?;
+            self.handle_shred(payload).await?;
+        }
+    }
+
+    async fn handle_shred(&self, payload: Vec<u8>) -> Result<(), IngestionError> {
+        let batches = self.assembler.lock().await.insert(payload);
+        if batches.is_empty() {
+            return Ok(());
+        }
+        let (accounts, programs) = (self.accounts.read().await, self.programs.read().await);
+
+        for (slot, entries) in batches {
+            for transaction in entries.into_iter().flat_map(|entry| entry.transactions) {
+                // Lookup tables can't be resolved from shreds alone, but program
+                // ids are always static keys
+                let account_keys = transaction.message.static_account_keys();
+                if !account_keys.iter().any(|key| accounts.contains(key) || programs.contains(key)) {
+                    continue;
+                }
+                let signature = match transaction.signatures.first() {
+                    Some(signature) => *signature,
+                    None => continue,
+                };
+                let program_ids = transaction.message.instructions().iter()
+                    .filter_map(|ix| account_keys.get(ix.program_id_index as usize).copied())
+                    .collect();
+
+                let info = TransactionInfo {
+                    pending: true,
+                    ..transaction_info(signature, slot, program_ids, None, CommitmentLevel::Processed)
+                };
+                self.update_channel.send(UpdateEvent::TransactionUpdate(info)).await
+                    .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+}
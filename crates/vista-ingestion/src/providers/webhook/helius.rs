@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use actix_web::HttpRequest;
+use serde_json::Value;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use vista_core::models::TransactionInfo;
+use vista_core::UpdateEvent;
+
+use super::constant_time_eq;
+use crate::error::IngestionError;
+use crate::providers::{encoded_transaction_info, invoked_program_ids, transaction_info};
+
+pub(super) fn verify(request: &HttpRequest, expected: &str) -> bool {
+    request.headers().get("authorization")
+        .map(|value| constant_time_eq(value.as_bytes(), expected.as_bytes()))
+        .unwrap_or(false)
+}
+
+// Helius posts an array of transactions, either raw (`getTransaction` shape)
+// or enhanced (pre-parsed, with `type`/`source` and flattened instructions)
+pub(super) fn normalize(payload: &Value, commitment: CommitmentLevel) -> Result<Vec<UpdateEvent>, IngestionError> {
+    let items = payload.as_array()
+        .ok_or_else(|| IngestionError::ProviderError("Expected an array of transactions".to_string()))?;
+
+    let mut events = Vec::with_capacity(items.len());
+    for item in items {
+        let info = if item.get("transaction").is_some() {
+            let transaction: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(item.clone())
+                .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+            encoded_transaction_info(transaction.slot, &transaction.transaction, commitment)
+                .ok_or_else(|| IngestionError::ProviderError("Undecodable raw transaction".to_string()))?
+        } else {
+            enhanced_transaction_info(item, commitment)?
+        };
+        events.push(UpdateEvent::TransactionUpdate(info));
+    }
+    Ok(events)
+}
+
+fn enhanced_transaction_info(item: &Value, commitment: CommitmentLevel) -> Result<TransactionInfo, IngestionError> {
+    let signature = item["signature"].as_str()
+        .and_then(|s| Signature::from_str(s).ok())
+        .ok_or_else(|| IngestionError::ProviderError("Missing signature".to_string()))?;
+    let slot = item["slot"].as_u64()
+        .ok_or_else(|| IngestionError::ProviderError(format!("Missing slot for {}", signature)))?;
+
+    let program_id = |instruction: &Value| instruction["programId"].as_str().and_then(|p| Pubkey::from_str(p).ok());
+    let instructions = item["instructions"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let program_ids = instructions.iter().filter_map(program_id).collect();
+    let inner = instructions.iter()
+        .flat_map(|instruction| instruction["innerInstructions"].as_array().into_iter().flatten())
+        .filter_map(program_id);
+
+    let mut info = transaction_info(signature, slot, program_ids, enhanced_error(&item["transactionError"]), commitment);
+    info.invoked_program_ids = invoked_program_ids(inner);
+    Ok(info)
+}
+
+// Enhanced payloads carry the error either as the RPC JSON value itself or
+// wrapped as `{"error": "<json string>"}`
+fn enhanced_error(value: &Value) -> Option<TransactionError> {
+    if value.is_null() {
+        return None;
+    }
+    let parsed = serde_json::from_value::<TransactionError>(value.clone()).ok()
+        .or_else(|| value["error"].as_str().and_then(|s| serde_json::from_str(s).ok()));
+    if parsed.is_none() {
+        eprintln!("Unrecognized Helius transaction error: {}", value);
+    }
+    parsed
+}
@@ -0,0 +1,262 @@
+mod helius;
+mod quicknode;
+
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::dev::ServerHandle;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use async_trait::async_trait;
+use serde_json::Value;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::Pubkey;
+use vista_core::{IndexerError, UpdateEvent};
+
+use crate::error::IngestionError;
+use crate::providers::{update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub bind_address: String,
+    // Value Helius sends verbatim in the `Authorization` header
+    pub helius_auth_header: Option<String>,
+    // Security token QuickNode Streams uses to sign each delivery
+    pub quicknode_secret: Option<String>,
+    // Each route is only served when its secret is set
+    // Commitment the pushed data was delivered at
+    pub commitment: CommitmentLevel,
+}
+
+impl WebhookConfig {
+    pub fn new(bind_address: &str) -> Self {
+        Self {
+            bind_address: bind_address.to_string(),
+            helius_auth_header: None,
+            quicknode_secret: None,
+            commitment: CommitmentLevel::Confirmed,
+        }
+    }
+}
+
+// Receives pushed webhooks instead of holding connections open. Which
+// addresses are delivered is configured on the sender's side, so the
+// subscribe calls have nothing to do here.
+pub struct WebhookProvider {
+    name: String,
+    inner: Arc<Inner>,
+    updates: UpdateReceiver,
+    // Set once started; the server runs on its own threads until stopped
+    server: Mutex<Option<ServerHandle>>,
+}
+
+struct Inner {
+    config: WebhookConfig,
+    update_channel: UpdateSender,
+    quicknode_nonces: quicknode::SeenNonces,
+}
+
+impl WebhookProvider {
+    pub fn new(config: WebhookConfig) -> Self {
+        let (update_channel, updates) = update_channel();
+        let inner = Inner { config, update_channel, quicknode_nonces: Default::default() };
+        Self { name: "webhook".to_string(), inner: Arc::new(inner), updates, server: Mutex::new(None) }
+    }
+
+    // Stops accepting deliveries and releases the bind address once
+    // in-flight requests finish
+    pub async fn stop(&self) {
+        let server = self.server.lock().unwrap().take();
+        if let Some(server) = server {
+            server.stop(true).await;
+        }
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+impl Drop for WebhookProvider {
+    fn drop(&mut self) {
+        // The stop command is sent on the call; its completion isn't waited for
+        if let Some(server) = self.server.lock().unwrap().take() {
+            drop(server.stop(true));
+        }
+    }
+}
+
+#[async_trait]
+impl RpcProvider for WebhookProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        RpcProviderType::Webhook
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        // What is delivered is configured on the webhook services, not through
+        // subscriptions. Pushed account updates are still forwarded, but
+        // nothing here can make a tracked account's state arrive.
+        ProviderCapabilities {
+            transactions: true,
+            commitments: vec![self.inner.config.commitment],
+            ..Default::default()
+        }
+    }
+
+    async fn subscribe_account(&self, _pubkey: &Pubkey) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn subscribe_program(&self, _program_id: &Pubkey) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        // Unauthenticated routes would let anyone inject updates
+        let helius = self.inner.config.helius_auth_header.is_some();
+        let quicknode = self.inner.config.quicknode_secret.is_some();
+        if !helius && !quicknode {
+            return Err(IndexerError::ConfigError(
+                "Webhook provider needs a Helius auth header or a QuickNode secret".to_string()));
+        }
+
+        let updates = self.updates.take()?;
+        let inner = self.inner.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::new(inner.clone()))
+                .app_data(web::PayloadConfig::new(16 * 1024 * 1024))
+                .configure(|cfg| {
+                    if helius {
+                        cfg.service(web::resource("/webhooks/helius").route(web::post().to(helius_handler)));
+                    }
+                    if quicknode {
+                        cfg.service(web::resource("/webhooks/quicknode").route(web::post().to(quicknode_handler)));
+                    }
+                })
+        })
+        .bind(&self.inner.config.bind_address)
+        .map_err(|e| IngestionError::ProviderError(e.to_string()))?
+        .run();
+        *self.server.lock().unwrap() = Some(server.handle());
+
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                eprintln!("Webhook server error: {}", e);
+            }
+        });
+        Ok(updates)
+    }
+}
+
+impl Inner {
+    async fn forward(&self, events: Vec<UpdateEvent>) -> HttpResponse {
+        for event in events {
+            if self.update_channel.send(event).await.is_err() {
+                return HttpResponse::ServiceUnavailable().finish();
+            }
+        }
+        HttpResponse::Ok().finish()
+    }
+}
+
+async fn helius_handler(inner: web::Data<Arc<Inner>>, request: HttpRequest, body: web::Bytes) -> HttpResponse {
+    let verified = match &inner.config.helius_auth_header {
+        Some(expected) => helius::verify(&request, expected),
+        None => false,
+    };
+    if !verified {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    match helius::normalize(&payload, inner.config.commitment) {
+        Ok(events) => inner.forward(events).await,
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+async fn quicknode_handler(inner: web::Data<Arc<Inner>>, request: HttpRequest, body: web::Bytes) -> HttpResponse {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let verified = match &inner.config.quicknode_secret {
+        Some(secret) => quicknode::verify(&request, secret, &body, &inner.quicknode_nonces, now),
+        None => false,
+    };
+    if !verified {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    match quicknode::normalize(&payload, inner.config.commitment) {
+        Ok(events) => inner.forward(events).await,
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+// Compares secrets without short-circuiting on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    use super::*;
+
+    // Status line of an empty Helius delivery, or `None` if nothing is listening
+    async fn deliver(address: &str) -> Option<String> {
+        let mut stream = TcpStream::connect(address).await.ok()?;
+        let request = "POST /webhooks/helius HTTP/1.1\r\nHost: vista\r\nAuthorization: secret\r\n\
+            Content-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n[]";
+        stream.write_all(request.as_bytes()).await.ok()?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.ok()?;
+        response.lines().next().map(String::from)
+    }
+
+    #[tokio::test]
+    async fn start_refuses_to_serve_without_a_secret() {
+        let provider = WebhookProvider::new(WebhookConfig::new("127.0.0.1:0"));
+        assert!(matches!(provider.start().await, Err(IndexerError::ConfigError(_))));
+    }
+
+    #[tokio::test]
+    async fn stopped_servers_release_their_address() {
+        let address = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let config = WebhookConfig { helius_auth_header: Some("secret".to_string()), ..WebhookConfig::new(&address) };
+
+        let first = WebhookProvider::new(config.clone());
+        let _updates = first.start().await.unwrap();
+        assert_eq!(deliver(&address).await.as_deref(), Some("HTTP/1.1 200 OK"));
+        first.stop().await;
+        assert_eq!(deliver(&address).await, None);
+
+        // Restarted on the same address, then released by dropping the provider
+        let second = WebhookProvider::new(config.clone());
+        let _updates = second.start().await.unwrap();
+        assert_eq!(deliver(&address).await.as_deref(), Some("HTTP/1.1 200 OK"));
+        drop(second);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while std::net::TcpListener::bind(&address).is_err() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await.unwrap();
+
+        let third = WebhookProvider::new(config);
+        let _updates = third.start().await.unwrap();
+        assert_eq!(deliver(&address).await.as_deref(), Some("HTTP/1.1 200 OK"));
+    }
+}
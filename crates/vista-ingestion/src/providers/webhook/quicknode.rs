@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use actix_web::HttpRequest;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use solana_account_decoder::UiAccount;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::EncodedTransactionWithStatusMeta;
+use vista_core::models::AccountInfo;
+use vista_core::UpdateEvent;
+
+use super::constant_time_eq;
+use crate::error::IngestionError;
+use crate::providers::encoded_transaction_info;
+
+// How far `x-qn-timestamp` may be from our clock; nonces are kept as long
+// as their delivery could still pass this check
+const MAX_TIMESTAMP_SKEW_SECS: u64 = 300;
+
+// Nonces of recently accepted deliveries, by their signed timestamp
+#[derive(Default)]
+pub(super) struct SeenNonces(Mutex<HashMap<String, u64>>);
+
+impl SeenNonces {
+    fn accept(&self, nonce: &str, timestamp: u64, now: u64) -> bool {
+        if timestamp.abs_diff(now) > MAX_TIMESTAMP_SKEW_SECS {
+            return false;
+        }
+        let mut seen = self.0.lock().unwrap();
+        seen.retain(|_, seen_at| *seen_at + MAX_TIMESTAMP_SKEW_SECS >= now);
+        seen.insert(nonce.to_string(), timestamp).is_none()
+    }
+}
+
+// Streams signs `nonce || timestamp || body` with HMAC-SHA256 over the
+// stream's security token and sends the hex digest in `x-qn-signature`.
+// A valid signature is accepted once, and only with a fresh timestamp.
+pub(super) fn verify(request: &HttpRequest, secret: &str, body: &[u8], nonces: &SeenNonces, now: u64) -> bool {
+    let header = |name: &str| request.headers().get(name).and_then(|v| v.to_str().ok());
+    let (signature, nonce, timestamp) = match (header("x-qn-signature"), header("x-qn-nonce"), header("x-qn-timestamp")) {
+        (Some(signature), Some(nonce), Some(timestamp)) => (signature, nonce, timestamp),
+        _ => return false,
+    };
+
+    let mut mac = match Hmac::<Sha256>::new_from_slice(secret.as_bytes()) {
+        Ok(mac) => mac,
+        Err(_) => return false,
+    };
+    mac.update(nonce.as_bytes());
+    mac.update(timestamp.as_bytes());
+    mac.update(body);
+    let expected = hex::encode(mac.finalize().into_bytes());
+    if !constant_time_eq(expected.as_bytes(), signature.to_ascii_lowercase().as_bytes()) {
+        return false;
+    }
+    match timestamp.parse() {
+        Ok(timestamp) => nonces.accept(nonce, timestamp, now),
+        Err(_) => false,
+    }
+}
+
+// Streams deliver a batch of dataset items, optionally wrapped with metadata.
+// Block items carry their transactions; filtered streams can also emit bare
+// transactions or keyed accounts.
+pub(super) fn normalize(payload: &Value, commitment: CommitmentLevel) -> Result<Vec<UpdateEvent>, IngestionError> {
+    let (items, batch_slot) = match payload.get("data") {
+        Some(data) => (data, single_slot_batch(&payload["metadata"])),
+        None => (payload, None),
+    };
+    let items = match items.as_array() {
+        Some(items) => items.clone(),
+        None => vec![items.clone()],
+    };
+
+    let mut events = Vec::new();
+    for item in &items {
+        let slot = item["slot"].as_u64().or(batch_slot);
+
+        if let Some(transactions) = item["transactions"].as_array() {
+            let slot = slot.ok_or_else(|| IngestionError::ProviderError("Block without a slot".to_string()))?;
+            for transaction in transactions {
+                events.extend(transaction_event(transaction, slot, commitment)?);
+            }
+        } else if item.get("transaction").is_some() {
+            let slot = slot.ok_or_else(|| IngestionError::ProviderError("Transaction without a slot".to_string()))?;
+            events.extend(transaction_event(item, slot, commitment)?);
+        } else if item.get("account").is_some() {
+            let slot = slot.ok_or_else(|| IngestionError::ProviderError("Account without a slot".to_string()))?;
+            events.push(account_event(item, slot)?);
+        }
+    }
+    Ok(events)
+}
+
+// Items without their own slot can only be placed when the batch covers
+// exactly one slot; a batch range can skip slots and doesn't map to indices
+fn single_slot_batch(metadata: &Value) -> Option<u64> {
+    match (metadata["batch_start_range"].as_u64(), metadata["batch_end_range"].as_u64()) {
+        (Some(start), Some(end)) if start == end => Some(start),
+        _ => None,
+    }
+}
+
+fn transaction_event(value: &Value, slot: u64, commitment: CommitmentLevel) -> Result<Option<UpdateEvent>, IngestionError> {
+    let transaction: EncodedTransactionWithStatusMeta = serde_json::from_value(value.clone())
+        .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+    Ok(encoded_transaction_info(slot, &transaction, commitment).map(UpdateEvent::TransactionUpdate))
+}
+
+fn account_event(value: &Value, slot: u64) -> Result<UpdateEvent, IngestionError> {
+    let pubkey = value["pubkey"].as_str()
+        .and_then(|p| Pubkey::from_str(p).ok())
+        .ok_or_else(|| IngestionError::ProviderError("Missing account pubkey".to_string()))?;
+    let account: UiAccount = serde_json::from_value(value["account"].clone())
+        .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+    let account: Account = account.decode()
+        .ok_or_else(|| IngestionError::ProviderError(format!("Failed to decode account {}", pubkey)))?;
+    Ok(UpdateEvent::AccountUpdate(AccountInfo {
+        pubkey,
+        lamports: account.lamports,
+        owner: account.owner,
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        data: account.data,
+        slot,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+    use serde_json::json;
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn signed_request(secret: &str, nonce: &str, timestamp: u64, body: &[u8]) -> HttpRequest {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(nonce.as_bytes());
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(body);
+        TestRequest::default()
+            .insert_header(("x-qn-signature", hex::encode(mac.finalize().into_bytes())))
+            .insert_header(("x-qn-nonce", nonce))
+            .insert_header(("x-qn-timestamp", timestamp.to_string()))
+            .to_http_request()
+    }
+
+    #[test]
+    fn accepts_each_signed_delivery_once() {
+        let nonces = SeenNonces::default();
+        let request = signed_request("secret", "n1", NOW, b"{}");
+        assert!(verify(&request, "secret", b"{}", &nonces, NOW));
+        assert!(!verify(&request, "secret", b"{}", &nonces, NOW + 1));
+        assert!(verify(&signed_request("secret", "n2", NOW, b"{}"), "secret", b"{}", &nonces, NOW + 1));
+
+        assert!(!verify(&signed_request("other", "n3", NOW, b"{}"), "secret", b"{}", &nonces, NOW));
+        assert!(!verify(&signed_request("secret", "n4", NOW, b"{}"), "secret", b"[]", &nonces, NOW));
+    }
+
+    #[test]
+    fn rejects_stale_or_future_timestamps() {
+        let nonces = SeenNonces::default();
+        let stale = NOW - MAX_TIMESTAMP_SKEW_SECS - 1;
+        assert!(!verify(&signed_request("secret", "n1", stale, b"{}"), "secret", b"{}", &nonces, NOW));
+        let future = NOW + MAX_TIMESTAMP_SKEW_SECS + 1;
+        assert!(!verify(&signed_request("secret", "n2", future, b"{}"), "secret", b"{}", &nonces, NOW));
+    }
+
+    #[test]
+    fn forgets_nonces_once_their_timestamp_expires() {
+        let nonces = SeenNonces::default();
+        assert!(nonces.accept("n1", NOW, NOW));
+        assert!(nonces.accept("n2", NOW + MAX_TIMESTAMP_SKEW_SECS, NOW + MAX_TIMESTAMP_SKEW_SECS + 1));
+        assert_eq!(nonces.0.lock().unwrap().len(), 1);
+    }
+
+    fn account_item(slot: Option<u64>) -> Value {
+        let mut item = json!({
+            "pubkey": Pubkey::new_unique().to_string(),
+            "account": {
+                "lamports": 1,
+                "owner": Pubkey::new_unique().to_string(),
+                "data": ["", "base64"],
+                "executable": false,
+                "rentEpoch": 0,
+                "space": 0,
+            },
+        });
+        if let Some(slot) = slot {
+            item["slot"] = json!(slot);
+        }
+        item
+    }
+
+    fn slots(events: &[UpdateEvent]) -> Vec<u64> {
+        events.iter().map(|event| match event {
+            UpdateEvent::AccountUpdate(account) => account.slot,
+            _ => panic!("expected an account update"),
+        }).collect()
+    }
+
+    #[test]
+    fn takes_slots_from_the_payload_only() {
+        let payload = json!({
+            "metadata": { "batch_start_range": 100, "batch_end_range": 104 },
+            "data": [account_item(Some(100)), account_item(Some(103))],
+        });
+        assert_eq!(slots(&normalize(&payload, CommitmentLevel::Confirmed).unwrap()), vec![100, 103]);
+
+        // A single-slot batch places items that don't carry their own
+        let payload = json!({
+            "metadata": { "batch_start_range": 100, "batch_end_range": 100 },
+            "data": [account_item(None), account_item(None)],
+        });
+        assert_eq!(slots(&normalize(&payload, CommitmentLevel::Confirmed).unwrap()), vec![100, 100]);
+
+        let payload = json!({
+            "metadata": { "batch_start_range": 100, "batch_end_range": 104 },
+            "data": [account_item(Some(100)), account_item(None)],
+        });
+        assert!(normalize(&payload, CommitmentLevel::Confirmed).is_err());
+    }
+}
@@ -0,0 +1,650 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use solana_account_decoder::UiAccount;
+use solana_client::rpc_config::RpcTransactionLogsFilter;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_client::rpc_response::{Response as RpcResponse, RpcKeyedAccount, RpcLogsResponse, SlotInfo as RpcSlotInfo};
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use vista_core::models::{AccountInfo, SlotInfo, SlotStatus};
+use vista_core::{IndexerError, UpdateEvent};
+
+use crate::error::IngestionError;
+use crate::providers::{failed_program, program_error, transaction_info, update_channel, UpdateReceiver, UpdateSender};
+use crate::traits::{ProviderCapabilities, RpcProvider, RpcProviderType, UpdateStream};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Subscription {
+    Account(Pubkey),
+    Program { program_id: Pubkey, filters: Vec<RpcFilterType> },
+    Logs(RpcTransactionLogsFilter),
+    Signature(Signature),
+    Slot,
+}
+
+impl Subscription {
+    fn request(&self, commitment: CommitmentConfig) -> (&'static str, Value) {
+        match self {
+            Subscription::Account(pubkey) => (
+                "accountSubscribe",
+                json!([pubkey.to_string(), {"encoding": "base64", "commitment": commitment.commitment}]),
+            ),
+            Subscription::Program { program_id, filters } => {
+                let mut config = json!({"encoding": "base64", "commitment": commitment.commitment});
+                if !filters.is_empty() {
+                    config["filters"] = json!(filters);
+                }
+                ("programSubscribe", json!([program_id.to_string(), config]))
+            }
+            Subscription::Logs(filter) => ("logsSubscribe", json!([filter, {"commitment": commitment.commitment}])),
+            Subscription::Signature(signature) => (
+                "signatureSubscribe",
+                json!([signature.to_string(), {"commitment": commitment.commitment}]),
+            ),
+            Subscription::Slot => ("slotSubscribe", json!([])),
+        }
+    }
+
+    fn unsubscribe_method(&self) -> &'static str {
+        match self {
+            Subscription::Account(_) => "accountUnsubscribe",
+            Subscription::Program { .. } => "programUnsubscribe",
+            Subscription::Logs(_) => "logsUnsubscribe",
+            Subscription::Signature(_) => "signatureUnsubscribe",
+            Subscription::Slot => "slotUnsubscribe",
+        }
+    }
+}
+
+pub struct WebSocketRpcProvider {
+    name: String,
+    inner: Arc<Inner>,
+    updates: UpdateReceiver,
+}
+
+struct Inner {
+    url: String,
+    commitment: CommitmentConfig,
+    update_channel: UpdateSender,
+    // Every subscription requested so far, keyed locally so they survive reconnects
+    subscriptions: RwLock<HashMap<u64, Subscription>>,
+    connection: Mutex<Connection>,
+    next_id: AtomicU64,
+}
+
+#[derive(Default)]
+struct Connection {
+    writer: Option<mpsc::UnboundedSender<Message>>,
+    // JSON-RPC request id -> subscription key, until the server confirms it
+    pending: HashMap<u64, u64>,
+    // Server-assigned subscription id -> subscription key
+    active: HashMap<u64, u64>,
+    // Subscriptions removed while still pending -> their unsubscribe method,
+    // sent as soon as the server confirms them
+    released: HashMap<u64, &'static str>,
+}
+
+impl WebSocketRpcProvider {
+    pub fn new(url: &str) -> Self {
+        Self::with_commitment(url, CommitmentConfig::confirmed())
+    }
+
+    pub fn with_commitment(url: &str, commitment: CommitmentConfig) -> Self {
+        let (update_channel, updates) = update_channel();
+        Self {
+            name: "websocket".to_string(),
+            inner: Arc::new(Inner {
+                url: url.to_string(),
+                commitment,
+                update_channel,
+                subscriptions: RwLock::new(HashMap::new()),
+                connection: Mutex::new(Connection::default()),
+                next_id: AtomicU64::new(1),
+            }),
+            updates,
+        }
+    }
+
+    pub async fn subscribe_program_with_filters(&self, program_id: &Pubkey, filters: Vec<RpcFilterType>) -> Result<(), IngestionError> {
+        self.inner.add(Subscription::Program { program_id: *program_id, filters }).await
+    }
+
+    pub async fn subscribe_logs(&self, filter: RpcTransactionLogsFilter) -> Result<(), IngestionError> {
+        self.inner.add(Subscription::Logs(filter)).await
+    }
+
+    pub async fn subscribe_signature(&self, signature: &Signature) -> Result<(), IngestionError> {
+        self.inner.add(Subscription::Signature(*signature)).await
+    }
+
+    pub async fn subscribe_slots(&self) -> Result<(), IngestionError> {
+        self.inner.add(Subscription::Slot).await
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl RpcProvider for WebSocketRpcProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn provider_type(&self) -> RpcProviderType {
+        RpcProviderType::WebSocket
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            accounts: true,
+            programs: true,
+            transactions: true,
+            slots: true,
+            program_filters: true,
+            commitments: vec![CommitmentLevel::Processed, CommitmentLevel::Confirmed, CommitmentLevel::Finalized],
+            ..Default::default()
+        }
+    }
+
+    // Transactions touching the target come from a logs subscription next to
+    // the state one
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.add(Subscription::Account(*pubkey)).await?;
+        Ok(self.subscribe_logs(mentions(pubkey)).await?)
+    }
+
+    async fn subscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.subscribe_program_with_filters(program_id, Vec::new()).await?;
+        Ok(self.subscribe_logs(mentions(program_id)).await?)
+    }
+
+    async fn unsubscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.remove(|subscription| *subscription == Subscription::Account(*pubkey)).await?;
+        Ok(self.inner.remove_mentions(pubkey).await?)
+    }
+
+    // Every filtered subscription of the program goes with it
+    async fn unsubscribe_program(&self, program_id: &Pubkey) -> Result<(), IndexerError> {
+        self.inner.remove(|subscription| matches!(subscription, Subscription::Program { program_id: id, .. } if id == program_id)).await?;
+        Ok(self.inner.remove_mentions(program_id).await?)
+    }
+
+    async fn start(&self) -> Result<UpdateStream, IndexerError> {
+        let updates = self.updates.take()?;
+        self.subscribe_slots().await?;
+        let inner = self.inner.clone();
+        tokio::spawn(async move { inner.run().await });
+        Ok(updates)
+    }
+}
+
+impl Inner {
+    async fn add(&self, subscription: Subscription) -> Result<(), IngestionError> {
+        let key = {
+            let mut subscriptions = self.subscriptions.write().await;
+            if subscriptions.values().any(|s| *s == subscription) {
+                return Ok(());
+            }
+            let key = self.next_id.fetch_add(1, Ordering::Relaxed);
+            subscriptions.insert(key, subscription.clone());
+            key
+        };
+        // Not connected yet: the subscription is sent once the socket opens
+        let mut connection = self.connection.lock().await;
+        if connection.writer.is_some() {
+            self.send_subscribe(&mut connection, key, &subscription)?;
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, matches: impl Fn(&Subscription) -> bool) -> Result<(), IngestionError> {
+        let removed: Vec<(u64, Subscription)> = {
+            let mut subscriptions = self.subscriptions.write().await;
+            let keys: Vec<u64> = subscriptions.iter()
+                .filter(|(_, subscription)| matches(subscription))
+                .map(|(key, _)| *key)
+                .collect();
+            keys.into_iter().filter_map(|key| Some((key, subscriptions.remove(&key)?))).collect()
+        };
+
+        let mut connection = self.connection.lock().await;
+        for (key, subscription) in removed {
+            let method = subscription.unsubscribe_method();
+            let active = connection.active.iter().find(|(_, k)| **k == key).map(|(id, _)| *id);
+            if let Some(subscription_id) = active {
+                connection.active.remove(&subscription_id);
+                self.send_unsubscribe(&connection, method, subscription_id)?;
+            } else if connection.pending.values().any(|k| *k == key) {
+                connection.released.insert(key, method);
+            }
+        }
+        Ok(())
+    }
+
+    // The logs subscription of an address serves both an account and a
+    // program there, so it stays until neither is tracked
+    async fn remove_mentions(&self, pubkey: &Pubkey) -> Result<(), IngestionError> {
+        let tracked = self.subscriptions.read().await.values().any(|subscription| matches!(subscription,
+            Subscription::Account(address) | Subscription::Program { program_id: address, .. } if address == pubkey));
+        if tracked {
+            return Ok(());
+        }
+        let filter = Subscription::Logs(mentions(pubkey));
+        self.remove(|subscription| *subscription == filter).await
+    }
+
+    fn send_unsubscribe(&self, connection: &Connection, method: &str, subscription_id: u64) -> Result<(), IngestionError> {
+        let writer = match &connection.writer {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": [subscription_id]});
+        writer.send(Message::Text(request.to_string()))
+            .map_err(|e| IngestionError::SubscriptionError(e.to_string()))
+    }
+
+    fn send_subscribe(&self, connection: &mut Connection, key: u64, subscription: &Subscription) -> Result<(), IngestionError> {
+        let writer = match &connection.writer {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (method, params) = subscription.request(self.commitment);
+        let request = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        writer.send(Message::Text(request.to_string()))
+            .map_err(|e| IngestionError::SubscriptionError(e.to_string()))?;
+        connection.pending.insert(id, key);
+        Ok(())
+    }
+
+    async fn run(self: Arc<Self>) {
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            if let Err(e) = self.stream_updates(&mut backoff).await {
+                eprintln!("WebSocket provider error: {}", e);
+            }
+            *self.connection.lock().await = Connection::default();
+            if self.update_channel.is_closed() {
+                return;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    async fn stream_updates(&self, backoff: &mut Duration) -> Result<(), IngestionError> {
+        let (socket, _) = connect_async(self.url.as_str()).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+        let (mut sink, mut stream) = socket.split();
+
+        let (writer, mut outgoing) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(message) = outgoing.recv().await {
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        // Restore every subscription on the fresh connection
+        {
+            let subscriptions = self.subscriptions.read().await;
+            let mut connection = self.connection.lock().await;
+            *connection = Connection { writer: Some(writer.clone()), ..Default::default() };
+            for (key, subscription) in subscriptions.iter() {
+                self.send_subscribe(&mut connection, *key, subscription)?;
+            }
+        }
+        *backoff = INITIAL_BACKOFF;
+
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        loop {
+            tokio::select! {
+                message = stream.next() => {
+                    match message {
+                        Some(Ok(Message::Text(text))) => self.handle_message(&text).await?,
+                        Some(Ok(Message::Ping(payload))) => {
+                            let _ = writer.send(Message::Pong(payload));
+                        }
+                        Some(Ok(Message::Close(_))) | None => return Ok(()),
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Err(IngestionError::ProviderError(e.to_string())),
+                    }
+                }
+                _ = ping.tick() => {
+                    let _ = writer.send(Message::Ping(Vec::new()));
+                }
+            }
+        }
+    }
+
+    async fn handle_message(&self, text: &str) -> Result<(), IngestionError> {
+        let message: Value = serde_json::from_str(text)
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+
+        // Response to one of our subscribe requests
+        if let Some(id) = message["id"].as_u64() {
+            let mut connection = self.connection.lock().await;
+            let key = connection.pending.remove(&id);
+            match (key, message["result"].as_u64()) {
+                (Some(key), Some(subscription_id)) => match connection.released.remove(&key) {
+                    Some(method) => self.send_unsubscribe(&connection, method, subscription_id)?,
+                    None => {
+                        connection.active.insert(subscription_id, key);
+                    }
+                },
+                (Some(key), None) => {
+                    connection.released.remove(&key);
+                    eprintln!("Subscription {} rejected: {}", key, message["error"]);
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let method = match message["method"].as_str() {
+            Some(method) => method,
+            None => return Ok(()),
+        };
+        let params = &message["params"];
+        let key = match params["subscription"].as_u64() {
+            Some(subscription_id) => self.connection.lock().await.active.get(&subscription_id).copied(),
+            None => None,
+        };
+        let subscription = match key {
+            Some(key) => self.subscriptions.read().await.get(&key).cloned(),
+            None => None,
+        };
+        let result = params["result"].clone();
+
+        match (method, subscription) {
+            ("accountNotification", Some(Subscription::Account(pubkey))) => {
+                let response: RpcResponse<UiAccount> = parse(result)?;
+                self.emit_account(pubkey, response.context.slot, response.value).await
+            }
+            ("programNotification", Some(Subscription::Program { .. })) => {
+                let response: RpcResponse<RpcKeyedAccount> = parse(result)?;
+                let pubkey = Pubkey::from_str(&response.value.pubkey)
+                    .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                self.emit_account(pubkey, response.context.slot, response.value.account).await
+            }
+            ("logsNotification", Some(Subscription::Logs(_))) => {
+                let response: RpcResponse<RpcLogsResponse> = parse(result)?;
+                let signature = Signature::from_str(&response.value.signature)
+                    .map_err(|e| IngestionError::ProviderError(e.to_string()))?;
+                let (program_ids, invoked_program_ids) = invoked_programs(&response.value.logs);
+                let mut info = transaction_info(signature, response.context.slot, program_ids, response.value.err, self.commitment.commitment);
+                info.invoked_program_ids = invoked_program_ids;
+                info.error = program_error(info.status.err.as_ref(), failed_program(&response.value.logs));
+                self.emit(UpdateEvent::TransactionUpdate(info)).await
+            }
+            ("signatureNotification", Some(Subscription::Signature(signature))) => {
+                // The server drops signature subscriptions after their single notification
+                if let Some(key) = key {
+                    self.subscriptions.write().await.remove(&key);
+                }
+                let response: RpcResponse<Value> = parse(result)?;
+                let err: Option<TransactionError> = parse(response.value["err"].clone())?;
+                let info = transaction_info(signature, response.context.slot, Vec::new(), err, self.commitment.commitment);
+                self.emit(UpdateEvent::TransactionUpdate(info)).await
+            }
+            ("slotNotification", Some(Subscription::Slot)) => {
+                let slot: RpcSlotInfo = parse(result)?;
+                self.emit(UpdateEvent::SlotUpdate(SlotInfo {
+                    slot: slot.slot,
+                    parent: Some(slot.parent),
+                    status: SlotStatus::Processed,
+                })).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn emit(&self, event: UpdateEvent) -> Result<(), IngestionError> {
+        self.update_channel.send(event).await
+            .map_err(|e| IngestionError::ProviderError(e.to_string()))
+    }
+
+    async fn emit_account(&self, pubkey: Pubkey, slot: u64, account: UiAccount) -> Result<(), IngestionError> {
+        let account: Account = account.decode()
+            .ok_or_else(|| IngestionError::ProviderError(format!("Failed to decode account {}", pubkey)))?;
+        self.emit(UpdateEvent::AccountUpdate(AccountInfo {
+            pubkey,
+            lamports: account.lamports,
+            owner: account.owner,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: account.data,
+            slot,
+        })).await
+    }
+}
+
+// logsSubscribe accepts a single address per subscription
+fn mentions(pubkey: &Pubkey) -> RpcTransactionLogsFilter {
+    RpcTransactionLogsFilter::Mentions(vec![pubkey.to_string()])
+}
+
+fn parse<T: DeserializeOwned>(value: Value) -> Result<T, IngestionError> {
+    serde_json::from_value(value).map_err(|e| IngestionError::ProviderError(e.to_string()))
+}
+
+// Programs invoked by a transaction, recovered from its "Program <id> invoke [n]"
+// log lines: depth 1 is one top-level instruction each, in order, and deeper
+// ones are CPIs. Truncated logs can miss instructions at the end.
+fn invoked_programs(logs: &[String]) -> (Vec<Pubkey>, Vec<Pubkey>) {
+    let mut program_ids = Vec::new();
+    let mut invoked_program_ids = Vec::new();
+    for line in logs {
+        let invoke = line.strip_prefix("Program ")
+            .and_then(|rest| rest.split_once(" invoke ["))
+            .and_then(|(id, depth)| Some((Pubkey::from_str(id).ok()?, depth.strip_suffix(']')?)));
+        match invoke {
+            Some((program_id, "1")) => program_ids.push(program_id),
+            Some((program_id, _)) if !invoked_program_ids.contains(&program_id) => invoked_program_ids.push(program_id),
+            _ => {}
+        }
+    }
+    (program_ids, invoked_program_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::{accept_async, WebSocketStream};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn tracked_targets_stream_transactions_and_slots() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let provider = WebSocketRpcProvider::new(&format!("ws://{}", listener.local_addr().unwrap()));
+        let (account, program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        provider.subscribe_account(&account).await.unwrap();
+        provider.subscribe_program(&program).await.unwrap();
+        let mut updates = provider.start().await.unwrap();
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = accept_async(stream).await.unwrap();
+        let mut requests = Vec::new();
+        while requests.len() < 5 {
+            if let Some(Ok(Message::Text(text))) = socket.next().await {
+                requests.push(serde_json::from_str::<Value>(&text).unwrap());
+            }
+        }
+        let mut methods: Vec<(String, Value)> = requests.iter()
+            .map(|r| (r["method"].as_str().unwrap().to_string(), r["params"][0].clone()))
+            .collect();
+        let mut expected = vec![
+            ("accountSubscribe".to_string(), json!(account.to_string())),
+            ("logsSubscribe".to_string(), json!({ "mentions": [account.to_string()] })),
+            ("logsSubscribe".to_string(), json!({ "mentions": [program.to_string()] })),
+            ("programSubscribe".to_string(), json!(program.to_string())),
+            ("slotSubscribe".to_string(), Value::Null),
+        ];
+        // Subscriptions go out in no particular order
+        methods.sort_by_key(|(method, params)| (method.clone(), params.to_string()));
+        expected.sort_by_key(|(method, params)| (method.clone(), params.to_string()));
+        assert_eq!(methods, expected);
+
+        // Confirm every subscription with its request id as the server id
+        for request in &requests {
+            let reply = json!({ "jsonrpc": "2.0", "id": request["id"], "result": request["id"] });
+            socket.send(Message::Text(reply.to_string())).await.unwrap();
+        }
+        let logs = requests.iter()
+            .find(|r| r["params"][0] == json!({ "mentions": [program.to_string()] }))
+            .unwrap();
+        let slots = requests.iter().find(|r| r["method"] == "slotSubscribe").unwrap();
+        let signature = Signature::new_unique();
+        let notification = json!({ "jsonrpc": "2.0", "method": "logsNotification", "params": {
+            "subscription": logs["id"],
+            "result": {
+                "context": { "slot": 40 },
+                "value": {
+                    "signature": signature.to_string(),
+                    "err": { "InstructionError": [0, { "Custom": 1 }] },
+                    "logs": [
+                        format!("Program {} invoke [1]", program),
+                        format!("Program {} failed: custom program error: 0x1", program),
+                    ],
+                },
+            },
+        } });
+        socket.send(Message::Text(notification.to_string())).await.unwrap();
+        let notification = json!({ "jsonrpc": "2.0", "method": "slotNotification", "params": {
+            "subscription": slots["id"],
+            "result": { "slot": 41, "parent": 40, "root": 8 },
+        } });
+        socket.send(Message::Text(notification.to_string())).await.unwrap();
+
+        match updates.next().await.unwrap().event {
+            UpdateEvent::TransactionUpdate(info) => {
+                assert_eq!((info.signature, info.status.slot), (signature, 40));
+                assert_eq!(info.program_ids, vec![program]);
+                assert_eq!(info.error.map(|error| error.program_id), Some(program));
+            }
+            _ => panic!("expected a transaction"),
+        }
+        match updates.next().await.unwrap().event {
+            UpdateEvent::SlotUpdate(slot) => assert_eq!((slot.slot, slot.parent), (41, Some(40))),
+            _ => panic!("expected a slot"),
+        }
+    }
+
+    async fn next_request(socket: &mut WebSocketStream<TcpStream>) -> Value {
+        loop {
+            if let Message::Text(text) = socket.next().await.unwrap().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    async fn confirm(socket: &mut WebSocketStream<TcpStream>, request: &Value, subscription_id: u64) {
+        let reply = json!({ "jsonrpc": "2.0", "id": request["id"], "result": subscription_id });
+        socket.send(Message::Text(reply.to_string())).await.unwrap();
+    }
+
+    fn released(request: &Value) -> (String, Value) {
+        (request["method"].as_str().unwrap().to_string(), request["params"][0].clone())
+    }
+
+    #[tokio::test]
+    async fn unsubscribes_release_the_state_and_logs_subscriptions() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let provider = WebSocketRpcProvider::new(&format!("ws://{}", listener.local_addr().unwrap()));
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        provider.subscribe_account(&first).await.unwrap();
+        let mut updates = provider.start().await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = accept_async(stream).await.unwrap();
+
+        // Confirmed with the request id as the server id
+        let mut ids = HashMap::new();
+        for _ in 0..3 {
+            let request = next_request(&mut socket).await;
+            let id = request["id"].as_u64().unwrap();
+            ids.insert(request["method"].as_str().unwrap().to_string(), id);
+            confirm(&mut socket, &request, id).await;
+        }
+
+        // Whether or not the confirmations were handled yet, both are released
+        provider.unsubscribe_account(&first).await.unwrap();
+        let mut unsubscribes = vec![released(&next_request(&mut socket).await), released(&next_request(&mut socket).await)];
+        unsubscribes.sort_by_key(|(method, _)| method.clone());
+        assert_eq!(unsubscribes, vec![
+            ("accountUnsubscribe".to_string(), json!(ids["accountSubscribe"])),
+            ("logsUnsubscribe".to_string(), json!(ids["logsSubscribe"])),
+        ]);
+
+        // Released before the server confirmed them, so they are unsubscribed
+        // once it does
+        provider.subscribe_account(&second).await.unwrap();
+        let pending = [next_request(&mut socket).await, next_request(&mut socket).await];
+        provider.unsubscribe_account(&second).await.unwrap();
+        for (request, subscription_id) in pending.iter().zip([100, 101]) {
+            confirm(&mut socket, request, subscription_id).await;
+            let method = request["method"].as_str().unwrap().replace("Subscribe", "Unsubscribe");
+            assert_eq!(released(&next_request(&mut socket).await), (method, json!(subscription_id)));
+        }
+
+        // Late notifications of a released subscription are dropped
+        let late = json!({ "jsonrpc": "2.0", "method": "logsNotification", "params": {
+            "subscription": ids["logsSubscribe"],
+            "result": { "context": { "slot": 40 }, "value": { "signature": Signature::new_unique().to_string(), "err": null, "logs": [] } },
+        } });
+        socket.send(Message::Text(late.to_string())).await.unwrap();
+        let slot = json!({ "jsonrpc": "2.0", "method": "slotNotification", "params": {
+            "subscription": ids["slotSubscribe"],
+            "result": { "slot": 41, "parent": 40, "root": 8 },
+        } });
+        socket.send(Message::Text(slot.to_string())).await.unwrap();
+        match updates.next().await.unwrap().event {
+            UpdateEvent::SlotUpdate(slot) => assert_eq!(slot.slot, 41),
+            _ => panic!("expected a slot"),
+        }
+    }
+
+    #[test]
+    fn invoked_programs_splits_top_level_from_cpis() {
+        let (outer, token, inner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let logs: Vec<String> = [
+            format!("Program {} invoke [1]", outer),
+            format!("Program {} invoke [2]", token),
+            "Program log: Instruction: Transfer".to_string(),
+            format!("Program {} success", token),
+            format!("Program {} invoke [2]", inner),
+            format!("Program {} invoke [3]", token),
+            format!("Program {} success", outer),
+            format!("Program {} invoke [1]", token),
+            format!("Program {} invoke [1]", outer),
+            "Program log: invoke [1] is just a message".to_string(),
+        ].into_iter().collect();
+
+        let (program_ids, invoked_program_ids) = invoked_programs(&logs);
+        assert_eq!(program_ids, vec![outer, token, outer]);
+        assert_eq!(invoked_program_ids, vec![token, inner]);
+    }
+}
@@ -0,0 +1,139 @@
+use solana_sdk::pubkey::Pubkey;
+
+// Layout of an account in an AppendVec storage file: StoredMeta
+// (write_version, data_len, pubkey), AccountMeta (lamports, rent_epoch,
+// owner, executable, padded to 8 bytes), the account hash, then the data,
+// with every entry aligned to 8 bytes
+const STORED_META_LEN: usize = 8 + 8 + 32;
+const ACCOUNT_META_LEN: usize = 56;
+const HASH_LEN: usize = 32;
+const HEADER_LEN: usize = STORED_META_LEN + ACCOUNT_META_LEN + HASH_LEN;
+// Largest account the runtime allows
+const MAX_DATA_LEN: u64 = 10 * 1024 * 1024;
+
+pub(crate) struct StoredAccount<'a> {
+    pub(crate) write_version: u64,
+    pub(crate) pubkey: Pubkey,
+    pub(crate) lamports: u64,
+    pub(crate) rent_epoch: u64,
+    pub(crate) owner: Pubkey,
+    pub(crate) executable: bool,
+    pub(crate) data: &'a [u8],
+}
+
+// Walks the accounts of one storage file. Files are preallocated and zero
+// filled past the last account, so iteration stops at the first entry that
+// is empty or doesn't fit.
+pub(crate) struct AppendVecIter<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> AppendVecIter<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+}
+
+impl<'a> Iterator for AppendVecIter<'a> {
+    type Item = StoredAccount<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.bytes.get(self.offset..)?;
+        if entry.len() < HEADER_LEN {
+            return None;
+        }
+        let u64_at = |at: usize| u64::from_le_bytes(entry[at..at + 8].try_into().unwrap());
+        let pubkey_at = |at: usize| Pubkey::new_from_array(entry[at..at + 32].try_into().unwrap());
+
+        let write_version = u64_at(0);
+        let data_len = u64_at(8);
+        let pubkey = pubkey_at(16);
+        let lamports = u64_at(STORED_META_LEN);
+        let rent_epoch = u64_at(STORED_META_LEN + 8);
+        let owner = pubkey_at(STORED_META_LEN + 16);
+        let executable = entry[STORED_META_LEN + 48];
+
+        let is_padding = write_version == 0 && data_len == 0 && lamports == 0 && pubkey == Pubkey::default();
+        if is_padding || data_len > MAX_DATA_LEN || executable > 1 {
+            return None;
+        }
+        let data = entry.get(HEADER_LEN..HEADER_LEN + data_len as usize)?;
+
+        self.offset += (HEADER_LEN + data_len as usize + 7) & !7;
+        Some(StoredAccount {
+            write_version,
+            pubkey,
+            lamports,
+            rent_epoch,
+            owner,
+            executable: executable == 1,
+            data,
+        })
+    }
+}
+
+// One stored account as a validator writes it, padded to the next entry
+#[cfg(test)]
+pub(crate) fn stored_account_bytes(write_version: u64, pubkey: &Pubkey, lamports: u64, owner: &Pubkey, data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&write_version.to_le_bytes());
+    bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(pubkey.as_ref());
+    bytes.extend_from_slice(&lamports.to_le_bytes());
+    bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+    bytes.extend_from_slice(owner.as_ref());
+    bytes.extend_from_slice(&[0; 8]);
+    bytes.extend_from_slice(&[7; HASH_LEN]);
+    bytes.extend_from_slice(data);
+    bytes.resize((bytes.len() + 7) & !7, 0);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walks_aligned_entries_until_the_zero_fill() {
+        let (first, second, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut bytes = stored_account_bytes(1, &first, 10, &owner, &[1, 2, 3]);
+        bytes.extend(stored_account_bytes(2, &second, 20, &owner, &[4; 8]));
+        bytes.resize(bytes.len() + 4096, 0);
+
+        let accounts: Vec<StoredAccount> = AppendVecIter::new(&bytes).collect();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].pubkey, first);
+        assert_eq!(accounts[0].write_version, 1);
+        assert_eq!(accounts[0].lamports, 10);
+        assert_eq!(accounts[0].rent_epoch, u64::MAX);
+        assert_eq!(accounts[0].owner, owner);
+        assert!(!accounts[0].executable);
+        assert_eq!(accounts[0].data, &[1, 2, 3]);
+        assert_eq!(accounts[1].pubkey, second);
+        assert_eq!(accounts[1].data, &[4; 8]);
+    }
+
+    #[test]
+    fn stops_at_an_entry_cut_short() {
+        let owner = Pubkey::new_unique();
+        let mut bytes = stored_account_bytes(1, &Pubkey::new_unique(), 10, &owner, &[]);
+        let mut truncated = stored_account_bytes(2, &Pubkey::new_unique(), 10, &owner, &[9; 64]);
+        truncated.truncate(HEADER_LEN + 10);
+        bytes.extend(truncated);
+
+        assert_eq!(AppendVecIter::new(&bytes).count(), 1);
+        assert_eq!(AppendVecIter::new(&bytes[..HEADER_LEN - 1]).count(), 0);
+    }
+
+    #[test]
+    fn rejects_garbage_lengths_and_flags() {
+        let mut bytes = stored_account_bytes(1, &Pubkey::new_unique(), 10, &Pubkey::new_unique(), &[]);
+        bytes[8..16].copy_from_slice(&(MAX_DATA_LEN + 1).to_le_bytes());
+        assert_eq!(AppendVecIter::new(&bytes).count(), 0);
+
+        let mut bytes = stored_account_bytes(1, &Pubkey::new_unique(), 10, &Pubkey::new_unique(), &[]);
+        bytes[STORED_META_LEN + 48] = 2;
+        assert_eq!(AppendVecIter::new(&bytes).count(), 0);
+    }
+}
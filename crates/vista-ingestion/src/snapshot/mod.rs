@@ -0,0 +1,246 @@
+mod append_vec;
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use solana_sdk::pubkey::Pubkey;
+use vista_core::models::AccountInfo;
+use vista_core::traits::StoragePlugin;
+
+use self::append_vec::AppendVecIter;
+use crate::error::IngestionError;
+
+#[derive(Debug, Clone)]
+pub struct SnapshotArchive {
+    pub path: PathBuf,
+    pub slot: u64,
+    // Full snapshot slot an incremental snapshot applies on top of
+    pub base_slot: Option<u64>,
+}
+
+impl SnapshotArchive {
+    // Recognizes `snapshot-<slot>-<hash>.tar.zst` and
+    // `incremental-snapshot-<base>-<slot>-<hash>.tar.zst`
+    pub fn from_path(path: impl Into<PathBuf>) -> Option<Self> {
+        let path = path.into();
+        let name = path.file_name()?.to_str()?.strip_suffix(".tar.zst")?;
+        let (base_slot, slot) = if let Some(rest) = name.strip_prefix("incremental-snapshot-") {
+            let mut parts = rest.splitn(3, '-');
+            let base = parts.next()?.parse().ok()?;
+            (Some(base), parts.next()?.parse().ok()?)
+        } else {
+            (None, name.strip_prefix("snapshot-")?.split('-').next()?.parse().ok()?)
+        };
+        Some(Self { path, slot, base_slot })
+    }
+
+    // The newest full snapshot in `dir`, followed by the newest incremental
+    // snapshot built on it if there is one
+    pub fn latest_in(dir: impl AsRef<Path>) -> Result<Vec<SnapshotArchive>, IngestionError> {
+        let entries = std::fs::read_dir(dir).map_err(|e| IngestionError::SnapshotError(e.to_string()))?;
+        let archives: Vec<SnapshotArchive> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| SnapshotArchive::from_path(entry.path()))
+            .collect();
+
+        let full = archives.iter()
+            .filter(|archive| archive.base_slot.is_none())
+            .max_by_key(|archive| archive.slot)
+            .cloned()
+            .ok_or_else(|| IngestionError::SnapshotError("No full snapshot archive found".to_string()))?;
+        let incremental = archives.iter()
+            .filter(|archive| archive.base_slot == Some(full.slot))
+            .max_by_key(|archive| archive.slot)
+            .cloned();
+        Ok(std::iter::once(full).chain(incremental).collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotLoadStats {
+    pub slot: u64,
+    pub accounts: usize,
+}
+
+// Seeds storage with tracked accounts straight from snapshot archives, for
+// programs too large to fetch with getProgramAccounts
+pub struct SnapshotLoader {
+    owners: HashSet<Pubkey>,
+    accounts: HashSet<Pubkey>,
+}
+
+// Newest version of an account seen so far, ordered by storage slot then
+// write version. `account` is only kept while that version is tracked, so a
+// newer one written after a close or an owner change supersedes it.
+struct Candidate {
+    version: (u64, u64),
+    account: Option<AccountInfo>,
+}
+
+impl SnapshotLoader {
+    pub fn new(owners: impl IntoIterator<Item = Pubkey>, accounts: impl IntoIterator<Item = Pubkey>) -> Self {
+        Self {
+            owners: owners.into_iter().collect(),
+            accounts: accounts.into_iter().collect(),
+        }
+    }
+
+    // Writes every tracked account as of the last archive's slot. `archives`
+    // is a full snapshot optionally followed by incrementals on top of it.
+    pub async fn load(&self, archives: &[SnapshotArchive], storage: &dyn StoragePlugin) -> Result<SnapshotLoadStats, IngestionError> {
+        let slot = archives.last()
+            .map(|archive| archive.slot)
+            .ok_or_else(|| IngestionError::SnapshotError("No snapshot archives given".to_string()))?;
+
+        let paths: Vec<PathBuf> = archives.iter().map(|archive| archive.path.clone()).collect();
+        let owners = self.owners.clone();
+        let accounts = self.accounts.clone();
+        let live = tokio::task::spawn_blocking(move || scan(&paths, &owners, &accounts)).await
+            .map_err(|e| IngestionError::SnapshotError(e.to_string()))??;
+
+        let mut stats = SnapshotLoadStats { slot, accounts: 0 };
+        for account in live {
+            storage.store_account(AccountInfo { slot, ..account }).await
+                .map_err(|e| IngestionError::SnapshotError(e.to_string()))?;
+            stats.accounts += 1;
+        }
+        Ok(stats)
+    }
+}
+
+// Tracked accounts alive as of the last archive, each at its newest version
+fn scan(paths: &[PathBuf], owners: &HashSet<Pubkey>, accounts: &HashSet<Pubkey>) -> Result<Vec<AccountInfo>, IngestionError> {
+    let mut latest = HashMap::new();
+    // Storages aren't archived in slot order, so a program account can be
+    // closed or reassigned in a storage read before the one where it was
+    // still tracked. A second pass, once all such accounts are known, sees
+    // those versions. Accounts tracked by pubkey are caught in the first.
+    let passes = if owners.is_empty() { 1 } else { 2 };
+    for _ in 0..passes {
+        for path in paths {
+            scan_archive(path, owners, accounts, &mut latest)?;
+        }
+    }
+    Ok(latest.into_values()
+        .filter_map(|candidate| candidate.account)
+        // Zero lamports marks an account deleted as of its storage slot
+        .filter(|account| account.lamports > 0)
+        .collect())
+}
+
+fn scan_archive(
+    path: &Path,
+    owners: &HashSet<Pubkey>,
+    accounts: &HashSet<Pubkey>,
+    latest: &mut HashMap<Pubkey, Candidate>,
+) -> Result<(), IngestionError> {
+    let to_error = |e: std::io::Error| IngestionError::SnapshotError(format!("{}: {}", path.display(), e));
+    let decoder = zstd::stream::read::Decoder::new(File::open(path).map_err(to_error)?).map_err(to_error)?;
+    let mut archive = tar::Archive::new(decoder);
+
+    for entry in archive.entries().map_err(to_error)? {
+        let mut entry = entry.map_err(to_error)?;
+        // Storage files are `accounts/<slot>.<id>`
+        let storage_slot = match entry.path().map_err(to_error)?
+            .strip_prefix("accounts")
+            .ok()
+            .and_then(|name| name.to_str()?.split('.').next()?.parse::<u64>().ok())
+        {
+            Some(slot) => slot,
+            None => continue,
+        };
+
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes).map_err(to_error)?;
+        for stored in AppendVecIter::new(&bytes) {
+            let tracked = accounts.contains(&stored.pubkey) || owners.contains(&stored.owner);
+            let version = (storage_slot, stored.write_version);
+            match latest.get(&stored.pubkey) {
+                Some(current) if current.version >= version => continue,
+                // Untracked versions only matter for accounts that were tracked at some point
+                None if !tracked => continue,
+                _ => {}
+            }
+            let account = tracked.then(|| AccountInfo {
+                pubkey: stored.pubkey,
+                lamports: stored.lamports,
+                owner: stored.owner,
+                executable: stored.executable,
+                rent_epoch: stored.rent_epoch,
+                data: stored.data.to_vec(),
+                slot: storage_slot,
+            });
+            latest.insert(stored.pubkey, Candidate { version, account });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::append_vec::stored_account_bytes;
+    use super::*;
+
+    #[test]
+    fn parses_archive_names() {
+        let full = SnapshotArchive::from_path("/snapshots/snapshot-1000-7Xk2.tar.zst").unwrap();
+        assert_eq!((full.slot, full.base_slot), (1000, None));
+        let incremental = SnapshotArchive::from_path("incremental-snapshot-1000-1200-9aB.tar.zst").unwrap();
+        assert_eq!((incremental.slot, incremental.base_slot), (1200, Some(1000)));
+
+        assert!(SnapshotArchive::from_path("snapshot-1000-7Xk2.tar.bz2").is_none());
+        assert!(SnapshotArchive::from_path("snapshot-abc-7Xk2.tar.zst").is_none());
+        assert!(SnapshotArchive::from_path("incremental-snapshot-1000.tar.zst").is_none());
+        assert!(SnapshotArchive::from_path("genesis.tar.zst").is_none());
+    }
+
+    fn write_archive(name: &str, storages: &[(&str, Vec<u8>)]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("vista-{}-{}-{}.tar.zst", name, std::process::id(), Pubkey::new_unique()));
+        let encoder = zstd::stream::write::Encoder::new(File::create(&path).unwrap(), 0).unwrap();
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        builder.append_data(&mut header, "version", &b"1.2.0"[..]).unwrap();
+        for (name, bytes) in storages {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(bytes.len() as u64);
+            builder.append_data(&mut header, format!("accounts/{}", name), bytes.as_slice()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn scan_keeps_the_newest_version_before_filtering() {
+        let program = Pubkey::new_unique();
+        let (kept, closed, reassigned, pinned) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut old = stored_account_bytes(1, &kept, 10, &program, &[1]);
+        old.extend(stored_account_bytes(2, &closed, 10, &program, &[1]));
+        old.extend(stored_account_bytes(3, &reassigned, 10, &program, &[1]));
+        old.extend(stored_account_bytes(4, &pinned, 10, &Pubkey::new_unique(), &[1]));
+        // Written later, but read first
+        let mut new = stored_account_bytes(5, &closed, 0, &Pubkey::default(), &[]);
+        new.extend(stored_account_bytes(6, &reassigned, 10, &Pubkey::new_unique(), &[2]));
+        new.extend(stored_account_bytes(7, &kept, 20, &program, &[2]));
+        let full = write_archive("full", &[("9.1", new), ("5.0", old)]);
+
+        let owners = HashSet::from([program]);
+        let accounts = HashSet::from([pinned]);
+        let mut live = scan(std::slice::from_ref(&full), &owners, &accounts).unwrap();
+        live.sort_by_key(|account| account.lamports);
+        assert_eq!(live.len(), 2);
+        assert_eq!((live[0].pubkey, live[0].slot), (pinned, 5));
+        assert_eq!((live[1].pubkey, live[1].lamports, live[1].slot, live[1].data.as_slice()), (kept, 20, 9, &[2][..]));
+
+        // An incremental archive on top closes the account tracked by pubkey
+        let incremental = write_archive("incremental", &[("12.2", stored_account_bytes(8, &pinned, 0, &Pubkey::default(), &[]))]);
+        let live = scan(&[full.clone(), incremental.clone()], &owners, &accounts).unwrap();
+        assert_eq!(live.iter().map(|account| account.pubkey).collect::<Vec<_>>(), vec![kept]);
+
+        std::fs::remove_file(full).unwrap();
+        std::fs::remove_file(incremental).unwrap();
+    }
+}
@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use futures::StreamExt;
+use solana_sdk::pubkey::Pubkey;
+use vista_core::UpdateEvent;
+use vista_ingestion::providers::{ShredSource, ShredStreamProvider};
+use vista_ingestion::traits::RpcProvider;
+
+// Prints transactions of the given programs as soon as their shreds land.
+//
+//   jito_shredstream_indexer udp 0.0.0.0:20000 <program_id>...
+//   jito_shredstream_indexer file shreds.bin <program_id>...
+//
+// For `udp`, start the proxy with `--dest-ip-ports` pointing at that address.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let source = match (args.next().as_deref(), args.next()) {
+        (Some("udp"), Some(bind)) => ShredSource::Udp(bind),
+        (Some("file"), Some(path)) => ShredSource::File(PathBuf::from(path)),
+        _ => return Err("usage: jito_shredstream_indexer <udp ADDR | file PATH> <program_id>...".into()),
+    };
+
+    let provider = ShredStreamProvider::new(source);
+    for program in args {
+        provider.subscribe_program(&Pubkey::from_str(&program)?).await?;
+    }
+    let mut updates = provider.start().await?.map(|update| update.event);
+
+    println!("Listening for shreds. Press Ctrl+C to stop.");
+    loop {
+        tokio::select! {
+            update = updates.next() => match update {
+                Some(UpdateEvent::TransactionUpdate(transaction)) => {
+                    let programs: Vec<String> = transaction.program_ids.iter().map(|p| p.to_string()).collect();
+                    println!("slot {} {} [{}]", transaction.status.slot, transaction.signature, programs.join(", "));
+                }
+                Some(_) => {}
+                None => break,
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use std::str::FromStr;
+
+use libloading::Library;
+use solana_sdk::pubkey::Pubkey;
+use vista_core::{plugin_abi, Config};
+use vista_ingestion::snapshot::{SnapshotArchive, SnapshotLoader};
+
+// Seeds a fresh index with the tracked accounts and programs of config.json
+// from the newest snapshot archives a validator left in a directory.
+//
+//   seed_from_snapshot /mnt/ledger/snapshots
+//
+// Start the indexer afterwards; live updates pick up from the snapshot slot.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::args().nth(1).ok_or("usage: seed_from_snapshot <snapshot_dir>")?;
+    let config = Config::from_file("config.json")?;
+
+    let library = unsafe { Library::new(&config.storage.plugin)? };
+    let storage = unsafe { plugin_abi::load_storage_plugin(&library)? };
+    storage.init(&config.storage.config).await?;
+
+    let owners = config.tracked_programs.iter()
+        .map(|program| Pubkey::from_str(&program.address))
+        .collect::<Result<Vec<_>, _>>()?;
+    let accounts = config.tracked_accounts.iter()
+        .map(|account| Pubkey::from_str(account))
+        .collect::<Result<Vec<_>, _>>()?;
+    let loader = SnapshotLoader::new(owners, accounts);
+
+    let archives = SnapshotArchive::latest_in(&dir)?;
+    for archive in &archives {
+        println!("Reading {}", archive.path.display());
+    }
+    let stats = loader.load(&archives, storage.as_ref()).await?;
+    println!("Stored {} accounts at slot {}", stats.accounts, stats.slot);
+
+    Ok(())
+}
@@ -0,0 +1,427 @@
+pub mod wire;
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+use serde::Deserialize;
+use solana_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, GeyserPluginError, ReplicaAccountInfoVersions, ReplicaBlockInfoVersions,
+    ReplicaTransactionInfoVersions, Result, SlotStatus as GeyserSlotStatus,
+};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::wire::{encode_frame, read_frame, ClientMessage, GeyserMessage, SlotStatus};
+
+#[derive(Debug, Deserialize)]
+struct PluginConfig {
+    // `tcp://host:port` or `unix:///path/to/socket`
+    bind: String,
+    #[serde(default)]
+    include_startup: bool,
+    #[serde(default)]
+    include_votes: bool,
+    // Frames buffered per client before it is considered too slow and dropped
+    #[serde(default = "default_client_buffer")]
+    client_buffer: usize,
+}
+
+fn default_client_buffer() -> usize {
+    100_000
+}
+
+#[derive(Default)]
+struct Filter {
+    accounts: HashSet<Pubkey>,
+    programs: HashSet<Pubkey>,
+}
+
+impl Filter {
+    fn matches_account(&self, pubkey: &Pubkey, owner: &Pubkey) -> bool {
+        self.accounts.contains(pubkey) || self.programs.contains(owner)
+    }
+
+    fn matches_transaction<'a>(&self, mut account_keys: impl Iterator<Item = &'a Pubkey>) -> bool {
+        account_keys.any(|key| self.accounts.contains(key) || self.programs.contains(key))
+    }
+}
+
+struct Client {
+    sender: SyncSender<Arc<Vec<u8>>>,
+    filter: Arc<RwLock<Filter>>,
+    stream: Stream,
+}
+
+// A dropped client's writer may be stuck on a full socket and its reader on
+// an idle one, so the connection is closed for the client to notice and reconnect
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.stream.shutdown();
+    }
+}
+
+#[derive(Default)]
+pub struct VistaGeyserPlugin {
+    config: Option<PluginConfig>,
+    // Notify threads only read this; clients are added and dropped under the write lock
+    clients: Arc<RwLock<Vec<Arc<Client>>>>,
+}
+
+impl std::fmt::Debug for VistaGeyserPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VistaGeyserPlugin").field("config", &self.config).finish()
+    }
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl Stream {
+    fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            Stream::Tcp(stream) => stream.try_clone().map(Stream::Tcp),
+            Stream::Unix(stream) => stream.try_clone().map(Stream::Unix),
+        }
+    }
+
+    fn shutdown(&self) {
+        let _ = match self {
+            Stream::Tcp(stream) => stream.shutdown(Shutdown::Both),
+            Stream::Unix(stream) => stream.shutdown(Shutdown::Both),
+        };
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            Stream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            Stream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            Stream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+impl VistaGeyserPlugin {
+    fn config(&self) -> &PluginConfig {
+        self.config.as_ref().expect("plugin used before on_load")
+    }
+
+    fn listen(&self, bind: &str, client_buffer: usize) -> io::Result<()> {
+        let accept: Box<dyn FnMut() -> io::Result<Stream> + Send> = if let Some(path) = bind.strip_prefix("unix://") {
+            let _ = fs::remove_file(path);
+            let listener = UnixListener::bind(path)?;
+            Box::new(move || listener.accept().map(|(stream, _)| Stream::Unix(stream)))
+        } else {
+            let listener = TcpListener::bind(bind.strip_prefix("tcp://").unwrap_or(bind))?;
+            Box::new(move || listener.accept().map(|(stream, _)| {
+                let _ = stream.set_nodelay(true);
+                Stream::Tcp(stream)
+            }))
+        };
+
+        let clients = self.clients.clone();
+        thread::Builder::new().name("vista-geyser-accept".to_string()).spawn(move || {
+            let mut accept = accept;
+            loop {
+                match accept() {
+                    Ok(stream) => {
+                        if let Err(e) = spawn_client(stream, &clients, client_buffer) {
+                            eprintln!("Failed to set up Vista client: {}", e);
+                        }
+                    }
+                    Err(e) => eprintln!("Vista geyser accept error: {}", e),
+                }
+            }
+        })?;
+        Ok(())
+    }
+
+    // Sends a message to every client whose filter accepts it. The message is
+    // built and encoded at most once, and only if some client wants it.
+    // Clients that can't keep up are dropped rather than stalling the validator.
+    fn broadcast(&self, wanted: impl Fn(&Filter) -> bool, message: impl FnOnce() -> GeyserMessage) -> Result<()> {
+        let mut message = Some(message);
+        let mut frame = None;
+        let mut failed = Vec::new();
+        for client in self.clients.read().unwrap().iter() {
+            if !wanted(&client.filter.read().unwrap()) {
+                continue;
+            }
+            if let Some(message) = message.take() {
+                frame = Some(Arc::new(encode_frame(&message()).map_err(|e| GeyserPluginError::Custom(Box::new(e)))?));
+            }
+            let frame = frame.as_ref().unwrap();
+            match client.sender.try_send(frame.clone()) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => failed.push(client.clone()),
+            }
+        }
+        if !failed.is_empty() {
+            self.clients.write().unwrap().retain(|client| !failed.iter().any(|f| Arc::ptr_eq(client, f)));
+        }
+        Ok(())
+    }
+}
+
+fn spawn_client(stream: Stream, clients: &Arc<RwLock<Vec<Arc<Client>>>>, client_buffer: usize) -> io::Result<()> {
+    let (sender, receiver) = sync_channel::<Arc<Vec<u8>>>(client_buffer);
+    let filter = Arc::new(RwLock::new(Filter::default()));
+
+    let mut writer = stream.try_clone()?;
+    thread::Builder::new().name("vista-geyser-write".to_string()).spawn(move || {
+        while let Ok(frame) = receiver.recv() {
+            if writer.write_all(&frame).is_err() {
+                break;
+            }
+        }
+    })?;
+
+    let mut reader = stream.try_clone()?;
+    let client_filter = filter.clone();
+    thread::Builder::new().name("vista-geyser-read".to_string()).spawn(move || {
+        while let Ok(message) = read_frame::<_, ClientMessage>(&mut reader) {
+            match message {
+                ClientMessage::Subscribe { accounts, programs } => {
+                    *client_filter.write().unwrap() = Filter {
+                        accounts: accounts.into_iter().collect(),
+                        programs: programs.into_iter().collect(),
+                    };
+                }
+            }
+        }
+    })?;
+
+    clients.write().unwrap().push(Arc::new(Client { sender, filter, stream }));
+    Ok(())
+}
+
+fn to_pubkey(bytes: &[u8]) -> Result<Pubkey> {
+    Pubkey::try_from(bytes).map_err(|_| GeyserPluginError::AccountsUpdateError {
+        msg: format!("Invalid pubkey length: {}", bytes.len()),
+    })
+}
+
+impl GeyserPlugin for VistaGeyserPlugin {
+    fn name(&self) -> &'static str {
+        "vista-geyser-plugin"
+    }
+
+    fn on_load(&mut self, config_file: &str, _is_reload: bool) -> Result<()> {
+        let contents = fs::read_to_string(config_file)
+            .map_err(|e| GeyserPluginError::ConfigFileReadError { msg: e.to_string() })?;
+        let config: PluginConfig = serde_json::from_str(&contents)
+            .map_err(|e| GeyserPluginError::ConfigFileReadError { msg: e.to_string() })?;
+        self.listen(&config.bind, config.client_buffer)
+            .map_err(|e| GeyserPluginError::Custom(Box::new(e)))?;
+        self.config = Some(config);
+        Ok(())
+    }
+
+    fn on_unload(&mut self) {
+        // Dropping the clients ends their threads and closes their connections
+        self.clients.write().unwrap().clear();
+    }
+
+    fn update_account(&self, account: ReplicaAccountInfoVersions, slot: u64, is_startup: bool) -> Result<()> {
+        if is_startup && !self.config().include_startup {
+            return Ok(());
+        }
+        let (pubkey, lamports, owner, executable, rent_epoch, data, write_version) = match account {
+            ReplicaAccountInfoVersions::V0_0_1(a) => (a.pubkey, a.lamports, a.owner, a.executable, a.rent_epoch, a.data, a.write_version),
+            ReplicaAccountInfoVersions::V0_0_2(a) => (a.pubkey, a.lamports, a.owner, a.executable, a.rent_epoch, a.data, a.write_version),
+            ReplicaAccountInfoVersions::V0_0_3(a) => (a.pubkey, a.lamports, a.owner, a.executable, a.rent_epoch, a.data, a.write_version),
+        };
+        let pubkey = to_pubkey(pubkey)?;
+        let owner = to_pubkey(owner)?;
+
+        // Most accounts interest no client; their data is never copied
+        self.broadcast(|filter| filter.matches_account(&pubkey, &owner), || GeyserMessage::Account {
+            pubkey,
+            lamports,
+            owner,
+            executable,
+            rent_epoch,
+            data: data.to_vec(),
+            slot,
+            write_version,
+            is_startup,
+        })
+    }
+
+    fn update_slot_status(&self, slot: u64, parent: Option<u64>, status: GeyserSlotStatus) -> Result<()> {
+        let status = match status {
+            GeyserSlotStatus::Processed => SlotStatus::Processed,
+            GeyserSlotStatus::Confirmed => SlotStatus::Confirmed,
+            GeyserSlotStatus::Rooted => SlotStatus::Rooted,
+        };
+        self.broadcast(|_| true, || GeyserMessage::Slot { slot, parent, status })
+    }
+
+    fn notify_transaction(&self, transaction: ReplicaTransactionInfoVersions, slot: u64) -> Result<()> {
+        let (signature, is_vote, sanitized, meta, index) = match transaction {
+            ReplicaTransactionInfoVersions::V0_0_1(t) => (t.signature, t.is_vote, t.transaction, t.transaction_status_meta, None),
+            ReplicaTransactionInfoVersions::V0_0_2(t) => (t.signature, t.is_vote, t.transaction, t.transaction_status_meta, Some(t.index)),
+        };
+        if is_vote && !self.config().include_votes {
+            return Ok(());
+        }
+
+        let account_keys = sanitized.message().account_keys();
+        self.broadcast(|filter| filter.matches_transaction(account_keys.iter()), || {
+            let program_ids: Vec<Pubkey> = sanitized.message().instructions().iter()
+                .filter_map(|ix| account_keys.get(ix.program_id_index as usize).copied())
+                .collect();
+            let mut seen = HashSet::new();
+            let invoked_program_ids: Vec<Pubkey> = meta.inner_instructions.iter().flatten()
+                .flat_map(|inner| &inner.instructions)
+                .filter_map(|ix| account_keys.get(ix.instruction.program_id_index as usize).copied())
+                .filter(|program_id| seen.insert(*program_id))
+                .collect();
+            GeyserMessage::Transaction {
+                signature: *signature,
+                slot,
+                index,
+                is_vote,
+                program_ids,
+                invoked_program_ids,
+                err: meta.status.clone().err(),
+                failed_program_id: meta.log_messages.iter().flatten().find_map(|line| {
+                    let (program_id, _) = line.strip_prefix("Program ")?.split_once(" failed: ")?;
+                    program_id.parse().ok()
+                }),
+            }
+        })
+    }
+
+    fn notify_block_metadata(&self, block_info: ReplicaBlockInfoVersions) -> Result<()> {
+        // V0_0_2 and V0_0_3 only differ in fields Vista doesn't use
+        macro_rules! block_meta {
+            ($block:expr) => {
+                GeyserMessage::BlockMeta {
+                    slot: $block.slot,
+                    blockhash: $block.blockhash.to_string(),
+                    parent_slot: $block.parent_slot,
+                    parent_blockhash: $block.parent_blockhash.to_string(),
+                    block_time: $block.block_time,
+                    block_height: $block.block_height,
+                    executed_transaction_count: $block.executed_transaction_count,
+                    rewards: $block.rewards.to_vec(),
+                }
+            };
+        }
+        // V0_0_1 predates parent slot and transaction count, which Vista requires
+        let message = match block_info {
+            ReplicaBlockInfoVersions::V0_0_1(_) => return Ok(()),
+            ReplicaBlockInfoVersions::V0_0_2(block) => block_meta!(block),
+            ReplicaBlockInfoVersions::V0_0_3(block) => block_meta!(block),
+        };
+        self.broadcast(|_| true, || message)
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        true
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        true
+    }
+}
+
+/// # Safety
+///
+/// Called by the validator's plugin manager, which takes ownership of the
+/// returned plugin.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
+    let plugin: Box<dyn GeyserPlugin> = Box::new(VistaGeyserPlugin::default());
+    Box::into_raw(plugin)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::Receiver;
+
+    use super::*;
+
+    // The frames queued for a client, and the far end of its connection
+    fn add_client(plugin: &VistaGeyserPlugin, accounts: &[Pubkey], buffer: usize) -> (Receiver<Arc<Vec<u8>>>, UnixStream) {
+        let (sender, receiver) = sync_channel(buffer);
+        let (stream, peer) = UnixStream::pair().unwrap();
+        let filter = Filter { accounts: accounts.iter().copied().collect(), programs: HashSet::new() };
+        let client = Client { sender, filter: Arc::new(RwLock::new(filter)), stream: Stream::Unix(stream) };
+        plugin.clients.write().unwrap().push(Arc::new(client));
+        (receiver, peer)
+    }
+
+    fn slot(slot: u64) -> GeyserMessage {
+        GeyserMessage::Slot { slot, parent: None, status: SlotStatus::Processed }
+    }
+
+    #[test]
+    fn messages_nobody_wants_are_never_built() {
+        let plugin = VistaGeyserPlugin::default();
+        let (receiver, _peer) = add_client(&plugin, &[Pubkey::new_unique()], 10);
+        let other = Pubkey::new_unique();
+        plugin.broadcast(|filter| filter.matches_account(&other, &other), || panic!("built for nobody")).unwrap();
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn one_frame_goes_to_every_interested_client() {
+        let plugin = VistaGeyserPlugin::default();
+        let tracked = Pubkey::new_unique();
+        let ((first, _first_peer), (second, _second_peer)) = (add_client(&plugin, &[tracked], 10), add_client(&plugin, &[tracked], 10));
+        let (uninterested, _uninterested_peer) = add_client(&plugin, &[], 10);
+
+        let mut builds = 0;
+        plugin.broadcast(|filter| filter.matches_account(&tracked, &tracked), || {
+            builds += 1;
+            slot(1)
+        }).unwrap();
+        assert_eq!(builds, 1);
+        let frame = first.try_recv().unwrap();
+        assert!(Arc::ptr_eq(&frame, &second.try_recv().unwrap()));
+        assert_eq!(*frame, encode_frame(&slot(1)).unwrap());
+        assert!(uninterested.try_recv().is_err());
+    }
+
+    #[test]
+    fn clients_that_fall_behind_are_dropped() {
+        let plugin = VistaGeyserPlugin::default();
+        let (slow, mut slow_peer) = add_client(&plugin, &[], 1);
+        let (_fast, fast_peer) = add_client(&plugin, &[], 10);
+        plugin.broadcast(|_| true, || slot(1)).unwrap();
+        plugin.broadcast(|_| true, || slot(2)).unwrap();
+        assert_eq!(plugin.clients.read().unwrap().len(), 1);
+        assert!(slow.try_recv().is_ok());
+
+        // The slow client sees its connection end; the other one stays open
+        assert_eq!(slow_peer.read(&mut [0u8; 1]).unwrap(), 0);
+        fast_peer.set_nonblocking(true).unwrap();
+        assert_eq!((&fast_peer).read(&mut [0u8; 1]).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+    }
+}
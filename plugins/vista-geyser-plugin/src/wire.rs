@@ -0,0 +1,166 @@
+use std::io::{self, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::Reward;
+
+// Frames are a little-endian u32 length followed by a bincode payload
+pub const MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SlotStatus {
+    Processed,
+    Confirmed,
+    Rooted,
+}
+
+// Plugin -> client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GeyserMessage {
+    Account {
+        pubkey: Pubkey,
+        lamports: u64,
+        owner: Pubkey,
+        executable: bool,
+        rent_epoch: u64,
+        data: Vec<u8>,
+        slot: u64,
+        write_version: u64,
+        is_startup: bool,
+    },
+    Transaction {
+        signature: Signature,
+        slot: u64,
+        index: Option<usize>,
+        is_vote: bool,
+        // One per top-level instruction, in order
+        program_ids: Vec<Pubkey>,
+        // CPI targets, once each
+        invoked_program_ids: Vec<Pubkey>,
+        err: Option<TransactionError>,
+        // Program whose "Program <id> failed" log line comes first
+        failed_program_id: Option<Pubkey>,
+    },
+    Slot {
+        slot: u64,
+        parent: Option<u64>,
+        status: SlotStatus,
+    },
+    BlockMeta {
+        slot: u64,
+        blockhash: String,
+        parent_slot: u64,
+        parent_blockhash: String,
+        block_time: Option<i64>,
+        block_height: Option<u64>,
+        executed_transaction_count: u64,
+        rewards: Vec<Reward>,
+    },
+}
+
+// Client -> plugin. Each subscribe replaces the client's filter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Subscribe {
+        accounts: Vec<Pubkey>,
+        programs: Vec<Pubkey>,
+    },
+}
+
+pub fn encode_frame<T: Serialize>(message: &T) -> io::Result<Vec<u8>> {
+    let payload = bincode::serialize(message).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+pub fn write_frame<W: Write, T: Serialize>(writer: &mut W, message: &T) -> io::Result<()> {
+    writer.write_all(&encode_frame(message)?)
+}
+
+pub fn read_frame<R: Read, T: DeserializeOwned>(reader: &mut R) -> io::Result<T> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let mut payload = vec![0u8; checked_frame_len(len)?];
+    reader.read_exact(&mut payload)?;
+    decode_payload(&payload)
+}
+
+pub fn checked_frame_len(len: [u8; 4]) -> io::Result<usize> {
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Frame of {} bytes exceeds the limit", len)));
+    }
+    Ok(len)
+}
+
+pub fn decode_payload<T: DeserializeOwned>(payload: &[u8]) -> io::Result<T> {
+    bincode::deserialize(payload).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use solana_sdk::instruction::InstructionError;
+    use solana_transaction_status::RewardType;
+
+    use super::*;
+
+    fn round_trip<T: Serialize + DeserializeOwned>(message: &T) -> T {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, message).unwrap();
+        let mut reader = Cursor::new(buffer);
+        let decoded = read_frame(&mut reader).unwrap();
+        assert_eq!(reader.position() as usize, reader.get_ref().len());
+        decoded
+    }
+
+    #[test]
+    fn messages_survive_framing() {
+        let (pubkey, owner, program) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let signature = Signature::new_unique();
+        let messages = vec![
+            GeyserMessage::Account {
+                pubkey, lamports: 5, owner, executable: false, rent_epoch: 1, data: vec![1, 2, 3],
+                slot: 10, write_version: 7, is_startup: false,
+            },
+            GeyserMessage::Transaction {
+                signature, slot: 10, index: Some(3), is_vote: false,
+                program_ids: vec![program], invoked_program_ids: vec![owner],
+                err: Some(TransactionError::InstructionError(0, InstructionError::Custom(6000))),
+                failed_program_id: Some(owner),
+            },
+            GeyserMessage::Slot { slot: 10, parent: Some(9), status: SlotStatus::Confirmed },
+            GeyserMessage::BlockMeta {
+                slot: 10, blockhash: "hash".to_string(), parent_slot: 9, parent_blockhash: "parent".to_string(),
+                block_time: Some(1_700_000_000), block_height: Some(8), executed_transaction_count: 2,
+                rewards: vec![Reward {
+                    pubkey: pubkey.to_string(), lamports: 5, post_balance: 10,
+                    reward_type: Some(RewardType::Fee), commission: None,
+                }],
+            },
+        ];
+        for message in &messages {
+            assert_eq!(&round_trip(message), message);
+        }
+        let subscribe = ClientMessage::Subscribe { accounts: vec![pubkey], programs: vec![program] };
+        assert_eq!(round_trip(&subscribe), subscribe);
+    }
+
+    #[test]
+    fn oversized_and_truncated_frames_are_rejected() {
+        let mut oversized = Cursor::new(((MAX_FRAME_SIZE + 1) as u32).to_le_bytes().to_vec());
+        let err = read_frame::<_, GeyserMessage>(&mut oversized).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut frame = encode_frame(&GeyserMessage::Slot { slot: 1, parent: None, status: SlotStatus::Rooted }).unwrap();
+        frame.pop();
+        let err = read_frame::<_, GeyserMessage>(&mut Cursor::new(frame)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS idl_versions (
+    program_id TEXT NOT NULL,
+    start_slot BIGINT NOT NULL,
+    idl TEXT NOT NULL,
+    PRIMARY KEY (program_id, start_slot)
+);
\ No newline at end of file
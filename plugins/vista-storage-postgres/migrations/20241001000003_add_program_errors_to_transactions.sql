@@ -0,0 +1,9 @@
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS program_ids TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS error_instruction_index SMALLINT,
+    ADD COLUMN IF NOT EXISTS error_program_id TEXT,
+    ADD COLUMN IF NOT EXISTS error_code BIGINT,
+    ADD COLUMN IF NOT EXISTS error_name TEXT,
+    ADD COLUMN IF NOT EXISTS error_message TEXT;
+
+CREATE INDEX IF NOT EXISTS transactions_error_idx ON transactions (error_program_id, error_name);
\ No newline at end of file
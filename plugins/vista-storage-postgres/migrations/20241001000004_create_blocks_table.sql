@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS blocks (
+    slot BIGINT PRIMARY KEY,
+    blockhash TEXT NOT NULL,
+    parent_slot BIGINT NOT NULL,
+    parent_blockhash TEXT NOT NULL,
+    block_time BIGINT,
+    block_height BIGINT,
+    executed_transaction_count BIGINT NOT NULL,
+    rewards JSONB NOT NULL
+);
\ No newline at end of file
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS slot_repairs (
+    id BIGSERIAL PRIMARY KEY,
+    slot BIGINT NOT NULL,
+    kind TEXT NOT NULL,
+    detected_by TEXT NOT NULL,
+    repaired_by TEXT NOT NULL,
+    transactions BIGINT NOT NULL,
+    accounts BIGINT NOT NULL,
+    error TEXT,
+    repaired_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS slot_repairs_slot_idx ON slot_repairs (slot);
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS invoked_program_ids TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,11 @@
+-- Rows were keyed by program and type, so each held whichever account of that
+-- type was written last and can't be attributed to an account
+DELETE FROM parsed_accounts;
+
+ALTER TABLE parsed_accounts
+    DROP CONSTRAINT IF EXISTS parsed_accounts_pkey,
+    ADD COLUMN IF NOT EXISTS pubkey TEXT NOT NULL,
+    ADD COLUMN IF NOT EXISTS slot BIGINT NOT NULL DEFAULT 0,
+    ADD PRIMARY KEY (pubkey);
+
+CREATE INDEX IF NOT EXISTS parsed_accounts_type_idx ON parsed_accounts (program_id, account_type);
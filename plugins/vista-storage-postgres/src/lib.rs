@@ -1,35 +1,110 @@
 use async_trait::async_trait;
 use sqlx::postgres::{PgPool, PgPoolOptions};
 use vista_core::traits::StoragePlugin;
-use vista_core::models::{AccountInfo, TransactionInfo};
+use vista_core::models::{AccountInfo, BlockMetaInfo, IdlVersionInfo, ProgramErrorInfo, SlotRepairInfo, TransactionInfo};
 use vista_core::IndexerError;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
 use serde_json::Value;
+use std::future::Future;
 use std::str::FromStr;
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
 
+// Connects in `init`, which the indexer calls once with the storage config
 pub struct PostgresStoragePlugin {
-    pool: PgPool,
+    // Loaded as a cdylib this library has its own copy of tokio, which can't
+    // see the host's reactor, so sqlx runs on a runtime owned by the plugin
+    runtime: Option<Runtime>,
+    pool: OnceLock<PgPool>,
+}
+
+struct TransactionRow {
+    signature: String,
+    status: Value,
+    program_ids: Vec<String>,
+    invoked_program_ids: Vec<String>,
+    error_instruction_index: Option<i16>,
+    error_program_id: Option<String>,
+    error_code: Option<i64>,
+    error_name: Option<String>,
+    error_message: Option<String>,
+}
+
+impl TryFrom<TransactionRow> for TransactionInfo {
+    type Error = IndexerError;
+
+    fn try_from(row: TransactionRow) -> Result<Self, Self::Error> {
+        let parse_pubkey = |s: &str| Pubkey::from_str(s).map_err(|e| IndexerError::StorageError(e.to_string()));
+        let error = match (row.error_instruction_index, row.error_program_id, row.error_code) {
+            (Some(index), Some(program_id), Some(code)) => Some(ProgramErrorInfo {
+                instruction_index: index as u8,
+                program_id: parse_pubkey(&program_id)?,
+                code: code as u32,
+                name: row.error_name,
+                message: row.error_message,
+            }),
+            _ => None,
+        };
+        Ok(TransactionInfo {
+            signature: Signature::from_str(&row.signature).map_err(|e| IndexerError::StorageError(e.to_string()))?,
+            status: serde_json::from_value(row.status).map_err(|e| IndexerError::StorageError(e.to_string()))?,
+            program_ids: row.program_ids.iter().map(|p| parse_pubkey(p)).collect::<Result<_, _>>()?,
+            invoked_program_ids: row.invoked_program_ids.iter().map(|p| parse_pubkey(p)).collect::<Result<_, _>>()?,
+            error,
+            pending: false,
+        })
+    }
 }
 
 impl PostgresStoragePlugin {
-    pub async fn new(config: &Value) -> Result<Self, IndexerError> {
-        let database_url = config["url"].as_str()
-            .ok_or_else(|| IndexerError::ConfigError("Missing database URL".to_string()))?;
+    pub fn new() -> Self {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("vista-postgres")
+            .enable_all()
+            .build()
+            .expect("failed to start the Postgres plugin runtime");
+        Self { runtime: Some(runtime), pool: OnceLock::new() }
+    }
 
-        let pool = PgPoolOptions::new()
-            .max_connections(5)
-            .connect(database_url)
-            .await
-            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+    fn pool(&self) -> Result<&PgPool, IndexerError> {
+        self.pool.get().ok_or_else(|| IndexerError::StorageError("Postgres storage is not initialized".to_string()))
+    }
 
-        // Run migrations
-        sqlx::migrate!("./migrations")
-            .run(&pool)
-            .await
-            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+    // Runs `query` on the plugin's runtime; the caller only awaits the join
+    // handle, which doesn't need a reactor
+    async fn run<T, F>(&self, query: impl FnOnce(PgPool) -> F) -> Result<T, IndexerError>
+    where
+        T: Send + 'static,
+        F: Future<Output = Result<T, IndexerError>> + Send + 'static,
+    {
+        let pool = self.pool()?.clone();
+        self.spawn(query(pool)).await
+    }
+
+    async fn spawn<T, F>(&self, future: F) -> Result<T, IndexerError>
+    where
+        T: Send + 'static,
+        F: Future<Output = Result<T, IndexerError>> + Send + 'static,
+    {
+        let runtime = self.runtime.as_ref().expect("runtime is only taken on drop");
+        runtime.spawn(future).await.map_err(|e| IndexerError::StorageError(e.to_string()))?
+    }
+}
 
-        Ok(Self { pool })
+impl Drop for PostgresStoragePlugin {
+    fn drop(&mut self) {
+        // A plain drop blocks on the workers, which panics inside async code
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl Default for PostgresStoragePlugin {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -39,118 +114,394 @@ impl StoragePlugin for PostgresStoragePlugin {
         "postgres"
     }
 
-    async fn init(&self, _config: &Value) -> Result<(), IndexerError> {
-        // Initialization is done in new(), so this is a no-op
-        Ok(())
+    async fn init(&self, config: &Value) -> Result<(), IndexerError> {
+        let database_url = config["url"].as_str()
+            .ok_or_else(|| IndexerError::ConfigError("Missing database URL".to_string()))?
+            .to_string();
+
+        let pool = self.spawn(async move {
+            let pool = PgPoolOptions::new()
+                .max_connections(5)
+                .connect(&database_url)
+                .await
+                .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+
+            // Run migrations
+            sqlx::migrate!("./migrations")
+                .run(&pool)
+                .await
+                .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+            Ok(pool)
+        }).await?;
+
+        self.pool.set(pool)
+            .map_err(|_| IndexerError::StorageError("Postgres storage is already initialized".to_string()))
     }
 
     async fn store_account(&self, account: AccountInfo) -> Result<(), IndexerError> {
-        sqlx::query!(
-            r#"
-            INSERT INTO accounts (pubkey, lamports, owner, executable, rent_epoch, data)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            ON CONFLICT (pubkey) DO UPDATE
-            SET lamports = $2, owner = $3, executable = $4, rent_epoch = $5, data = $6
-            "#,
-            account.pubkey.to_string(),
-            account.lamports as i64,
-            account.owner.to_string(),
-            account.executable,
-            account.rent_epoch as i64,
-            account.data
-        )
-        .execute(&self.pool)
-        .await
-        .map_err(|e| IndexerError::StorageError(e.to_string()))?;
-
-        Ok(())
+        self.run(move |pool| async move {
+            sqlx::query!(
+                r#"
+                INSERT INTO accounts (pubkey, lamports, owner, executable, rent_epoch, data, slot)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (pubkey) DO UPDATE
+                SET lamports = $2, owner = $3, executable = $4, rent_epoch = $5, data = $6, slot = $7
+                "#,
+                account.pubkey.to_string(),
+                account.lamports as i64,
+                account.owner.to_string(),
+                account.executable,
+                account.rent_epoch as i64,
+                account.data,
+                account.slot as i64
+            )
+            .execute(&pool)
+            .await
+            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+
+            Ok(())
+        }).await
     }
 
     async fn store_transaction(&self, transaction: TransactionInfo) -> Result<(), IndexerError> {
-        sqlx::query!(
-            r#"
-            INSERT INTO transactions (signature, status)
-            VALUES ($1, $2)
-            ON CONFLICT (signature) DO UPDATE
-            SET status = $2
-            "#,
-            transaction.signature.to_string(),
-            serde_json::to_value(transaction.status).map_err(|e| IndexerError::StorageError(e.to_string()))?
-        )
-        .execute(&self.pool)
-        .await
-        .map_err(|e| IndexerError::StorageError(e.to_string()))?;
-
-        Ok(())
+        self.run(move |pool| async move {
+            let error = transaction.error.as_ref();
+            sqlx::query!(
+                r#"
+                INSERT INTO transactions (signature, status, program_ids, invoked_program_ids, error_instruction_index, error_program_id, error_code, error_name, error_message)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                ON CONFLICT (signature) DO UPDATE
+                SET status = $2, program_ids = $3, invoked_program_ids = $4, error_instruction_index = $5, error_program_id = $6,
+                    error_code = $7, error_name = $8, error_message = $9
+                "#,
+                transaction.signature.to_string(),
+                serde_json::to_value(&transaction.status).map_err(|e| IndexerError::StorageError(e.to_string()))?,
+                &transaction.program_ids.iter().map(|p| p.to_string()).collect::<Vec<_>>(),
+                &transaction.invoked_program_ids.iter().map(|p| p.to_string()).collect::<Vec<_>>(),
+                error.map(|e| e.instruction_index as i16),
+                error.map(|e| e.program_id.to_string()),
+                error.map(|e| e.code as i64),
+                error.and_then(|e| e.name.clone()),
+                error.and_then(|e| e.message.clone())
+            )
+            .execute(&pool)
+            .await
+            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+
+            Ok(())
+        }).await
     }
 
     async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<AccountInfo>, IndexerError> {
-        let result = sqlx::query!(
-            r#"
-            SELECT pubkey, lamports, owner, executable, rent_epoch, data
-            FROM accounts
-            WHERE pubkey = $1
-            "#,
-            pubkey.to_string()
-        )
-        .fetch_optional(&self.pool)
-        .await
-        .map_err(|e| IndexerError::StorageError(e.to_string()))?;
-
-        result.map(|row| {
-            Ok(AccountInfo {
-                pubkey: Pubkey::from_str(&row.pubkey).map_err(|e| IndexerError::StorageError(e.to_string()))?,
-                lamports: row.lamports as u64,
-                owner: Pubkey::from_str(&row.owner).map_err(|e| IndexerError::StorageError(e.to_string()))?,
-                executable: row.executable,
-                rent_epoch: row.rent_epoch as u64,
-                data: row.data,
-            })
-        }).transpose()
+        let pubkey = *pubkey;
+        self.run(move |pool| async move {
+            let result = sqlx::query!(
+                r#"
+                SELECT pubkey, lamports, owner, executable, rent_epoch, data, slot
+                FROM accounts
+                WHERE pubkey = $1
+                "#,
+                pubkey.to_string()
+            )
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+
+            result.map(|row| {
+                Ok(AccountInfo {
+                    pubkey: Pubkey::from_str(&row.pubkey).map_err(|e| IndexerError::StorageError(e.to_string()))?,
+                    lamports: row.lamports as u64,
+                    owner: Pubkey::from_str(&row.owner).map_err(|e| IndexerError::StorageError(e.to_string()))?,
+                    executable: row.executable,
+                    rent_epoch: row.rent_epoch as u64,
+                    data: row.data,
+                    slot: row.slot as u64,
+                })
+            }).transpose()
+        }).await
     }
 
     async fn get_transaction(&self, signature: &Signature) -> Result<Option<TransactionInfo>, IndexerError> {
-        let result = sqlx::query!(
-            r#"
-            SELECT signature, status
-            FROM transactions
-            WHERE signature = $1
-            "#,
-            signature.to_string()
-        )
-        .fetch_optional(&self.pool)
-        .await
-        .map_err(|e| IndexerError::StorageError(e.to_string()))?;
-
-        result.map(|row| {
-            Ok(TransactionInfo {
-                signature: Signature::from_str(&row.signature).map_err(|e| IndexerError::StorageError(e.to_string()))?,
-                status: serde_json::from_value(row.status).map_err(|e| IndexerError::StorageError(e.to_string()))?,
-            })
-        }).transpose()
-    }
-
-    async fn store_parsed_account(&self, program_id: &str, account_type: &str, data: &Value) -> Result<(), IndexerError> {
-        sqlx::query!(
-            r#"
-            INSERT INTO parsed_accounts (program_id, account_type, data)
-            VALUES ($1, $2, $3)
-            ON CONFLICT (program_id, account_type) DO UPDATE
-            SET data = $3
-            "#,
-            program_id,
-            account_type,
-            data
-        )
-        .execute(&self.pool)
-        .await
-        .map_err(|e| IndexerError::StorageError(e.to_string()))?;
-
-        Ok(())
+        let signature = *signature;
+        self.run(move |pool| async move {
+            let result = sqlx::query_as!(
+                TransactionRow,
+                r#"
+                SELECT signature, status, program_ids, invoked_program_ids, error_instruction_index, error_program_id, error_code, error_name, error_message
+                FROM transactions
+                WHERE signature = $1
+                "#,
+                signature.to_string()
+            )
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+
+            result.map(TransactionInfo::try_from).transpose()
+        }).await
+    }
+
+    async fn get_failed_transactions(&self, program_id: &Pubkey, error_name: Option<&str>) -> Result<Vec<TransactionInfo>, IndexerError> {
+        let program_id = *program_id;
+        let error_name = error_name.map(str::to_string);
+        self.run(move |pool| async move {
+            let rows = sqlx::query_as!(
+                TransactionRow,
+                r#"
+                SELECT signature, status, program_ids, invoked_program_ids, error_instruction_index, error_program_id, error_code, error_name, error_message
+                FROM transactions
+                WHERE error_program_id = $1 AND ($2::TEXT IS NULL OR error_name = $2)
+                "#,
+                program_id.to_string(),
+                error_name.as_deref()
+            )
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+
+            rows.into_iter().map(TransactionInfo::try_from).collect()
+        }).await
+    }
+
+    async fn store_parsed_account(&self, pubkey: &Pubkey, program_id: &str, account_type: &str, slot: u64, data: &Value) -> Result<(), IndexerError> {
+        let (pubkey, program_id, account_type, data) = (pubkey.to_string(), program_id.to_string(), account_type.to_string(), data.clone());
+        self.run(move |pool| async move {
+            sqlx::query!(
+                r#"
+                INSERT INTO parsed_accounts (pubkey, program_id, account_type, slot, data)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (pubkey) DO UPDATE
+                SET program_id = $2, account_type = $3, slot = $4, data = $5
+                "#,
+                pubkey,
+                program_id,
+                account_type,
+                slot as i64,
+                data
+            )
+            .execute(&pool)
+            .await
+            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+
+            Ok(())
+        }).await
+    }
+
+    async fn store_block_meta(&self, block_meta: BlockMetaInfo) -> Result<(), IndexerError> {
+        self.run(move |pool| async move {
+            sqlx::query!(
+                r#"
+                INSERT INTO blocks (slot, blockhash, parent_slot, parent_blockhash, block_time, block_height, executed_transaction_count, rewards)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (slot) DO UPDATE
+                SET blockhash = $2, parent_slot = $3, parent_blockhash = $4, block_time = $5,
+                    block_height = $6, executed_transaction_count = $7, rewards = $8
+                "#,
+                block_meta.slot as i64,
+                block_meta.blockhash,
+                block_meta.parent_slot as i64,
+                block_meta.parent_blockhash,
+                block_meta.block_time,
+                block_meta.block_height.map(|h| h as i64),
+                block_meta.executed_transaction_count as i64,
+                serde_json::to_value(&block_meta.rewards).map_err(|e| IndexerError::StorageError(e.to_string()))?
+            )
+            .execute(&pool)
+            .await
+            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+
+            Ok(())
+        }).await
+    }
+
+    async fn store_idl_version(&self, version: IdlVersionInfo) -> Result<(), IndexerError> {
+        self.run(move |pool| async move {
+            sqlx::query!(
+                r#"
+                INSERT INTO idl_versions (program_id, start_slot, idl)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (program_id, start_slot) DO UPDATE
+                SET idl = $3
+                "#,
+                version.program_id,
+                version.start_slot as i64,
+                version.idl_json
+            )
+            .execute(&pool)
+            .await
+            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+
+            Ok(())
+        }).await
+    }
+
+    async fn get_idl_versions(&self) -> Result<Vec<IdlVersionInfo>, IndexerError> {
+        self.run(move |pool| async move {
+            let rows = sqlx::query!(
+                r#"
+                SELECT program_id, start_slot, idl
+                FROM idl_versions
+                ORDER BY program_id, start_slot
+                "#
+            )
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+
+            Ok(rows.into_iter().map(|row| IdlVersionInfo {
+                program_id: row.program_id,
+                start_slot: row.start_slot as u64,
+                idl_json: row.idl,
+            }).collect())
+        }).await
+    }
+
+    async fn store_slot_repair(&self, repair: SlotRepairInfo) -> Result<(), IndexerError> {
+        self.run(move |pool| async move {
+            sqlx::query!(
+                r#"
+                INSERT INTO slot_repairs (slot, kind, detected_by, repaired_by, transactions, accounts, error, repaired_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, to_timestamp($8))
+                "#,
+                repair.slot as i64,
+                format!("{:?}", repair.kind),
+                repair.detected_by,
+                repair.repaired_by,
+                repair.transactions as i64,
+                repair.accounts as i64,
+                repair.error,
+                repair.repaired_at as f64
+            )
+            .execute(&pool)
+            .await
+            .map_err(|e| IndexerError::StorageError(e.to_string()))?;
+
+            Ok(())
+        }).await
     }
 }
 
-#[no_mangle]
 pub fn create_storage_plugin() -> Box<dyn StoragePlugin> {
     Box::new(PostgresStoragePlugin::new())
-}
\ No newline at end of file
+}
+
+vista_core::declare_storage_plugin!(create_storage_plugin);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn failed_status() -> Value {
+        let err = json!({ "InstructionError": [2, { "Custom": 6000 }] });
+        json!({ "slot": 10, "confirmations": null, "status": { "Err": err }, "err": err, "confirmationStatus": "confirmed" })
+    }
+
+    fn row(program_ids: &[Pubkey], error_program_id: Option<&Pubkey>) -> TransactionRow {
+        TransactionRow {
+            signature: Signature::new_unique().to_string(),
+            status: failed_status(),
+            program_ids: program_ids.iter().map(|p| p.to_string()).collect(),
+            invoked_program_ids: vec![program_ids[0].to_string()],
+            error_instruction_index: Some(2),
+            error_program_id: error_program_id.map(|p| p.to_string()),
+            error_code: Some(6000),
+            error_name: Some("Slippage".to_string()),
+            error_message: None,
+        }
+    }
+
+    #[test]
+    fn failed_transaction_rows_keep_their_program_error() {
+        let (router, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let row = row(&[router, router, pool], Some(&pool));
+        let signature = row.signature.clone();
+        let transaction = TransactionInfo::try_from(row).unwrap();
+
+        assert_eq!(transaction.signature.to_string(), signature);
+        assert_eq!(transaction.program_ids, vec![router, router, pool]);
+        assert_eq!(transaction.invoked_program_ids, vec![router]);
+        assert_eq!(serde_json::to_value(&transaction.status).unwrap(), failed_status());
+        let error = transaction.error.unwrap();
+        assert_eq!((error.instruction_index, error.program_id, error.code), (2, pool, 6000));
+        assert_eq!((error.name.as_deref(), error.message), (Some("Slippage"), None));
+        assert!(!transaction.pending);
+    }
+
+    #[test]
+    fn rows_without_a_complete_error_have_none() {
+        let program = Pubkey::new_unique();
+        let transaction = TransactionInfo::try_from(row(&[program], None)).unwrap();
+        assert!(transaction.error.is_none());
+
+        let mut bad_program = row(&[program], Some(&program));
+        bad_program.error_program_id = Some("not-a-pubkey".to_string());
+        assert!(TransactionInfo::try_from(bad_program).is_err());
+    }
+
+    // Needs a database the tests may migrate and write to, e.g.
+    // VISTA_TEST_DATABASE_URL=postgres://postgres@localhost/vista cargo test -p vista-storage-postgres
+    #[tokio::test]
+    async fn failed_transactions_are_found_by_program_and_error_name() {
+        let Ok(url) = std::env::var("VISTA_TEST_DATABASE_URL") else {
+            eprintln!("VISTA_TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let storage = PostgresStoragePlugin::new();
+        storage.init(&json!({ "url": url })).await.unwrap();
+
+        let (router, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut slippage = TransactionInfo::try_from(row(&[router, router, pool], Some(&pool))).unwrap();
+        let mut paused = TransactionInfo::try_from(row(&[router, pool], Some(&pool))).unwrap();
+        paused.error.as_mut().unwrap().name = Some("Paused".to_string());
+        let in_router = TransactionInfo::try_from(row(&[router, pool], Some(&router))).unwrap();
+        for transaction in [&slippage, &paused, &in_router] {
+            storage.store_transaction(transaction.clone()).await.unwrap();
+        }
+
+        let signatures = |transactions: Vec<TransactionInfo>| {
+            let mut signatures: Vec<_> = transactions.into_iter().map(|t| t.signature).collect();
+            signatures.sort();
+            signatures
+        };
+        let mut expected = vec![slippage.signature, paused.signature];
+        expected.sort();
+        assert_eq!(signatures(storage.get_failed_transactions(&pool, None).await.unwrap()), expected);
+        assert_eq!(signatures(storage.get_failed_transactions(&pool, Some("Paused")).await.unwrap()), vec![paused.signature]);
+        assert_eq!(signatures(storage.get_failed_transactions(&router, None).await.unwrap()), vec![in_router.signature]);
+
+        // Re-storing a transaction replaces its error
+        slippage.error = None;
+        storage.store_transaction(slippage).await.unwrap();
+        assert_eq!(signatures(storage.get_failed_transactions(&pool, None).await.unwrap()), vec![paused.signature]);
+    }
+
+    #[tokio::test]
+    async fn parsed_accounts_are_kept_per_account() {
+        let Ok(url) = std::env::var("VISTA_TEST_DATABASE_URL") else {
+            eprintln!("VISTA_TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let storage = PostgresStoragePlugin::new();
+        storage.init(&json!({ "url": url })).await.unwrap();
+
+        let program_id = Pubkey::new_unique().to_string();
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        storage.store_parsed_account(&first, &program_id, "Pool", 10, &json!({ "fee": 1 })).await.unwrap();
+        storage.store_parsed_account(&second, &program_id, "Pool", 11, &json!({ "fee": 2 })).await.unwrap();
+        storage.store_parsed_account(&first, &program_id, "Pool", 12, &json!({ "fee": 3 })).await.unwrap();
+
+        let rows = storage.run(move |pool| async move {
+            sqlx::query_as::<_, (String, i64, Value)>(
+                "SELECT pubkey, slot, data FROM parsed_accounts WHERE program_id = $1 ORDER BY slot"
+            )
+            .bind(program_id)
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| IndexerError::StorageError(e.to_string()))
+        }).await.unwrap();
+        assert_eq!(rows, vec![
+            (second.to_string(), 11, json!({ "fee": 2 })),
+            (first.to_string(), 12, json!({ "fee": 3 })),
+        ]);
+    }
+}
@@ -1,12 +1,38 @@
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
+use libloading::Library;
 use tokio::signal;
-use vista_core::{Config, RpcProviderRegistry, Indexer, IndexerError};
-use vista_storage::PostgresStorage;
-use vista_ingestion::providers::{GeyserRpcProvider, WebSocketRpcProvider, HttpRpcProvider};
+use vista_core::{Config, ConfigReloader, RpcProviderRegistry, Indexer, IndexerError, ProviderLatency};
+use vista_core::config::{ProviderConfig, StorageConfig};
+use vista_core::plugin_abi;
+use vista_core::traits::{RpcProvider, StoragePlugin};
+use vista_ingestion::providers::{BlockProvider, BlockProviderConfig, BlockSource, GeyserRpcProvider, HeliusFilterConfig, HeliusTransactionProvider, WebSocketRpcProvider, HttpRpcProvider, RecordingProvider, ReplayProvider, ReplaySpeed, WebhookConfig, WebhookProvider, YellowstoneGrpcConfig, YellowstoneGrpcProvider};
+#[cfg(any(feature = "kafka", feature = "nats"))]
+use vista_ingestion::providers::{MessageFormat, TopicKind};
+#[cfg(feature = "kafka")]
+use vista_ingestion::providers::{KafkaConfig, KafkaProvider};
+#[cfg(feature = "nats")]
+use vista_ingestion::providers::{NatsConfig, NatsProvider};
+#[cfg(feature = "shredstream")]
+use vista_ingestion::providers::{ShredSource, ShredStreamProvider};
+use solana_sdk::commitment_config::CommitmentLevel;
 use solana_sdk::pubkey::Pubkey;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // `latency-report [seconds]` indexes for a while, then prints how the
+    // providers compare instead of running until Ctrl+C
+    let mut args = std::env::args().skip(1);
+    let report_after = match args.next().as_deref() {
+        Some("latency-report") => {
+            let seconds = args.next().map(|s| s.parse::<u64>()).transpose()?.unwrap_or(300);
+            Some(Duration::from_secs(seconds))
+        }
+        Some(command) => return Err(format!("Unknown command {}", command).into()),
+        None => None,
+    };
+
     // Load configuration
     let config = Config::from_file("config.json")?;
 
@@ -18,27 +44,27 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let provider_registry = Arc::new(RpcProviderRegistry::new());
 
     // Create indexer
-    let indexer = Arc::new(Indexer::new(Arc::new(storage_plugin), provider_registry));
-    let update_channel = indexer.get_update_channel();
-
-    // Register RPC providers
-    if let Some(geyser_config) = config.providers.get("geyser") {
-        let geyser_provider = GeyserRpcProvider::new(&geyser_config.url, update_channel.clone());
-        provider_registry.register_provider(Box::new(geyser_provider));
+    let mut indexer = Indexer::new(Arc::from(storage_plugin), provider_registry.clone());
+    if let Some(rpc_url) = &config.bootstrap_rpc {
+        indexer = indexer.with_bootstrap_rpc(rpc_url);
     }
-
-    if let Some(ws_config) = config.providers.get("websocket") {
-        let ws_provider = WebSocketRpcProvider::new(&ws_config.url, update_channel.clone());
-        provider_registry.register_provider(Box::new(ws_provider));
+    if let Some(required) = config.quorum {
+        indexer = indexer.with_quorum(required);
     }
-
-    if let Some(http_config) = config.providers.get("http") {
-        let http_provider = HttpRpcProvider::new(&http_config.url, update_channel.clone());
-        provider_registry.register_provider(Box::new(http_provider));
+    if config.latency_report || report_after.is_some() {
+        indexer = indexer.with_latency_tracking();
     }
+    let indexer = Arc::new(indexer);
+
+    // Restore every IDL version uploaded in previous runs
+    indexer.load_idl_versions().await?;
+
+    // Register RPC providers; config.json is watched and reapplied on change or SIGHUP
+    let reloader = Arc::new(ConfigReloader::new("config.json", config.clone(), indexer.clone(), Box::new(build_provider)));
+    reloader.register_providers().await?;
 
     // Load plugins
-    provider_registry.load_plugins(&config).await?;
+    provider_registry.load_plugins(&config)?;
 
     // Add tracked accounts and programs
     for account in &config.tracked_accounts {
@@ -48,15 +74,27 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Load IDLs and track programs
     for program in &config.tracked_programs {
-        let pubkey = Pubkey::from_str(&program.address)?;
-        indexer.track_program(pubkey).await?;
-
+        // The IDL goes first so accounts bootstrapped by `start` are decoded
         let idl_json = std::fs::read_to_string(&program.idl_path)?;
         indexer.add_program_idl(&program.address, &idl_json).await?;
+
+        let pubkey = Pubkey::from_str(&program.address)?;
+        indexer.track_program(pubkey).await?;
     }
 
     // Start the indexer
     indexer.start().await?;
+    reloader.watch();
+
+    if let Some(duration) = report_after {
+        println!("Comparing providers for {}s. Press Ctrl+C to stop early.", duration.as_secs());
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            result = signal::ctrl_c() => result?,
+        }
+        print_latency_report(&indexer.latency_report());
+        return Ok(());
+    }
 
     println!("SolanaVista indexer is running. Press Ctrl+C to stop.");
 
@@ -64,19 +102,183 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     signal::ctrl_c().await?;
 
     println!("Shutting down indexer...");
+    if config.latency_report {
+        print_latency_report(&indexer.latency_report());
+    }
 
     Ok(())
 }
 
-fn handle_error(error: IndexerError) {
-    eprintln!("An error occurred: {}", error);
-    // Implement any error handling logic here
+fn print_latency_report(report: &[ProviderLatency]) {
+    println!("{:<20} {:>10} {:>10} {:>10} {:>9} {:>10} {:>10}", "provider", "updates", "first", "missed", "missing", "p50 ms", "p99 ms");
+    for provider in report {
+        println!(
+            "{:<20} {:>10} {:>10} {:>10} {:>8.2}% {:>10.1} {:>10.1}",
+            provider.provider,
+            provider.updates,
+            provider.first_seen,
+            provider.missed,
+            provider.missing_rate * 100.0,
+            provider.p50_delay.as_secs_f64() * 1000.0,
+            provider.p99_delay.as_secs_f64() * 1000.0,
+        );
+    }
 }
 
-fn load_storage_plugin(config: &StorageConfig) -> Result<Box<dyn StoragePlugin>, IndexerError> {
-    let library = unsafe { Library::new(&config.plugin)? };
-    let create_fn: Symbol<fn() -> Box<dyn StoragePlugin>> = unsafe {
-        library.get(b"create_storage_plugin")?
+// Built by provider_type and registered under the config key, so several
+// providers of the same type can run side by side
+fn build_provider(name: &str, config: &ProviderConfig) -> Result<Option<Box<dyn RpcProvider>>, IndexerError> {
+    let provider: Box<dyn RpcProvider> = match config.provider_type.as_str() {
+        "geyser" => Box::new(GeyserRpcProvider::new(&config.url).named(name)),
+        "websocket" => Box::new(WebSocketRpcProvider::new(&config.url).named(name)),
+        "grpc" => {
+            let mut yellowstone_config = YellowstoneGrpcConfig::new(&config.url);
+            yellowstone_config.x_token = config.x_token.clone();
+            yellowstone_config.from_slot = config.from_slot;
+            if let Some(commitment) = commitment(config)? {
+                yellowstone_config.commitment = commitment;
+            }
+            Box::new(YellowstoneGrpcProvider::new(yellowstone_config).named(name))
+        }
+        "http" => Box::new(HttpRpcProvider::new(&config.url).named(name)),
+        // Whole blocks from the RPC at url, streamed from ws_url when set
+        "block" => {
+            let source = match &config.ws_url {
+                Some(ws_url) => BlockSource::Subscribe(ws_url.clone()),
+                None => BlockSource::Poll,
+            };
+            let mut block_config = BlockProviderConfig::new(source);
+            block_config.start_slot = config.from_slot;
+            if let Some(commitment) = commitment(config)? {
+                block_config.commitment = commitment;
+            }
+            Box::new(BlockProvider::new(&config.url, block_config).named(name))
+        }
+        "helius" => {
+            let mut filters = HeliusFilterConfig::default();
+            if let Some(commitment) = commitment(config)? {
+                filters.commitment = commitment;
+            }
+            Box::new(HeliusTransactionProvider::with_filters(&config.url, filters).named(name))
+        }
+        // url lists the brokers
+        #[cfg(feature = "kafka")]
+        "kafka" => {
+            let (format, topics) = bus_topics(name, config)?;
+            let mut kafka_config = KafkaConfig::new(&config.url, config.consumer_group.as_deref().unwrap_or("vista"), format);
+            kafka_config.topics = topics;
+            if let Some(commitment) = commitment(config)? {
+                kafka_config.commitment = commitment;
+            }
+            Box::new(KafkaProvider::new(kafka_config).named(name))
+        }
+        #[cfg(feature = "nats")]
+        "nats" => {
+            let (format, subjects) = bus_topics(name, config)?;
+            let stream = config.stream.as_deref()
+                .ok_or_else(|| IndexerError::ConfigError(format!("Provider {} has no stream", name)))?;
+            let mut nats_config = NatsConfig::new(&config.url, stream, config.consumer_group.as_deref().unwrap_or("vista"), format);
+            nats_config.subjects = subjects;
+            if let Some(commitment) = commitment(config)? {
+                nats_config.commitment = commitment;
+            }
+            Box::new(NatsProvider::new(nats_config).named(name))
+        }
+        // url is the address the ShredStream proxy forwards shreds to
+        #[cfg(feature = "shredstream")]
+        "shredstream" => Box::new(ShredStreamProvider::new(ShredSource::Udp(config.url.clone())).named(name)),
+        #[cfg(not(feature = "kafka"))]
+        "kafka" => return Err(not_built(name, "kafka")),
+        #[cfg(not(feature = "nats"))]
+        "nats" => return Err(not_built(name, "nats")),
+        #[cfg(not(feature = "shredstream"))]
+        "shredstream" => return Err(not_built(name, "shredstream")),
+        // The webhook provider's url is the address its receiver binds to
+        "webhook" => {
+            let mut receiver_config = WebhookConfig::new(&config.url);
+            receiver_config.helius_auth_header = config.helius_auth_header.clone();
+            receiver_config.quicknode_secret = config.quicknode_secret.clone();
+            Box::new(WebhookProvider::new(receiver_config).named(name))
+        }
+        // Replays a recording made through `record_path`; its url is the file
+        "replay" => return Ok(Some(Box::new(ReplayProvider::new(&config.url, ReplaySpeed::Original).named(name)))),
+        _ => return Ok(None),
     };
-    Ok(create_fn())
-}
\ No newline at end of file
+    Ok(Some(recorded(provider, config)))
+}
+
+fn commitment(config: &ProviderConfig) -> Result<Option<CommitmentLevel>, IndexerError> {
+    config.commitment.as_deref()
+        .map(CommitmentLevel::from_str)
+        .transpose()
+        .map_err(|e| IndexerError::ConfigError(e.to_string()))
+}
+
+#[cfg(any(feature = "kafka", feature = "nats"))]
+fn bus_topics(name: &str, config: &ProviderConfig) -> Result<(MessageFormat, Vec<(String, TopicKind)>), IndexerError> {
+    let format = match config.message_format.as_deref() {
+        Some("yellowstone") | None => MessageFormat::Yellowstone,
+        Some("accountsdb") => MessageFormat::AccountsDbPlugin,
+        Some(other) => return Err(IndexerError::ConfigError(format!("Provider {} has unknown message format {}", name, other))),
+    };
+    if config.topics.is_empty() {
+        return Err(IndexerError::ConfigError(format!("Provider {} has no topics", name)));
+    }
+    let mut topics = Vec::new();
+    for (topic, kind) in &config.topics {
+        let kind = match kind.as_str() {
+            "accounts" => TopicKind::Accounts,
+            "transactions" => TopicKind::Transactions,
+            "slots" => TopicKind::Slots,
+            other => return Err(IndexerError::ConfigError(format!("Provider {} topic {} has unknown kind {}", name, topic, other))),
+        };
+        topics.push((topic.clone(), kind));
+    }
+    Ok((format, topics))
+}
+
+#[cfg(any(not(feature = "kafka"), not(feature = "nats"), not(feature = "shredstream")))]
+fn not_built(name: &str, feature: &str) -> IndexerError {
+    IndexerError::ConfigError(format!("Provider {} needs the {} feature", name, feature))
+}
+
+fn recorded(provider: Box<dyn RpcProvider>, config: &ProviderConfig) -> Box<dyn RpcProvider> {
+    match &config.record_path {
+        Some(path) => Box::new(RecordingProvider::new(provider, path)),
+        None => provider,
+    }
+}
+
+fn load_storage_plugin(config: &StorageConfig) -> Result<Box<dyn StoragePlugin>, IndexerError> {
+    let library = unsafe { Library::new(&config.plugin) }
+        .map_err(|e| IndexerError::ConfigError(format!("{}: {}", config.plugin, e)))?;
+    let plugin = unsafe { plugin_abi::load_storage_plugin(&library) }
+        .map_err(|e| IndexerError::ConfigError(format!("{}: {}", config.plugin, e)))?;
+    // The plugin's code lives in the library, which must outlive it
+    std::mem::forget(library);
+    Ok(plugin)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider_config(provider_type: &str) -> ProviderConfig {
+        serde_json::from_value(serde_json::json!({
+            "url": "http://localhost:8899",
+            "provider_type": provider_type,
+            "priority": 0,
+        })).unwrap()
+    }
+
+    #[test]
+    fn providers_are_built_by_type_and_named_after_their_key() {
+        let registry = RpcProviderRegistry::new();
+        for name in ["helius-rpc", "triton-rpc"] {
+            let provider = build_provider(name, &provider_config("http")).unwrap().unwrap();
+            assert_eq!(provider.name(), name);
+            registry.register_provider(provider);
+        }
+        assert_eq!(registry.get_providers().len(), 2);
+        assert!(build_provider("http", &provider_config("carrier-pigeon")).unwrap().is_none());
+    }
+}
@@ -0,0 +1,1043 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status, Streaming};
+use vista_core::models::{AccountInfo, BlockMetaInfo, IdlVersionInfo, SlotRepairInfo, SlotStatus, TransactionInfo};
+use vista_core::traits::StoragePlugin;
+use vista_core::{IndexerError, UpdateEvent};
+use vista_ingestion::providers::{YellowstoneGrpcConfig, YellowstoneGrpcProvider};
+use vista_ingestion::traits::RpcProvider;
+use yellowstone_grpc_proto::geyser::geyser_server::{Geyser, GeyserServer};
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+use yellowstone_grpc_proto::geyser::{
+    CommitmentLevel, GetBlockHeightRequest, GetBlockHeightResponse, GetLatestBlockhashRequest,
+    GetLatestBlockhashResponse, GetSlotRequest, GetSlotResponse, GetVersionRequest, GetVersionResponse,
+    IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest, PongResponse, SubscribeRequest,
+    SubscribeUpdate, SubscribeUpdateAccount, SubscribeUpdateAccountInfo, SubscribeUpdateSlot,
+};
+
+// Replays a fixed set of updates to every subscriber and reports the requests it receives
+struct MockGeyser {
+    updates: Vec<SubscribeUpdate>,
+    requests: mpsc::Sender<SubscribeRequest>,
+}
+
+#[tonic::async_trait]
+impl Geyser for MockGeyser {
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<SubscribeUpdate, Status>> + Send>>;
+
+    async fn subscribe(&self, request: Request<Streaming<SubscribeRequest>>) -> Result<Response<Self::SubscribeStream>, Status> {
+        let mut incoming = request.into_inner();
+        let requests = self.requests.clone();
+        tokio::spawn(async move {
+            while let Ok(Some(request)) = incoming.message().await {
+                let _ = requests.send(request).await;
+            }
+        });
+
+        let (tx, rx) = mpsc::channel(16);
+        for update in self.updates.clone() {
+            tx.send(Ok(update)).await.unwrap();
+        }
+        // Keep the stream open so the provider doesn't reconnect mid-test
+        tokio::spawn(async move {
+            tx.closed().await;
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn ping(&self, _request: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+        Err(Status::unimplemented("ping"))
+    }
+
+    async fn get_latest_blockhash(&self, _request: Request<GetLatestBlockhashRequest>) -> Result<Response<GetLatestBlockhashResponse>, Status> {
+        Err(Status::unimplemented("get_latest_blockhash"))
+    }
+
+    async fn get_block_height(&self, _request: Request<GetBlockHeightRequest>) -> Result<Response<GetBlockHeightResponse>, Status> {
+        Err(Status::unimplemented("get_block_height"))
+    }
+
+    async fn get_slot(&self, _request: Request<GetSlotRequest>) -> Result<Response<GetSlotResponse>, Status> {
+        Err(Status::unimplemented("get_slot"))
+    }
+
+    async fn is_blockhash_valid(&self, _request: Request<IsBlockhashValidRequest>) -> Result<Response<IsBlockhashValidResponse>, Status> {
+        Err(Status::unimplemented("is_blockhash_valid"))
+    }
+
+    async fn get_version(&self, _request: Request<GetVersionRequest>) -> Result<Response<GetVersionResponse>, Status> {
+        Err(Status::unimplemented("get_version"))
+    }
+}
+
+async fn spawn_mock_geyser(updates: Vec<SubscribeUpdate>) -> (String, mpsc::Receiver<SubscribeRequest>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (requests_tx, requests_rx) = mpsc::channel(16);
+    let service = GeyserServer::new(MockGeyser { updates, requests: requests_tx });
+    tokio::spawn(async move {
+        tonic::transport::Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+    (format!("http://{}", addr), requests_rx)
+}
+
+#[tokio::test]
+async fn yellowstone_provider_streams_account_and_slot_updates() {
+    let account = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let updates = vec![
+        SubscribeUpdate {
+            filters: vec!["tracked_accounts".to_string()],
+            update_oneof: Some(UpdateOneof::Account(SubscribeUpdateAccount {
+                account: Some(SubscribeUpdateAccountInfo {
+                    pubkey: account.to_bytes().to_vec(),
+                    lamports: 42,
+                    owner: owner.to_bytes().to_vec(),
+                    data: vec![1, 2, 3],
+                    ..Default::default()
+                }),
+                slot: 100,
+                is_startup: false,
+            })),
+        },
+        SubscribeUpdate {
+            filters: vec!["slots".to_string()],
+            update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot: 101,
+                parent: Some(100),
+                status: CommitmentLevel::Confirmed as i32,
+            })),
+        },
+    ];
+    let (endpoint, mut requests) = spawn_mock_geyser(updates).await;
+
+    let mut config = YellowstoneGrpcConfig::new(&endpoint);
+    config.from_slot = Some(90);
+    let provider = YellowstoneGrpcProvider::new(config);
+    provider.subscribe_account(&account).await.unwrap();
+    let mut updates = provider.start().await.unwrap().map(|update| update.event);
+
+    let request = tokio::time::timeout(Duration::from_secs(5), requests.recv()).await.unwrap().unwrap();
+    assert_eq!(request.commitment, Some(CommitmentLevel::Confirmed as i32));
+    assert_eq!(request.accounts["tracked_accounts"].account, vec![account.to_string()]);
+
+    match tokio::time::timeout(Duration::from_secs(5), updates.next()).await.unwrap() {
+        Some(UpdateEvent::AccountUpdate(info)) => {
+            assert_eq!(info.pubkey, account);
+            assert_eq!(info.owner, owner);
+            assert_eq!(info.lamports, 42);
+            assert_eq!(info.data, vec![1, 2, 3]);
+            assert_eq!(info.slot, 100);
+        }
+        _ => panic!("expected an account update"),
+    }
+    match tokio::time::timeout(Duration::from_secs(5), updates.next()).await.unwrap() {
+        Some(UpdateEvent::SlotUpdate(info)) => {
+            assert_eq!(info.slot, 101);
+            assert_eq!(info.parent, Some(100));
+            assert_eq!(info.status, SlotStatus::Confirmed);
+        }
+        _ => panic!("expected a slot update"),
+    }
+
+    // Tracking a program while connected pushes the new filters on the live stream
+    provider.subscribe_program(&owner).await.unwrap();
+    let request = loop {
+        let request = tokio::time::timeout(Duration::from_secs(5), requests.recv()).await.unwrap().unwrap();
+        if request.ping.is_none() {
+            break request;
+        }
+    };
+    assert_eq!(request.accounts["tracked_programs"].owner, vec![owner.to_string()]);
+}
+
+// The mock decodes the upstream `SubscribeRequest`, which drops `from_slot`
+#[test]
+fn yellowstone_request_carries_from_slot_on_the_wire() {
+    use prost::Message as _;
+    use vista_ingestion::providers::grpc::ReplaySubscribeRequest;
+
+    let request = ReplaySubscribeRequest {
+        request: SubscribeRequest { commitment: Some(CommitmentLevel::Confirmed as i32), ..Default::default() },
+        from_slot: Some(90),
+    };
+    let bytes = request.encode_to_vec();
+
+    let decoded = ReplaySubscribeRequest::decode(bytes.as_slice()).unwrap();
+    assert_eq!(decoded.from_slot, Some(90));
+    assert_eq!(decoded.request.commitment, Some(CommitmentLevel::Confirmed as i32));
+    // Servers without replay still read the rest of the request
+    let upstream = SubscribeRequest::decode(bytes.as_slice()).unwrap();
+    assert_eq!(upstream.commitment, Some(CommitmentLevel::Confirmed as i32));
+
+    let live = ReplaySubscribeRequest { from_slot: None, ..request };
+    assert_eq!(ReplaySubscribeRequest::decode(live.encode_to_vec().as_slice()).unwrap().from_slot, None);
+}
+
+// Needs the brokers from docker-compose.yml:
+//   cargo test --features kafka -- --ignored
+#[cfg(feature = "kafka")]
+#[tokio::test]
+#[ignore]
+async fn kafka_provider_consumes_yellowstone_topic_and_waits_for_checkpoint() {
+    use prost::Message as _;
+    use rdkafka::producer::{FutureProducer, FutureRecord};
+    use rdkafka::ClientConfig;
+    use vista_ingestion::providers::{KafkaConfig, KafkaProvider, MessageFormat, TopicKind};
+
+    let topic = format!("vista-test-{}", Pubkey::new_unique());
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", "localhost:9092")
+        .create()
+        .unwrap();
+    let update = SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot {
+            slot: 7,
+            parent: Some(6),
+            status: CommitmentLevel::Finalized as i32,
+        })),
+        ..Default::default()
+    };
+    producer.send(FutureRecord::<(), _>::to(&topic).payload(&update.encode_to_vec()), Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    let mut config = KafkaConfig::new("localhost:9092", "vista-test", MessageFormat::Yellowstone)
+        .topic(&topic, TopicKind::Slots);
+    config.commit_every = 1;
+    let provider = KafkaProvider::new(config);
+    let mut updates = provider.start().await.unwrap().map(|update| update.event);
+
+    match tokio::time::timeout(Duration::from_secs(30), updates.next()).await.unwrap() {
+        Some(UpdateEvent::SlotUpdate(info)) => {
+            assert_eq!(info.slot, 7);
+            assert_eq!(info.status, SlotStatus::Finalized);
+        }
+        _ => panic!("expected a slot update"),
+    }
+    // The offset is only committed once the indexer confirms it caught up
+    match tokio::time::timeout(Duration::from_secs(5), updates.next()).await.unwrap() {
+        Some(UpdateEvent::Checkpoint(done)) => done.send(()).unwrap(),
+        _ => panic!("expected a checkpoint"),
+    }
+}
+
+// Needs the brokers from docker-compose.yml:
+//   cargo test --features nats -- --ignored
+#[cfg(feature = "nats")]
+#[tokio::test]
+#[ignore]
+async fn nats_provider_consumes_yellowstone_subject_and_survives_a_lost_consumer() {
+    use async_nats::jetstream::{self, stream};
+    use prost::Message as _;
+    use vista_ingestion::providers::{MessageFormat, NatsConfig, NatsProvider, TopicKind};
+
+    let name = format!("vista-test-{}", Pubkey::new_unique());
+    let subject = format!("{}.slots", name);
+    let context = jetstream::new(async_nats::connect("localhost:4222").await.unwrap());
+    let stream = context.create_stream(stream::Config {
+        name: name.clone(),
+        subjects: vec![subject.clone()],
+        ..Default::default()
+    }).await.unwrap();
+    let publish = |slot: u64| {
+        let update = SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot,
+                parent: Some(slot - 1),
+                status: CommitmentLevel::Finalized as i32,
+            })),
+            ..Default::default()
+        };
+        let (context, subject) = (context.clone(), subject.clone());
+        async move { context.publish(subject, update.encode_to_vec().into()).await.unwrap().await.unwrap() }
+    };
+    publish(7).await;
+
+    let mut config = NatsConfig::new("localhost:4222", &name, "vista-test", MessageFormat::Yellowstone)
+        .subject(&subject, TopicKind::Slots);
+    config.ack_every = 1;
+    let provider = NatsProvider::new(config);
+    let mut updates = provider.start().await.unwrap().map(|update| update.event);
+
+    match tokio::time::timeout(Duration::from_secs(30), updates.next()).await.unwrap() {
+        Some(UpdateEvent::SlotUpdate(info)) => {
+            assert_eq!(info.slot, 7);
+            assert_eq!(info.status, SlotStatus::Finalized);
+        }
+        _ => panic!("expected a slot update"),
+    }
+    // The message is only acknowledged once the indexer confirms it caught up
+    match tokio::time::timeout(Duration::from_secs(5), updates.next()).await.unwrap() {
+        Some(UpdateEvent::Checkpoint(done)) => done.send(()).unwrap(),
+        _ => panic!("expected a checkpoint"),
+    }
+
+    // Losing the durable ends its message stream; the provider recreates it
+    // instead of going quiet. The recreated durable may deliver slot 7 again.
+    let durable = format!("vista-test-{}", subject.replace('.', "_"));
+    stream.delete_consumer(&durable).await.unwrap();
+    publish(8).await;
+    let resumed = tokio::time::timeout(Duration::from_secs(60), async {
+        while let Some(event) = updates.next().await {
+            match event {
+                UpdateEvent::SlotUpdate(info) if info.slot == 8 => return true,
+                UpdateEvent::Checkpoint(done) => { let _ = done.send(()); }
+                _ => {}
+            }
+        }
+        false
+    }).await.unwrap();
+    assert!(resumed);
+    context.delete_stream(&name).await.unwrap();
+}
+
+fn recording_path(name: &str) -> std::path::PathBuf {
+    // `new_unique` restarts in every process, so a file left by an aborted run would be reused
+    std::env::temp_dir().join(format!("vista-{}-{}-{}.rec", name, std::process::id(), Pubkey::new_unique()))
+}
+
+fn slot_update(slot: u64) -> UpdateEvent {
+    UpdateEvent::SlotUpdate(vista_core::models::SlotInfo { slot, parent: Some(slot - 1), status: SlotStatus::Confirmed })
+}
+
+#[tokio::test]
+async fn replay_provider_plays_back_recording_in_order() {
+    use vista_ingestion::providers::replay::RecordWriter;
+    use vista_ingestion::providers::{ReplayProvider, ReplaySpeed};
+
+    let path = recording_path("replay");
+    let mut writer = RecordWriter::open(&path).unwrap();
+    for slot in 1..=3 {
+        writer.write(slot * 1_000, &slot_update(slot)).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let provider = ReplayProvider::new(&path, ReplaySpeed::Max);
+    let mut updates = provider.start().await.unwrap().map(|update| update.event);
+    for expected in 1..=3 {
+        match tokio::time::timeout(Duration::from_secs(5), updates.next()).await.unwrap() {
+            Some(UpdateEvent::SlotUpdate(info)) => assert_eq!(info.slot, expected),
+            _ => panic!("expected a slot update"),
+        }
+    }
+    // The stream ends with the recording
+    assert!(tokio::time::timeout(Duration::from_secs(5), updates.next()).await.unwrap().is_none());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn providers_of_the_same_type_are_compared_under_their_own_names() {
+    use vista_ingestion::providers::replay::RecordWriter;
+    use vista_ingestion::providers::{ReplayProvider, ReplaySpeed};
+
+    let path = recording_path("latency");
+    let mut writer = RecordWriter::open(&path).unwrap();
+    for slot in 1..=3 {
+        writer.write(slot * 1_000, &slot_update(slot)).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let registry = Arc::new(vista_core::RpcProviderRegistry::new());
+    for name in ["vendor-a", "vendor-b"] {
+        registry.register_provider(Box::new(ReplayProvider::new(&path, ReplaySpeed::Max).named(name)) as Box<dyn RpcProvider>);
+    }
+    let indexer = Arc::new(vista_core::Indexer::new(Arc::new(MemoryStorage::default()), registry).with_latency_tracking());
+    indexer.start().await.unwrap();
+
+    let mut report = Vec::new();
+    for _ in 0..50 {
+        report = indexer.latency_report();
+        if report.len() == 2 && report.iter().all(|provider| provider.updates == 3) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let names: Vec<_> = report.iter().map(|provider| (provider.provider.as_str(), provider.updates)).collect();
+    assert_eq!(names, vec![("vendor-a", 3), ("vendor-b", 3)]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn stepped_replay_waits_for_each_step() {
+    use vista_ingestion::providers::replay::RecordWriter;
+    use vista_ingestion::providers::{ReplayProvider, ReplaySpeed};
+
+    let path = recording_path("stepped");
+    let mut writer = RecordWriter::open(&path).unwrap();
+    writer.write(1_000, &slot_update(10)).unwrap();
+    writer.write(2_000, &slot_update(11)).unwrap();
+    writer.flush().unwrap();
+
+    let provider = ReplayProvider::new(&path, ReplaySpeed::Stepped);
+    let stepper = provider.stepper();
+    let mut updates = provider.start().await.unwrap().map(|update| update.event);
+    assert!(tokio::time::timeout(Duration::from_millis(200), updates.next()).await.is_err());
+
+    stepper.step(1);
+    match tokio::time::timeout(Duration::from_secs(5), updates.next()).await.unwrap() {
+        Some(UpdateEvent::SlotUpdate(info)) => assert_eq!(info.slot, 10),
+        _ => panic!("expected a slot update"),
+    }
+    assert!(tokio::time::timeout(Duration::from_millis(200), updates.next()).await.is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn recording_provider_tees_updates_to_file() {
+    use vista_ingestion::providers::replay::{RecordReader, RecordWriter};
+    use vista_ingestion::providers::{RecordingProvider, ReplayProvider, ReplaySpeed};
+
+    let source = recording_path("source");
+    let mut writer = RecordWriter::open(&source).unwrap();
+    writer.write(1_000, &slot_update(20)).unwrap();
+    writer.write(2_000, &slot_update(21)).unwrap();
+    writer.flush().unwrap();
+
+    let copy = recording_path("copy");
+    let provider = RecordingProvider::new(Box::new(ReplayProvider::new(&source, ReplaySpeed::Max)), &copy);
+    let mut updates = provider.start().await.unwrap().map(|update| update.event);
+    let mut passed_through = Vec::new();
+    while let Some(event) = tokio::time::timeout(Duration::from_secs(5), updates.next()).await.unwrap() {
+        if let UpdateEvent::SlotUpdate(info) = event {
+            passed_through.push(info.slot);
+        }
+    }
+    assert_eq!(passed_through, vec![20, 21]);
+
+    // The writer flushes once the stream is dropped and its backlog drained
+    drop(updates);
+    let mut recorded = Vec::new();
+    for _ in 0..50 {
+        // A read can land before the header or mid-record while the writer flushes
+        let records = RecordReader::open(&copy).and_then(|reader| reader.collect::<Result<Vec<_>, _>>());
+        if let Ok(records) = records {
+            recorded = records.into_iter()
+                .filter_map(|record| match record.event {
+                    UpdateEvent::SlotUpdate(info) => Some(info.slot),
+                    _ => None,
+                })
+                .collect();
+            if recorded.len() == 2 {
+                break;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(recorded, vec![20, 21]);
+    std::fs::remove_file(&source).unwrap();
+    std::fs::remove_file(&copy).unwrap();
+}
+
+// Keeps what the indexer stores, for asserting on it
+#[derive(Default)]
+struct MemoryStorage {
+    accounts: Mutex<HashMap<Pubkey, AccountInfo>>,
+    transactions: Mutex<HashMap<Signature, TransactionInfo>>,
+}
+
+#[async_trait]
+impl StoragePlugin for MemoryStorage {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    async fn init(&self, _config: &Value) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn store_account(&self, account: AccountInfo) -> Result<(), IndexerError> {
+        self.accounts.lock().unwrap().insert(account.pubkey, account);
+        Ok(())
+    }
+
+    async fn store_transaction(&self, transaction: TransactionInfo) -> Result<(), IndexerError> {
+        self.transactions.lock().unwrap().insert(transaction.signature, transaction);
+        Ok(())
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<AccountInfo>, IndexerError> {
+        Ok(self.accounts.lock().unwrap().get(pubkey).cloned())
+    }
+
+    async fn get_transaction(&self, signature: &Signature) -> Result<Option<TransactionInfo>, IndexerError> {
+        Ok(self.transactions.lock().unwrap().get(signature).cloned())
+    }
+
+    async fn get_failed_transactions(&self, program_id: &Pubkey, _error_name: Option<&str>) -> Result<Vec<TransactionInfo>, IndexerError> {
+        Ok(self.transactions.lock().unwrap().values()
+            .filter(|transaction| transaction.error.as_ref().is_some_and(|error| error.program_id == *program_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn store_parsed_account(&self, _pubkey: &Pubkey, _program_id: &str, _account_type: &str, _slot: u64, _data: &Value) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn store_block_meta(&self, _block_meta: BlockMetaInfo) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn store_idl_version(&self, _version: IdlVersionInfo) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn get_idl_versions(&self) -> Result<Vec<IdlVersionInfo>, IndexerError> {
+        Ok(Vec::new())
+    }
+
+    async fn store_slot_repair(&self, _repair: SlotRepairInfo) -> Result<(), IndexerError> {
+        Ok(())
+    }
+}
+
+fn reload_config(accounts: &[&str], providers: &[(&str, &str)]) -> vista_core::Config {
+    serde_json::from_value(serde_json::json!({
+        "providers": providers.iter().map(|(name, url)| (name.to_string(), serde_json::json!({
+            "url": url,
+            "provider_type": name,
+            "priority": 0,
+        }))).collect::<serde_json::Map<_, _>>(),
+        "plugin_dir": null,
+        "tracked_accounts": accounts,
+        "tracked_programs": [],
+        "storage": { "plugin": "libvista_storage_postgres.so", "config": {} },
+    })).unwrap()
+}
+
+#[test]
+fn config_diff_groups_changes_for_reload() {
+    let kept = Pubkey::new_unique().to_string();
+    let removed = Pubkey::new_unique().to_string();
+    let added = Pubkey::new_unique().to_string();
+    let current = reload_config(&[&kept, &removed], &[("http", "http://a"), ("websocket", "ws://a")]);
+    let new = reload_config(&[&kept, &added], &[("http", "http://b"), ("grpc", "http://grpc")]);
+
+    let diff = current.diff(&new);
+    assert_eq!(diff.added_accounts, vec![added]);
+    assert_eq!(diff.removed_accounts, vec![removed]);
+    assert_eq!(diff.added_providers, vec!["grpc".to_string()]);
+    assert_eq!(diff.removed_providers, vec!["websocket".to_string()]);
+    assert_eq!(diff.changed_providers, vec!["http".to_string()]);
+    assert!(diff.requires_restart.is_empty());
+}
+
+#[test]
+fn config_validation_rejects_bad_pubkeys() {
+    let config = reload_config(&["not-a-pubkey"], &[]);
+    assert!(config.validate().is_err());
+    assert!(reload_config(&[&Pubkey::new_unique().to_string()], &[]).validate().is_ok());
+}
+
+// Registers as "flaky"; fails to start when its url is "fail"
+struct FlakyProvider {
+    url: String,
+    started: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl RpcProvider for FlakyProvider {
+    fn name(&self) -> &str {
+        "flaky"
+    }
+
+    fn provider_type(&self) -> vista_core::traits::RpcProviderType {
+        vista_core::traits::RpcProviderType::Http
+    }
+
+    fn capabilities(&self) -> vista_core::traits::ProviderCapabilities {
+        Default::default()
+    }
+
+    async fn subscribe_account(&self, _pubkey: &Pubkey) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn subscribe_program(&self, _program_id: &Pubkey) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<vista_core::traits::UpdateStream, IndexerError> {
+        if self.url == "fail" {
+            return Err(IndexerError::RpcError("refused".to_string()));
+        }
+        self.started.lock().unwrap().push(self.url.clone());
+        Ok(Box::pin(futures::stream::pending()))
+    }
+}
+
+#[tokio::test]
+async fn reload_keeps_the_running_provider_when_its_replacement_fails() {
+    let path = std::env::temp_dir().join(format!("vista-reload-{}-{}.json", std::process::id(), Pubkey::new_unique()));
+    let write = |config: &vista_core::Config| std::fs::write(&path, serde_json::to_string(config).unwrap()).unwrap();
+    let initial = reload_config(&[], &[("flaky", "a")]);
+    write(&initial);
+
+    let started = Arc::new(Mutex::new(Vec::new()));
+    let factory_started = started.clone();
+    let factory: vista_core::reload::ProviderFactory = Box::new(move |_, config| {
+        Ok(Some(Box::new(FlakyProvider { url: config.url.clone(), started: factory_started.clone() })))
+    });
+    let registry = Arc::new(vista_core::RpcProviderRegistry::new());
+    let indexer = Arc::new(vista_core::Indexer::new(Arc::new(MemoryStorage::default()), registry.clone()));
+    let reloader = vista_core::ConfigReloader::new(&path, initial, indexer.clone(), factory);
+    reloader.register_providers().await.unwrap();
+    indexer.start().await.unwrap();
+    assert_eq!(*started.lock().unwrap(), vec!["a"]);
+
+    // The replacement can't start, so the previous provider is rebuilt
+    write(&reload_config(&[], &[("flaky", "fail")]));
+    reloader.reload().await.unwrap();
+    assert_eq!(*started.lock().unwrap(), vec!["a", "a"]);
+    assert!(registry.get_provider("flaky").is_some());
+
+    // Still recorded as running "a", so reloading the same file retries it
+    let diff = reloader.reload().await.unwrap();
+    assert_eq!(diff.changed_providers, vec!["flaky".to_string()]);
+    assert_eq!(*started.lock().unwrap(), vec!["a", "a", "a"]);
+
+    write(&reload_config(&[], &[("flaky", "b")]));
+    reloader.reload().await.unwrap();
+    assert_eq!(*started.lock().unwrap(), vec!["a", "a", "a", "b"]);
+    assert!(registry.get_provider("flaky").is_some());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+// Streams account state; refuses to subscribe the accounts in `refused`
+struct PickyProvider {
+    refused: Arc<Mutex<Vec<Pubkey>>>,
+}
+
+#[async_trait]
+impl RpcProvider for PickyProvider {
+    fn name(&self) -> &str {
+        "picky"
+    }
+
+    fn provider_type(&self) -> vista_core::traits::RpcProviderType {
+        vista_core::traits::RpcProviderType::Http
+    }
+
+    fn capabilities(&self) -> vista_core::traits::ProviderCapabilities {
+        vista_core::traits::ProviderCapabilities { accounts: true, ..Default::default() }
+    }
+
+    async fn subscribe_account(&self, pubkey: &Pubkey) -> Result<(), IndexerError> {
+        if self.refused.lock().unwrap().contains(pubkey) {
+            return Err(IndexerError::RpcError(format!("refused {}", pubkey)));
+        }
+        Ok(())
+    }
+
+    async fn subscribe_program(&self, _program_id: &Pubkey) -> Result<(), IndexerError> {
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<vista_core::traits::UpdateStream, IndexerError> {
+        Ok(Box::pin(futures::stream::pending()))
+    }
+}
+
+#[tokio::test]
+async fn reload_applies_the_rest_when_an_account_fails_to_track_and_retries_it() {
+    let path = std::env::temp_dir().join(format!("vista-reload-{}-{}.json", std::process::id(), Pubkey::new_unique()));
+    let write = |config: &vista_core::Config| std::fs::write(&path, serde_json::to_string(config).unwrap()).unwrap();
+    let initial = reload_config(&[], &[("picky", "a")]);
+    write(&initial);
+
+    let (accepted, refused) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let refusals = Arc::new(Mutex::new(vec![refused]));
+    let factory_refusals = refusals.clone();
+    let factory: vista_core::reload::ProviderFactory = Box::new(move |_, _| {
+        Ok(Some(Box::new(PickyProvider { refused: factory_refusals.clone() })))
+    });
+    let indexer = Arc::new(vista_core::Indexer::new(Arc::new(MemoryStorage::default()), Arc::new(vista_core::RpcProviderRegistry::new())));
+    let reloader = vista_core::ConfigReloader::new(&path, initial, indexer.clone(), factory);
+    reloader.register_providers().await.unwrap();
+    indexer.start().await.unwrap();
+
+    // The refused account is backed out; the other one is still tracked
+    write(&reload_config(&[&refused.to_string(), &accepted.to_string()], &[("picky", "a")]));
+    let diff = reloader.reload().await.unwrap();
+    assert_eq!(diff.added_accounts, vec![accepted.to_string()]);
+    assert_eq!(indexer.tracked_accounts().await, vec![accepted]);
+
+    // Recorded as not tracked, so reloading the same file retries it
+    refusals.lock().unwrap().clear();
+    let diff = reloader.reload().await.unwrap();
+    assert_eq!(diff.added_accounts, vec![refused.to_string()]);
+    let mut tracked = indexer.tracked_accounts().await;
+    tracked.sort();
+    let mut expected = vec![accepted, refused];
+    expected.sort();
+    assert_eq!(tracked, expected);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+fn failed_transaction(instruction: u8, code: u32, program_ids: Vec<Pubkey>, invoked_program_ids: Vec<Pubkey>) -> TransactionInfo {
+    let err = serde_json::json!({ "InstructionError": [instruction, { "Custom": code }] });
+    TransactionInfo {
+        signature: Signature::new_unique(),
+        status: serde_json::from_value(serde_json::json!({
+            "slot": 10,
+            "confirmations": null,
+            "status": { "Err": err },
+            "err": err,
+            "confirmationStatus": "confirmed",
+        })).unwrap(),
+        program_ids,
+        invoked_program_ids,
+        error: None,
+        pending: false,
+    }
+}
+
+#[tokio::test]
+async fn program_errors_are_attributed_to_the_failing_instruction() {
+    let errors = |name: &str| serde_json::json!({
+        "version": "0.1.0",
+        "name": name,
+        "instructions": [],
+        "errors": [{ "code": 6000, "name": format!("{}Error", name), "msg": name }],
+    }).to_string();
+    let (router, pool, oracle) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let storage = Arc::new(MemoryStorage::default());
+    let indexer = Arc::new(vista_core::Indexer::new(storage.clone(), Arc::new(vista_core::RpcProviderRegistry::new())));
+    indexer.add_program_idl(&router.to_string(), &errors("Router")).await.unwrap();
+    indexer.add_program_idl(&pool.to_string(), &errors("Pool")).await.unwrap();
+    indexer.start().await.unwrap();
+
+    // The router runs twice before the pool, and both reach other programs
+    // through CPI, which must not shift the instruction indexes
+    let program_ids = vec![router, router, pool];
+    let invoked = vec![oracle, router];
+    let in_pool = failed_transaction(2, 6000, program_ids.clone(), invoked.clone());
+    let in_router = failed_transaction(1, 3012, program_ids.clone(), invoked.clone());
+    let unknown = failed_transaction(0, 6001, program_ids, invoked);
+    let updates = indexer.get_update_channel();
+    for transaction in [&in_pool, &in_router, &unknown] {
+        updates.send(UpdateEvent::TransactionUpdate(transaction.clone())).await.unwrap();
+    }
+    let (done, processed) = tokio::sync::oneshot::channel();
+    updates.send(UpdateEvent::Checkpoint(done)).await.unwrap();
+    processed.await.unwrap();
+
+    let error = |signature| storage.transactions.lock().unwrap()[signature].error.clone().unwrap();
+    let pool_error = error(&in_pool.signature);
+    assert_eq!((pool_error.instruction_index, pool_error.program_id, pool_error.code), (2, pool, 6000));
+    assert_eq!(pool_error.name.as_deref(), Some("PoolError"));
+    assert_eq!(pool_error.message.as_deref(), Some("Pool"));
+
+    let router_error = error(&in_router.signature);
+    assert_eq!((router_error.instruction_index, router_error.program_id), (1, router));
+    assert_eq!(router_error.name.as_deref(), Some("AccountNotInitialized"));
+
+    // Codes the IDL doesn't declare are kept without a name
+    let unknown_error = error(&unknown.signature);
+    assert_eq!((unknown_error.program_id, unknown_error.code, unknown_error.name), (router, 6001, None));
+
+    let failed = storage.get_failed_transactions(&pool, None).await.unwrap();
+    assert_eq!(failed.iter().map(|transaction| transaction.signature).collect::<Vec<_>>(), vec![in_pool.signature]);
+}
+
+#[tokio::test]
+async fn cpi_errors_are_attributed_to_the_callee() {
+    let errors = |name: &str, codes: &[u32]| serde_json::json!({
+        "version": "0.1.0",
+        "name": name,
+        "instructions": [],
+        "errors": codes.iter().map(|code| serde_json::json!({ "code": code, "name": format!("{}Error{}", name, code) })).collect::<Vec<_>>(),
+    }).to_string();
+    let (router, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let storage = Arc::new(MemoryStorage::default());
+    let indexer = Arc::new(vista_core::Indexer::new(storage.clone(), Arc::new(vista_core::RpcProviderRegistry::new())));
+    indexer.add_program_idl(&router.to_string(), &errors("Router", &[6000])).await.unwrap();
+    indexer.add_program_idl(&pool.to_string(), &errors("Pool", &[6000, 6001])).await.unwrap();
+    indexer.start().await.unwrap();
+
+    // Both programs declare 6000, so only the logs, which the provider read
+    // to attribute the error, tell which one failed
+    let mut logged = failed_transaction(0, 6000, vec![router], vec![pool]);
+    logged.error = Some(vista_core::models::ProgramErrorInfo {
+        instruction_index: 0,
+        program_id: pool,
+        code: 6000,
+        name: None,
+        message: None,
+    });
+    // Without logs the caller keeps codes it declares, and the callee gets the rest
+    let in_router = failed_transaction(0, 6000, vec![router], vec![pool]);
+    let in_pool = failed_transaction(0, 6001, vec![router], vec![pool]);
+    let updates = indexer.get_update_channel();
+    for transaction in [&logged, &in_router, &in_pool] {
+        updates.send(UpdateEvent::TransactionUpdate(transaction.clone())).await.unwrap();
+    }
+    let (done, processed) = tokio::sync::oneshot::channel();
+    updates.send(UpdateEvent::Checkpoint(done)).await.unwrap();
+    processed.await.unwrap();
+
+    let error = |signature| storage.transactions.lock().unwrap()[signature].error.clone().unwrap();
+    let logged_error = error(&logged.signature);
+    assert_eq!((logged_error.instruction_index, logged_error.program_id), (0, pool));
+    assert_eq!(logged_error.name.as_deref(), Some("PoolError6000"));
+    let router_error = error(&in_router.signature);
+    assert_eq!((router_error.program_id, router_error.name.as_deref()), (router, Some("RouterError6000")));
+    let pool_error = error(&in_pool.signature);
+    assert_eq!((pool_error.program_id, pool_error.name.as_deref()), (pool, Some("PoolError6001")));
+
+    let failed = storage.get_failed_transactions(&pool, None).await.unwrap();
+    let mut signatures: Vec<_> = failed.iter().map(|transaction| transaction.signature).collect();
+    signatures.sort();
+    let mut expected = vec![logged.signature, in_pool.signature];
+    expected.sort();
+    assert_eq!(signatures, expected);
+}
+
+#[tokio::test]
+async fn pending_transactions_reach_subscribers_without_being_stored() {
+    let storage = Arc::new(MemoryStorage::default());
+    let indexer = Arc::new(vista_core::Indexer::new(storage.clone(), Arc::new(vista_core::RpcProviderRegistry::new())));
+    let mut pending = indexer.subscribe_pending_transactions();
+    indexer.start().await.unwrap();
+
+    let transaction = TransactionInfo {
+        signature: Signature::new_unique(),
+        status: serde_json::from_value(serde_json::json!({
+            "slot": 10,
+            "confirmations": null,
+            "status": { "Ok": null },
+            "err": null,
+            "confirmationStatus": "processed",
+        })).unwrap(),
+        program_ids: vec![Pubkey::new_unique()],
+        invoked_program_ids: Vec::new(),
+        error: None,
+        pending: true,
+    };
+    let updates = indexer.get_update_channel();
+    updates.send(UpdateEvent::TransactionUpdate(transaction.clone())).await.unwrap();
+    let (done, processed) = tokio::sync::oneshot::channel();
+    updates.send(UpdateEvent::Checkpoint(done)).await.unwrap();
+    processed.await.unwrap();
+
+    let received = pending.try_recv().unwrap();
+    assert_eq!((received.signature, received.pending), (transaction.signature, true));
+    assert!(storage.transactions.lock().unwrap().is_empty());
+}
+
+// Runs programs in an in-process bank, no validator or network needed:
+//   cargo test --features local-validator
+#[cfg(feature = "local-validator")]
+mod local_validator {
+    use std::sync::Arc;
+
+    use solana_sdk::account::Account;
+    use solana_sdk::account_info::AccountInfo as ProgramAccount;
+    use solana_sdk::entrypoint::ProgramResult;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::program_error::ProgramError;
+    use solana_sdk::pubkey::Pubkey;
+    use vista_core::traits::StoragePlugin;
+    use vista_core::{Indexer, RpcProviderRegistry};
+    use vista_ingestion::providers::local::{processor, ProgramTest};
+    use vista_ingestion::providers::LocalValidator;
+
+    use super::MemoryStorage;
+
+    // Increments the u64 at the start of its first account; fails with
+    // custom error 7 when given any instruction data
+    fn counter_program(_program_id: &Pubkey, accounts: &[ProgramAccount], data: &[u8]) -> ProgramResult {
+        if !data.is_empty() {
+            return Err(ProgramError::Custom(7));
+        }
+        let mut counter = accounts[0].try_borrow_mut_data()?;
+        let count = u64::from_le_bytes(counter[..8].try_into().unwrap()) + 1;
+        counter[..8].copy_from_slice(&count.to_le_bytes());
+        Ok(())
+    }
+
+    // A validator running the counter program with one counter account, and
+    // an indexer tracking the program through it
+    async fn start_counter() -> (LocalValidator, Arc<Indexer>, Arc<MemoryStorage>, Pubkey, Pubkey) {
+        let program_id = Pubkey::new_unique();
+        let counter = Pubkey::new_unique();
+        let mut program_test = ProgramTest::default();
+        program_test.add_program("counter", program_id, processor!(counter_program));
+        program_test.add_account(counter, Account {
+            lamports: 1_000_000,
+            data: vec![0; 8],
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        });
+        let (validator, provider) = LocalValidator::start(program_test).await.unwrap();
+
+        let storage = Arc::new(MemoryStorage::default());
+        let indexer = Arc::new(Indexer::new(storage.clone(), Arc::new(RpcProviderRegistry::new())));
+        indexer.track_program(program_id).await.unwrap();
+        indexer.start().await.unwrap();
+        indexer.add_provider(Box::new(provider)).await.unwrap();
+        (validator, indexer, storage, program_id, counter)
+    }
+
+    #[tokio::test]
+    async fn local_validator_transactions_are_indexed() {
+        let (validator, _indexer, storage, program_id, counter) = start_counter().await;
+
+        let increment = Instruction::new_with_bytes(program_id, &[], vec![AccountMeta::new(counter, false)]);
+        let sent = validator.send_transaction(&[increment], &[]).await.unwrap();
+        assert!(sent.err.is_none(), "{:?}", sent.logs);
+        validator.settle().await.unwrap();
+
+        let stored = storage.get_account(&counter).await.unwrap().expect("counter account stored");
+        assert_eq!(stored.data, 1u64.to_le_bytes());
+        assert_eq!(stored.slot, sent.slot);
+        let transaction = storage.get_transaction(&sent.signature).await.unwrap().expect("transaction stored");
+        assert_eq!(transaction.program_ids, vec![program_id]);
+        assert!(transaction.status.err.is_none());
+
+        // Accounts of untracked owners aren't streamed
+        let recipient = Pubkey::new_unique();
+        validator.airdrop(&recipient, 1_000_000).await.unwrap();
+        validator.settle().await.unwrap();
+        assert!(storage.get_account(&recipient).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn local_validator_failed_transactions_keep_their_error() {
+        let (validator, indexer, storage, program_id, counter) = start_counter().await;
+
+        let failing = Instruction::new_with_bytes(program_id, &[1], vec![AccountMeta::new(counter, false)]);
+        let sent = validator.send_transaction(&[failing], &[]).await.unwrap();
+        assert!(sent.err.is_some());
+        validator.warp_to_slot(100).await.unwrap();
+        validator.settle().await.unwrap();
+
+        let failed = storage.get_failed_transactions(&program_id, None).await.unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].signature, sent.signature);
+        assert_eq!(failed[0].error.as_ref().unwrap().code, 7);
+        // The failed instruction left the counter untouched
+        assert_eq!(validator.get_account(&counter).await.unwrap().unwrap().data, 0u64.to_le_bytes());
+        assert_eq!(indexer.latest_slot(), 100);
+    }
+}
+
+// Answers JSON-RPC over HTTP, one request per connection, failing the first
+// `failures` getProgramAccounts calls
+async fn spawn_flaky_rpc(failures: usize, program_id: Pubkey, account: Pubkey) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let mut failures = failures;
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 4096];
+            let body = loop {
+                let read = socket.read(&mut chunk).await.unwrap();
+                if read == 0 {
+                    break None;
+                }
+                request.extend_from_slice(&chunk[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text[..end].lines()
+                        .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|value| value.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break Some(serde_json::from_slice::<Value>(&request[end + 4..end + 4 + length]).unwrap());
+                    }
+                }
+            };
+            let Some(body) = body else { continue };
+            let response = match body["method"].as_str().unwrap() {
+                "getProgramAccounts" if failures > 0 => {
+                    failures -= 1;
+                    serde_json::json!({ "jsonrpc": "2.0", "error": { "code": -32000, "message": "busy" }, "id": body["id"] })
+                }
+                "getProgramAccounts" => serde_json::json!({ "jsonrpc": "2.0", "result": {
+                    "context": { "slot": 50 },
+                    "value": [{ "pubkey": account.to_string(), "account": {
+                        "lamports": 7,
+                        "data": ["", "base64"],
+                        "owner": program_id.to_string(),
+                        "executable": false,
+                        "rentEpoch": 0,
+                    }}],
+                }, "id": body["id"] }),
+                _ => serde_json::json!({ "jsonrpc": "2.0", "result": { "solana-core": "1.18.26", "feature-set": 0 }, "id": body["id"] }),
+            };
+            let response = response.to_string();
+            let reply = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                response.len(),
+                response,
+            );
+            let _ = socket.write_all(reply.as_bytes()).await;
+        }
+    });
+    url
+}
+
+#[tokio::test]
+async fn failed_bootstraps_are_retried_while_live_updates_keep_flowing() {
+    let (program_id, snapshot_account, live_account) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let url = spawn_flaky_rpc(1, program_id, snapshot_account).await;
+    let storage = Arc::new(MemoryStorage::default());
+    let indexer = Arc::new(
+        vista_core::Indexer::new(storage.clone(), Arc::new(vista_core::RpcProviderRegistry::new()))
+            .with_bootstrap_rpc(&url),
+    );
+    indexer.track_program(program_id).await.unwrap();
+    indexer.start().await.unwrap();
+
+    let updates = indexer.get_update_channel();
+    updates.send(UpdateEvent::AccountUpdate(AccountInfo {
+        pubkey: live_account,
+        lamports: 3,
+        owner: program_id,
+        executable: false,
+        rent_epoch: 0,
+        data: Vec::new(),
+        slot: 60,
+    })).await.unwrap();
+    let (done, processed) = tokio::sync::oneshot::channel();
+    updates.send(UpdateEvent::Checkpoint(done)).await.unwrap();
+    processed.await.unwrap();
+    assert!(storage.accounts.lock().unwrap().contains_key(&live_account));
+    assert!(!storage.accounts.lock().unwrap().contains_key(&snapshot_account));
+
+    // The retry loads the snapshot without dropping the live account
+    for _ in 0..50 {
+        if storage.accounts.lock().unwrap().contains_key(&snapshot_account) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let accounts = storage.accounts.lock().unwrap();
+    assert_eq!(accounts.get(&snapshot_account).map(|account| (account.lamports, account.slot)), Some((7, 50)));
+    assert_eq!(accounts.get(&live_account).map(|account| account.lamports), Some(3));
+}